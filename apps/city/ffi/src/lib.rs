@@ -0,0 +1,79 @@
+// We have a lot of c-types in here, stop warning about their names!
+#![allow(non_camel_case_types)]
+
+use city_lib::{CitySim, Zone};
+
+#[no_mangle]
+pub extern "C" fn rs_CitySim_new(width: u16, height: u16) -> *mut CitySim {
+    let cs = CitySim::new(width, height);
+    Box::into_raw(Box::new(cs))
+}
+
+#[no_mangle]
+pub extern "C" fn rs_CitySim_free(p_cs: *mut CitySim) {
+    if !p_cs.is_null() {
+        unsafe {
+            let _ = Box::from_raw(p_cs);
+        };
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rs_CitySim_zone(p_cs: *mut CitySim, x: u16, y: u16, kind: u8) -> i8 {
+    if p_cs.is_null() {
+        return -1;
+    }
+    let cs = unsafe { &mut *p_cs };
+    match Zone::from_u8(kind).and_then(|z| cs.zone(x, y, z)) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rs_CitySim_bulldoze(p_cs: *mut CitySim, x: u16, y: u16) -> i8 {
+    if p_cs.is_null() {
+        return -1;
+    }
+    let cs = unsafe { &mut *p_cs };
+    match cs.bulldoze(x, y) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rs_CitySim_tick(p_cs: *mut CitySim, dt: f32) -> i8 {
+    if p_cs.is_null() {
+        return -1;
+    }
+    let cs = unsafe { &mut *p_cs };
+    cs.tick(dt);
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn rs_CitySim_population(p_cs: *mut CitySim, p_out: *mut u32) -> i8 {
+    if p_cs.is_null() || p_out.is_null() {
+        return -1;
+    }
+    let cs = unsafe { &*p_cs };
+    unsafe { *p_out = cs.population() };
+    0
+}
+
+/// writes the state buffer into p_out, which holds 2 + width * height * 2 bytes
+#[no_mangle]
+pub extern "C" fn rs_CitySim_state(p_cs: *mut CitySim, p_out: *mut u8, out_len: u32) -> i8 {
+    if p_cs.is_null() || p_out.is_null() {
+        return -1;
+    }
+    let cs = unsafe { &*p_cs };
+    let buf = cs.state_buffer();
+    if (out_len as usize) < buf.len() {
+        return -1;
+    }
+    let outs = unsafe { std::slice::from_raw_parts_mut(p_out, buf.len()) };
+    outs.copy_from_slice(&buf);
+    0
+}
@@ -0,0 +1,318 @@
+//
+// implement core algorithm...
+//
+// CitySim is a tiny city growth simulation on a grid of zones.
+// Zoned tiles join a road network when a road is next to them,
+// residents move into residential tiles of a network as long as
+// the commercial and industrial tiles of the same network have jobs,
+// and move out when jobs are gone. No randomness, so the same
+// zoning and ticks always give the same city.
+//
+
+use rust_pixel::util::GridMap;
+
+/// people a residential tile can hold
+pub const RES_CAPACITY: u16 = 8;
+/// jobs of a commercial tile
+pub const COM_JOBS: u16 = 4;
+/// jobs of an industrial tile
+pub const IND_JOBS: u16 = 6;
+/// share of jobs wanted in commerce, in tenths
+pub const COM_SHARE: i32 = 4;
+/// seconds of one growth step
+pub const STEP_TIME: f32 = 1.0;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Zone {
+    #[default]
+    Empty = 0,
+    Road,
+    Residential,
+    Commercial,
+    Industrial,
+}
+
+impl Zone {
+    pub fn from_u8(v: u8) -> Result<Self, String> {
+        Ok(match v {
+            0 => Zone::Empty,
+            1 => Zone::Road,
+            2 => Zone::Residential,
+            3 => Zone::Commercial,
+            4 => Zone::Industrial,
+            _ => return Err(format!("invalid zone {}", v)),
+        })
+    }
+
+    pub fn jobs(&self) -> u16 {
+        match self {
+            Zone::Commercial => COM_JOBS,
+            Zone::Industrial => IND_JOBS,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Tile {
+    pub zone: Zone,
+    pub pop: u16,
+}
+
+pub struct CitySim {
+    pub map: GridMap<Tile>,
+    pub steps: u32,
+    timer: f32,
+}
+
+impl CitySim {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            map: GridMap::new(width, height, Tile::default()),
+            steps: 0,
+            timer: 0.0,
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.map.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.map.height
+    }
+
+    pub fn tile(&self, x: u16, y: u16) -> Option<Tile> {
+        self.map.get(x, y).copied()
+    }
+
+    /// zones an empty tile, bulldoze it first to change a zone
+    pub fn zone(&mut self, x: u16, y: u16, kind: Zone) -> Result<(), String> {
+        if kind == Zone::Empty {
+            return Err("use bulldoze to clear a tile".to_string());
+        }
+        let t = self
+            .map
+            .get_mut(x, y)
+            .ok_or(format!("({}, {}) out of map", x, y))?;
+        if t.zone != Zone::Empty {
+            return Err(format!("({}, {}) already zoned {:?}", x, y, t.zone));
+        }
+        t.zone = kind;
+        Ok(())
+    }
+
+    /// clears a tile, returns the residents moved out
+    pub fn bulldoze(&mut self, x: u16, y: u16) -> Result<u16, String> {
+        let t = self
+            .map
+            .get_mut(x, y)
+            .ok_or(format!("({}, {}) out of map", x, y))?;
+        if t.zone == Zone::Empty {
+            return Err(format!("({}, {}) is empty", x, y));
+        }
+        let pop = t.pop;
+        *t = Tile::default();
+        Ok(pop)
+    }
+
+    /// runs a growth step every STEP_TIME seconds
+    pub fn tick(&mut self, dt: f32) {
+        self.timer += dt;
+        while self.timer >= STEP_TIME {
+            self.timer -= STEP_TIME;
+            self.step();
+        }
+    }
+
+    /// network id of every tile, roads are flood filled,
+    /// zoned tiles take the smallest id of their neighbor roads
+    fn networks(&self) -> (GridMap<Option<usize>>, usize) {
+        let (w, h) = (self.width(), self.height());
+        let mut net = GridMap::new(w, h, None);
+        let mut count = 0;
+        for y in 0..h {
+            for x in 0..w {
+                if self.map.cells[y as usize][x as usize].zone != Zone::Road
+                    || net.cells[y as usize][x as usize].is_some()
+                {
+                    continue;
+                }
+                for (rx, ry) in self.map.flood_fill((x, y), |t| t.zone == Zone::Road) {
+                    net.set(rx, ry, Some(count));
+                }
+                count += 1;
+            }
+        }
+        for y in 0..h {
+            for x in 0..w {
+                let z = self.map.cells[y as usize][x as usize].zone;
+                if z == Zone::Road || z == Zone::Empty {
+                    continue;
+                }
+                let id = self
+                    .map
+                    .neighbors4(x, y)
+                    .filter(|(nx, ny)| {
+                        self.map.cells[*ny as usize][*nx as usize].zone == Zone::Road
+                    })
+                    .filter_map(|(nx, ny)| net.cells[ny as usize][nx as usize])
+                    .min();
+                net.set(x, y, id);
+            }
+        }
+        (net, count)
+    }
+
+    /// moves one resident in or out of every residential tile,
+    /// in scan order, until residents match jobs of their network
+    pub fn step(&mut self) {
+        self.steps += 1;
+        let (net, count) = self.networks();
+        let mut jobs = vec![0i32; count];
+        let mut workers = vec![0i32; count];
+        let mut homes: Vec<(u16, u16, usize)> = vec![];
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let t = self.map.cells[y as usize][x as usize];
+                let n = net.cells[y as usize][x as usize];
+                match (t.zone, n) {
+                    (Zone::Residential, Some(n)) => {
+                        workers[n] += t.pop as i32;
+                        homes.push((x, y, n));
+                    }
+                    // cut off from roads, everyone leaves
+                    (Zone::Residential, None) => self.map.cells[y as usize][x as usize].pop = 0,
+                    (Zone::Commercial | Zone::Industrial, Some(n)) => {
+                        jobs[n] += t.zone.jobs() as i32
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for &(x, y, n) in homes.iter() {
+            let t = &mut self.map.cells[y as usize][x as usize];
+            if workers[n] < jobs[n] && t.pop < RES_CAPACITY {
+                t.pop += 1;
+                workers[n] += 1;
+            }
+        }
+        for &(x, y, n) in homes.iter().rev() {
+            let t = &mut self.map.cells[y as usize][x as usize];
+            if workers[n] > jobs[n] && t.pop > 0 {
+                t.pop -= 1;
+                workers[n] -= 1;
+            }
+        }
+    }
+
+    pub fn population(&self) -> u32 {
+        self.map.cells.iter().flatten().map(|t| t.pop as u32).sum()
+    }
+
+    fn jobs_of(&self, zone: Zone) -> i32 {
+        let (net, _) = self.networks();
+        let mut n = 0;
+        for (y, row) in self.map.cells.iter().enumerate() {
+            for (x, t) in row.iter().enumerate() {
+                if t.zone == zone && net.cells[y][x].is_some() {
+                    n += t.zone.jobs() as i32;
+                }
+            }
+        }
+        n
+    }
+
+    /// positive means the city wants more of this zone,
+    /// residential demand is free jobs, commercial and industrial
+    /// demand is jobs missing for the current population
+    pub fn demand(&self, zone: Zone) -> i32 {
+        let pop = self.population() as i32;
+        let com = self.jobs_of(Zone::Commercial);
+        let ind = self.jobs_of(Zone::Industrial);
+        match zone {
+            Zone::Residential => com + ind - pop,
+            Zone::Commercial => pop * COM_SHARE / 10 - com,
+            Zone::Industrial => pop * (10 - COM_SHARE) / 10 - ind,
+            _ => 0,
+        }
+    }
+
+    /// width, height, then zone and population of every tile row by row,
+    /// population is clamped to 255
+    pub fn state_buffer(&self) -> Vec<u8> {
+        let mut buf = vec![self.width() as u8, self.height() as u8];
+        for t in self.map.cells.iter().flatten() {
+            buf.push(t.zone as u8);
+            buf.push(t.pop.min(255) as u8);
+        }
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // road across row 2, homes above, shops and factories below,
+    // one home at the corner without road
+    fn town() -> CitySim {
+        let mut c = CitySim::new(8, 6);
+        for x in 0..8 {
+            c.zone(x, 2, Zone::Road).unwrap();
+        }
+        for x in 0..4 {
+            c.zone(x, 1, Zone::Residential).unwrap();
+        }
+        c.zone(0, 3, Zone::Commercial).unwrap();
+        c.zone(1, 3, Zone::Commercial).unwrap();
+        c.zone(2, 3, Zone::Industrial).unwrap();
+        c.zone(3, 3, Zone::Industrial).unwrap();
+        c.zone(7, 5, Zone::Residential).unwrap();
+        c
+    }
+
+    #[test]
+    fn fixed_layout_reaches_known_population() {
+        let mut c = town();
+        assert_eq!(c.demand(Zone::Residential), 20);
+        for _ in 0..3 {
+            c.tick(1.0);
+        }
+        assert_eq!(c.population(), 12);
+        for _ in 0..20 {
+            c.tick(0.5);
+        }
+        assert_eq!(c.steps, 13);
+        // 2 shops and 2 factories give 20 jobs
+        assert_eq!(c.population(), 20);
+        assert_eq!(c.tile(7, 5).unwrap().pop, 0);
+        assert_eq!(c.demand(Zone::Residential), 0);
+        assert_eq!(c.demand(Zone::Commercial), 0);
+        assert_eq!(c.demand(Zone::Industrial), 0);
+
+        // jobs gone, people move out
+        assert_eq!(c.bulldoze(0, 3), Ok(0));
+        assert_eq!(c.bulldoze(1, 3), Ok(0));
+        c.tick(2.0);
+        assert_eq!(c.population(), 12);
+        assert_eq!(c.demand(Zone::Commercial), 4);
+
+        let buf = c.state_buffer();
+        assert_eq!(buf.len(), 2 + 8 * 6 * 2);
+        assert_eq!(&buf[0..2], &[8, 6]);
+    }
+
+    #[test]
+    fn zone_validation() {
+        let mut c = town();
+        assert!(c.zone(0, 2, Zone::Residential).is_err());
+        assert!(c.zone(8, 0, Zone::Road).is_err());
+        assert!(c.zone(5, 5, Zone::Empty).is_err());
+        assert!(c.bulldoze(5, 5).is_err());
+        assert!(Zone::from_u8(5).is_err());
+        assert_eq!(c.bulldoze(0, 2), Ok(0));
+        assert!(c.zone(0, 2, Zone::Industrial).is_ok());
+    }
+}
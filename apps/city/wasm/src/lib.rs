@@ -0,0 +1,45 @@
+use city_lib::{CitySim, Zone};
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub struct WasmCity {
+    cs: CitySim,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl WasmCity {
+    // js调用创建city结构
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            cs: CitySim::new(width, height),
+        }
+    }
+
+    /// false if kind is invalid or the tile can not be zoned
+    pub fn zone(&mut self, x: u16, y: u16, kind: u8) -> bool {
+        Zone::from_u8(kind)
+            .and_then(|z| self.cs.zone(x, y, z))
+            .is_ok()
+    }
+
+    pub fn bulldoze(&mut self, x: u16, y: u16) -> bool {
+        self.cs.bulldoze(x, y).is_ok()
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        self.cs.tick(dt);
+    }
+
+    pub fn population(&self) -> u32 {
+        self.cs.population()
+    }
+
+    pub fn demand(&self, kind: u8) -> i32 {
+        Zone::from_u8(kind).map_or(0, |z| self.cs.demand(z))
+    }
+
+    /// width, height, then zone and population of every tile
+    pub fn state_bytes(&self) -> Vec<u8> {
+        self.cs.state_buffer()
+    }
+}
@@ -1,6 +1,6 @@
 // use log::info;
 use itertools::Itertools;
-use poker_lib::{PokerCard, PokerCards};
+use poker_lib::{PokerCard, PokerCards, Suit};
 use std::collections::HashSet;
 
 // 3张或4张
@@ -120,6 +120,86 @@ fn get_all_melds_freeze(pc: &PokerCards) -> Vec<Vec<&PokerCard>> {
     am
 }
 
+// 排序默认的花色顺序：黑桃,红心,草花,方片
+// default suit order: Spade, Heart, Club, Diamond
+const SUIT_ORDER: [Suit; 4] = [Suit::Spade, Suit::Heart, Suit::Club, Suit::Diamond];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortGroup {
+    Suit,
+    Number,
+}
+
+// 排序选项，默认值与sort()一致
+// sort options, the default is what sort() does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortOptions {
+    pub ace_high: bool,
+    pub suit_order: [Suit; 4],
+    pub group_by: SortGroup,
+}
+
+impl Default for SortOptions {
+    fn default() -> Self {
+        Self {
+            ace_high: false,
+            suit_order: SUIT_ORDER,
+            group_by: SortGroup::Suit,
+        }
+    }
+}
+
+impl SortOptions {
+    // ffi用的flags字节
+    // flags byte used by ffi:
+    // bit0     ace high
+    // bit1     group by number
+    // bit2..6  suit order, index of the 24 permutations of
+    //          Spade, Heart, Club, Diamond in lexicographic order,
+    //          0 is the default, invalid index falls back to 0
+    pub fn from_flags(flags: u8) -> Self {
+        let pidx = ((flags >> 2) & 0x1f) as usize;
+        let suit_order = SUIT_ORDER
+            .iter()
+            .copied()
+            .permutations(4)
+            .nth(pidx)
+            .map(|p| [p[0], p[1], p[2], p[3]])
+            .unwrap_or(SUIT_ORDER);
+        Self {
+            ace_high: flags & 1 != 0,
+            suit_order,
+            group_by: if flags & 2 != 0 {
+                SortGroup::Number
+            } else {
+                SortGroup::Suit
+            },
+        }
+    }
+
+    // Joker排在最后
+    // Joker goes last
+    fn suit_rank(&self, s: Suit) -> u16 {
+        self.suit_order.iter().position(|x| *x == s).unwrap_or(4) as u16
+    }
+
+    fn number_rank(&self, n: u8) -> u16 {
+        if self.ace_high && n == 1 {
+            14
+        } else {
+            n as u16
+        }
+    }
+
+    fn key(&self, pc: &PokerCard, group: SortGroup) -> u16 {
+        let (s, n) = (self.suit_rank(pc.suit), self.number_rank(pc.number));
+        match group {
+            SortGroup::Suit => s * 100 + n,
+            SortGroup::Number => n * 100 + s,
+        }
+    }
+}
+
 pub struct GinRummyCards {
     pub cards: PokerCards,
     pub sort_cards_suit: Vec<PokerCard>,
@@ -167,18 +247,27 @@ impl GinRummyCards {
     }
 
     pub fn sort(&mut self) {
-        self.sort_cards_suit.clear();
-        self.sort_cards_number.clear();
-        for v in &self.cards.cards {
-            self.sort_cards_suit.push(*v);
-            self.sort_cards_number.push(*v);
+        self.sort_with(&SortOptions::default());
+    }
+
+    // 稳定排序，相同key保持原有顺序
+    // stable sort, cards with equal keys keep their order in hand
+    pub fn sort_with(&mut self, opts: &SortOptions) {
+        self.sort_cards_suit = self.cards.cards.clone();
+        self.sort_cards_number = self.cards.cards.clone();
+        self.sort_cards_suit
+            .sort_by_key(|pc| opts.key(pc, SortGroup::Suit));
+        self.sort_cards_number
+            .sort_by_key(|pc| opts.key(pc, SortGroup::Number));
+    }
+
+    // group_by指定的排序结果
+    // sorted cards of the given group
+    pub fn sorted(&self, group: SortGroup) -> &Vec<PokerCard> {
+        match group {
+            SortGroup::Suit => &self.sort_cards_suit,
+            SortGroup::Number => &self.sort_cards_number,
         }
-        self.sort_cards_suit.sort_by(|a, b| {
-            (a.suit as u16 * 100 + a.number as u16).cmp(&(b.suit as u16 * 100 + b.number as u16))
-        });
-        self.sort_cards_number.sort_by(|a, b| {
-            (a.number as u16 * 100 + a.suit as u16).cmp(&(b.number as u16 * 100 + b.suit as u16))
-        });
     }
 
     // 遍历所有组合，不冲突则算deadwood，找到最优解
@@ -225,3 +314,95 @@ impl GinRummyCards {
         self.best_deadwood = bestdw.clone();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HAND: [u16; 10] = [1, 40, 2, 13, 14, 26, 27, 31, 45, 3];
+
+    fn sorted_u8(gc: &GinRummyCards, group: SortGroup) -> Vec<u8> {
+        gc.sorted(group).iter().map(|c| c.to_u8()).collect()
+    }
+
+    #[test]
+    fn sort_default_options() {
+        let mut gc = GinRummyCards::new();
+        gc.assign(&HAND, false).unwrap();
+        gc.sort();
+        assert_eq!(
+            sorted_u8(&gc, SortGroup::Suit),
+            vec![1, 2, 3, 13, 14, 26, 27, 31, 40, 45]
+        );
+        assert_eq!(
+            sorted_u8(&gc, SortGroup::Number),
+            vec![1, 14, 27, 40, 2, 3, 31, 45, 13, 26]
+        );
+
+        gc.sort_with(&SortOptions::from_flags(1));
+        assert_eq!(
+            sorted_u8(&gc, SortGroup::Suit),
+            vec![2, 3, 13, 1, 26, 14, 31, 27, 45, 40]
+        );
+        assert_eq!(
+            sorted_u8(&gc, SortGroup::Number),
+            vec![2, 3, 31, 45, 13, 26, 1, 14, 27, 40]
+        );
+    }
+
+    #[test]
+    fn sort_fixed_orders() {
+        let mut gc = GinRummyCards::new();
+        gc.assign(&HAND, false).unwrap();
+        // (flags, by suit, by number)
+        let cases: [(u8, [u8; 10], [u8; 10]); 3] = [
+            // ace low, Diamond Club Heart Spade
+            (
+                23 << 2,
+                [40, 45, 27, 31, 14, 26, 1, 2, 3, 13],
+                [40, 27, 14, 1, 2, 3, 31, 45, 26, 13],
+            ),
+            // ace high, Diamond Club Heart Spade
+            (
+                (23 << 2) | 1,
+                [45, 40, 31, 27, 26, 14, 2, 3, 13, 1],
+                [2, 3, 31, 45, 26, 13, 40, 27, 14, 1],
+            ),
+            // ace high, Heart Club Diamond Spade
+            (
+                (9 << 2) | 1,
+                [26, 14, 31, 27, 45, 40, 2, 3, 13, 1],
+                [2, 3, 31, 45, 26, 13, 14, 27, 40, 1],
+            ),
+        ];
+        for (flags, by_suit, by_number) in cases {
+            // group_by doesn't change the sorted results
+            for g in [0, 2] {
+                gc.sort_with(&SortOptions::from_flags(flags | g));
+                assert_eq!(sorted_u8(&gc, SortGroup::Suit), by_suit);
+                assert_eq!(sorted_u8(&gc, SortGroup::Number), by_number);
+            }
+        }
+    }
+
+    #[test]
+    fn sort_all_options() {
+        let mut orders = vec![];
+        for flags in 0..(24u8 << 2) {
+            let opts = SortOptions::from_flags(flags);
+            assert_eq!(opts.ace_high, flags & 1 != 0);
+            // group_by only picks which result comes first in ffi output
+            assert_eq!(opts.group_by == SortGroup::Number, flags & 2 != 0);
+            orders.push(opts.suit_order);
+        }
+        // every suit order shows up
+        orders.sort_by_key(|o| o.iter().map(|s| *s as u8).collect::<Vec<_>>());
+        orders.dedup();
+        assert_eq!(orders.len(), 24);
+        assert_eq!(
+            SortOptions::from_flags(23 << 2).suit_order,
+            [Suit::Diamond, Suit::Club, Suit::Heart, Suit::Spade]
+        );
+        assert_eq!(SortOptions::from_flags(0), SortOptions::default());
+    }
+}
@@ -5,7 +5,7 @@
 use lazy_static::lazy_static;
 use log::info;
 use rust_pixel::render::style::{
-    delta_e_ciede2000, ColorData, ColorGradient, ColorPro, ColorSpace::*, Fraction,
+    ColorData, ColorGradient, ColorIndex, ColorPro, ColorSpace::*, Fraction,
 };
 use rust_pixel::util::Rand;
 use std::collections::HashMap;
@@ -169,16 +169,16 @@ lazy_static! {
         }
         rgb_index
     };
+    pub static ref COLORS_WITH_NAME_INDEX: ColorIndex = {
+        let colors: Vec<ColorPro> = COLORS_WITH_NAME.iter().map(|c| c.1).collect();
+        ColorIndex::build(&colors)
+    };
 }
 
+/// 3 nearest named colors, skipping the color itself
 pub fn find_similar_colors(color: &ColorPro) -> (usize, usize, usize) {
-    let mut deltas: Vec<(usize, f64)> = vec![];
-    for idx in 0..COLORS_WITH_NAME.len() {
-        let c = COLORS_WITH_NAME[idx];
-        let d = delta_e_ciede2000(color[LabA].unwrap(), c.1[LabA].unwrap());
-        deltas.push((idx, d));
-    }
-    deltas.sort_by_key(|nc| (nc.1 * 1000.0) as i32);
+    let deltas =
+        COLORS_WITH_NAME_INDEX.nearest_n_in(color[LabA].unwrap(), 4, 0..COLORS_WITH_NAME.len());
     if deltas[0].1 == 0.0 {
         (deltas[1].0, deltas[2].0, deltas[3].0)
     } else {
@@ -13,7 +13,9 @@ use rust_pixel::{
     context::Context,
     event::{event_emit, Event, KeyCode, MouseButton, MouseEventKind::*},
     game::Model,
-    render::style::{ColorPro, ColorSpace, ColorSpace::*, COLOR_SPACE_COUNT},
+    render::style::{
+        simulate_cvd, ColorPro, ColorSpace, ColorSpace::*, CvdKind, COLOR_SPACE_COUNT,
+    },
 };
 use PaletteState::*;
 
@@ -71,6 +73,8 @@ pub struct PaletteModel {
     pub random_colors: Vec<ColorPro>,
     pub picker_colors: Vec<ColorPro>,
     pub select: Select,
+    // color blindness preview, None shows real colors
+    pub cvd: Option<CvdKind>,
 }
 
 impl PaletteModel {
@@ -90,6 +94,34 @@ impl PaletteModel {
             random_colors: vec![],
             picker_colors: vec![],
             select: Select::new(),
+            cvd: None,
+        }
+    }
+
+    /// color as seen in the current cvd preview mode
+    pub fn preview(&self, c: ColorPro) -> ColorPro {
+        match self.cvd {
+            Some(k) => simulate_cvd(c, k),
+            None => c,
+        }
+    }
+
+    /// none -> protanopia -> deuteranopia -> tritanopia -> none
+    fn switch_cvd(&mut self) {
+        self.cvd = match self.cvd {
+            None => Some(CvdKind::Protanopia),
+            Some(CvdKind::Protanopia) => Some(CvdKind::Deuteranopia),
+            Some(CvdKind::Deuteranopia) => Some(CvdKind::Tritanopia),
+            Some(CvdKind::Tritanopia) => None,
+        };
+        for e in [
+            "Palette.RedrawNamed",
+            "Palette.RedrawMainColor",
+            "Palette.RedrawPicker",
+            "Palette.RedrawGradient",
+            "Palette.RedrawRandom",
+        ] {
+            event_emit(e);
         }
     }
 
@@ -577,6 +609,9 @@ impl Model for PaletteModel {
                     KeyCode::Char('g') => {
                         self.do_gradient(context);
                     }
+                    KeyCode::Char('c') => {
+                        self.switch_cvd();
+                    }
                     KeyCode::Up => {
                         self.select.cur().backward_y();
                         self.update_main_color(context);
@@ -601,6 +636,7 @@ impl Model for PaletteModel {
                         // context.state = PaletteState::Picker as u8;
                     }
                 },
+                _ => {}
             }
         }
         context.input_events.clear();
@@ -169,6 +169,7 @@ impl PaletteRender {
         event_register("Palette.RedrawPicker", "draw_picker");
         event_register("Palette.RedrawGradient", "draw_gradient");
         event_register("Palette.RedrawRandom", "draw_random");
+        event_register("Palette.RedrawNamed", "draw_named_colors");
 
         Self { panel }
     }
@@ -376,7 +377,7 @@ impl PaletteRender {
                     0,
                     0,
                     "████████",
-                    Color::from(d.gradient_input_colors[i as usize]),
+                    Color::from(d.preview(d.gradient_input_colors[i as usize])),
                     Color::Reset,
                 );
             } else {
@@ -394,7 +395,7 @@ impl PaletteRender {
                         0,
                         "            ",
                         Color::White,
-                        Color::from(d.gradient_colors[idx as usize]),
+                        Color::from(d.preview(d.gradient_colors[idx as usize])),
                     );
                 } else {
                     pl.set_hidden(true);
@@ -417,7 +418,7 @@ impl PaletteRender {
                     0,
                     &format!(" {:width$}", " ", width = C_WIDTH as usize - 1),
                     Color::Reset,
-                    Color::from(d.random_colors[i as usize]),
+                    Color::from(d.preview(d.random_colors[i as usize])),
                 );
             }
         }
@@ -447,7 +448,7 @@ impl PaletteRender {
                             0,
                         );
 
-                        let color = Color::from(cr);
+                        let color = Color::from(d.preview(cr));
                         pl.set_color_str(0, 0, "  ", color, color);
                         pl.set_color_str(0, 0, "  ", color, color);
                     }
@@ -463,7 +464,7 @@ impl PaletteRender {
                         1.0,
                         1.0,
                     );
-                    let color = Color::from(cr);
+                    let color = Color::from(d.preview(cr));
                     pl.set_color_str(0, 0, " ", color, color);
                 }
             }
@@ -484,7 +485,7 @@ impl PaletteRender {
                         .panel
                         .get_layer_sprite(&format!("{}", i), &format!("{}", idx));
                     let s = d.named_colors[idx].0;
-                    let cr = d.named_colors[idx].1;
+                    let cr = d.preview(d.named_colors[idx].1);
                     let color = Color::from(cr);
                     pl.set_color_str(
                         0,
@@ -510,7 +511,7 @@ impl PaletteRender {
                 i,
                 "            ",
                 Color::White,
-                Color::from(d.main_color),
+                Color::from(d.preview(d.main_color)),
             );
         }
 
@@ -519,7 +520,15 @@ impl PaletteRender {
         pl.set_color_str(
             0,
             0,
-            &format!("{:width$}", &get_color_info(d.main_color, 0), width = 18),
+            &format!(
+                "{:width$} {}",
+                &get_color_info(d.main_color, 0),
+                match d.cvd {
+                    Some(k) => format!("c: {:?}", k),
+                    None => "c: cvd preview".to_string(),
+                },
+                width = 18
+            ),
             Color::DarkGray,
             Color::Black,
         );
@@ -557,7 +566,7 @@ impl PaletteRender {
             let pl = self.panel.get_layer_sprite("main", &format!("simi{}", i));
 
             let s = COLORS_WITH_NAME[ids[i]].0;
-            let cr = COLORS_WITH_NAME[ids[i]].1;
+            let cr = d.preview(COLORS_WITH_NAME[ids[i]].1);
             let color = Color::from(cr);
 
             pl.set_color_str(
@@ -675,6 +684,9 @@ impl Render for PaletteRender {
         if event_check("Palette.RedrawRandom", "draw_random") {
             self.draw_random(context, data);
         }
+        if event_check("Palette.RedrawNamed", "draw_named_colors") {
+            self.draw_named_colors(context, data);
+        }
     }
 
     fn handle_timer(&mut self, _context: &mut Context, _model: &mut Self::Model, _dt: f32) {}
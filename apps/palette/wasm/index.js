@@ -4,9 +4,7 @@ const wasm = await init();
 const wgr = WasmTemplate.new();
 console.log("before shuffle...");
 wgr.shuffle();
-wgr.next();
-const wbuflen = wgr.web_buffer_len();
-const wbufptr = wgr.web_buffer();
-let wbuf = new Uint8Array(wasm.memory.buffer, wbufptr, wbuflen);
+// next_bytes returns a copy, still valid after later calls
+let wbuf = wgr.next_bytes();
 console.log("after assign...", wbuf);
 window.alert(wbuf);
@@ -8,7 +8,6 @@ pub struct WasmPalette {
     webbuf: Vec<u8>,
 }
 
-#[cfg(target_arch = "wasm32")]
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 impl WasmPalette {
     // js调用创建game结构
@@ -30,12 +29,56 @@ impl WasmPalette {
         self.webbuf.push(cs);
     }
 
+    /// @deprecated the view built on this pointer is invalid after the next
+    /// call that writes the buffer, use web_buffer_bytes or take_buffer
     pub fn web_buffer_len(&self) -> usize {
         self.webbuf.len()
     }
 
+    /// @deprecated the view built on this pointer is invalid after the next
+    /// call that writes the buffer, use web_buffer_bytes or take_buffer
     pub fn web_buffer(&self) -> *const u8 {
         self.webbuf.as_slice().as_ptr()
     }
+
+    /// copy of the current buffer, returned to js as a fresh Uint8Array
+    pub fn web_buffer_bytes(&self) -> Vec<u8> {
+        self.webbuf.clone()
+    }
+
+    /// copies the buffer into a js owned Uint8Array and clears it,
+    /// the array stays valid whatever happens to wasm memory later
+    pub fn take_buffer(&mut self) -> js_sys::Uint8Array {
+        let arr = js_sys::Uint8Array::from(self.webbuf.as_slice());
+        self.webbuf.clear();
+        arr
+    }
+
+    /// same as next, but returns the buffer content
+    pub fn next_bytes(&mut self) -> Vec<u8> {
+        self.next();
+        self.web_buffer_bytes()
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy(w: &WasmPalette) -> Vec<u8> {
+        unsafe { std::slice::from_raw_parts(w.web_buffer(), w.web_buffer_len()).to_vec() }
+    }
+
+    #[test]
+    fn next_bytes_same_as_legacy_buffer() {
+        let mut w = WasmPalette::new();
+        w.shuffle();
+        for _ in 0..3 {
+            let bytes = w.next_bytes();
+            assert_eq!(bytes.len(), 1);
+            assert_eq!(bytes, legacy(&w));
+        }
+        w.next();
+        assert_eq!(w.web_buffer_bytes(), legacy(&w));
+    }
+}
@@ -5,10 +5,50 @@
 #![allow(dead_code)]
 use rust_pixel::util::Rand;
 
+/// transition styles between two images,
+/// the value is the shader id in rust_pixel gl shader_source::TRANS_FS
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    Squares,
+    Heart,
+    Noise,
+    RotateScale,
+    Bounce,
+    Displace,
+    Ripple,
+    Crossfade,
+    EdgeDetect,
+}
+
+impl TransitionKind {
+    pub const ALL: [TransitionKind; 9] = [
+        TransitionKind::Squares,
+        TransitionKind::Heart,
+        TransitionKind::Noise,
+        TransitionKind::RotateScale,
+        TransitionKind::Bounce,
+        TransitionKind::Displace,
+        TransitionKind::Ripple,
+        TransitionKind::Crossfade,
+        TransitionKind::EdgeDetect,
+    ];
+
+    pub fn shader_id(self) -> usize {
+        self as usize
+    }
+
+    /// the following kind, wraps to the first one
+    pub fn next(self) -> Self {
+        Self::ALL[(self.shader_id() + 1) % Self::ALL.len()]
+    }
+}
+
 pub struct PetviewData {
     pub rand: Rand,
     pub pool: Vec<u8>,
     pub index: usize,
+    pub transition: TransitionKind,
 }
 
 impl PetviewData {
@@ -19,9 +59,25 @@ impl PetviewData {
             rand: rd,
             pool: vec![],
             index: 0,
+            transition: TransitionKind::Squares,
         }
     }
 
+    pub fn set_transition(&mut self, kind: TransitionKind) {
+        self.transition = kind;
+    }
+
+    /// shader id of the current transition
+    pub fn transition_shader(&self) -> usize {
+        self.transition.shader_id()
+    }
+
+    /// moves to the next transition kind, returns its shader id
+    pub fn next_transition(&mut self) -> usize {
+        self.transition = self.transition.next();
+        self.transition.shader_id()
+    }
+
     pub fn shuffle(&mut self) {
         self.pool.clear();
         for i in 1..=52u8 {
@@ -45,9 +101,24 @@ impl PetviewData {
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
     #[test]
     fn it_works() {
         // let result = PetviewData::new();
     }
+
+    #[test]
+    fn select_transition_shader() {
+        let mut d = PetviewData::new();
+        d.set_transition(TransitionKind::Crossfade);
+        assert_eq!(d.transition_shader(), 7);
+        d.set_transition(TransitionKind::EdgeDetect);
+        assert_eq!(d.transition_shader(), 8);
+        // cycles through every kind and wraps
+        assert_eq!(d.next_transition(), 0);
+        for i in 1..TransitionKind::ALL.len() {
+            assert_eq!(d.next_transition(), i);
+        }
+        assert_eq!(d.transition, TransitionKind::EdgeDetect);
+    }
 }
@@ -81,7 +81,7 @@ impl Model for PetviewModel {
                 self.transbuf_stage += 1;
                 if self.transbuf_stage > 20 {
                     ctx.state = PetviewState::TransGl as u8;
-                    self.trans_effect = (ctx.rand.rand() % 7) as usize;
+                    self.trans_effect = self.data.next_transition();
                     self.progress = 0.0;
                     self.tex_ready = false;
                 }
@@ -4,6 +4,10 @@
 #include <ostream>
 #include <new>
 
+constexpr static const int8_t rs_TEXAS_ERR_ARG = -1;
+
+constexpr static const int8_t rs_TEXAS_ERR_HANDLE = -2;
+
 enum class rs_Suit {
   spade = 0,
   heart = 1,
@@ -56,11 +60,13 @@ struct rs_TexasCardBuffer {
 
 extern "C" {
 
+void rs_set_log_callback(void (*cb)(uint8_t level, const char *msg));
+
 rs_GinRummyCards *rs_GinRummyCards_new();
 
 void rs_GinRummyCards_free(rs_GinRummyCards *p_pcs);
 
-int8_t rs_GinRummyCards_sort(rs_GinRummyCards *p_pcs, uint8_t *p_out);
+int8_t rs_GinRummyCards_sort(rs_GinRummyCards *p_pcs, uint8_t flags, uint8_t *p_out);
 
 int8_t rs_GinRummyCards_assign(rs_GinRummyCards *p_pcs,
                                const uint16_t *p_data,
@@ -98,4 +104,12 @@ rs_TexasCardBuffer rs_TexasCards_get_best(rs_TexasCards *p_pcs);
 
 void rs_TexasCardBuffer_free(rs_TexasCardBuffer buf);
 
+uint64_t rs_Texas_create();
+
+int8_t rs_Texas_destroy(uint64_t handle);
+
+int8_t rs_Texas_assign(uint64_t handle, const uint16_t *p_data, uintptr_t data_len);
+
+int8_t rs_Texas_get_best(uint64_t handle, rs_TexasCardBuffer *p_out);
+
 } // extern "C"
@@ -90,7 +90,7 @@ void test_gin_rummy() {
             mcnt++;
         }
     }
-    r = rs_GinRummyCards_sort(gc, ret);
+    r = rs_GinRummyCards_sort(gc, 0, ret);
     printf("sort...ret=%d\n", r);
     if (r > 0) {
         for(int i=0; i<r; i++) 
@@ -1,3 +1,7 @@
+#define rs_TEXAS_ERR_ARG -1
+
+#define rs_TEXAS_ERR_HANDLE -2
+
 typedef enum rs_Suit {
   spade = 0,
   heart = 1,
@@ -48,11 +52,13 @@ typedef struct rs_TexasCardBuffer {
   uint64_t score;
 } rs_TexasCardBuffer;
 
+void rs_set_log_callback(void (*cb)(uint8_t level, const char *msg));
+
 struct rs_GinRummyCards *rs_GinRummyCards_new(void);
 
 void rs_GinRummyCards_free(struct rs_GinRummyCards *p_pcs);
 
-int8_t rs_GinRummyCards_sort(struct rs_GinRummyCards *p_pcs, uint8_t *p_out);
+int8_t rs_GinRummyCards_sort(struct rs_GinRummyCards *p_pcs, uint8_t flags, uint8_t *p_out);
 
 int8_t rs_GinRummyCards_assign(struct rs_GinRummyCards *p_pcs,
                                const uint16_t *p_data,
@@ -93,3 +99,11 @@ int8_t rs_TexasCards_assign(struct rs_TexasCards *p_pcs,
 struct rs_TexasCardBuffer rs_TexasCards_get_best(struct rs_TexasCards *p_pcs);
 
 void rs_TexasCardBuffer_free(struct rs_TexasCardBuffer buf);
+
+uint64_t rs_Texas_create(void);
+
+int8_t rs_Texas_destroy(uint64_t handle);
+
+int8_t rs_Texas_assign(uint64_t handle, const uint16_t *p_data, uintptr_t data_len);
+
+int8_t rs_Texas_get_best(uint64_t handle, struct rs_TexasCardBuffer *p_out);
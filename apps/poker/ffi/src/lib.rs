@@ -1,9 +1,63 @@
 // We have a lot of c-types in here, stop warning about their names!
 #![allow(non_camel_case_types)]
 
+// 线程安全:
+// 指针接口(rs_XXX_new/assign/free)不加锁, 同一个指针同时只能由一个线程使用,
+// 多线程同时使用同一指针是未定义行为. 不同指针可以在不同线程并发使用.
+// 多线程共用时请用德州的句柄接口(rs_Texas_create/assign/get_best/destroy),
+// 每个句柄有自己的锁, 无效句柄返回TEXAS_ERR_HANDLE而不会崩溃.
+// thread safety:
+// the pointer api (rs_XXX_new/assign/free) takes no lock, a pointer must be
+// used by one thread at a time, using it from two threads at once is UB.
+// Distinct pointers can be used from different threads concurrently.
+// For shared use take the texas handle api (rs_Texas_create/assign/get_best/destroy),
+// every handle locks its own instance, invalid handles return TEXAS_ERR_HANDLE.
+
 use poker_lib::{Counter, PokerCard, PokerCards, Suit};
 use texas_lib::{TexasCards, TexasType};
-use gin_rummy_lib::cards::GinRummyCards;
+use ginrummy_lib::cards::{GinRummyCards, SortGroup, SortOptions};
+use std::collections::HashMap;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock, RwLock};
+
+// 日志回调，未设置时不输出任何内容
+// log callback, no output at all when unset
+type LogCallback = extern "C" fn(level: u8, msg: *const c_char);
+
+pub const LOG_ERROR: u8 = 1;
+pub const LOG_INFO: u8 = 2;
+pub const LOG_DEBUG: u8 = 3;
+
+// 消息截断长度，含结尾的0
+// max message length, including the trailing 0
+const LOG_BUF_LEN: usize = 256;
+
+static LOG_CALLBACK: Mutex<Option<LogCallback>> = Mutex::new(None);
+
+// 设置日志回调，传入空指针关闭日志
+// set log callback, passing null disables logging
+#[no_mangle]
+pub extern "C" fn rs_set_log_callback(cb: Option<LogCallback>) {
+    if let Ok(mut c) = LOG_CALLBACK.lock() {
+        *c = cb;
+    }
+}
+
+fn ffi_log(level: u8, msg: &str) {
+    let cb = match LOG_CALLBACK.lock() {
+        Ok(c) => *c,
+        Err(_) => None,
+    };
+    if let Some(cb) = cb {
+        let mut buf = [0u8; LOG_BUF_LEN];
+        for (i, &b) in msg.as_bytes().iter().take(LOG_BUF_LEN - 1).enumerate() {
+            // 内部的0会截断c字符串
+            buf[i] = if b == 0 { b' ' } else { b };
+        }
+        cb(level, buf.as_ptr() as *const c_char);
+    }
+}
 
 #[no_mangle]
 pub extern "C" fn rs_GinRummyCards_new() -> *mut GinRummyCards {
@@ -23,6 +77,7 @@ pub extern "C" fn rs_GinRummyCards_free(p_pcs: *mut GinRummyCards) {
 #[no_mangle]
 pub extern "C" fn rs_GinRummyCards_sort(
     p_pcs: *mut GinRummyCards,
+    flags: u8,
     p_out: *mut u8,
 ) -> i8 {
     if p_pcs.is_null() {
@@ -34,24 +89,30 @@ pub extern "C" fn rs_GinRummyCards_sort(
     // 要求传入足够的32字节的数据缓冲区
     let outs = unsafe { std::slice::from_raw_parts_mut(p_out, 32usize) };
 
-    ps.sort(); 
+    // flags编码见SortOptions::from_flags, 0为默认排序
+    // flags are decoded by SortOptions::from_flags, 0 is the default sort
+    let opts = SortOptions::from_flags(flags);
+    ps.sort_with(&opts);
+    let (first, second) = match opts.group_by {
+        SortGroup::Suit => (SortGroup::Suit, SortGroup::Number),
+        SortGroup::Number => (SortGroup::Number, SortGroup::Suit),
+    };
     let mut idx = 0usize;
     // 有效的out数据格式：
-    // suit长度 card1 card2...
-    // number长度 card1 card2...
+    // group_by长度 card1 card2...
+    // 另一种排序长度 card1 card2...
     // ...
     // 长度32足够了
-    outs[idx] = ps.cards.cards.len() as u8;
-    idx += 1;
-    for v in &ps.sort_cards_suit {
-        outs[idx] = v.to_u8();
-        idx += 1;
-    }
-    outs[idx] = ps.cards.cards.len() as u8;
-    idx += 1;
-    for v in &ps.sort_cards_number {
-        outs[idx] = v.to_u8();
+    // valid out data:
+    // group_by ordering: len card1 card2...
+    // the other ordering: len card1 card2...
+    for g in [first, second] {
+        outs[idx] = ps.cards.cards.len() as u8;
         idx += 1;
+        for v in ps.sorted(g) {
+            outs[idx] = v.to_u8();
+            idx += 1;
+        }
     }
     // 返回out数据有效长度
     ret = idx as i8;
@@ -110,8 +171,8 @@ pub extern "C" fn rs_GinRummyCards_assign(
             // 返回out数据有效长度
             ret = idx as i8;
         }
-        Err(_) => {
-            // println!("{:?}", e);
+        Err(e) => {
+            ffi_log(LOG_ERROR, &format!("GinRummyCards assign error: {}", e));
             ret = -1;
         }
     }
@@ -160,7 +221,7 @@ pub extern "C" fn rs_PokerCards_assign(
             ret = -1;
         }
     }
-    println!("{}", ps);
+    ffi_log(LOG_DEBUG, &format!("{}", ps));
     std::mem::forget(ps);
     return ret;
 }
@@ -197,7 +258,7 @@ pub extern "C" fn rs_PokerCards_get_counter(p_stu: *mut PokerCards, s: Suit) ->
     if p_stu.is_null() {
         return std::ptr::null::<Counter>() as *mut _;
     }
-    unsafe { &mut ((*p_stu)[s]) as *mut Counter }
+    unsafe { &mut (&mut *p_stu)[s] as *mut Counter }
 }
 
 #[no_mangle]
@@ -271,7 +332,7 @@ pub extern "C" fn rs_TexasCards_assign(
             ret = -1;
         }
     }
-    println!("{}", ps);
+    ffi_log(LOG_DEBUG, &format!("{}", ps));
     std::mem::forget(ps);
     return ret;
 }
@@ -309,3 +370,203 @@ pub extern "C" fn rs_TexasCardBuffer_free(buf: TexasCardBuffer) {
         let _ = Box::from_raw(ps);
     };
 }
+
+// 德州句柄接口, 句柄从1开始, 0是无效句柄
+// texas handle api, handles start at 1, 0 is never valid
+
+// 参数错误
+// bad arguments
+pub const TEXAS_ERR_ARG: i8 = -1;
+// 句柄无效或已销毁
+// invalid or destroyed handle
+pub const TEXAS_ERR_HANDLE: i8 = -2;
+
+type TexasSlab = RwLock<HashMap<u64, Mutex<TexasCards>>>;
+
+static TEXAS_NEXT: AtomicU64 = AtomicU64::new(1);
+
+fn texas_slab() -> &'static TexasSlab {
+    static SLAB: OnceLock<TexasSlab> = OnceLock::new();
+    SLAB.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+// 读锁下找到句柄, 再锁住这一个实例调用f
+// finds the handle under the read lock, then locks only that instance for f
+fn with_texas<F: FnOnce(&mut TexasCards) -> i8>(handle: u64, f: F) -> i8 {
+    let slab = match texas_slab().read() {
+        Ok(s) => s,
+        Err(_) => return TEXAS_ERR_HANDLE,
+    };
+    let tc = match slab.get(&handle) {
+        Some(m) => m.lock(),
+        None => {
+            ffi_log(LOG_ERROR, &format!("invalid texas handle: {}", handle));
+            return TEXAS_ERR_HANDLE;
+        }
+    };
+    match tc {
+        Ok(mut tc) => f(&mut tc),
+        Err(_) => TEXAS_ERR_HANDLE,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rs_Texas_create() -> u64 {
+    let handle = TEXAS_NEXT.fetch_add(1, Ordering::Relaxed);
+    match texas_slab().write() {
+        Ok(mut s) => {
+            s.insert(handle, Mutex::new(TexasCards::new()));
+            handle
+        }
+        Err(_) => 0,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rs_Texas_destroy(handle: u64) -> i8 {
+    match texas_slab().write().map(|mut s| s.remove(&handle)) {
+        Ok(Some(_)) => 0,
+        _ => TEXAS_ERR_HANDLE,
+    }
+}
+
+// 返回值同rs_TexasCards_assign, 另外无效句柄返回TEXAS_ERR_HANDLE
+// returns as rs_TexasCards_assign, or TEXAS_ERR_HANDLE for an invalid handle
+#[no_mangle]
+pub extern "C" fn rs_Texas_assign(handle: u64, p_data: *const u16, data_len: usize) -> i8 {
+    if p_data.is_null() || data_len == 0 {
+        return TEXAS_ERR_ARG;
+    }
+    let slice = unsafe { std::slice::from_raw_parts(p_data, data_len) };
+    with_texas(handle, |tc| match tc.assign(slice) {
+        Ok(n) => n as i8,
+        Err(_) => TEXAS_ERR_ARG,
+    })
+}
+
+// 最大牌型写入p_out, 成功返回0, p_out用rs_TexasCardBuffer_free释放
+// writes the best hand to p_out and returns 0, free it with rs_TexasCardBuffer_free
+#[no_mangle]
+pub extern "C" fn rs_Texas_get_best(handle: u64, p_out: *mut TexasCardBuffer) -> i8 {
+    if p_out.is_null() {
+        return TEXAS_ERR_ARG;
+    }
+    with_texas(handle, |tc| {
+        let buf = tc.best.clone().into_boxed_slice();
+        let len = buf.len();
+        let data: *mut PokerCard = Box::into_raw(buf) as _;
+        unsafe {
+            p_out.write(TexasCardBuffer {
+                cardbuf: CardBuffer { data, len },
+                texas: tc.texas,
+                score: tc.score,
+            });
+        }
+        0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    static LOGS: Mutex<Vec<(u8, String)>> = Mutex::new(vec![]);
+    // 日志回调是全局的, 会产生日志的测试要串行执行
+    // the log callback is global, tests that log run one at a time
+    static LOG_TEST: Mutex<()> = Mutex::new(());
+
+    fn log_test_lock() -> std::sync::MutexGuard<'static, ()> {
+        LOG_TEST.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    extern "C" fn capture(level: u8, msg: *const c_char) {
+        let s = unsafe { CStr::from_ptr(msg) }.to_string_lossy().into_owned();
+        LOGS.lock().unwrap().push((level, s));
+    }
+
+    #[test]
+    fn log_callback_receives_messages() {
+        let _guard = log_test_lock();
+        let hand = [1u16, 2, 3, 4, 14, 15];
+        let p = rs_PokerCards_new();
+
+        // unset, nothing arrives
+        rs_set_log_callback(None);
+        LOGS.lock().unwrap().clear();
+        assert_eq!(rs_PokerCards_assign(p, hand.as_ptr(), hand.len()), 6);
+        assert!(LOGS.lock().unwrap().is_empty());
+
+        rs_set_log_callback(Some(capture));
+        assert_eq!(rs_PokerCards_assign(p, hand.as_ptr(), hand.len()), 6);
+        {
+            let mut logs = LOGS.lock().unwrap();
+            let debug: Vec<_> = logs.iter().filter(|l| l.0 == LOG_DEBUG).collect();
+            assert_eq!(debug.len(), 1);
+            assert!(debug[0].1.len() < LOG_BUF_LEN);
+            logs.clear();
+        }
+
+        // long messages are truncated
+        ffi_log(LOG_INFO, &"x".repeat(LOG_BUF_LEN * 2));
+        {
+            let logs = LOGS.lock().unwrap();
+            let long: Vec<_> = logs
+                .iter()
+                .filter(|l| l.0 == LOG_INFO && l.1.starts_with('x'))
+                .collect();
+            assert_eq!(long.len(), 1);
+            assert_eq!(long[0].1, "x".repeat(LOG_BUF_LEN - 1));
+        }
+
+        rs_set_log_callback(None);
+        rs_PokerCards_free(p);
+    }
+
+    #[test]
+    fn texas_handles_across_threads() {
+        let _guard = log_test_lock();
+        let royal = [1u16, 13, 12, 11, 10, 14, 17];
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    let h = rs_Texas_create();
+                    assert_ne!(h, 0);
+                    for _ in 0..200 {
+                        assert_eq!(rs_Texas_assign(h, royal.as_ptr(), royal.len()), 7);
+                        let mut out = std::mem::MaybeUninit::<TexasCardBuffer>::uninit();
+                        assert_eq!(rs_Texas_get_best(h, out.as_mut_ptr()), 0);
+                        let best = unsafe { out.assume_init() };
+                        assert_eq!(best.texas, TexasType::RoyalFlush);
+                        assert_eq!(best.cardbuf.len, 5);
+                        rs_TexasCardBuffer_free(best);
+                    }
+                    assert_eq!(rs_Texas_destroy(h), 0);
+                    h
+                })
+            })
+            .collect();
+        let bad = std::thread::spawn(move || {
+            for _ in 0..200 {
+                assert_eq!(
+                    rs_Texas_assign(u64::MAX, royal.as_ptr(), royal.len()),
+                    TEXAS_ERR_HANDLE
+                );
+                assert_eq!(rs_Texas_destroy(0), TEXAS_ERR_HANDLE);
+            }
+        });
+        let mut hs: Vec<u64> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        bad.join().unwrap();
+        hs.sort();
+        hs.dedup();
+        assert_eq!(hs.len(), 4);
+
+        // destroyed handles stay invalid
+        let mut out = std::mem::MaybeUninit::<TexasCardBuffer>::uninit();
+        assert_eq!(rs_Texas_get_best(hs[0], out.as_mut_ptr()), TEXAS_ERR_HANDLE);
+        assert_eq!(rs_Texas_destroy(hs[0]), TEXAS_ERR_HANDLE);
+        let h = rs_Texas_create();
+        assert_eq!(rs_Texas_assign(h, royal.as_ptr(), 0), TEXAS_ERR_ARG);
+        rs_Texas_destroy(h);
+    }
+}
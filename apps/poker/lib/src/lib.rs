@@ -3,6 +3,11 @@
 use crate::Suit::*;
 use std::fmt::{self, Display, Formatter};
 use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+//爬坡类玩法的牌型
+mod plays;
+pub use plays::*;
 
 //多处用到, 由花色和点数合成牌ID, 封成一个宏
 //用宏还有一个好处，可以用as强制转换类型
@@ -50,6 +55,15 @@ impl Suit {
     }
 }
 
+//顺子判断时A的点数
+//Low: A只算1(A-2-3), High: A只算14(Q-K-A), Both: 两头都可以
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum AceRank {
+    Low,
+    High,
+    Both,
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct Counter {
@@ -77,6 +91,60 @@ impl Counter {
         self.n += 1;
         self.bucket[num as usize] += one;
     }
+
+    //点数r(1~14)是否有牌, 1和14都表示A, 由ace决定是否算数
+    fn has_rank(&self, r: u8, ace: AceRank) -> bool {
+        match r {
+            1 => ace != AceRank::High && self.bucket[1] > 0,
+            14 => ace != AceRank::Low && self.bucket[1] > 0,
+            2..=13 => self.bucket[r as usize] > 0,
+            _ => false,
+        }
+    }
+
+    //所有最长连续段(起始点数, 长度), 按点数从小到大
+    pub fn all_runs(&self, ace: AceRank) -> Vec<(u8, u8)> {
+        let mut rs = vec![];
+        let mut len = 0u8;
+        for r in 1..=15u8 {
+            if self.has_rank(r, ace) {
+                len += 1;
+            } else if len > 0 {
+                rs.push((r - len, len));
+                len = 0;
+            }
+        }
+        rs
+    }
+
+    //最长的顺子(起始点数, 长度), 一样长取点数大的, 没牌返回(0, 0)
+    //Q-K-A 返回(12, 3)
+    pub fn longest_run(&self, ace: AceRank) -> (u8, u8) {
+        self.all_runs(ace)
+            .into_iter()
+            .fold((0, 0), |best, r| if r.1 >= best.1 { r } else { best })
+    }
+
+    //点数不小于rank的牌数, A按14算
+    pub fn count_at_least(&self, rank: u8) -> u8 {
+        let mut n = 0;
+        for r in 2..=13u8 {
+            if r >= rank {
+                n += self.bucket[r as usize];
+            }
+        }
+        if rank <= 14 {
+            n += self.bucket[1];
+        }
+        n
+    }
+
+    //有牌的点数, 从小到大, A是1
+    pub fn ranks_present(&self) -> Vec<u8> {
+        (1..=13u8)
+            .filter(|r| self.bucket[*r as usize] > 0)
+            .collect()
+    }
 }
 
 impl Display for Counter {
@@ -171,6 +239,19 @@ impl PokerCards {
         }
     }
 
+    //各花色(不含王)中长度不小于min_len的顺子(花色, 起始点数, 长度)
+    pub fn runs(&self, min_len: u8, ace: AceRank) -> Vec<(Suit, u8, u8)> {
+        let mut rs = vec![];
+        for c in &self.counters[0..4] {
+            for (start, len) in c.all_runs(ace) {
+                if len >= min_len {
+                    rs.push((c.t, start, len));
+                }
+            }
+        }
+        rs
+    }
+
     pub fn get_suit_cards(&self, suit: Suit) -> Vec<u16> {
         let mut vc: Vec<u16> = vec![];
         for i in 1..=13 {
@@ -238,6 +319,12 @@ impl PokerCards {
         }
         false
     }
+
+    //短格式, 空格分隔, 例如 "AS KH TD"
+    pub fn to_short(&self) -> String {
+        let vs: Vec<String> = self.cards.iter().map(|c| c.to_short()).collect();
+        vs.join(" ")
+    }
 }
 
 impl Display for PokerCards {
@@ -257,6 +344,23 @@ impl Display for PokerCards {
     }
 }
 
+//"AS KH TD", 空格或逗号分隔
+impl FromStr for PokerCards {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut vc = vec![];
+        for cs in s.split(|c: char| c.is_whitespace() || c == ',') {
+            if !cs.is_empty() {
+                vc.push(cs.parse::<PokerCard>()?);
+            }
+        }
+        let mut pcs = PokerCards::new();
+        pcs.assign_by_cards(&vc)?;
+        Ok(pcs)
+    }
+}
+
 impl fmt::Debug for PokerCards {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt::Display::fmt(self, f)
@@ -394,10 +498,68 @@ impl PokerCard {
     pub fn is_trump_card(&self) -> bool {
         self.suit == Suit::Spade || self.suit == Suit::Joker
     }
+
+    //短格式, 点数+花色字母, 例如 AS 表示黑桃A, TD 表示方片10
+    //jk 表示小王, JK 表示大王, 与 from_str 互逆
+    pub fn to_short(&self) -> String {
+        let (t, n) = self.get_suit_num();
+        if t == 4 {
+            return if n == 1 { "jk" } else { "JK" }.to_string();
+        }
+        format!("{}{}", SHORT_NUMS[n as usize], SHORT_SUITS[t as usize])
+    }
+}
+
+const SHORT_NUMS: [char; 14] = [
+    ' ', 'A', '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K',
+];
+const SHORT_SUITS: [char; 4] = ['S', 'H', 'C', 'D'];
+
+//"AS" "kh" "10D", jk小王, JK大王
+impl FromStr for PokerCard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jk" => return Self::from_u8(53),
+            "JK" => return Self::from_u8(54),
+            _ => {}
+        }
+        let err = || format!("invaild card str:{:?}", s);
+        if !s.is_ascii() {
+            return Err(err());
+        }
+        let us = s.to_uppercase();
+        let (ns, ts) = us.split_at(us.len().saturating_sub(1));
+        let n = match ns {
+            "10" => 10,
+            _ => {
+                let mut cs = ns.chars();
+                match (cs.next(), cs.next()) {
+                    (Some(c), None) => SHORT_NUMS
+                        .iter()
+                        .position(|x| *x == c)
+                        .filter(|i| *i > 0)
+                        .ok_or_else(err)?,
+                    _ => return Err(err()),
+                }
+            }
+        };
+        let t = ts
+            .chars()
+            .next()
+            .and_then(|c| SHORT_SUITS.iter().position(|x| *x == c))
+            .ok_or_else(err)?;
+        Self::from_suit_num(t as u8, n as u8)
+    }
 }
 
 impl Display for PokerCard {
+    //{:#} 输出短格式, 例如 AS
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if f.alternate() {
+            return write!(f, "{}", self.to_short());
+        }
         let tt = ["♠", "♥", "♣", "♦", "🎩"];
         let nn = [
             "", "A", "2", "3", "4", "5", "6", "7", "8", "9", "T", "J", "Q", "K",
@@ -423,4 +585,57 @@ mod tests {
         assert_eq!(n, 4);
         assert_eq!(t, 0);
     }
+
+    #[test]
+    fn short_str_round_trip() {
+        for v in 1..=54u8 {
+            let c = PokerCard::from_u8(v).unwrap();
+            let s = c.to_short();
+            assert_eq!(format!("{:#}", c), s);
+            assert_eq!(s.parse::<PokerCard>().unwrap(), c, "{}", s);
+            assert_eq!(
+                s.to_lowercase().parse::<PokerCard>().unwrap().to_u8(),
+                v.min(53)
+            );
+        }
+        assert_eq!("10h".parse::<PokerCard>().unwrap().to_short(), "TH");
+        assert!("1S".parse::<PokerCard>().is_err());
+        assert!("AX".parse::<PokerCard>().is_err());
+        assert!("Jk".parse::<PokerCard>().is_err());
+        assert!("A♠".parse::<PokerCard>().is_err());
+
+        let pcs: PokerCards = "AS KH, TD jk".parse().unwrap();
+        assert_eq!(pcs.len(), 4);
+        assert_eq!(pcs.to_short(), "AS KH TD jk");
+        assert_eq!(pcs[Suit::Heart].n, 1);
+        assert!("AS ZZ".parse::<PokerCards>().is_err());
+    }
+
+    #[test]
+    fn runs_with_ace_low_and_high() {
+        let pcs: PokerCards = "AS 2S 3S QS KS 5H".parse().unwrap();
+        let sp = &pcs[Suit::Spade];
+        assert_eq!(sp.longest_run(AceRank::Low), (1, 3));
+        assert_eq!(sp.longest_run(AceRank::High), (12, 3));
+        assert_eq!(sp.all_runs(AceRank::Both), [(1, 3), (12, 3)]);
+        assert_eq!(pcs[Suit::Club].longest_run(AceRank::Both), (0, 0));
+        assert_eq!(sp.count_at_least(12), 3);
+        assert_eq!(sp.count_at_least(3), 4);
+        assert_eq!(sp.ranks_present(), [1, 2, 3, 12, 13]);
+
+        assert_eq!(pcs.runs(3, AceRank::Low), [(Suit::Spade, 1, 3)]);
+        assert_eq!(pcs.runs(3, AceRank::High), [(Suit::Spade, 12, 3)]);
+        assert_eq!(
+            pcs.runs(2, AceRank::High),
+            [(Suit::Spade, 2, 2), (Suit::Spade, 12, 3)]
+        );
+        assert_eq!(
+            pcs.runs(1, AceRank::Both),
+            [
+                (Suit::Spade, 1, 3),
+                (Suit::Spade, 12, 3),
+                (Suit::Heart, 5, 1)
+            ]
+        );
+    }
 }
@@ -0,0 +1,224 @@
+//爬坡类玩法(锄大地, 拖拉机等)的出牌牌型
+//直接用PokerCards里维护的Counter计数, 不再重新扫描牌
+//
+//点数按爬坡大小排: 3 < 4 < ... < K < A < 2, 记为3~15
+//2不能连进顺子和连对, 王不参与这里的牌型
+//连对(Pairs)可以不同花色, 拖拉机(Tractor)要求同一花色
+
+use crate::{Counter, PokerCards, Suit};
+use std::cmp::Ordering;
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum PlayKind {
+    Single,
+    Pair,
+    Triple,
+    Straight,
+    Pairs,
+    Tractor,
+}
+
+//一手牌: 牌型, 最大一组的点数(3~15), 组数
+//顺子的组数是张数, 连对和拖拉机的组数是对数
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct Play {
+    pub kind: PlayKind,
+    pub rank: u8,
+    pub len: u8,
+    //拖拉机的花色
+    pub suit: Option<Suit>,
+}
+
+//顺子最少5张, 连对和拖拉机最少2对
+pub const MIN_STRAIGHT: u8 = 5;
+pub const MIN_PAIRS: u8 = 2;
+
+//连牌最大到A
+const TOP_RUN_RANK: u8 = 14;
+
+//爬坡点数r(3~15)的牌数
+fn climb_count(c: &Counter, r: u8) -> u8 {
+    match r {
+        3..=13 => c.bucket[r as usize],
+        14 => c.bucket[1],
+        15 => c.bucket[2],
+        _ => 0,
+    }
+}
+
+//以top结尾的len个连续点数每个都至少有n张
+fn has_run(c: &Counter, top: u8, len: u8, n: u8) -> bool {
+    len > 0
+        && top <= TOP_RUN_RANK
+        && top + 1 >= 3 + len
+        && (top + 1 - len..=top).all(|r| climb_count(c, r) >= n)
+}
+
+impl Play {
+    //同牌型同组数才能比较, 点数大的大
+    pub fn compare(&self, other: &Play) -> Option<Ordering> {
+        if self.kind != other.kind || self.len != other.len {
+            return None;
+        }
+        Some(self.rank.cmp(&other.rank))
+    }
+
+    pub fn beats(&self, other: &Play) -> bool {
+        self.compare(other) == Some(Ordering::Greater)
+    }
+
+    //识别一组牌的牌型, 不成牌型返回None
+    pub fn detect(pcs: &PokerCards) -> Option<Play> {
+        let n = pcs.len() as u8;
+        let c = &pcs.counter_all_without_joker;
+        if n == 0 || pcs[Suit::Joker].n > 0 {
+            return None;
+        }
+        let ranks: Vec<u8> = (3..=15u8).filter(|r| climb_count(c, *r) > 0).collect();
+        let top = ranks[ranks.len() - 1];
+        let same = |k: u8| ranks.iter().all(|r| climb_count(c, *r) == k);
+        let play = |kind, len| {
+            Some(Play {
+                kind,
+                rank: top,
+                len,
+                suit: None,
+            })
+        };
+        match (ranks.len(), n) {
+            (1, 1) => play(PlayKind::Single, 1),
+            (1, 2) => play(PlayKind::Pair, 1),
+            (1, 3) => play(PlayKind::Triple, 1),
+            (l, _) if l as u8 == n && n >= MIN_STRAIGHT && has_run(c, top, n, 1) => {
+                play(PlayKind::Straight, n)
+            }
+            (l, _) if same(2) && l as u8 >= MIN_PAIRS && has_run(c, top, l as u8, 2) => {
+                let l = l as u8;
+                match pcs.counters[0..4].iter().find(|s| s.n == n) {
+                    Some(s) => Some(Play {
+                        kind: PlayKind::Tractor,
+                        rank: top,
+                        len: l,
+                        suit: Some(s.t),
+                    }),
+                    None => play(PlayKind::Pairs, l),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl PokerCards {
+    //手里能出的某种牌型, len是组数(单张对子三张忽略), 从小到大
+    pub fn plays(&self, kind: PlayKind, len: u8) -> Vec<Play> {
+        let c = &self.counter_all_without_joker;
+        let mut ps = vec![];
+        let mut push = |rank, len, suit| {
+            ps.push(Play {
+                kind,
+                rank,
+                len,
+                suit,
+            })
+        };
+        match kind {
+            PlayKind::Single | PlayKind::Pair | PlayKind::Triple => {
+                let n = match kind {
+                    PlayKind::Single => 1,
+                    PlayKind::Pair => 2,
+                    _ => 3,
+                };
+                for r in 3..=15u8 {
+                    if climb_count(c, r) >= n {
+                        push(r, 1, None);
+                    }
+                }
+            }
+            PlayKind::Straight | PlayKind::Pairs => {
+                let n = if kind == PlayKind::Straight { 1 } else { 2 };
+                for top in 3..=TOP_RUN_RANK {
+                    if has_run(c, top, len, n) {
+                        push(top, len, None);
+                    }
+                }
+            }
+            PlayKind::Tractor => {
+                for s in &self.counters[0..4] {
+                    for top in 3..=TOP_RUN_RANK {
+                        if has_run(s, top, len, 2) {
+                            push(top, len, Some(s.t));
+                        }
+                    }
+                }
+                ps.sort_by_key(|p| p.rank);
+            }
+        }
+        ps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(s: &str) -> Option<Play> {
+        Play::detect(&s.parse::<PokerCards>().unwrap())
+    }
+
+    #[test]
+    fn detect_pair_straight_tractor() {
+        let pair = detect("7S 7H").unwrap();
+        assert_eq!((pair.kind, pair.rank), (PlayKind::Pair, 7));
+        assert_eq!(detect("7S 8H"), None);
+        assert!(detect("2S 2D").unwrap().beats(&detect("AS AH").unwrap()));
+
+        let st = detect("9S TH JC QD KS").unwrap();
+        assert_eq!((st.kind, st.rank, st.len), (PlayKind::Straight, 13, 5));
+        assert_eq!(detect("TS JH QC KD AS").unwrap().rank, 14);
+        // 2不能连
+        assert_eq!(detect("JS QH KC AD 2S"), None);
+        assert_eq!(detect("9S TH JC QD AS"), None);
+        assert_eq!(pair.compare(&st), None);
+
+        // 两副牌, 同花色连对是拖拉机
+        let tr = detect("5H 5H 6H 6H").unwrap();
+        assert_eq!(
+            tr,
+            Play {
+                kind: PlayKind::Tractor,
+                rank: 6,
+                len: 2,
+                suit: Some(Suit::Heart)
+            }
+        );
+        let ps = detect("5H 5S 6H 6D").unwrap();
+        assert_eq!((ps.kind, ps.rank, ps.len), (PlayKind::Pairs, 6, 2));
+        assert_eq!(detect("5H 5S 7H 7D"), None);
+        assert!(detect("7C 7C 8C 8C").unwrap().beats(&tr));
+    }
+
+    #[test]
+    fn plays_from_counters() {
+        let pcs: PokerCards = "3S 4H 5C 6D 7S 8S 8H 2C 2D".parse().unwrap();
+        let tops: Vec<u8> = pcs
+            .plays(PlayKind::Straight, 5)
+            .iter()
+            .map(|p| p.rank)
+            .collect();
+        assert_eq!(tops, [7, 8]);
+        let pairs: Vec<u8> = pcs
+            .plays(PlayKind::Pair, 1)
+            .iter()
+            .map(|p| p.rank)
+            .collect();
+        assert_eq!(pairs, [8, 15]);
+
+        let pcs: PokerCards = "9D 9D TD TD JD JD JS JS".parse().unwrap();
+        let tr = pcs.plays(PlayKind::Tractor, 3);
+        assert_eq!(tr.len(), 1);
+        assert_eq!((tr[0].rank, tr[0].suit), (11, Some(Suit::Diamond)));
+        assert_eq!(pcs.plays(PlayKind::Tractor, 2).len(), 2);
+        assert_eq!(pcs.plays(PlayKind::Pairs, 2).len(), 2);
+    }
+}
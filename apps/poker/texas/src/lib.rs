@@ -1,9 +1,7 @@
 #![allow(dead_code)]
 
-use itertools::Itertools;
 // use log::info;
-use poker_lib::{sn2poker, PokerCard};
-use std::collections::HashSet;
+use poker_lib::{sn2poker, PokerCard, Suit};
 use std::fmt::{self, Display, Formatter};
 use TexasType::*;
 
@@ -23,16 +21,22 @@ pub enum TexasType {
     RoyalFlush,
 }
 
+//支持两种用法：
+//assign一次性传入5~7张牌计算
+//或者set_hole设置2张底牌，每一轮用add_board_card追加公共牌，
+//计数结构增量维护，满5张才重新计算牌型
 #[derive(Debug)]
 pub struct TexasCards {
     pub cards: Vec<PokerCard>,
     pub best: Vec<PokerCard>,
     pub texas: TexasType,
     pub score: u64,
-    count_suit: [Vec<u8>; 4],
-    count_num: [Vec<u8>; 15],
-    order_by_count: Vec<(u8, u8)>,
-    nums_uniq: Vec<u8>,
+    //按花色和点数标记是否有这张牌, 点数中1被转成了14
+    has: [[bool; 15]; 4],
+    count_suit: [u8; 4],
+    count_num: [u8; 15],
+    //每个点数的花色, 按加入顺序, 前count_num[n]个有效
+    num_suits: [[u8; 4]; 15],
 }
 
 impl Display for TexasCards {
@@ -54,12 +58,12 @@ impl Display for TexasCards {
 impl TexasCards {
     pub fn new() -> Self {
         Self {
-            cards: vec![],
+            cards: Vec::with_capacity(7),
+            has: Default::default(),
             count_suit: Default::default(),
             count_num: Default::default(),
-            order_by_count: Default::default(),
-            nums_uniq: Default::default(),
-            best: vec![],
+            num_suits: Default::default(),
+            best: Vec::with_capacity(5),
             texas: NoCalc,
             score: 0,
         }
@@ -67,17 +71,56 @@ impl TexasCards {
 
     fn reset(&mut self) {
         self.cards.clear();
-        for i in 0..4 {
-            self.count_suit[i].clear();
+        self.has = Default::default();
+        self.count_suit = Default::default();
+        self.count_num = Default::default();
+        self.num_suits = Default::default();
+        self.best.clear();
+        self.texas = NoCalc;
+        self.score = 0;
+    }
+
+    fn parse_card(card: u16) -> Result<PokerCard, String> {
+        let c = if card < 100 {
+            PokerCard::from_u8(card as u8)?
+        } else {
+            PokerCard::from_u16(card)?
+        };
+        if c.suit == Suit::Joker {
+            return Err(format!("joker not allowed {}", card));
         }
-        for i in 0..15 {
-            self.count_num[i].clear();
+        Ok(c)
+    }
+
+    //返回花色和计数用的点数(1转成14)
+    fn suit_num(c: &PokerCard) -> (usize, usize) {
+        let (t, n) = c.get_suit_num();
+        (t as usize, if n == 1 { 14 } else { n as usize })
+    }
+
+    fn add_card(&mut self, card: u16) -> Result<(), String> {
+        let c = Self::parse_card(card)?;
+        let (t, n) = Self::suit_num(&c);
+        if self.has[t][n] {
+            return Err(format!("cards not unique {}", card));
         }
-        self.order_by_count.clear();
-        self.nums_uniq.clear();
+        self.has[t][n] = true;
+        self.count_suit[t] += 1;
+        self.num_suits[n][self.count_num[n] as usize] = t as u8;
+        self.count_num[n] += 1;
+        self.cards.push(c);
+        Ok(())
+    }
+
+    //不足5张牌时不计算，texas为NoCalc
+    fn evaluate(&mut self) {
         self.best.clear();
         self.texas = NoCalc;
         self.score = 0;
+        if self.cards.len() >= 5 {
+            self.calc_best();
+            self.calc_score();
+        }
     }
 
     pub fn assign(&mut self, cards: &[u16]) -> Result<u8, String> {
@@ -86,75 +129,74 @@ impl TexasCards {
         if ccount < 5 || ccount > 7 {
             return Err(format!("cards length {} not in [5~7]", ccount));
         }
-        if cards.iter().unique().collect::<Vec<_>>().len() != ccount {
-            return Err(format!("cards not unique {:?}", cards));
-        }
-        //按花色和点数统计，并整理出去重点数列表nums_uniq
-        for i in 0..ccount {
-            let c = if cards[i] < 100 {
-                PokerCard::from_u8(cards[i] as u8)?
-            } else {
-                PokerCard::from_u16(cards[i])?
-            };
-            let (t, n) = c.get_suit_num();
-            let cn = if n == 1 { 14 } else { n };
-            //counter中1被转成了14
-            self.count_suit[t as usize].push(cn);
-            self.count_num[cn as usize].push(t);
-            self.cards.push(c);
-        }
-        for i in 0..4 {
-            self.count_suit[i].sort();
-            self.count_suit[i].reverse();
-        }
-        for i in 0..15 {
-            if self.count_num[i].len() > 0 {
-                self.nums_uniq.push(i as u8);
-                self.order_by_count
-                    .push((self.count_num[i].len() as u8, i as u8));
+        //按花色和点数统计
+        for c in cards {
+            if let Err(e) = self.add_card(*c) {
+                self.reset();
+                return Err(e);
             }
         }
-        self.order_by_count.sort_by_key(|x| x.0);
-        self.order_by_count.reverse();
 
         //计算牌型和分数
-        self.calc_best();
-        self.calc_score();
+        self.evaluate();
 
         // info!("{}", self);
         Ok(self.cards.len() as u8)
     }
 
-    //返回0表示无顺子,14表示TJQKA,5表示A2345
-    //其他返回顺子最大牌点
-    fn find_max_seq(&self, nums: &[u8]) -> u8 {
-        //去重排序
-        //注意送进来的同花色和全局两种情况都已经去重了
-        //这里的unique可以省略
-        let ns = nums.iter().sorted().unique().collect::<Vec<_>>();
+    //设置底牌，清空公共牌
+    pub fn set_hole(&mut self, hole: &[u16; 2]) -> Result<(), String> {
+        self.reset();
+        for c in hole {
+            if let Err(e) = self.add_card(*c) {
+                self.reset();
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
 
-        //用索引-牌点进行分组,得到所有的连续牌
-        //例:[1,3,4,5,7,8,9,T,J] -> 1 345 789TJ
-        let s = ns
-            .iter()
-            .enumerate()
-            .group_by(|i| (*i).0 as i32 - **((*i).1) as i32);
+    //追加一张公共牌，满5张后重新计算牌型
+    pub fn add_board_card(&mut self, card: u16) -> Result<(), String> {
+        if self.cards.len() < 2 {
+            return Err(String::from("hole cards not set"));
+        }
+        if self.cards.len() >= 7 {
+            return Err(String::from("board already has 5 cards"));
+        }
+        self.add_card(card)?;
+        self.evaluate();
+        Ok(())
+    }
 
-        //遍历找到最大的5张顺
-        let mut smax: u8 = 0;
-        for (_, g) in &s {
-            let ps = g.map(|x| x.1).collect::<Vec<_>>();
-            let maxp = **ps[ps.len() - 1];
-            let maxn = *ns[ns.len() - 1];
-            //5432A
-            if ps.len() == 4 && maxp == 5 && maxn == 14 {
-                return 5;
-            }
-            if ps.len() >= 5 && maxp > smax {
-                smax = maxp;
+    //撤销最后一张公共牌，底牌不会被移除
+    pub fn remove_last_board_card(&mut self) {
+        if self.cards.len() <= 2 {
+            return;
+        }
+        if let Some(c) = self.cards.pop() {
+            let (t, n) = Self::suit_num(&c);
+            self.has[t][n] = false;
+            self.count_suit[t] -= 1;
+            self.count_num[n] -= 1;
+        }
+        self.evaluate();
+    }
+
+    //返回0表示无顺子,14表示TJQKA,5表示A2345
+    //其他返回顺子最大牌点
+    fn find_max_seq(nums: &[bool; 15]) -> u8 {
+        //从大到小找连续5张
+        for top in (6..15).rev() {
+            if (top - 4..=top).all(|n| nums[n]) {
+                return top as u8;
             }
         }
-        smax
+        //5432A
+        if (2..=5).all(|n| nums[n]) && nums[14] {
+            return 5;
+        }
+        0
     }
 
     fn push_best(&mut self, color: u8, num: u8) {
@@ -164,23 +206,18 @@ impl TexasCards {
         }
     }
 
-    //按从大到小补充剩下的牌，凑够5张best
+    //按从大到小补充剩下的牌，凑够5张best, 同点数按加入顺序
     fn fill_best(&mut self) {
-        let cb: HashSet<u8> = HashSet::from_iter(self.best.iter().map(|x| x.to_u8()));
-        let mut fill_count = 5 - self.best.len();
-        for i in 0..15 {
-            for color in &self.count_num[15 - i - 1] {
-                match sn2poker!(*color, 15 - i - 1) {
-                    Ok(uc) => {
-                        if !cb.contains(&uc.to_u8()) {
-                            self.best.push(uc);
-                            fill_count -= 1;
-                            if fill_count == 0 {
-                                return;
-                            }
+        for n in (2..15u8).rev() {
+            for i in 0..self.count_num[n as usize] as usize {
+                let t = self.num_suits[n as usize][i];
+                if let Ok(uc) = sn2poker!(t, n) {
+                    if !self.best.contains(&uc) {
+                        self.best.push(uc);
+                        if self.best.len() == 5 {
+                            return;
                         }
                     }
-                    Err(_) => (),
                 }
             }
         }
@@ -192,18 +229,33 @@ impl TexasCards {
         for b in 0..5 {
             let (s, bn) = self.best[b].get_suit_num();
             let n = if bn == 1 { 14 } else { bn };
-            let nc = n as u64 + (((3 - s) as u64) << 4); 
+            let nc = n as u64 + (((3 - s) as u64) << 4);
             // println!("{} {}#####...{:6b}", s, n, nc);
             self.score += nc << ((4 - b) * 6);
         }
     }
 
+    //按数量从多到少排序的点数，数量相同时点数大的在前
+    fn order_by_count(&self) -> [(u8, u8); 2] {
+        let mut order = [(0u8, 0u8); 2];
+        for n in (2..15u8).rev() {
+            let c = self.count_num[n as usize];
+            if c > order[0].0 {
+                order[1] = order[0];
+                order[0] = (c, n);
+            } else if c > order[1].0 {
+                order[1] = (c, n);
+            }
+        }
+        order
+    }
+
     //分析牌型，填充best...
     pub fn calc_best(&mut self) {
         for suit in 0..4 {
             let i = suit as usize;
-            if self.count_suit[i].len() >= 5 {
-                let smax = self.find_max_seq(&self.count_suit[i]);
+            if self.count_suit[i] >= 5 {
+                let smax = Self::find_max_seq(&self.has[i]);
                 if smax == 14 {
                     self.texas = RoyalFlush;
                     for b in 0..5 {
@@ -218,70 +270,58 @@ impl TexasCards {
                     return;
                 } else {
                     self.texas = Flush;
-                    for b in 0..5 {
-                        self.push_best(suit, self.count_suit[i][b]);
+                    for n in (2..15u8).rev() {
+                        if self.has[i][n as usize] && self.best.len() < 5 {
+                            self.push_best(suit, n);
+                        }
                     }
                     return;
                 }
             }
         }
-        if self.order_by_count[0].0 == 4 {
+        let order_by_count = self.order_by_count();
+        if order_by_count[0].0 == 4 {
             self.texas = Four;
             for suit in 0..4 {
-                self.push_best(suit, self.order_by_count[0].1);
+                self.push_best(suit, order_by_count[0].1);
             }
             self.fill_best();
             return;
-        } else if self.order_by_count[0].0 == 3 && self.order_by_count[1].0 >= 2 {
+        } else if order_by_count[0].0 == 3 && order_by_count[1].0 >= 2 {
             self.texas = FullHouse;
-            let a = self.order_by_count[0].1;
-            let b = self.order_by_count[1].1;
-            for i in 0..3 {
-                self.push_best(self.count_num[a as usize][i], a);
-            }
-            for i in 0..2 {
-                self.push_best(self.count_num[b as usize][i], b);
-            }
+            let a = order_by_count[0].1;
+            let b = order_by_count[1].1;
+            self.push_suits(a, 3);
+            self.push_suits(b, 2);
             return;
         }
-        let smax = self.find_max_seq(&self.nums_uniq);
+        let nums: [bool; 15] = std::array::from_fn(|n| self.count_num[n] > 0);
+        let smax = Self::find_max_seq(&nums);
         if smax > 0 {
             self.texas = Straight;
             for b in 0..5 {
                 let n = smax - b;
                 let bn = if n == 1 { 14 } else { n };
-                self.push_best(self.count_num[bn as usize][0], bn);
+                self.push_suits(bn, 1);
             }
             return;
         }
-        if self.order_by_count[0].0 == 3 {
+        if order_by_count[0].0 == 3 {
             self.texas = Three;
-            let a = self.order_by_count[0].1;
-            for i in 0..3 {
-                self.push_best(self.count_num[a as usize][i], a);
-            }
+            self.push_suits(order_by_count[0].1, 3);
             self.fill_best();
             return;
         }
-        if self.order_by_count[0].0 == 2 && self.order_by_count[1].0 == 2 {
+        if order_by_count[0].0 == 2 && order_by_count[1].0 == 2 {
             self.texas = TwoPair;
-            let a = self.order_by_count[0].1;
-            let b = self.order_by_count[1].1;
-            for i in 0..2 {
-                self.push_best(self.count_num[a as usize][i], a);
-            }
-            for i in 0..2 {
-                self.push_best(self.count_num[b as usize][i], b);
-            }
+            self.push_suits(order_by_count[0].1, 2);
+            self.push_suits(order_by_count[1].1, 2);
             self.fill_best();
             return;
         }
-        if self.order_by_count[0].0 == 2 {
+        if order_by_count[0].0 == 2 {
             self.texas = OnePair;
-            let a = self.order_by_count[0].1;
-            for i in 0..2 {
-                self.push_best(self.count_num[a as usize][i], a);
-            }
+            self.push_suits(order_by_count[0].1, 2);
             self.fill_best();
             return;
         }
@@ -289,6 +329,14 @@ impl TexasCards {
         self.fill_best();
         // return;
     }
+
+    //把某点数的前count张牌放入best，按加入顺序
+    fn push_suits(&mut self, num: u8, count: usize) {
+        let n = num as usize;
+        for i in 0..count.min(self.count_num[n] as usize) {
+            self.push_best(self.num_suits[n][i], num);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -344,4 +392,67 @@ mod tests {
             .unwrap();
         assert_eq!(tc.texas, HighCard);
     }
+
+    //简单的lcg洗牌，发7张不重复的牌
+    fn deal(seed: &mut u64) -> [u16; 7] {
+        let mut deck: Vec<u16> = (1..=52).collect();
+        let mut ret = [0u16; 7];
+        for i in 0..7 {
+            *seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let j = ((*seed >> 33) as usize) % deck.len();
+            ret[i] = deck.swap_remove(j);
+        }
+        ret
+    }
+
+    fn same(a: &TexasCards, b: &TexasCards) {
+        assert_eq!(a.texas, b.texas);
+        assert_eq!(a.score, b.score);
+        assert!(a.best == b.best);
+    }
+
+    #[test]
+    fn incremental_equals_assign() {
+        let mut seed = 20240601u64;
+        let mut inc = TexasCards::new();
+        let mut batch = TexasCards::new();
+        for _ in 0..1000 {
+            let d = deal(&mut seed);
+            inc.set_hole(&[d[0], d[1]]).unwrap();
+            for i in 2..7 {
+                inc.add_board_card(d[i]).unwrap();
+                if i < 4 {
+                    assert_eq!(inc.texas, NoCalc);
+                    continue;
+                }
+                batch.assign(&d[..i + 1]).unwrap();
+                same(&inc, &batch);
+            }
+            inc.remove_last_board_card();
+            batch.assign(&d[..6]).unwrap();
+            same(&inc, &batch);
+        }
+    }
+
+    #[test]
+    fn incremental_errors() {
+        let mut tc = TexasCards::new();
+        assert!(tc.add_board_card(5).is_err());
+        tc.set_hole(&[1, 14]).unwrap();
+        assert!(tc.add_board_card(14).is_err());
+        assert!(tc.add_board_card(53).is_err());
+        for c in [2, 3, 4, 5, 6] {
+            tc.add_board_card(c).unwrap();
+        }
+        assert!(tc.add_board_card(7).is_err());
+        assert_eq!(tc.texas, StraightFlush);
+        //底牌不会被撤销
+        for _ in 0..10 {
+            tc.remove_last_board_card();
+        }
+        assert_eq!(tc.cards.len(), 2);
+        assert_eq!(tc.texas, NoCalc);
+    }
 }
@@ -10,7 +10,6 @@ pub struct WasmGinRummy {
     webbuf: Vec<u8>,
 }
 
-#[cfg(target_arch = "wasm32")]
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 impl WasmGinRummy {
     // js调用创建game结构
@@ -71,12 +70,61 @@ impl WasmGinRummy {
         }
     }
 
+    /// @deprecated the view built on this pointer is invalid after the next
+    /// call that writes the buffer, use web_buffer_bytes or take_buffer
     pub fn web_buffer_len(&self) -> usize {
         self.webbuf.len()
     }
 
+    /// @deprecated the view built on this pointer is invalid after the next
+    /// call that writes the buffer, use web_buffer_bytes or take_buffer
     pub fn web_buffer(&self) -> *const u8 {
         self.webbuf.as_slice().as_ptr()
     }
+
+    /// copy of the current buffer, returned to js as a fresh Uint8Array
+    pub fn web_buffer_bytes(&self) -> Vec<u8> {
+        self.webbuf.clone()
+    }
+
+    /// copies the buffer into a js owned Uint8Array and clears it,
+    /// the array stays valid whatever happens to wasm memory later
+    pub fn take_buffer(&mut self) -> js_sys::Uint8Array {
+        let arr = js_sys::Uint8Array::from(self.webbuf.as_slice());
+        self.webbuf.clear();
+        arr
+    }
+
+    /// same as sort, but returns the buffer content
+    pub fn sort_bytes(&mut self) -> Vec<u8> {
+        self.sort();
+        self.web_buffer_bytes()
+    }
+
+    /// same as assign, but returns the buffer content
+    pub fn assign_bytes(&mut self, arr: js_sys::Uint16Array, freeze: u8) -> Vec<u8> {
+        self.assign(arr, freeze);
+        self.web_buffer_bytes()
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_bytes_same_as_legacy_buffer() {
+        let mut w = WasmGinRummy::new();
+        // assign takes a js array, fill the cards through the lib type
+        let hand: [u16; 10] = [1, 40, 2, 13, 14, 26, 27, 31, 45, 3];
+        w.gcs.assign(&hand, false).unwrap();
+        let bytes = w.sort_bytes();
+        // suit length, 10 cards, number length, 10 cards
+        assert_eq!(bytes.len(), 22);
+        assert_eq!(bytes[0], 10);
+        assert_eq!(bytes[11], 10);
+        let legacy =
+            unsafe { std::slice::from_raw_parts(w.web_buffer(), w.web_buffer_len()).to_vec() };
+        assert_eq!(bytes, legacy);
+    }
+}
@@ -0,0 +1,360 @@
+//
+// implement core algorithm...
+//
+// GameState is the snake rule without rendering, a Controller decides
+// the next direction from it, so the game can be played by an autopilot
+// or run headless by benchmark.
+//
+// GreedyFood walks the shortest path to food avoiding the body,
+// Hamiltonian follows a fixed cycle through every cell, it is slow
+// but never dies, it needs an even width or height.
+//
+
+use rust_pixel::{algorithm::astar::a_star, util::Dir, util::Rand};
+
+/// (x, y)
+pub type Pos = (usize, usize);
+
+pub fn dir_delta(d: Dir) -> (i32, i32) {
+    match d {
+        Dir::Up => (0, -1),
+        Dir::Down => (0, 1),
+        Dir::Left => (-1, 0),
+        Dir::Right => (1, 0),
+        _ => (0, 0),
+    }
+}
+
+pub fn opposite(d: Dir) -> Dir {
+    match d {
+        Dir::Up => Dir::Down,
+        Dir::Down => Dir::Up,
+        Dir::Left => Dir::Right,
+        Dir::Right => Dir::Left,
+        other => other,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameState {
+    pub width: usize,
+    pub height: usize,
+    /// head first
+    pub body: Vec<Pos>,
+    pub food: Pos,
+    pub dir: Dir,
+    pub score: u32,
+    pub steps: u32,
+    pub alive: bool,
+    /// the board is full
+    pub won: bool,
+    /// the tail cell kills too, as in the snake game,
+    /// by default it is free because the tail moves away
+    pub tail_kills: bool,
+}
+
+impl GameState {
+    /// snake of length 1 at center, food at a random free cell
+    pub fn new(width: usize, height: usize, rand: &mut Rand) -> Self {
+        let mut s = Self {
+            width,
+            height,
+            body: vec![(width / 2, height / 2)],
+            food: (0, 0),
+            dir: Dir::Down,
+            score: 0,
+            steps: 0,
+            alive: true,
+            won: false,
+            tail_kills: false,
+        };
+        s.place_food(rand);
+        s
+    }
+
+    pub fn head(&self) -> Pos {
+        self.body[0]
+    }
+
+    /// next head position, None if out of board
+    pub fn next_pos(&self, p: Pos, d: Dir) -> Option<Pos> {
+        let (dx, dy) = dir_delta(d);
+        let x = p.0 as i32 + dx;
+        let y = p.1 as i32 + dy;
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+
+    /// moving to p next step kills the snake,
+    /// the tail cell is free unless p is food or tail_kills is set
+    pub fn is_deadly(&self, p: Pos) -> bool {
+        let n = if p == self.food || self.tail_kills {
+            self.body.len()
+        } else {
+            self.body.len() - 1
+        };
+        self.body[..n].contains(&p)
+    }
+
+    fn place_food(&mut self, rand: &mut Rand) {
+        let free: Vec<Pos> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|p| !self.body.contains(p))
+            .collect();
+        if free.is_empty() {
+            self.won = true;
+            self.alive = false;
+            return;
+        }
+        self.food = free[rand.rand() as usize % free.len()];
+    }
+
+    /// moves one step, turning back is ignored when longer than 1,
+    /// returns false when the game is over
+    pub fn step(&mut self, d: Dir, rand: &mut Rand) -> bool {
+        if !self.alive {
+            return false;
+        }
+        let d = if self.body.len() > 1 && d == opposite(self.dir) {
+            self.dir
+        } else {
+            d
+        };
+        self.steps += 1;
+        let np = match self.next_pos(self.head(), d) {
+            Some(p) if !self.is_deadly(p) => p,
+            _ => {
+                self.alive = false;
+                return false;
+            }
+        };
+        self.dir = d;
+        self.body.insert(0, np);
+        if np == self.food {
+            self.score += 1;
+            self.place_food(rand);
+        } else {
+            self.body.pop();
+        }
+        self.alive
+    }
+}
+
+pub trait Controller {
+    fn decide(&mut self, s: &GameState) -> Dir;
+}
+
+/// shortest path to food by astar, or any safe move if there is no path
+#[derive(Default)]
+pub struct GreedyFood {}
+
+impl Controller for GreedyFood {
+    fn decide(&mut self, s: &GameState) -> Dir {
+        // astar map is map[y][x], 1 is passable
+        let mut map = vec![vec![1u8; s.width]; s.height];
+        for p in &s.body {
+            if s.is_deadly(*p) {
+                map[p.1][p.0] = 0;
+            }
+        }
+        let (hx, hy) = s.head();
+        if let Some(path) = a_star(&map, (hy, hx), (s.food.1, s.food.0), |v| v != 0) {
+            if path.len() > 1 {
+                let (ny, nx) = path[1];
+                for d in [Dir::Up, Dir::Down, Dir::Left, Dir::Right] {
+                    if s.next_pos(s.head(), d) == Some((nx, ny)) {
+                        return d;
+                    }
+                }
+            }
+        }
+        [s.dir, Dir::Up, Dir::Right, Dir::Down, Dir::Left]
+            .into_iter()
+            .find(|d| {
+                (s.body.len() == 1 || *d != opposite(s.dir))
+                    && s.next_pos(s.head(), *d).is_some_and(|p| !s.is_deadly(p))
+            })
+            .unwrap_or(s.dir)
+    }
+}
+
+/// follows a cycle visiting every cell once
+pub struct Hamiltonian {
+    width: usize,
+    // index in the cycle of every cell, order[y][x]
+    order: Vec<Vec<usize>>,
+    cycle: Vec<Pos>,
+}
+
+impl Hamiltonian {
+    /// None if both width and height are odd or smaller than 2
+    pub fn new(width: usize, height: usize) -> Option<Self> {
+        if width < 2 || height < 2 {
+            return None;
+        }
+        let cycle: Vec<Pos> = if height.is_multiple_of(2) {
+            Self::cycle_rows(width, height)
+        } else if width.is_multiple_of(2) {
+            Self::cycle_rows(height, width)
+                .into_iter()
+                .map(|(x, y)| (y, x))
+                .collect()
+        } else {
+            return None;
+        };
+        let mut order = vec![vec![0; width]; height];
+        for (i, p) in cycle.iter().enumerate() {
+            order[p.1][p.0] = i;
+        }
+        Some(Self {
+            width,
+            order,
+            cycle,
+        })
+    }
+
+    // zigzag rows on columns 1.., back up by column 0, height is even
+    fn cycle_rows(width: usize, height: usize) -> Vec<Pos> {
+        let mut c = vec![];
+        for y in 0..height {
+            if y % 2 == 0 {
+                let start = if y == 0 { 0 } else { 1 };
+                c.extend((start..width).map(|x| (x, y)));
+            } else {
+                c.extend((1..width).rev().map(|x| (x, y)));
+            }
+        }
+        c.extend((1..height).rev().map(|y| (0, y)));
+        c
+    }
+}
+
+impl Controller for Hamiltonian {
+    fn decide(&mut self, s: &GameState) -> Dir {
+        let (hx, hy) = s.head();
+        if s.width != self.width {
+            return s.dir;
+        }
+        let next = self.cycle[(self.order[hy][hx] + 1) % self.cycle.len()];
+        [Dir::Up, Dir::Down, Dir::Left, Dir::Right]
+            .into_iter()
+            .find(|d| s.next_pos(s.head(), *d) == Some(next))
+            .unwrap_or(s.dir)
+    }
+}
+
+/// autopilot setting of the snake model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AutoPilot {
+    #[default]
+    Off,
+    GreedyFood,
+    Hamiltonian,
+}
+
+impl AutoPilot {
+    pub fn controller(&self, width: usize, height: usize) -> Option<Box<dyn Controller>> {
+        match self {
+            AutoPilot::Off => None,
+            AutoPilot::GreedyFood => Some(Box::<GreedyFood>::default()),
+            AutoPilot::Hamiltonian => {
+                Hamiltonian::new(width, height).map(|h| Box::new(h) as Box<dyn Controller>)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BenchResult {
+    pub games: u32,
+    pub avg_score: f64,
+    pub avg_steps: f64,
+    pub wins: u32,
+}
+
+/// runs games without rendering, a game also stops after max_steps
+pub fn benchmark(
+    c: &mut dyn Controller,
+    games: u32,
+    width: usize,
+    height: usize,
+    max_steps: u32,
+    seed: u64,
+) -> BenchResult {
+    let mut rand = Rand::new();
+    rand.srand(seed);
+    let mut r = BenchResult {
+        games,
+        ..Default::default()
+    };
+    for _ in 0..games {
+        let mut s = GameState::new(width, height, &mut rand);
+        while s.alive && s.steps < max_steps {
+            let d = c.decide(&s);
+            s.step(d, &mut rand);
+        }
+        r.avg_score += s.score as f64;
+        r.avg_steps += s.steps as f64;
+        if s.won {
+            r.wins += 1;
+        }
+    }
+    if games > 0 {
+        r.avg_score /= games as f64;
+        r.avg_steps /= games as f64;
+    }
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn greedy_finishes_small_board() {
+        let r = benchmark(&mut GreedyFood::default(), 5, 6, 6, 5000, 7);
+        assert_eq!(r.games, 5);
+        assert!(r.avg_score > 3.0);
+        assert!(r.avg_steps <= 5000.0);
+    }
+
+    #[test]
+    fn tail_kills_rule() {
+        let mut rand = Rand::new();
+        rand.srand(3);
+        let mut s = GameState::new(4, 4, &mut rand);
+        // head at (1,1) moved left, the tail (1,2) is right below it
+        s.body = vec![(1, 1), (2, 1), (2, 2), (1, 2)];
+        s.dir = Dir::Left;
+        s.food = (3, 3);
+        assert!(!s.is_deadly((1, 2)));
+        let mut free = s.clone();
+        assert!(free.step(Dir::Down, &mut rand));
+        assert_eq!(free.head(), (1, 2));
+
+        s.tail_kills = true;
+        assert!(s.is_deadly((1, 2)));
+        assert_ne!(GreedyFood::default().decide(&s), Dir::Down);
+        assert!(!s.step(Dir::Down, &mut rand));
+        assert!(!s.alive);
+    }
+
+    #[test]
+    fn hamiltonian_never_dies() {
+        let mut h = Hamiltonian::new(8, 8).unwrap();
+        let mut rand = Rand::new();
+        rand.srand(1);
+        let mut s = GameState::new(8, 8, &mut rand);
+        while s.alive && s.steps < 64 * 64 * 2 {
+            let d = h.decide(&s);
+            s.step(d, &mut rand);
+        }
+        // only leaves the loop by filling the board
+        assert!(s.won);
+        assert_eq!(s.body.len(), 64);
+        assert!(Hamiltonian::new(5, 5).is_none());
+        assert!(Hamiltonian::new(6, 5).is_some());
+    }
+}
@@ -7,6 +7,7 @@ use rust_pixel::{
     game::Model,
     util::{Dir, ParticleSystem, ParticleSystemInfo, PointU16},
 };
+use snake_lib::{opposite, AutoPilot, Controller, GameState};
 use std::f64::consts::PI;
 
 pub const SNAKEW: usize = 60;
@@ -26,6 +27,9 @@ pub struct SnakeModel {
     pub dir: Dir,
     pub count: f64,
     pub timeout_auto: f32,
+    /// plays by itself when set, 'p' cycles the modes, see set_autopilot
+    pub autopilot_mode: AutoPilot,
+    pub autopilot: Option<Box<dyn Controller>>,
 }
 
 impl SnakeModel {
@@ -66,6 +70,44 @@ impl SnakeModel {
             dir: Dir::Down,
             count: 0.0,
             timeout_auto: 0.0,
+            autopilot_mode: AutoPilot::Off,
+            autopilot: None,
+        }
+    }
+
+    pub fn set_autopilot(&mut self, ap: AutoPilot) {
+        self.autopilot_mode = ap;
+        self.autopilot = ap.controller(SNAKEW, SNAKEH);
+    }
+
+    /// Off -> GreedyFood -> Hamiltonian -> Off
+    pub fn next_autopilot(&mut self) {
+        let ap = match self.autopilot_mode {
+            AutoPilot::Off => AutoPilot::GreedyFood,
+            AutoPilot::GreedyFood => AutoPilot::Hamiltonian,
+            AutoPilot::Hamiltonian => AutoPilot::Off,
+        };
+        self.set_autopilot(ap);
+    }
+
+    /// snapshot for the autopilot controller, with the game rule that
+    /// moving onto the tail kills
+    pub fn game_state(&self) -> GameState {
+        GameState {
+            width: SNAKEW,
+            height: SNAKEH,
+            body: self
+                .body
+                .iter()
+                .map(|p| (p.x as usize, p.y as usize))
+                .collect(),
+            food: (self.seed.x as usize, self.seed.y as usize),
+            dir: self.dir,
+            score: self.body.len().saturating_sub(1) as u32,
+            steps: 0,
+            alive: true,
+            won: false,
+            tail_kills: true,
         }
     }
 
@@ -194,6 +236,7 @@ impl Model for SnakeModel {
                     KeyCode::Char('a') => d = Some(Dir::Left),
                     KeyCode::Char('s') => d = Some(Dir::Down),
                     KeyCode::Char('d') => d = Some(Dir::Right),
+                    KeyCode::Char('p') => self.next_autopilot(),
                     _ => {}
                 }
                 if d.is_some() {
@@ -214,7 +257,14 @@ impl Model for SnakeModel {
             .move_to(10.0 + 5.0 * self.count, 10.0 + 5.0 * self.count, false);
         if self.timeout_auto > 0.4 {
             self.timeout_auto = 0.0;
-            self.act(self.dir, context);
+            let st = self.game_state();
+            // act ignores turning back, keep going instead
+            let d = match self.autopilot.as_mut() {
+                Some(c) => c.decide(&st),
+                None => self.dir,
+            };
+            let d = if d == opposite(self.dir) { self.dir } else { d };
+            self.act(d, context);
         } else {
             self.timeout_auto += dt;
         }
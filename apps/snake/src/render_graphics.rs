@@ -1,6 +1,6 @@
 use crate::model::{SnakeModel, SNAKEH, SNAKEW};
 #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
-use rust_pixel::{asset::AssetType, asset2sprite};
+use rust_pixel::{asset::AssetType, asset2sprite, render::sprite::Motion, util::Easing};
 use rust_pixel::{
     context::Context,
     event::{event_check, event_register, timer_fire, timer_register},
@@ -174,10 +174,11 @@ impl Render for SnakeRender {
                 1,
                 1
             );
-            if context.stage % 8 == 0 {
-                let pl = self.panel.get_pixel_sprite("PL1");
-                pl.content.area.x += 2;
-                pl.content.area.y += 2;
+            // drifts down right, bounces back, and again
+            let pl = self.panel.get_pixel_sprite("PL1");
+            if pl.motion().is_none() || pl.motion_finished() {
+                let m = Motion::pos((4.0, 6.0), (84.0, 86.0), 4.0, Easing::Linear);
+                pl.set_motion(m.then((4.0, 6.0), 1.0, Easing::Bounce));
             }
         }
         self.draw_movie(context, model);
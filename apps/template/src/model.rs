@@ -2,8 +2,9 @@ use keyframe::{functions::*, AnimationSequence};
 use rust_pixel::{
     algorithm::draw_bezier_curves,
     context::Context,
-    event::{event_emit, Event, KeyCode},
-    game::Model,
+    event::{event_check, event_emit, event_register, Event, KeyCode},
+    game::{Model, Scene, SceneOp, SceneStack},
+    render::panel::Panel,
     util::{ParticleSystem, ParticleSystemInfo, PointF32},
 };
 // use log::info;
@@ -19,10 +20,72 @@ pub const TEMPLATEW: u16 = 80;
 pub const TEMPLATEH: u16 = 40;
 
 #[repr(u8)]
-enum TemplateState {
+pub enum TemplateState {
+    Title,
     Normal,
 }
 
+// title scene, also pushed over the play scene as a pause menu
+pub struct TitleScene {
+    pub paused: bool,
+}
+
+impl Scene for TitleScene {
+    fn init(&mut self, ctx: &mut Context) {
+        ctx.state = TemplateState::Title as u8;
+        event_emit("Template.RedrawMsg");
+    }
+
+    fn update(&mut self, ctx: &mut Context, _dt: f32) -> SceneOp {
+        let pressed = ctx.input_events.iter().any(|e| matches!(e, Event::Key(_)));
+        ctx.input_events.clear();
+        if !pressed {
+            return SceneOp::None;
+        }
+        if self.paused {
+            SceneOp::Pop
+        } else {
+            SceneOp::Replace(Box::new(PlayScene {}))
+        }
+    }
+
+    fn draw(&mut self, _ctx: &mut Context, _panel: &mut Panel, _dt: f32) {}
+}
+
+// play scene, cards are kept in TemplateModel and changed by events
+pub struct PlayScene {}
+
+impl Scene for PlayScene {
+    fn init(&mut self, ctx: &mut Context) {
+        ctx.state = TemplateState::Normal as u8;
+        event_emit("Template.RedrawMsg");
+        event_emit("Template.RedrawTile");
+    }
+
+    fn update(&mut self, ctx: &mut Context, _dt: f32) -> SceneOp {
+        let es = ctx.input_events.clone();
+        for e in &es {
+            if let Event::Key(key) = e {
+                match key.code {
+                    KeyCode::Char('s') => event_emit("Template.Shuffle"),
+                    KeyCode::Char('n') => event_emit("Template.Next"),
+                    KeyCode::Char('p') => ctx.push_scene(Box::new(TitleScene { paused: true })),
+                    _ => {}
+                }
+            }
+        }
+        ctx.input_events.clear();
+        SceneOp::None
+    }
+
+    fn draw(&mut self, _ctx: &mut Context, _panel: &mut Panel, _dt: f32) {}
+
+    fn on_resume(&mut self, ctx: &mut Context) {
+        ctx.state = TemplateState::Normal as u8;
+        event_emit("Template.RedrawMsg");
+    }
+}
+
 pub struct TemplateModel {
     // TemplateData defined in template/lib/src/lib.rs
     pub data: TemplateData,
@@ -30,6 +93,8 @@ pub struct TemplateModel {
     pub bezier: AnimationSequence<PointF32>,
     pub count: f64,
     pub card: u8,
+    // input goes to the top scene, particles keep moving under all scenes
+    pub scenes: SceneStack,
 }
 
 impl TemplateModel {
@@ -64,18 +129,23 @@ impl TemplateModel {
         // create particle system
         let pats = ParticleSystem::new(particle_system_info);
 
+        // card events emitted by PlayScene
+        event_register("Template.Shuffle", "shuffle");
+        event_register("Template.Next", "next");
+
         Self {
             pats,
             data: TemplateData::new(),
             bezier: AnimationSequence::new(),
             count: 0.0,
             card: 0,
+            scenes: SceneStack::new(),
         }
     }
 }
 
 impl Model for TemplateModel {
-    fn init(&mut self, _context: &mut Context) {
+    fn init(&mut self, context: &mut Context) {
         let in_points = [
             PointF32 { x: 10.0, y: 30.0 },
             PointF32 { x: 210.0, y: 450.0 },
@@ -107,34 +177,14 @@ impl Model for TemplateModel {
         // Fire particle system...
         self.pats.fire_at(10.0, 10.0);
 
-        // Emit event...
-        event_emit("Template.RedrawTile");
+        // Start with the title scene...
+        self.scenes
+            .push(context, Box::new(TitleScene { paused: false }));
     }
 
-    fn handle_input(&mut self, context: &mut Context, _dt: f32) {
-        let es = context.input_events.clone();
-        for e in &es {
-            match e {
-                Event::Key(key) => match key.code {
-                    KeyCode::Char('s') => {
-                        self.data.shuffle();
-                        self.card = self.data.next();
-                        // Emit event...
-                        event_emit("Template.RedrawTile");
-                    }
-                    KeyCode::Char('n') => {
-                        self.card = self.data.next();
-                        // Emit event...
-                        event_emit("Template.RedrawTile");
-                    }
-                    _ => {
-                        context.state = TemplateState::Normal as u8;
-                    }
-                },
-                _ => {}
-            }
-        }
-        context.input_events.clear();
+    fn handle_input(&mut self, context: &mut Context, dt: f32) {
+        // route input to the top scene and apply scene ops
+        self.scenes.update(context, dt);
     }
 
     fn handle_auto(&mut self, _context: &mut Context, dt: f32) {
@@ -147,6 +197,18 @@ impl Model for TemplateModel {
             .move_to(10.0 + 2.0 * self.count, 10.0 + 2.0 * self.count, false);
     }
 
-    fn handle_event(&mut self, _context: &mut Context, _dt: f32) {}
+    fn handle_event(&mut self, _context: &mut Context, _dt: f32) {
+        if event_check("Template.Shuffle", "shuffle") {
+            self.data.shuffle();
+            self.card = self.data.next();
+            // Emit event...
+            event_emit("Template.RedrawTile");
+        }
+        if event_check("Template.Next", "next") {
+            self.card = self.data.next();
+            // Emit event...
+            event_emit("Template.RedrawTile");
+        }
+    }
     fn handle_timer(&mut self, _context: &mut Context, _dt: f32) {}
 }
@@ -1,6 +1,6 @@
 #![allow(unused_imports)]
 #![allow(unused_variables)]
-use crate::model::{TemplateModel, CARDH, CARDW, TEMPLATEH, TEMPLATEW};
+use crate::model::{TemplateModel, TemplateState, CARDH, CARDW, TEMPLATEH, TEMPLATEW};
 // use log::info;
 use rust_pixel::{
     asset::AssetType,
@@ -28,12 +28,8 @@ impl TemplateRender {
 
         // msg...
         let adj = 2u16;
-        let mut msg1 = Sprite::new(0 + adj, 14, 40, 1);
-        msg1.set_default_str("press N for next card");
-        panel.add_sprite(msg1, "msg1");
-        let mut msg2 = Sprite::new(40 + adj, 14, 40, 1);
-        msg2.set_default_str("press S shuffle cards");
-        panel.add_sprite(msg2, "msg2");
+        panel.add_sprite(Sprite::new(0 + adj, 14, 40, 1), "msg1");
+        panel.add_sprite(Sprite::new(40 + adj, 14, 40, 1), "msg2");
 
         panel.add_sprite(
             Sprite::new(0, (TEMPLATEH - 3) as u16, TEMPLATEW as u16, 1u16),
@@ -42,6 +38,7 @@ impl TemplateRender {
 
         // Register Block.RedrawTile event, associated draw_tile method
         event_register("Template.RedrawTile", "draw_tile");
+        event_register("Template.RedrawMsg", "draw_msg");
 
         // Register a timer, then fire it...
         timer_register("Template.TestTimer", 0.1, "test_timer");
@@ -50,6 +47,23 @@ impl TemplateRender {
         Self { panel }
     }
 
+    // messages of the top scene, the card is hidden in title scene
+    pub fn draw_msg(&mut self, ctx: &mut Context) {
+        let title = ctx.state == TemplateState::Title as u8;
+        let (m1, m2) = if title {
+            ("press any key to start", "")
+        } else {
+            ("press N for next card", "press S shuffle, P pause")
+        };
+        self.panel
+            .get_sprite("msg1")
+            .set_default_str(format!("{:40}", m1));
+        self.panel
+            .get_sprite("msg2")
+            .set_default_str(format!("{:40}", m2));
+        self.panel.get_sprite("t0").set_hidden(title);
+    }
+
     pub fn draw_tile(&mut self, ctx: &mut Context, d: &mut TemplateModel) {
         let l = self.panel.get_sprite("t0");
 
@@ -87,6 +101,9 @@ impl Render for TemplateRender {
         if event_check("Template.RedrawTile", "draw_tile") {
             self.draw_tile(context, data);
         }
+        if event_check("Template.RedrawMsg", "draw_msg") {
+            self.draw_msg(context);
+        }
     }
 
     fn handle_timer(&mut self, context: &mut Context, d: &mut Self::Model, _dt: f32) {
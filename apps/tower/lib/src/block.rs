@@ -1,5 +1,5 @@
 use crate::{BH, BW};
-use rust_pixel::util::{objpool::GObj, PointU16};
+use rust_pixel::util::{objpool::GObj, GridMap, PointU16};
 
 #[derive(Default)]
 pub struct Block {
@@ -22,12 +22,12 @@ impl GObj for Block {
 }
 
 impl Block {
-    pub fn set_in_grid(&self, grid: &mut [Vec<u8>]) {
+    pub fn set_in_grid(&self, grid: &mut GridMap<u8>) {
         let x = self.pos.x as usize * BW;
         let y = self.pos.y as usize * BH;
         for i in 0..BW {
             for j in 0..BH {
-                grid[y + i][x + j] = 1;
+                grid.set((x + j) as u16, (y + i) as u16, 1);
             }
         }
     }
@@ -20,3 +20,4 @@ pub mod bullet;
 pub mod laser;
 pub mod monster;
 pub mod tower;
+pub mod wave;
@@ -2,7 +2,7 @@ use crate::{check_passable, TOWERH, TOWERW};
 // use log::info;
 use rust_pixel::{
     algorithm::astar::{a_star, PointUsize},
-    util::{objpool::GObj, PointF32, PointU16, Rand},
+    util::{objpool::GObj, GridMap, PointF32, PointU16, Rand},
 };
 use std::collections::{HashMap, HashSet};
 
@@ -26,6 +26,7 @@ impl GObj for Monster {
         Default::default()
     }
 
+    // ps: cell_w, cell_h, 可选入口 x, y 和血量百分比
     fn reset(&mut self, mtype: u8, ps: &[u32]) {
         self.mtype = mtype;
         if mtype == 1 {
@@ -35,13 +36,20 @@ impl GObj for Monster {
             self.life = 500;
             self.speed = 3;
         }
+        if ps.len() > 4 {
+            self.life = self.life * ps[4] as i32 / 100;
+        }
+        let (ex, ey) = if ps.len() > 3 { (ps[2], ps[3]) } else { (0, 0) };
         self.max_life = self.life;
         self.fspeed = PointF32 { x: 0.0, y: 0.0 };
-        self.pos = PointU16 { x: 0, y: 0 };
-        self.next_pos = PointU16 { x: 0, y: 0 };
+        self.pos = PointU16 {
+            x: ex as u16,
+            y: ey as u16,
+        };
+        self.next_pos = self.pos;
         self.pixel_pos = PointF32 {
-            x: ps[0] as f32,
-            y: ps[1] as f32,
+            x: ((ex + 1) * ps[0]) as f32,
+            y: ((ey + 1) * ps[1]) as f32,
         };
         self.interval = 1;
         self.cd = 0;
@@ -50,22 +58,25 @@ impl GObj for Monster {
 }
 
 impl Monster {
-    pub fn find_path<P>(&mut self, grids: &mut [Vec<u8>], start_p: P)
+    pub fn find_path<P>(&mut self, grids: &mut GridMap<u8>, start_p: P)
     where
         P: Into<PointUsize>,
     {
-        self.path = a_star(grids, start_p.into(), (TOWERH - 1, TOWERW - 1), |v| {
-            check_passable(v)
-        })
+        self.path = a_star(
+            &grids.cells,
+            start_p.into(),
+            (TOWERH - 1, TOWERW - 1),
+            check_passable,
+        )
         .unwrap();
     }
 
-    pub fn get_next_pos(&mut self, grids: &mut [Vec<u8>], rand: &mut Rand) {
+    pub fn get_next_pos(&mut self, grids: &mut GridMap<u8>, rand: &mut Rand) {
         if self.path.is_empty() || rand.rand() % 10 == 0 {
             self.find_path(grids, self.pos);
         }
         let mut ng = self.path.remove(1);
-        if check_passable(grids[ng.0][ng.1]) {
+        if matches!(grids.get(ng.1 as u16, ng.0 as u16), Some(v) if check_passable(*v)) {
             self.next_pos = PointU16 {
                 x: ng.1 as u16,
                 y: ng.0 as u16,
@@ -114,7 +125,7 @@ impl Monster {
     pub fn update(
         &mut self,
         mid: usize,
-        grids: &mut [Vec<u8>],
+        grids: &mut GridMap<u8>,
         mmap: &mut HashMap<usize, HashSet<usize>>,
         w: f32,
         h: f32,
@@ -2,7 +2,7 @@ use crate::monster::Monster;
 use crate::{BH, BW};
 use rust_pixel::util::{
     objpool::{GObj, GameObjPool},
-    PointU16, Rand,
+    GridMap, PointU16, Rand,
 };
 
 #[derive(Default)]
@@ -43,12 +43,12 @@ impl GObj for Tower {
 }
 
 impl Tower {
-    pub fn set_in_grid(&self, grid: &mut [Vec<u8>]) {
+    pub fn set_in_grid(&self, grid: &mut GridMap<u8>) {
         let x = self.pos.x as usize * BW;
         let y = self.pos.y as usize * BH;
         for i in 0..BW {
             for j in 0..BH {
-                grid[y + i][x + j] = 2;
+                grid.set((x + j) as u16, (y + i) as u16, 2);
             }
         }
     }
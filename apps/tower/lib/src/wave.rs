@@ -0,0 +1,226 @@
+// 怪物波次定义和刷怪器
+// waves are data: a WavePlan is parsed from json and a Spawner turns it
+// into SpawnEvents on a tick clock
+//
+// - groups of a wave spawn at the same time, each with its own delay
+//   (from the wave start), count and interval
+// - a wave starts delay seconds after the last spawn of the previous wave
+// - boss groups get BOSS_HP_SCALE times hp_scale
+use serde::Deserialize;
+
+// 怪物类型名, 下标即 Monster.mtype
+pub const MONSTER_KINDS: [&str; 2] = ["normal", "big"];
+pub const BOSS_HP_SCALE: f32 = 3.0;
+// 浮点累加误差, 避免晚一帧刷怪
+const CLOCK_EPS: f32 = 1e-4;
+
+// 原先写死在 model 里的两波怪
+pub const DEFAULT_WAVES: &str = r#"{
+    "entries": [[0, 0]],
+    "waves": [
+        { "delay": 0.1, "groups": [{ "kind": "normal", "count": 4, "interval": 1.5 }] },
+        { "delay": 1.5, "groups": [{ "kind": "big", "count": 4, "interval": 1.5 }] }
+    ]
+}"#;
+
+fn one() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GroupDef {
+    pub kind: String,
+    pub count: u32,
+    #[serde(default)]
+    pub interval: f32,
+    #[serde(default)]
+    pub delay: f32,
+    #[serde(default)]
+    pub entry: usize,
+    #[serde(default = "one")]
+    pub hp_scale: f32,
+    #[serde(default)]
+    pub boss: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WaveDef {
+    #[serde(default)]
+    pub delay: f32,
+    pub groups: Vec<GroupDef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WavePlan {
+    // 路径入口格子 (x, y)
+    pub entries: Vec<(u16, u16)>,
+    pub waves: Vec<WaveDef>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpawnEvent {
+    pub wave: usize,
+    pub monster_kind: u8,
+    pub entry: (u16, u16),
+    pub hp_scale: f32,
+}
+
+impl WavePlan {
+    pub fn from_json(s: &str) -> Result<Self, String> {
+        let plan: WavePlan = serde_json::from_str(s).map_err(|e| e.to_string())?;
+        plan.validate()?;
+        Ok(plan)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, w) in self.waves.iter().enumerate() {
+            if w.delay < 0.0 {
+                return Err(format!("wave {}: negative delay {}", i, w.delay));
+            }
+            for g in &w.groups {
+                if !MONSTER_KINDS.contains(&g.kind.as_str()) {
+                    return Err(format!("wave {}: unknown monster kind {:?}", i, g.kind));
+                }
+                if g.interval < 0.0 {
+                    return Err(format!("wave {}: negative interval {}", i, g.interval));
+                }
+                if g.delay < 0.0 {
+                    return Err(format!("wave {}: negative group delay {}", i, g.delay));
+                }
+                if g.entry >= self.entries.len() {
+                    return Err(format!("wave {}: no entry {}", i, g.entry));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // (时间, 事件), 按时间排序, 同一时刻按 group 顺序
+    pub fn timeline(&self) -> Vec<(f32, SpawnEvent)> {
+        let mut tl = vec![];
+        let mut start = 0.0f32;
+        for (i, w) in self.waves.iter().enumerate() {
+            start += w.delay;
+            let mut end = start;
+            for g in &w.groups {
+                let kind = MONSTER_KINDS.iter().position(|k| *k == g.kind).unwrap() as u8;
+                let hp_scale = if g.boss {
+                    g.hp_scale * BOSS_HP_SCALE
+                } else {
+                    g.hp_scale
+                };
+                for n in 0..g.count {
+                    let t = start + g.delay + n as f32 * g.interval;
+                    end = end.max(t);
+                    tl.push((
+                        t,
+                        SpawnEvent {
+                            wave: i,
+                            monster_kind: kind,
+                            entry: self.entries[g.entry],
+                            hp_scale,
+                        },
+                    ));
+                }
+            }
+            start = end;
+        }
+        tl.sort_by(|a, b| a.0.total_cmp(&b.0));
+        tl
+    }
+}
+
+pub struct Spawner {
+    pub clock: f32,
+    timeline: Vec<(f32, SpawnEvent)>,
+    next: usize,
+}
+
+impl Spawner {
+    pub fn new(plan: &WavePlan) -> Self {
+        Self {
+            clock: 0.0,
+            timeline: plan.timeline(),
+            next: 0,
+        }
+    }
+
+    // 推进时钟, 返回到期的刷怪事件
+    pub fn tick(&mut self, dt: f32) -> Vec<SpawnEvent> {
+        self.clock += dt;
+        let mut es = vec![];
+        while self.next < self.timeline.len()
+            && self.timeline[self.next].0 <= self.clock + CLOCK_EPS
+        {
+            es.push(self.timeline[self.next].1);
+            self.next += 1;
+        }
+        es
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.timeline.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_waves_timeline() {
+        let plan = WavePlan::from_json(
+            r#"{
+                "entries": [[0, 0], [0, 5]],
+                "waves": [
+                    { "groups": [
+                        { "kind": "normal", "count": 3, "interval": 1.0 },
+                        { "kind": "big", "count": 1, "delay": 1.0, "entry": 1 }
+                    ] },
+                    { "delay": 2.0, "groups": [
+                        { "kind": "big", "count": 2, "interval": 0.5, "boss": true }
+                    ] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let mut sp = Spawner::new(&plan);
+        let mut got = vec![];
+        while !sp.is_finished() {
+            for e in sp.tick(0.5) {
+                got.push((sp.clock, e.wave, e.monster_kind, e.entry, e.hp_scale));
+            }
+        }
+        assert_eq!(
+            got,
+            [
+                (0.5, 0, 0, (0, 0), 1.0),
+                (1.0, 0, 0, (0, 0), 1.0),
+                (1.0, 0, 1, (0, 5), 1.0),
+                (2.0, 0, 0, (0, 0), 1.0),
+                (4.0, 1, 1, (0, 0), BOSS_HP_SCALE),
+                (4.5, 1, 1, (0, 0), BOSS_HP_SCALE),
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_plan_reports_wave() {
+        let bad = r#"{ "entries": [[0, 0]], "waves": [
+            { "groups": [{ "kind": "normal", "count": 1 }] },
+            { "groups": [{ "kind": "dragon", "count": 1 }] }
+        ] }"#;
+        assert_eq!(
+            WavePlan::from_json(bad).unwrap_err(),
+            "wave 1: unknown monster kind \"dragon\""
+        );
+        let bad = r#"{ "entries": [[0, 0]], "waves": [
+            { "groups": [{ "kind": "big", "count": 2, "interval": -1.0 }] }
+        ] }"#;
+        assert_eq!(
+            WavePlan::from_json(bad).unwrap_err(),
+            "wave 0: negative interval -1"
+        );
+        assert!(WavePlan::from_json(DEFAULT_WAVES).is_ok());
+    }
+}
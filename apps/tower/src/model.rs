@@ -2,14 +2,14 @@ use rust_pixel::event::Event;
 // use log::info;
 use rust_pixel::{
     context::Context,
-    event::{event_check, event_emit, timer_fire, timer_register},
+    event::event_emit,
     game::Model,
-    util::objpool::GameObjPool,
+    util::{objpool::GameObjPool, GridMap},
 };
 use std::collections::{HashMap, HashSet};
 use tower_lib::{
-    block::*, bomb::*, bullet::*, laser::*, monster::*, tower::*, MAX_BLOCK_COUNT, MAX_BOMB_COUNT,
-    MAX_LASER_COUNT, MAX_MONSTER_COUNT, MAX_TOWER_COUNT, TOWERH, TOWERW,
+    block::*, bomb::*, bullet::*, laser::*, monster::*, tower::*, wave::*, MAX_BLOCK_COUNT,
+    MAX_BOMB_COUNT, MAX_LASER_COUNT, MAX_MONSTER_COUNT, MAX_TOWER_COUNT, TOWERH, TOWERW,
 };
 
 enum TowerState {
@@ -18,7 +18,7 @@ enum TowerState {
 
 pub struct TowerModel {
     // map grid...
-    pub grid: Vec<Vec<u8>>,
+    pub grid: GridMap<u8>,
 
     //  用于子弹进行碰撞检测
     //  key: grid ID
@@ -32,12 +32,15 @@ pub struct TowerModel {
     pub bullets: GameObjPool<Bullet>,
     pub lasers: GameObjPool<Laser>,
     pub monsters: GameObjPool<Monster>,
+
+    // 按波次刷怪
+    pub spawner: Spawner,
 }
 
 impl TowerModel {
     pub fn new() -> Self {
         Self {
-            grid: vec![],
+            grid: GridMap::new(TOWERW as u16, TOWERH as u16, 0),
             monster_map: HashMap::new(),
             // timeout_auto: 0.0,
             bombs: GameObjPool::<Bomb>::new("BB", MAX_BOMB_COUNT),
@@ -46,14 +49,12 @@ impl TowerModel {
             bullets: GameObjPool::<Bullet>::new("B", MAX_BLOCK_COUNT),
             lasers: GameObjPool::<Laser>::new("L", MAX_LASER_COUNT),
             monsters: GameObjPool::<Monster>::new("M", MAX_MONSTER_COUNT),
+            spawner: Spawner::new(&WavePlan::from_json(DEFAULT_WAVES).unwrap()),
         }
     }
 
     pub fn make_grid(&mut self) {
-        self.grid = vec![vec![]; TOWERH];
-        for i in 0..TOWERH {
-            self.grid[i] = vec![0u8; TOWERW];
-        }
+        self.grid.fill(0);
         for b in &self.blocks.pool {
             b.obj.set_in_grid(&mut self.grid);
         }
@@ -99,13 +100,6 @@ impl Model for TowerModel {
             self.towers.create(2, &[p.0, p.1]);
         }
 
-        // 注册创建怪物定时器，以便延迟创建怪物
-        for i in 0..8 {
-            let tstr = format!("Tower.CreatMonster{}", i);
-            timer_register(&tstr, 0.1 + 1.5 * i as f32, "_");
-            timer_fire(&tstr, 0u8);
-        }
-
         // 更新grid
         self.make_grid();
 
@@ -186,20 +180,23 @@ impl Model for TowerModel {
 
     fn handle_event(&mut self, _ctx: &mut Context, _dt: f32) {}
 
-    fn handle_timer(&mut self, ctx: &mut Context, _dt: f32) {
+    fn handle_timer(&mut self, ctx: &mut Context, dt: f32) {
         let csp = (
             ctx.adapter.cell_width() as u32,
             ctx.adapter.cell_height() as u32,
         );
-        for i in 0..8 {
-            let tstr = format!("Tower.CreatMonster{}", i);
-            if event_check(&tstr, "_") {
-                if i > 3 {
-                    self.monsters.create(1, &[csp.0, csp.1]);
-                } else {
-                    self.monsters.create(0, &[csp.0, csp.1]);
-                }
-            }
+        // 按波次计划创建怪物
+        for e in self.spawner.tick(dt) {
+            self.monsters.create(
+                e.monster_kind,
+                &[
+                    csp.0,
+                    csp.1,
+                    e.entry.0 as u32,
+                    e.entry.1 as u32,
+                    (e.hp_scale * 100.0) as u32,
+                ],
+            );
         }
     }
 }
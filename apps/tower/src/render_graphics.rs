@@ -39,20 +39,26 @@ impl TowerRender {
     pub fn create_sprites(&mut self, ctx: &mut Context, d: &mut TowerModel) {
         let w = BW as u16;
         let h = BH as u16;
-        self.panel.creat_objpool_sprites(&d.blocks, w, h, |bl| {
-            asset2sprite!(bl, ctx, "pix/block.pix");
-        });
-        self.panel.creat_objpool_sprites(&d.towers, w, h, |_bl| {});
-        self.panel.creat_objpool_sprites(&d.monsters, 1, 2, |pl| {
-            pl.set_graph_sym(0, 0, 2, 15, Color::Indexed(15));
-            pl.set_graph_sym(0, 1, 2, 7, Color::Indexed(15));
-        });
-        self.panel.creat_objpool_sprites(&d.bullets, 1, 1, |pl| {
-            pl.set_graph_sym(0, 0, 2, 29, Color::Indexed(10));
-        });
+        // monsters under towers under projectiles...
+        self.panel
+            .creat_objpool_sprites_z(&d.blocks, w, h, 0, |bl| {
+                asset2sprite!(bl, ctx, "pix/block.pix");
+            });
+        self.panel
+            .creat_objpool_sprites_z(&d.monsters, 1, 2, 10, |pl| {
+                pl.set_graph_sym(0, 0, 2, 15, Color::Indexed(15));
+                pl.set_graph_sym(0, 1, 2, 7, Color::Indexed(15));
+            });
+        self.panel
+            .creat_objpool_sprites_z(&d.towers, w, h, 20, |_bl| {});
+        self.panel
+            .creat_objpool_sprites_z(&d.bullets, 1, 1, 30, |pl| {
+                pl.set_graph_sym(0, 0, 2, 29, Color::Indexed(10));
+            });
+        self.panel
+            .creat_objpool_sprites_z(&d.lasers, TOWERW as u16, TOWERH as u16, 30, |_pl| {});
         self.panel
-            .creat_objpool_sprites(&d.lasers, TOWERW as u16, TOWERH as u16, |_pl| {});
-        self.panel.creat_objpool_sprites(&d.bombs, 1, 1, |_pl| {});
+            .creat_objpool_sprites_z(&d.bombs, 1, 1, 40, |_pl| {});
     }
 
     pub fn draw_movie(&mut self, ctx: &mut Context, d: &mut TowerModel) {
@@ -141,7 +147,7 @@ impl TowerRender {
         let l = self.panel.get_sprite("TOWER");
         for i in 0..TOWERH {
             for j in 0..TOWERW {
-                if d.grid[i][j] == 0 {
+                if d.grid.cells[i][j] == 0 {
                     let mut sym = 32u8;
                     if i % 3 == 0 && j % 3 == 0 {
                         sym = 102u8;
@@ -4,27 +4,59 @@
 //! asset provides the resource manager.
 //! It supports async load. It calls JavaScript methods to load resources asynchronously when runs in wasm mode.
 //! https://www.reddit.com/r/rust/comments/8ymzwg/common_data_and_behavior/
+//!
+//! An optional memory budget turns the manager into a LRU cache,
+//! least-recently-used assets are evicted when the budget is exceeded.
+//! Pinned assets are never evicted. Evicted assets are reloaded by the next load call.
+//!
+//! preload starts loading a list of assets at once, for a loading screen:
+//! native reads the files in a thread, picked up by poll_preload every tick,
+//! wasm asks js for them as load does. preload_progress tells how many are done.
+//!
+//! An asset failing to parse (e.g. a .pix file of unknown version) is logged
+//! and stays in Failed state, sprites requesting it are left untouched.
 
 #[cfg(not(target_arch = "wasm32"))]
 use crate::util::get_abs_path;
 use crate::{
-    render::buffer::Buffer,
-    render::image::{EscAsset, PixAsset, SeqFrameAsset},
+    render::{buffer::Buffer, cell::Cell},
+    render::image::{EscAsset, PaletteRemap, PixAsset, SeqFrameAsset},
     render::sprite::Sprite,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc::{channel, Receiver, Sender};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
-#[cfg(not(target_arch = "wasm32"))]
-use log::info;
+use log::{error, info};
 
 #[derive(PartialEq, Clone, Copy)]
 pub enum AssetState {
     Loading,
     Parsing,
     Ready,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssetError {
+    UnsupportedVersion { location: String, version: u32 },
 }
 
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::UnsupportedVersion { location, version } => write!(
+                f,
+                "asset {:?} has unsupported version {}, please rebuild it",
+                location, version
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
 #[derive(PartialEq, Clone, Copy)]
 pub enum AssetType {
     ImgPix,
@@ -43,6 +75,10 @@ pub struct AssetBase {
     pub parsed_buffers: Vec<Buffer>,
     pub frame_count: usize,
     pub state: AssetState,
+    // last access tick, used by LRU eviction
+    pub last_access: u64,
+    // pinned asset is never evicted
+    pub pinned: bool,
 }
 
 impl AssetBase {
@@ -54,8 +90,16 @@ impl AssetBase {
             parsed_buffers: vec![],
             frame_count: 1,
             state: AssetState::Loading,
+            last_access: 0,
+            pinned: false,
         }
     }
+
+    /// approximate memory used by raw data and parsed buffers
+    pub fn mem_size(&self) -> usize {
+        let cells: usize = self.parsed_buffers.iter().map(|b| b.content.len()).sum();
+        self.raw_data.len() + cells * std::mem::size_of::<Cell>()
+    }
 }
 
 pub trait Asset {
@@ -65,7 +109,7 @@ pub trait Asset {
 
     fn set_sprite(&mut self, sp: &mut Sprite, frame_idx: usize, off_x: u16, off_y: u16) {
         let bs = self.get_base();
-        let _ = sp.content.blit(
+        let _ = sp.content.blit_alpha(
             off_x,
             off_y,
             &bs.parsed_buffers[frame_idx % bs.frame_count],
@@ -74,6 +118,21 @@ pub trait Asset {
         );
     }
 
+    /// same as set_sprite, but rewrites color indices with remap while copying
+    fn set_sprite_remap(
+        &mut self,
+        sp: &mut Sprite,
+        frame_idx: usize,
+        off_x: u16,
+        off_y: u16,
+        remap: &PaletteRemap,
+    ) {
+        let bs = self.get_base();
+        let mut buf = bs.parsed_buffers[frame_idx % bs.frame_count].clone();
+        remap.apply(&mut buf);
+        let _ = sp.content.blit_alpha(off_x, off_y, &buf, buf.area, sp.alpha);
+    }
+
     fn get_base(&mut self) -> &mut AssetBase;
 
     fn set_data(&mut self, data: &[u8]) {
@@ -91,7 +150,7 @@ pub trait Asset {
         self.get_base().state
     }
 
-    fn parse(&mut self);
+    fn parse(&mut self) -> Result<(), AssetError>;
 
     fn save(&mut self, buf: &Buffer);
 }
@@ -99,6 +158,27 @@ pub trait Asset {
 pub struct AssetManager {
     pub assets: Vec<Box<dyn Asset>>,
     pub assets_index: HashMap<String, usize>,
+    // memory budget in bytes, None means no limit
+    pub cache_budget: Option<usize>,
+    // access counter for LRU
+    tick: u64,
+    // locations passed to preload, for preload_progress
+    preload_set: Vec<String>,
+    // files read by preload threads
+    #[cfg(not(target_arch = "wasm32"))]
+    preload_chan: Option<(Sender<PreloadData>, Receiver<PreloadData>)>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type PreloadData = (String, std::io::Result<Vec<u8>>);
+
+/// asset type by file extension, .pix .ssf or esc for others
+pub fn asset_type_of(loc: &str) -> AssetType {
+    match loc.rsplit('.').next() {
+        Some("pix") => AssetType::ImgPix,
+        Some("ssf") => AssetType::ImgSsf,
+        _ => AssetType::ImgEsc,
+    }
 }
 
 impl Default for AssetManager {
@@ -112,28 +192,106 @@ impl AssetManager {
         Self {
             assets: vec![],
             assets_index: HashMap::new(),
+            cache_budget: None,
+            tick: 0,
+            preload_set: vec![],
+            #[cfg(not(target_arch = "wasm32"))]
+            preload_chan: None,
+        }
+    }
+
+    /// set memory budget, evicts LRU assets if exceeded
+    pub fn set_cache_budget(&mut self, bytes: usize) {
+        self.cache_budget = Some(bytes);
+        self.evict();
+    }
+
+    pub fn clear_cache_budget(&mut self) {
+        self.cache_budget = None;
+    }
+
+    /// pinned asset is exempt from eviction
+    pub fn pin(&mut self, loc: &str, pinned: bool) {
+        if let Some(idx) = self.assets_index.get(loc) {
+            self.assets[*idx - 1].get_base().pinned = pinned;
+        }
+    }
+
+    pub fn mem_usage(&mut self) -> usize {
+        self.assets.iter_mut().map(|a| a.get_base().mem_size()).sum()
+    }
+
+    fn touch(&mut self, idx: usize) {
+        self.tick += 1;
+        self.assets[idx - 1].get_base().last_access = self.tick;
+    }
+
+    fn remove(&mut self, pos: usize) {
+        let mut ast = self.assets.remove(pos);
+        let loc = ast.get_base().location.clone();
+        info!("asset evict:{:?}", loc);
+        self.assets_index.remove(&loc);
+        for v in self.assets_index.values_mut() {
+            if *v > pos + 1 {
+                *v -= 1;
+            }
+        }
+    }
+
+    /// evicts least-recently-used assets until memory usage fits the budget
+    /// pinned, loading and the most recently touched assets are kept
+    pub fn evict(&mut self) {
+        let budget = match self.cache_budget {
+            Some(b) => b,
+            None => return,
+        };
+        let mut usage = self.mem_usage();
+        while usage > budget {
+            let mut lru: Option<(usize, u64)> = None;
+            for (i, a) in self.assets.iter_mut().enumerate() {
+                let bs = a.get_base();
+                if bs.pinned || bs.state != AssetState::Ready || bs.last_access == self.tick {
+                    continue;
+                }
+                let older = match lru {
+                    Some(l) => bs.last_access < l.1,
+                    None => true,
+                };
+                if older {
+                    lru = Some((i, bs.last_access));
+                }
+            }
+            match lru {
+                Some((i, _)) => {
+                    usage -= self.assets[i].get_base().mem_size();
+                    self.remove(i);
+                }
+                None => break,
+            }
         }
     }
 
-    #[allow(unused_mut)]
+    // adds an asset in Loading state, wasm also asks js to fetch it
+    fn register(&mut self, t: AssetType, loc: &str) {
+        let ab = AssetBase::new(t, loc);
+        #[cfg(target_arch = "wasm32")]
+        {
+            js_load_asset(loc);
+        }
+        let ast: Box<dyn Asset> = match t {
+            AssetType::ImgPix => Box::new(PixAsset::new(ab)),
+            AssetType::ImgEsc => Box::new(EscAsset::new(ab)),
+            AssetType::ImgSsf => Box::new(SeqFrameAsset::new(ab)),
+        };
+        self.assets.push(ast);
+        self.assets_index.insert(loc.to_string(), self.assets.len());
+    }
+
     pub fn load(&mut self, t: AssetType, loc: &str) {
         match self.assets_index.get(loc) {
             Some(_) => {}
             None => {
-                let mut ab = AssetBase::new(t, loc);
-                #[cfg(target_arch = "wasm32")]
-                {
-                    js_load_asset(loc);
-                }
-                #[cfg(not(target_arch = "wasm32"))]
-                {}
-                let mut ast: Box<dyn Asset> = match t {
-                    AssetType::ImgPix => Box::new(PixAsset::new(ab)),
-                    AssetType::ImgEsc => Box::new(EscAsset::new(ab)),
-                    AssetType::ImgSsf => Box::new(SeqFrameAsset::new(ab)),
-                };
-                self.assets.push(ast);
-                self.assets_index.insert(loc.to_string(), self.assets.len());
+                self.register(t, loc);
                 #[cfg(not(target_arch = "wasm32"))]
                 {
                     let fpstr = get_abs_path(loc);
@@ -145,9 +303,81 @@ impl AssetManager {
         }
     }
 
+    /// starts loading all paths without blocking, types come from the
+    /// file extensions, see asset_type_of
+    pub fn preload(&mut self, paths: &[&str]) {
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut files = vec![];
+        for loc in paths {
+            if !self.assets_index.contains_key(*loc) {
+                self.register(asset_type_of(loc), loc);
+                #[cfg(not(target_arch = "wasm32"))]
+                files.push((loc.to_string(), get_abs_path(loc)));
+            }
+            if !self.preload_set.iter().any(|p| p == loc) {
+                self.preload_set.push(loc.to_string());
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if !files.is_empty() {
+            let tx = self.preload_chan.get_or_insert_with(channel).0.clone();
+            std::thread::spawn(move || {
+                for (loc, path) in files {
+                    info!("asset preload:{:?}", path);
+                    if tx.send((loc, std::fs::read(path))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+
+    /// takes the files read by preload threads, called every tick by Game,
+    /// wasm gets them from js by set_data instead
+    pub fn poll_preload(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let mut done = vec![];
+            if let Some((_, rx)) = &self.preload_chan {
+                done.extend(rx.try_iter());
+            }
+            for (loc, data) in done {
+                match data {
+                    Ok(d) => self.set_data(&loc, &d),
+                    Err(e) => {
+                        error!("asset preload {:?}: {}", loc, e);
+                        if let Some(idx) = self.assets_index.get(&loc) {
+                            self.assets[*idx - 1].set_state(AssetState::Failed);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// (loaded, total) of preloaded assets, failed ones count as loaded
+    pub fn preload_progress(&mut self) -> (usize, usize) {
+        let mut loaded = 0;
+        for loc in &self.preload_set {
+            let done = match self.assets_index.get(loc) {
+                Some(idx) => self.assets[*idx - 1].get_state() != AssetState::Loading,
+                // evicted after it was ready
+                None => true,
+            };
+            if done {
+                loaded += 1;
+            }
+        }
+        (loaded, self.preload_set.len())
+    }
+
     pub fn get(&mut self, loc: &str) -> Option<&mut Box<(dyn Asset)>> {
         match self.assets_index.get(loc) {
-            Some(idx) => Some(&mut self.assets[*idx - 1]),
+            Some(idx) => {
+                let idx = *idx;
+                self.touch(idx);
+                Some(&mut self.assets[idx - 1])
+            }
             None => None,
         }
     }
@@ -157,10 +387,18 @@ impl AssetManager {
     // refer to rust-pixel/web-templates/index.js
     pub fn set_data(&mut self, loc: &str, data: &[u8]) {
         if let Some(idx) = self.assets_index.get(loc) {
-            self.assets[*idx - 1].set_data(data);
-            self.assets[*idx - 1].set_state(AssetState::Parsing);
-            self.assets[*idx - 1].parse();
-            self.assets[*idx - 1].set_state(AssetState::Ready);
+            let idx = *idx;
+            self.assets[idx - 1].set_data(data);
+            self.assets[idx - 1].set_state(AssetState::Parsing);
+            match self.assets[idx - 1].parse() {
+                Ok(()) => self.assets[idx - 1].set_state(AssetState::Ready),
+                Err(e) => {
+                    error!("{}", e);
+                    self.assets[idx - 1].set_state(AssetState::Failed);
+                }
+            }
+            self.touch(idx);
+            self.evict();
         }
     }
 }
@@ -171,3 +409,85 @@ impl AssetManager {
 extern "C" {
     fn js_load_asset(url: &str);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tmp_pix(name: &str) -> String {
+        let p = std::env::temp_dir().join(format!("rust_pixel_lru_{}.pix", name));
+        std::fs::write(&p, "width=2,height=1,texture=255\n1,5,0,6 2,7,0,6\n").unwrap();
+        p.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn lru_evicts_oldest_untouched() {
+        let (a, b, c) = (tmp_pix("a"), tmp_pix("b"), tmp_pix("c"));
+        let mut am = AssetManager::new();
+        am.load(AssetType::ImgPix, &a);
+        let one = am.mem_usage();
+        am.set_cache_budget(one * 2 + one / 2);
+        am.load(AssetType::ImgPix, &b);
+        // touch a, so b becomes the least recently used
+        assert!(am.get(&a).is_some());
+        am.load(AssetType::ImgPix, &c);
+        assert!(am.get(&b).is_none());
+        assert!(am.get(&a).is_some());
+        assert!(am.get(&c).is_some());
+        assert!(am.mem_usage() <= one * 2 + one / 2);
+    }
+
+    #[test]
+    fn lru_evicted_request_reloads() {
+        let (a, b) = (tmp_pix("ra"), tmp_pix("rb"));
+        let mut expect = Sprite::new(0, 0, 2, 1);
+        expect.set_content_by_asset(&mut AssetManager::new(), AssetType::ImgPix, &a, 0, 0, 0);
+
+        // a is ready but not taken by the sprite yet, then b evicts it
+        let mut am = AssetManager::new();
+        let mut sp = Sprite::new(0, 0, 2, 1);
+        sp.asset_request = Some((AssetType::ImgPix, a.clone(), 0, 0, 0));
+        am.load(AssetType::ImgPix, &a);
+        let one = am.mem_usage();
+        am.set_cache_budget(one + one / 2);
+        am.load(AssetType::ImgPix, &b);
+        assert!(am.get(&a).is_none());
+
+        assert!(sp.check_asset_request(&mut am));
+        assert!(sp.asset_request.is_none());
+        assert_eq!(sp.content, expect.content);
+    }
+
+    #[test]
+    fn preload_reports_progress() {
+        let (a, b) = (tmp_pix("pre_a"), tmp_pix("pre_b"));
+        let mut am = AssetManager::new();
+        am.preload(&[&a, &b]);
+        // nothing is taken before poll_preload
+        assert_eq!(am.preload_progress(), (0, 2));
+        for _ in 0..200 {
+            am.poll_preload();
+            if am.preload_progress() == (2, 2) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(am.preload_progress(), (2, 2));
+        assert!(am.get(&b).unwrap().get_state() == AssetState::Ready);
+    }
+
+    #[test]
+    fn lru_keeps_pinned() {
+        let (a, b, c) = (tmp_pix("pa"), tmp_pix("pb"), tmp_pix("pc"));
+        let mut am = AssetManager::new();
+        am.load(AssetType::ImgPix, &a);
+        let one = am.mem_usage();
+        am.pin(&a, true);
+        am.set_cache_budget(one * 2);
+        am.load(AssetType::ImgPix, &b);
+        am.load(AssetType::ImgPix, &c);
+        assert!(am.get(&a).is_some());
+        assert!(am.get(&b).is_none());
+        assert!(am.get(&c).is_some());
+    }
+}
@@ -4,13 +4,47 @@
 
 //! audio provides playing music and sound effect, reference
 //! https://docs.rs/rodio
+//!
+//! In wasm there is no rodio, play_file queues the clip instead. The generated
+//! wasm game's take_audio hands the queue to js after each tick, and js plays
+//! the clips with the web audio api, see web-templates/index.js
+//!
+//! Web clips are fetched by url in js, not by the AssetManager. Assets are
+//! parsed into buffers for sprites, a clip is only decoded by the browser,
+//! so loading it through wasm would just copy the bytes in and out again
 
 
+#[cfg(not(any(target_os = "android", target_os = "ios", target_arch = "wasm32")))]
 use crate::util::get_abs_path;
 #[cfg(not(any(target_os = "android", target_os = "ios", target_arch = "wasm32")))]
 use rodio::{source::Source, Decoder, OutputStream, OutputStreamHandle};
+#[cfg(not(any(target_os = "android", target_os = "ios", target_arch = "wasm32")))]
 use std::fs::File;
+#[cfg(not(any(target_os = "android", target_os = "ios", target_arch = "wasm32")))]
 use std::io::BufReader;
+use std::sync::Mutex;
+
+/// a clip to be played by js, clip is the asset path passed to play_file
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioPlay {
+    pub clip: String,
+    pub is_loop: bool,
+}
+
+static WEB_AUDIO_QUEUE: Mutex<Vec<AudioPlay>> = Mutex::new(vec![]);
+
+/// queues a clip for js, wasm play_file calls it
+pub fn web_audio_push(clip: &str, is_loop: bool) {
+    WEB_AUDIO_QUEUE.lock().unwrap().push(AudioPlay {
+        clip: clip.to_string(),
+        is_loop,
+    });
+}
+
+/// takes the clips queued since the last call, in play order
+pub fn web_audio_take() -> Vec<AudioPlay> {
+    std::mem::take(&mut *WEB_AUDIO_QUEUE.lock().unwrap())
+}
 
 pub struct Audio {
     #[cfg(not(any(target_os = "android", target_os = "ios", target_arch = "wasm32")))]
@@ -37,12 +71,16 @@ impl Audio {
             Self { _out: s, handle: h }
         }
     }
-    #[allow(unused)]
     pub fn play_file(&self, fpath: &str, is_loop: bool) {
-        let fpstr = get_abs_path(fpath);
-        let file = BufReader::new(File::open(fpstr).unwrap());
+        #[cfg(target_arch = "wasm32")]
+        web_audio_push(fpath, is_loop);
+        // no audio on mobile yet
+        #[cfg(any(target_os = "android", target_os = "ios"))]
+        let _ = (fpath, is_loop);
         #[cfg(not(any(target_os = "android", target_os = "ios", target_arch = "wasm32")))]
         {
+            let fpstr = get_abs_path(fpath);
+            let file = BufReader::new(File::open(fpstr).unwrap());
             if is_loop {
                 let source = Decoder::new(file).unwrap().repeat_infinite();
                 self.handle.play_raw(source.convert_samples()).unwrap();
@@ -53,3 +91,19 @@ impl Audio {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn web_play_queues_clip() {
+        web_audio_push("assets/sound/shoot.mp3", false);
+        web_audio_push("assets/sound/bgm.mp3", true);
+        let q = web_audio_take();
+        assert_eq!(q.len(), 2);
+        assert_eq!(q[0].clip, "assets/sound/shoot.mp3");
+        assert!(!q[0].is_loop && q[1].is_loop);
+        assert!(web_audio_take().is_empty());
+    }
+}
@@ -8,8 +8,20 @@
 //! An render adapter is also provided
 //! to make it compatible with web, SDL, or terminal modes.
 //! Finally, an asset_manager is included as well.
+//! dt and time are set by Game::on_tick before update, so models and renders
+//! can drive tweens (util::Tween) without passing dt around.
+//! key_repeat marks key repeats the same way on every adapter, see KeyRepeat.
+//! debug is the overlay Panel draws when enabled, see DebugOverlay.
+//! Scenes request push, pop or replace through push_scene etc., the ops are
+//! applied by SceneStack after the top scene's update.
 
-use crate::{asset::AssetManager, event::Event, render::adapter::Adapter, util::Rand};
+use crate::{
+    asset::AssetManager,
+    event::{Event, GamepadInfo, KeyRepeat},
+    game::{Scene, SceneOp},
+    render::{adapter::Adapter, overlay::DebugOverlay},
+    util::Rand,
+};
 
 #[cfg(all(not(target_arch = "wasm32"), not(feature = "sdl")))]
 use crate::render::adapter::cross::CrosstermAdapter;
@@ -25,9 +37,19 @@ pub struct Context {
     pub project_path: String,
     pub stage: u32,
     pub state: u8,
+    /// seconds of the current frame
+    pub dt: f32,
+    /// seconds since the first frame, sum of dt
+    pub time: f64,
     pub rand: Rand,
     pub asset_manager: AssetManager,
     pub input_events: Vec<Event>,
+    /// applied to input_events by Game::on_tick
+    pub key_repeat: KeyRepeat,
+    /// fed by Game::on_tick, drawn by Panel::draw
+    pub debug: DebugOverlay,
+    /// scene ops queued by push_scene, pop_scene and replace_scene
+    pub scene_ops: Vec<SceneOp>,
     pub adapter: Box<dyn Adapter>,
 }
 
@@ -38,9 +60,14 @@ impl Context {
             project_path: project_path.to_string(),
             stage: 0,
             state: 0,
+            dt: 0.0,
+            time: 0.0,
             rand: Rand::new(),
             asset_manager: AssetManager::new(),
             input_events: vec![],
+            key_repeat: KeyRepeat::default(),
+            debug: DebugOverlay::default(),
+            scene_ops: vec![],
             #[cfg(target_arch = "wasm32")]
             adapter: Box::new(WebAdapter::new(name, project_path)),
             #[cfg(all(not(target_arch = "wasm32"), feature = "sdl"))]
@@ -53,4 +80,54 @@ impl Context {
     pub fn set_asset_path(&mut self, project_path: &str) {
         self.project_path = project_path.to_string();
     }
+
+    /// connected gamepads, always empty in terminal and web mode
+    pub fn gamepads(&self) -> Vec<GamepadInfo> {
+        self.adapter.gamepads()
+    }
+
+    /// window title, icon and clipboard only work in sdl mode,
+    /// they are no-ops in terminal and web mode
+    pub fn set_window_title(&mut self, title: &str) {
+        self.adapter.set_window_title(title);
+    }
+
+    pub fn set_window_icon(&mut self, rgba: &[u8], w: u32, h: u32) {
+        self.adapter.set_window_icon(rgba, w, h);
+    }
+
+    pub fn clipboard_get(&mut self) -> Option<String> {
+        self.adapter.clipboard_get()
+    }
+
+    pub fn clipboard_set(&mut self, text: &str) {
+        self.adapter.clipboard_set(text);
+    }
+
+    pub fn push_scene(&mut self, scene: Box<dyn Scene>) {
+        self.scene_ops.push(SceneOp::Push(scene));
+    }
+
+    pub fn pop_scene(&mut self) {
+        self.scene_ops.push(SceneOp::Pop);
+    }
+
+    pub fn replace_scene(&mut self, scene: Box<dyn Scene>) {
+        self.scene_ops.push(SceneOp::Replace(scene));
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32"), not(feature = "sdl")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_and_clipboard_default_to_noop() {
+        let mut ctx = Context::new("ctx_test", ".");
+        ctx.set_window_title("score 100");
+        assert_eq!(ctx.adapter.get_base().title, "score 100");
+        ctx.set_window_icon(&[255; 16], 2, 2);
+        ctx.clipboard_set("hello");
+        assert_eq!(ctx.clipboard_get(), None);
+    }
 }
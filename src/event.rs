@@ -218,3 +218,15 @@ impl Timers {
 
 mod input;
 pub use input::*;
+
+mod shortcut;
+pub use shortcut::*;
+
+mod keyrepeat;
+pub use keyrepeat::*;
+
+mod gamepad;
+pub use gamepad::*;
+
+mod timerwheel;
+pub use timerwheel::*;
@@ -0,0 +1,274 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Gamepad events, buttons use the xbox layout by position
+//! (South is A on xbox, cross on playstation)
+//!
+//! Axis values are normalized by the adapter: the deadzone is cut off and
+//! the rest is scaled back to -32767..=32767, so 0 means the stick is centered.
+//! Only the sdl adapter emits gamepad events for now.
+//!
+//! GamepadKeyMap turns gamepad events into key events, so a game written for
+//! the keyboard can opt in with one line before handling input:
+//!
+//! self.pad_keys.apply(&mut ctx.input_events);
+//!
+//! apply only passes Press keys by default, games match on key.code alone,
+//! set releases to also get the Release keys of ButtonUp and stick return.
+
+use crate::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+/// default deadzone of sticks, about 25% of the range
+pub const AXIS_DEADZONE: i16 = 8000;
+pub const AXIS_MAX: i16 = 32767;
+
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    Back,
+    Guide,
+    Start,
+    LeftStick,
+    RightStick,
+    LeftShoulder,
+    RightShoulder,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    TriggerLeft,
+    TriggerRight,
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
+pub enum GamepadEventKind {
+    ButtonDown(GamepadButton),
+    ButtonUp(GamepadButton),
+    /// normalized value, y axes grow downward
+    Axis(GamepadAxis, i16),
+    Connected,
+    Disconnected,
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
+pub struct GamepadEvent {
+    /// pad id, stable while the pad is connected
+    pub id: u32,
+    pub kind: GamepadEventKind,
+}
+
+impl GamepadEvent {
+    pub fn new(id: u32, kind: GamepadEventKind) -> Self {
+        Self { id, kind }
+    }
+
+    /// axis value in -1.0..=1.0, None for other events
+    pub fn axis_value(&self) -> Option<f32> {
+        match self.kind {
+            GamepadEventKind::Axis(_, v) => Some(v as f32 / AXIS_MAX as f32),
+            _ => None,
+        }
+    }
+}
+
+/// a connected pad
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GamepadInfo {
+    pub id: u32,
+    pub name: String,
+}
+
+/// cuts off the deadzone and scales the rest to -32767..=32767
+pub fn apply_deadzone(raw: i16, deadzone: i16) -> i16 {
+    let dz = deadzone.clamp(0, AXIS_MAX - 1) as i32;
+    let v = (raw as i32).clamp(-(AXIS_MAX as i32), AXIS_MAX as i32);
+    if v.abs() <= dz {
+        return 0;
+    }
+    let scaled = (v.abs() - dz) * AXIS_MAX as i32 / (AXIS_MAX as i32 - dz);
+    (scaled * v.signum()) as i16
+}
+
+/// maps buttons and the left stick to keys
+pub struct GamepadKeyMap {
+    pub buttons: Vec<(GamepadButton, KeyCode)>,
+    /// the left stick acts as arrow keys past this normalized value
+    pub stick_threshold: i16,
+    /// apply keeps Release keys too, off by default
+    pub releases: bool,
+    // current arrow of the left stick, x and y
+    stick: [Option<KeyCode>; 2],
+}
+
+impl Default for GamepadKeyMap {
+    /// dpad as arrows, South as Enter, East as Esc, West as Space,
+    /// Start as Enter
+    fn default() -> Self {
+        Self {
+            buttons: vec![
+                (GamepadButton::DPadUp, KeyCode::Up),
+                (GamepadButton::DPadDown, KeyCode::Down),
+                (GamepadButton::DPadLeft, KeyCode::Left),
+                (GamepadButton::DPadRight, KeyCode::Right),
+                (GamepadButton::South, KeyCode::Enter),
+                (GamepadButton::East, KeyCode::Esc),
+                (GamepadButton::West, KeyCode::Char(' ')),
+                (GamepadButton::Start, KeyCode::Enter),
+            ],
+            stick_threshold: AXIS_MAX / 2,
+            releases: false,
+            stick: [None, None],
+        }
+    }
+}
+
+impl GamepadKeyMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// key event for a gamepad event, ButtonUp gives a Release key,
+    /// the stick gives Press when it leaves the center and Release back
+    pub fn map(&mut self, e: &GamepadEvent) -> Option<KeyEvent> {
+        let key = |code, kind| Some(KeyEvent::new_with_kind(code, KeyModifiers::NONE, kind));
+        match e.kind {
+            GamepadEventKind::ButtonDown(b) | GamepadEventKind::ButtonUp(b) => {
+                let code = self.buttons.iter().find(|m| m.0 == b)?.1;
+                let kind = if matches!(e.kind, GamepadEventKind::ButtonDown(_)) {
+                    KeyEventKind::Press
+                } else {
+                    KeyEventKind::Release
+                };
+                key(code, kind)
+            }
+            GamepadEventKind::Axis(axis, v) => {
+                let (i, neg, pos) = match axis {
+                    GamepadAxis::LeftX => (0, KeyCode::Left, KeyCode::Right),
+                    GamepadAxis::LeftY => (1, KeyCode::Up, KeyCode::Down),
+                    _ => return None,
+                };
+                let now = if v <= -self.stick_threshold {
+                    Some(neg)
+                } else if v >= self.stick_threshold {
+                    Some(pos)
+                } else {
+                    None
+                };
+                let old = self.stick[i];
+                if now == old {
+                    return None;
+                }
+                self.stick[i] = now;
+                match now {
+                    Some(code) => key(code, KeyEventKind::Press),
+                    None => key(old?, KeyEventKind::Release),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// replaces gamepad events by mapped key events, in place,
+    /// Release keys are dropped unless releases is set
+    pub fn apply(&mut self, events: &mut Vec<Event>) {
+        let es = std::mem::take(events);
+        for e in es {
+            match e {
+                Event::Gamepad(g) => {
+                    if let Some(k) = self.map(&g) {
+                        if self.releases || k.kind != KeyEventKind::Release {
+                            events.push(Event::Key(k));
+                        }
+                    }
+                }
+                _ => events.push(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadzone_is_cut_and_rescaled() {
+        assert_eq!(apply_deadzone(0, AXIS_DEADZONE), 0);
+        assert_eq!(apply_deadzone(AXIS_DEADZONE, AXIS_DEADZONE), 0);
+        assert_eq!(apply_deadzone(-AXIS_DEADZONE, AXIS_DEADZONE), 0);
+        assert_eq!(apply_deadzone(AXIS_DEADZONE + 1, AXIS_DEADZONE), 1);
+        assert_eq!(apply_deadzone(32767, AXIS_DEADZONE), 32767);
+        assert_eq!(apply_deadzone(-32768, AXIS_DEADZONE), -32767);
+        assert_eq!(apply_deadzone(1000, 0), 1000);
+        let half = apply_deadzone(
+            AXIS_DEADZONE + (AXIS_MAX - AXIS_DEADZONE) / 2,
+            AXIS_DEADZONE,
+        );
+        assert!((half - AXIS_MAX / 2).abs() <= 1);
+        let e = GamepadEvent::new(0, GamepadEventKind::Axis(GamepadAxis::LeftX, -32767));
+        assert_eq!(e.axis_value(), Some(-1.0));
+    }
+
+    #[test]
+    fn keymap_maps_buttons_and_stick() {
+        let mut km = GamepadKeyMap::new();
+        km.releases = true;
+        let ev = |kind| Event::Gamepad(GamepadEvent::new(1, kind));
+        let mut es = vec![
+            ev(GamepadEventKind::ButtonDown(GamepadButton::South)),
+            ev(GamepadEventKind::ButtonUp(GamepadButton::South)),
+            ev(GamepadEventKind::ButtonDown(GamepadButton::Guide)),
+            Event::Resize(10, 10),
+            ev(GamepadEventKind::Axis(GamepadAxis::LeftX, 30000)),
+            ev(GamepadEventKind::Axis(GamepadAxis::LeftX, 31000)),
+            ev(GamepadEventKind::Axis(GamepadAxis::LeftX, 100)),
+            ev(GamepadEventKind::Axis(GamepadAxis::LeftY, -20000)),
+            ev(GamepadEventKind::Axis(GamepadAxis::RightY, -20000)),
+        ];
+        km.apply(&mut es);
+        let key = |code, kind| Event::Key(KeyEvent::new_with_kind(code, KeyModifiers::NONE, kind));
+        assert_eq!(
+            es,
+            vec![
+                key(KeyCode::Enter, KeyEventKind::Press),
+                key(KeyCode::Enter, KeyEventKind::Release),
+                Event::Resize(10, 10),
+                key(KeyCode::Right, KeyEventKind::Press),
+                key(KeyCode::Right, KeyEventKind::Release),
+                key(KeyCode::Up, KeyEventKind::Press),
+            ]
+        );
+    }
+    #[test]
+    fn keymap_drops_releases_by_default() {
+        let mut km = GamepadKeyMap::new();
+        let ev = |kind| Event::Gamepad(GamepadEvent::new(1, kind));
+        let mut es = vec![
+            ev(GamepadEventKind::ButtonDown(GamepadButton::DPadUp)),
+            ev(GamepadEventKind::ButtonUp(GamepadButton::DPadUp)),
+            ev(GamepadEventKind::Axis(GamepadAxis::LeftY, 30000)),
+            ev(GamepadEventKind::Axis(GamepadAxis::LeftY, 0)),
+        ];
+        km.apply(&mut es);
+        let key = |code| {
+            Event::Key(KeyEvent::new_with_kind(
+                code,
+                KeyModifiers::NONE,
+                KeyEventKind::Press,
+            ))
+        };
+        assert_eq!(es, vec![key(KeyCode::Up), key(KeyCode::Down)]);
+    }
+}
@@ -5,18 +5,39 @@
 //! Input events triggered by renders adapter such as web, sdl or cross are converted here to
 //! unified Event
 
+use crate::event::GamepadEvent;
 use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 
-#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash)]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
 pub enum Event {
     /// A single key event with additional pressed modifiers.
     Key(KeyEvent),
     /// A single mouse event with additional pressed modifiers.
     Mouse(MouseEvent),
+    /// The screen was resized to (columns, rows).
+    Resize(u16, u16),
+    /// A gamepad button, axis or connection event, sdl mode only.
+    Gamepad(GamepadEvent),
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+/// collapses runs of consecutive mouse Moved events to the last one,
+/// other events (clicks, drags, keys) are kept in order
+/// Game calls it every tick, a fast moving mouse can queue hundreds of moves
+pub fn coalesce_mouse_moves(events: &mut Vec<Event>) {
+    let is_move = |e: &Event| matches!(e, Event::Mouse(m) if m.kind == MouseEventKind::Moved);
+    let es = std::mem::take(events);
+    for e in es {
+        if is_move(&e) && events.last().is_some_and(is_move) {
+            *events.last_mut().unwrap() = e;
+        } else {
+            events.push(e);
+        }
+    }
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub struct MouseEvent {
     /// The kind of mouse event that was caused.
     pub kind: MouseEventKind,
@@ -28,7 +49,7 @@ pub struct MouseEvent {
     pub modifiers: KeyModifiers,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum MouseEventKind {
     /// Pressed mouse button. Contains the button that was pressed.
     Down(MouseButton),
@@ -40,7 +61,7 @@ pub enum MouseEventKind {
     Moved,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum MouseButton {
     /// Left mouse button.
     Left,
@@ -53,7 +74,7 @@ pub enum MouseButton {
 bitflags! {
     /// Represents key modifiers (shift, control, alt, etc.).
     ///
-    #[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+    #[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
     pub struct KeyModifiers: u8 {
         const SHIFT = 0b0000_0001;
         const CONTROL = 0b0000_0010;
@@ -65,7 +86,7 @@ bitflags! {
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum KeyEventKind {
     Press,
     Repeat,
@@ -74,7 +95,7 @@ pub enum KeyEventKind {
 
 bitflags! {
     /// Represents extra state about the key event.
-    #[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+    #[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
     pub struct KeyEventState: u8 {
         /// The key event origins from the keypad.
         const KEYPAD = 0b0000_0001;
@@ -87,7 +108,7 @@ bitflags! {
 }
 
 /// Represents a key event.
-#[derive(Debug, PartialOrd, Clone, Copy)]
+#[derive(Debug, PartialOrd, Clone, Copy, Serialize, Deserialize)]
 pub struct KeyEvent {
     /// The key itself.
     pub code: KeyCode,
@@ -204,7 +225,7 @@ impl Hash for KeyEvent {
 }
 
 /// Represents a modifier key (as part of [`KeyCode::Modifier`]).
-#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum ModifierKeyCode {
     /// Left Shift key.
     LeftShift,
@@ -233,7 +254,7 @@ pub enum ModifierKeyCode {
 }
 
 /// Represents a key.
-#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum KeyCode {
     /// Backspace key.
     Backspace,
@@ -290,3 +311,48 @@ pub enum KeyCode {
     /// A modifier key.
     Modifier(ModifierKeyCode),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mouse(kind: MouseEventKind, column: u16) -> Event {
+        Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row: 1,
+            modifiers: KeyModifiers::NONE,
+        })
+    }
+
+    #[test]
+    fn moves_collapse_to_last() {
+        let mut es: Vec<Event> = (0..10).map(|x| mouse(MouseEventKind::Moved, x)).collect();
+        es.push(mouse(MouseEventKind::Down(MouseButton::Left), 9));
+        coalesce_mouse_moves(&mut es);
+        assert_eq!(
+            es,
+            [
+                mouse(MouseEventKind::Moved, 9),
+                mouse(MouseEventKind::Down(MouseButton::Left), 9)
+            ]
+        );
+        // moves around a key are not merged across it
+        let key = Event::Key(KeyCode::Enter.into());
+        let mut es = vec![
+            mouse(MouseEventKind::Moved, 1),
+            key.clone(),
+            mouse(MouseEventKind::Moved, 2),
+            mouse(MouseEventKind::Moved, 3),
+        ];
+        coalesce_mouse_moves(&mut es);
+        assert_eq!(
+            es,
+            [
+                mouse(MouseEventKind::Moved, 1),
+                key,
+                mouse(MouseEventKind::Moved, 3)
+            ]
+        );
+    }
+}
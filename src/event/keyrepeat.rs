@@ -0,0 +1,176 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! KeyRepeat makes key repeats look the same on every adapter:
+//! the first key down is a Press, held down it gives Repeat events
+//!
+//! - sdl and web report repeats, the adapters mark them as Repeat
+//! - terminals only send the same Press again, a Press of the same key
+//!   within 2 * interval of the last one is turned into Repeat
+//! - with synthesize, Repeat events are made from timing while a key is
+//!   held, after delay and then every interval, for adapters reporting
+//!   Release but no repeats
+//!
+//! Game applies context.key_repeat to the input events every tick. Games
+//! checking only key.code see repeats as presses, as before.
+
+use crate::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+pub struct KeyRepeat {
+    /// seconds before the first synthesized repeat
+    pub delay: f64,
+    /// seconds between repeats
+    pub interval: f64,
+    pub synthesize: bool,
+    // key held down and the time of its next repeat
+    held: Option<(KeyEvent, f64)>,
+    // last pressed key and when
+    last: Option<(KeyCode, KeyModifiers, f64)>,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self {
+            delay: 0.5,
+            interval: 0.05,
+            synthesize: false,
+            held: None,
+            last: None,
+        }
+    }
+}
+
+impl KeyRepeat {
+    pub fn new(delay: f64, interval: f64, synthesize: bool) -> Self {
+        Self {
+            delay,
+            interval,
+            synthesize,
+            ..Default::default()
+        }
+    }
+
+    /// marks and adds repeats in events, now is in seconds, e.g. context.time
+    pub fn apply(&mut self, events: &mut Vec<Event>, now: f64) {
+        let es = std::mem::take(events);
+        for e in es {
+            let mut k = match e {
+                Event::Key(k) => k,
+                _ => {
+                    events.push(e);
+                    continue;
+                }
+            };
+            let same = |h: &Option<(KeyEvent, f64)>| h.is_some_and(|h| h.0.code == k.code);
+            match k.kind {
+                KeyEventKind::Press => {
+                    let again = self.last.is_some_and(|(c, m, t)| {
+                        c == k.code && m == k.modifiers && now - t <= self.interval * 2.0
+                    });
+                    // without releases a key never looks held
+                    if again || (self.synthesize && same(&self.held)) {
+                        k.kind = KeyEventKind::Repeat;
+                    } else {
+                        self.held = Some((k, now + self.delay));
+                    }
+                    self.last = Some((k.code, k.modifiers, now));
+                }
+                KeyEventKind::Repeat => {
+                    // reported by the adapter, keep the synthesizer quiet
+                    if let Some(h) = &mut self.held {
+                        h.1 = now + self.delay;
+                    }
+                    self.last = Some((k.code, k.modifiers, now));
+                }
+                KeyEventKind::Release => {
+                    if same(&self.held) {
+                        self.held = None;
+                    }
+                    self.last = None;
+                }
+            }
+            events.push(Event::Key(k));
+        }
+        if !self.synthesize {
+            return;
+        }
+        if let Some((k, next)) = &mut self.held {
+            if now >= *next {
+                let mut r = *k;
+                r.kind = KeyEventKind::Repeat;
+                events.push(Event::Key(r));
+                *next += self.interval;
+                // a long frame gives one repeat, not a burst
+                if *next < now {
+                    *next = now + self.interval;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(es: &[Event]) -> Vec<KeyEventKind> {
+        es.iter()
+            .filter_map(|e| match e {
+                Event::Key(k) => Some(k.kind),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn repeats_from_timing() {
+        use KeyEventKind::*;
+        let a = |kind| {
+            Event::Key(KeyEvent::new_with_kind(
+                KeyCode::Char('a'),
+                KeyModifiers::NONE,
+                kind,
+            ))
+        };
+
+        // terminal: fast presses of the same key are repeats
+        let mut kr = KeyRepeat::new(0.5, 0.05, false);
+        let mut script = vec![];
+        for t in [0.0, 0.5, 0.53, 0.56, 1.5] {
+            let mut es = vec![a(Press)];
+            kr.apply(&mut es, t);
+            script.extend(kinds(&es));
+        }
+        assert_eq!(script, [Press, Press, Repeat, Repeat, Press]);
+
+        // release reported, repeats synthesized after delay, every interval
+        let mut kr = KeyRepeat::new(0.5, 0.25, true);
+        let mut got = vec![];
+        for (t, input) in [
+            (0.0, Some(Press)),
+            (0.25, None),
+            (0.5, None),
+            (0.625, None),
+            (0.75, None),
+            (2.0, None),
+            (2.125, Some(Release)),
+            (2.5, None),
+        ] {
+            let mut es: Vec<Event> = input.map(a).into_iter().collect();
+            kr.apply(&mut es, t);
+            for k in kinds(&es) {
+                got.push((t, k));
+            }
+        }
+        assert_eq!(
+            got,
+            [
+                (0.0, Press),
+                (0.5, Repeat),
+                (0.75, Repeat),
+                (2.0, Repeat),
+                (2.125, Release)
+            ]
+        );
+    }
+}
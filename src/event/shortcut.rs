@@ -0,0 +1,295 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Shortcuts maps key combinations and chords to action ids,
+//! so tools can register shortcuts instead of checking modifiers by hand
+//!
+//! A binding is written as modifiers and key joined by '+', e.g. "ctrl+s",
+//! "ctrl+shift+z", "f5". A chord is bindings separated by space, e.g. "g g".
+//! KeyBinding prints back in the same form, for key binding tables and menus.
+//!
+//! # Example
+//!
+//! let mut sc = Shortcuts::new();
+//! sc.register("ctrl+s", "save").unwrap();
+//! sc.register("g g", "goto_top").unwrap();
+//! for action in sc.process(&ctx.input_events) {
+//!     ...
+//! }
+
+use crate::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::{fmt, str::FromStr};
+
+// modifiers in the order they are printed
+const MOD_NAMES: [(KeyModifiers, &str); 5] = [
+    (KeyModifiers::CONTROL, "ctrl"),
+    (KeyModifiers::ALT, "alt"),
+    (KeyModifiers::SHIFT, "shift"),
+    (KeyModifiers::SUPER, "super"),
+    (KeyModifiers::META, "meta"),
+];
+
+/// a key with modifiers, chars are matched case insensitive,
+/// an uppercase char is the same as shift + char
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }.normalize()
+    }
+
+    fn normalize(mut self) -> Self {
+        if let KeyCode::Char(c) = self.code {
+            if c.is_ascii_uppercase() {
+                self.modifiers.insert(KeyModifiers::SHIFT);
+            }
+            self.code = KeyCode::Char(c.to_ascii_lowercase());
+        }
+        self
+    }
+
+    /// parses "ctrl+shift+z", "alt+enter", "f5", "g"...
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let parts: Vec<&str> = s.split('+').map(|p| p.trim()).collect();
+        let (key, mods) = parts.split_last().unwrap();
+        for m in mods {
+            modifiers.insert(match m.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "shift" => KeyModifiers::SHIFT,
+                "alt" | "option" => KeyModifiers::ALT,
+                "super" | "cmd" => KeyModifiers::SUPER,
+                "meta" => KeyModifiers::META,
+                _ => return Err(format!("unknown modifier {:?} in {:?}", m, s)),
+            });
+        }
+        let code = match key.to_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "plus" => KeyCode::Char('+'),
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            k => {
+                let mut cs = k.chars();
+                match (cs.next(), cs.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    (Some('f'), Some(_)) => match k[1..].parse::<u8>() {
+                        Ok(n) => KeyCode::F(n),
+                        Err(_) => return Err(format!("unknown key {:?} in {:?}", key, s)),
+                    },
+                    _ => return Err(format!("unknown key {:?} in {:?}", key, s)),
+                }
+            }
+        };
+        Ok(Self::new(code, modifiers))
+    }
+
+    pub fn matches(&self, e: &KeyEvent) -> bool {
+        *self == KeyBinding::new(e.code, e.modifiers)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    /// lowercase, modifiers as ctrl+alt+shift+super+meta, parse gives it back
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (m, name) in MOD_NAMES {
+            if self.modifiers.contains(m) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char('+') => write!(f, "plus"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "backtab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            c => f.write_str(&format!("{:?}", c).to_lowercase()),
+        }
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// registry of shortcuts, fed by input events
+#[derive(Debug, Default)]
+pub struct Shortcuts {
+    bindings: Vec<(Vec<KeyBinding>, String)>,
+    // chord keys typed so far
+    pending: Vec<KeyBinding>,
+}
+
+impl Shortcuts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// keys is a binding or a chord of bindings separated by space
+    pub fn register(&mut self, keys: &str, action: &str) -> Result<(), String> {
+        let seq = keys
+            .split_whitespace()
+            .map(KeyBinding::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if seq.is_empty() {
+            return Err("empty shortcut".to_string());
+        }
+        self.bindings.push((seq, action.to_string()));
+        Ok(())
+    }
+
+    fn lookup(&self) -> (Option<&str>, bool) {
+        let mut is_prefix = false;
+        for (seq, action) in &self.bindings {
+            if *seq == self.pending {
+                return (Some(action), false);
+            }
+            if seq.starts_with(&self.pending) {
+                is_prefix = true;
+            }
+        }
+        (None, is_prefix)
+    }
+
+    /// returns the action if the event completes a shortcut
+    pub fn feed(&mut self, e: &Event) -> Option<String> {
+        let ke = match e {
+            Event::Key(ke) if ke.kind != KeyEventKind::Release => ke,
+            _ => return None,
+        };
+        // pressing a modifier alone keeps the chord going
+        if let KeyCode::Modifier(_) = ke.code {
+            return None;
+        }
+        let key = KeyBinding::new(ke.code, ke.modifiers);
+        self.pending.push(key);
+        // a broken chord may still start a new one with this key
+        for retry in [false, true] {
+            if retry {
+                self.pending = vec![key];
+            }
+            match self.lookup() {
+                (Some(action), _) => {
+                    let action = action.to_string();
+                    self.pending.clear();
+                    return Some(action);
+                }
+                (None, true) => return None,
+                (None, false) => {}
+            }
+        }
+        self.pending.clear();
+        None
+    }
+
+    /// feeds all events, returns triggered actions in order
+    pub fn process(&mut self, events: &[Event]) -> Vec<String> {
+        events.iter().filter_map(|e| self.feed(e)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, m: KeyModifiers) -> Event {
+        Event::Key(KeyEvent::new(code, m))
+    }
+
+    #[test]
+    fn ctrl_s_matches_plain_s_not() {
+        let b = KeyBinding::parse("ctrl+s").unwrap();
+        assert!(b.matches(&KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL)));
+        assert!(!b.matches(&KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE)));
+        let redo = KeyBinding::parse("Ctrl+Shift+Z").unwrap();
+        assert!(redo.matches(&KeyEvent::new(KeyCode::Char('Z'), KeyModifiers::CONTROL)));
+        assert!(!redo.matches(&KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL)));
+        assert_eq!(KeyBinding::parse("f5").unwrap().code, KeyCode::F(5));
+        assert!(KeyBinding::parse("hyper+x").is_err());
+
+        let mut sc = Shortcuts::new();
+        sc.register("ctrl+s", "save").unwrap();
+        let es = vec![
+            key(KeyCode::Char('s'), KeyModifiers::NONE),
+            key(KeyCode::Char('s'), KeyModifiers::CONTROL),
+        ];
+        assert_eq!(sc.process(&es), vec!["save"]);
+    }
+
+    #[test]
+    fn binding_prints_back() {
+        for s in [
+            "ctrl+s",
+            "ctrl+shift+z",
+            "alt+enter",
+            "f12",
+            "shift+tab",
+            "backtab",
+            "ctrl+space",
+            "ctrl+plus",
+            "super+pageup",
+        ] {
+            let b: KeyBinding = s.parse().unwrap();
+            assert_eq!(b.to_string(), s);
+        }
+        // normalized to the canonical form
+        let b = KeyBinding::parse("Shift+Ctrl+Z").unwrap();
+        assert_eq!(b.to_string(), "ctrl+shift+z");
+        let b = KeyBinding::new(KeyCode::Char('A'), KeyModifiers::ALT);
+        assert_eq!(b.to_string(), "alt+shift+a");
+        assert_eq!(b.to_string().parse::<KeyBinding>(), Ok(b));
+    }
+
+    #[test]
+    fn chord_sequence() {
+        let mut sc = Shortcuts::new();
+        sc.register("g g", "top").unwrap();
+        sc.register("g e", "end").unwrap();
+        sc.register("x", "cut").unwrap();
+        let g = key(KeyCode::Char('g'), KeyModifiers::NONE);
+        let e = key(KeyCode::Char('e'), KeyModifiers::NONE);
+        let x = key(KeyCode::Char('x'), KeyModifiers::NONE);
+        assert_eq!(sc.process(&[g.clone(), g.clone()]), vec!["top"]);
+        assert_eq!(sc.process(&[g.clone(), e.clone()]), vec!["end"]);
+        // broken chord, x still works on its own
+        assert_eq!(
+            sc.process(&[g.clone(), x, g.clone(), g]),
+            vec!["cut", "top"]
+        );
+        assert!(sc.process(&[e]).is_empty());
+    }
+}
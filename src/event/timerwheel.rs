@@ -0,0 +1,220 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! TimerWheel keeps many one-shot timers, e.g. tower spawns, cooldowns and
+//! effect durations, without checking every timer each frame
+//!
+//! Timers are bucketed into slots of resolution seconds by their due time,
+//! advance(dt) only visits the slots the wheel passes, so its cost follows
+//! the timers fired rather than the timers waiting. A timer due more than one
+//! turn of the wheel away waits in its slot until its turn comes round.
+//!
+//! A timer fires when the elapsed time reaches its due time, timers fired in
+//! the same advance go in due order, then in schedule order. Each one either
+//! emits an event through event_emit, or calls a closure.
+//!
+//! Unlike the global Timers it is a plain value, a model owns as many as it
+//! needs and advances them with dt from update.
+//!
+//! # Example
+//! ```
+//! use rust_pixel::event::{event_check, event_register, TimerWheel};
+//! let mut tw = TimerWheel::default();
+//! event_register("Tower.Spawn", "spawn");
+//! tw.schedule(0.5, "Tower.Spawn");
+//! tw.advance(0.6);
+//! assert!(event_check("Tower.Spawn", "spawn"));
+//! ```
+
+use crate::{event::event_emit, GAME_FRAME};
+use std::collections::HashMap;
+
+pub type TimerId = u64;
+
+/// what a timer does when it fires
+pub enum TimerAction {
+    Emit(String),
+    Call(Box<dyn FnMut()>),
+}
+
+struct WheelTimer {
+    id: TimerId,
+    due: f64,
+    tick: u64,
+    action: TimerAction,
+}
+
+pub struct TimerWheel {
+    /// seconds per slot
+    pub resolution: f32,
+    slots: Vec<Vec<WheelTimer>>,
+    // slot of each waiting timer, for cancel
+    index: HashMap<TimerId, usize>,
+    now: f64,
+    // tick of now, slots before it are done
+    cursor: u64,
+    next_id: TimerId,
+}
+
+impl Default for TimerWheel {
+    /// a slot per frame, 256 frames a turn
+    fn default() -> Self {
+        Self::new(1.0 / GAME_FRAME as f32, 256)
+    }
+}
+
+impl TimerWheel {
+    pub fn new(resolution: f32, slots: usize) -> Self {
+        assert!(resolution > 0.0 && slots > 0);
+        Self {
+            resolution,
+            slots: (0..slots).map(|_| vec![]).collect(),
+            index: HashMap::new(),
+            now: 0.0,
+            cursor: 0,
+            next_id: 0,
+        }
+    }
+
+    /// emits event after delay seconds
+    pub fn schedule(&mut self, delay: f32, event: &str) -> TimerId {
+        self.schedule_action(delay, TimerAction::Emit(event.to_string()))
+    }
+
+    /// calls f after delay seconds
+    pub fn schedule_fn<F: FnMut() + 'static>(&mut self, delay: f32, f: F) -> TimerId {
+        self.schedule_action(delay, TimerAction::Call(Box::new(f)))
+    }
+
+    pub fn schedule_action(&mut self, delay: f32, action: TimerAction) -> TimerId {
+        let id = self.next_id;
+        self.next_id += 1;
+        let due = self.now + delay.max(0.0) as f64;
+        let tick = self.tick_of(due).max(self.cursor);
+        let slot = (tick % self.slots.len() as u64) as usize;
+        self.slots[slot].push(WheelTimer {
+            id,
+            due,
+            tick,
+            action,
+        });
+        self.index.insert(id, slot);
+        id
+    }
+
+    /// removes a waiting timer, false if it fired already or never existed
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        match self.index.remove(&id) {
+            Some(slot) => {
+                self.slots[slot].retain(|t| t.id != id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// advances dt seconds, runs the due timers and returns their ids in firing order
+    pub fn advance(&mut self, dt: f32) -> Vec<TimerId> {
+        self.now += dt.max(0.0) as f64;
+        let target = self.tick_of(self.now);
+        let n = self.slots.len() as u64;
+        // the cursor slot is visited again, it may hold timers later in its tick
+        let end = target.min(self.cursor + n - 1);
+        let mut fired = vec![];
+        for tick in self.cursor..=end {
+            let slot = &mut self.slots[(tick % n) as usize];
+            let mut i = 0;
+            while i < slot.len() {
+                if slot[i].tick <= target && slot[i].due <= self.now {
+                    fired.push(slot.swap_remove(i));
+                } else {
+                    i += 1;
+                }
+            }
+        }
+        self.cursor = target;
+        fired.sort_by(|a, b| a.due.total_cmp(&b.due).then(a.id.cmp(&b.id)));
+        let mut ids = vec![];
+        for mut t in fired {
+            self.index.remove(&t.id);
+            match &mut t.action {
+                TimerAction::Emit(e) => event_emit(e),
+                TimerAction::Call(f) => f(),
+            }
+            ids.push(t.id);
+        }
+        ids
+    }
+
+    /// seconds advanced so far
+    pub fn now(&self) -> f64 {
+        self.now
+    }
+
+    /// waiting timers
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    fn tick_of(&self, t: f64) -> u64 {
+        (t / self.resolution as f64).floor() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, rc::Rc};
+
+    #[test]
+    fn fires_in_due_order_with_small_steps() {
+        // 8 slots of 0.05s, a turn is 0.4s
+        let mut tw = TimerWheel::new(0.05, 8);
+        let delays = [0.5, 0.1, 0.3, 1.25, 0.3, 0.0, 0.72];
+        let ids: Vec<TimerId> = delays
+            .iter()
+            .map(|d| tw.schedule(*d, "Wheel.Test"))
+            .collect();
+        let calls = Rc::new(Cell::new(0));
+        let c = calls.clone();
+        let f = tw.schedule_fn(0.2, move || c.set(c.get() + 1));
+        let cancelled = tw.schedule(0.15, "Wheel.Test");
+        assert!(tw.cancel(cancelled));
+        assert!(!tw.cancel(cancelled));
+        assert_eq!(tw.len(), 8);
+
+        let mut order = vec![];
+        for _ in 0..100 {
+            let before = tw.now();
+            for id in tw.advance(0.02) {
+                let d = if id == f {
+                    0.2
+                } else {
+                    delays[id as usize] as f64
+                };
+                // fires in the step that reaches its due time
+                assert!(
+                    d <= tw.now() + 1e-6 && d > before - 1e-6,
+                    "{} at {}",
+                    d,
+                    tw.now()
+                );
+                order.push(id);
+            }
+        }
+        assert!(tw.is_empty());
+        assert_eq!(calls.get(), 1);
+        // ties keep schedule order
+        let expect = [ids[5], ids[1], f, ids[2], ids[4], ids[0], ids[6], ids[3]];
+        assert_eq!(order, expect);
+
+        // a long frame fires everything it passes, still in order
+        let a = tw.schedule(3.0, "Wheel.Test");
+        let b = tw.schedule(0.7, "Wheel.Test");
+        assert_eq!(tw.advance(10.0), [b, a]);
+    }
+}
@@ -20,10 +20,16 @@
 //!    Ok(())
 //! }
 
-use crate::{context::Context, event::timer_update, log::init_log, GAME_FRAME, LOGO_FRAME};
-use log::info;
+use crate::{
+    context::Context,
+    event::{coalesce_mouse_moves, timer_update},
+    log::init_log,
+    GAME_FRAME, LOGO_FRAME,
+};
+use log::{info, warn};
 use std::{
-    io,
+    fs, io,
+    path::Path,
     time::{Duration, Instant},
 };
 
@@ -45,6 +51,20 @@ pub trait Model {
     fn handle_event(&mut self, ctx: &mut Context, dt: f32);
     fn handle_input(&mut self, ctx: &mut Context, dt: f32);
     fn handle_auto(&mut self, ctx: &mut Context, dt: f32);
+
+    /// snapshot of the game state for autosave and checkpoints,
+    /// None (the default) means the model is not saved
+    fn save(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// restores a snapshot made by save
+    fn load(&mut self, _data: &[u8]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// starts a new game, see Game::reset
+    fn reset(&mut self, _ctx: &mut Context) {}
 }
 
 /// The Render interface, takes context and model as input params. It renders every single frame
@@ -71,6 +91,10 @@ where
     pub context: Context,
     pub model: M,
     pub render: R,
+    /// session being recorded, see start_record
+    pub recorder: Option<Replay>,
+    /// periodic checkpoint of the model, see set_autosave
+    pub autosave: Option<AutoSave<M>>,
 }
 
 impl<M, R> Game<M, R>
@@ -90,6 +114,8 @@ where
             context: ctx,
             model: m,
             render: r,
+            recorder: None,
+            autosave: None,
         }
     }
 
@@ -126,17 +152,92 @@ where
 
     /// calls every frame, update timer, model logic and does rendering
     pub fn on_tick(&mut self, dt: f32) {
+        coalesce_mouse_moves(&mut self.context.input_events);
+        self.context.asset_manager.poll_preload();
+        if let Some(r) = &mut self.recorder {
+            r.push_frame(dt, &self.context.input_events);
+        }
         self.context.stage += 1;
+        self.context.dt = dt;
+        self.context.time += dt as f64;
+        self.context
+            .key_repeat
+            .apply(&mut self.context.input_events, self.context.time);
+        self.context.debug.update(&self.context.input_events, dt);
         self.model.update(&mut self.context, dt);
         self.render.update(&mut self.context, &mut self.model, dt);
+        if let Some(a) = &mut self.autosave {
+            if a.tick(dt) {
+                if let Err(e) = a.write(&self.model) {
+                    warn!("autosave {:?} failed: {}", a.path, e);
+                }
+            }
+        }
     }
 
     /// init render and model
+    /// in terminal mode also installs a panic hook restoring the terminal
     pub fn init(&mut self) {
         info!("Init game...");
+        #[cfg(not(any(
+            feature = "sdl",
+            target_os = "android",
+            target_os = "ios",
+            target_arch = "wasm32"
+        )))]
+        crate::render::adapter::cross::install_panic_hook();
         self.model.init(&mut self.context);
         self.render.init(&mut self.context, &mut self.model);
     }
+
+    /// seeds context.rand and starts recording every tick,
+    /// call it before init so that init uses the seeded rand too
+    /// state is an optional model snapshot saved with the replay
+    pub fn start_record(&mut self, seed: u64, state: Vec<u8>) {
+        self.context.rand.srand(seed);
+        self.recorder = Some(Replay::new(&self.context.game_name, seed, state));
+    }
+
+    pub fn stop_record(&mut self) -> Option<Replay> {
+        self.recorder.take()
+    }
+
+    /// inits and plays back a recorded session tick by tick, without waiting
+    /// call it on a freshly created game instead of init, restore model
+    /// from replay.state first if the game saved one
+    pub fn replay(&mut self, replay: &Replay) {
+        info!("Replay {} frames...", replay.frames.len());
+        self.context.rand.srand(replay.seed);
+        self.init();
+        for f in &replay.frames {
+            self.context.input_events = f.events.clone();
+            self.on_tick(f.dt);
+        }
+    }
+
+    /// new game, resets the model without restarting the engine
+    pub fn reset(&mut self) {
+        self.model.reset(&mut self.context);
+    }
+
+    /// saves a checkpoint of the model to path every interval seconds,
+    /// models not implementing Model::save are never written
+    pub fn set_autosave<P: AsRef<Path>>(&mut self, interval: f32, path: P) {
+        self.autosave = Some(AutoSave::new(interval, path));
+    }
+
+    /// loads the model from a checkpoint, call it before init,
+    /// Ok(false) if there is no checkpoint yet
+    pub fn load_checkpoint<P: AsRef<Path>>(&mut self, path: P) -> Result<bool, String> {
+        let data = match fs::read(path.as_ref()) {
+            Ok(d) => d,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.to_string()),
+        };
+        self.model.load(&data)?;
+        info!("Load checkpoint {:?}...", path.as_ref());
+        Ok(true)
+    }
 }
 
 #[macro_export]
@@ -160,3 +261,12 @@ macro_rules! only_graphics_mode {
         }
     };
 }
+
+mod scene;
+pub use scene::*;
+mod replay;
+pub use replay::*;
+mod autosave;
+pub use autosave::*;
+mod ecs;
+pub use ecs::*;
@@ -0,0 +1,176 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! AutoSave writes a checkpoint of the model every interval seconds,
+//! so game progress survives a crash
+//!
+//! The model implements Model::save and load, the bytes are written to a temp file
+//! next to the checkpoint and then renamed over it, so a crash while writing
+//! never leaves a broken checkpoint.
+//!
+//! # Example
+//!
+//! let mut g = Game::new(m, r, "tower", &pp);
+//! let _ = g.load_checkpoint("save/tower.sav");
+//! g.set_autosave(30.0, "save/tower.sav");
+//! g.init();
+//! g.run()?;
+
+use crate::game::Model;
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// writes data to path.tmp first, then renames it to path
+pub fn write_atomic<P: AsRef<Path>>(path: P, data: &[u8]) -> io::Result<()> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        if !dir.as_os_str().is_empty() {
+            fs::create_dir_all(dir)?;
+        }
+    }
+    let mut tmp = OsString::from(path.as_os_str());
+    tmp.push(".tmp");
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, path)
+}
+
+pub struct AutoSave<M> {
+    /// seconds between checkpoints
+    pub interval: f32,
+    pub path: PathBuf,
+    elapsed: f32,
+    save: fn(&M) -> Option<Vec<u8>>,
+}
+
+impl<M: Model> AutoSave<M> {
+    pub fn new<P: AsRef<Path>>(interval: f32, path: P) -> Self {
+        Self {
+            interval,
+            path: path.as_ref().to_path_buf(),
+            elapsed: 0.0,
+            save: M::save,
+        }
+    }
+}
+
+impl<M> AutoSave<M> {
+    /// adds dt, true when a checkpoint is due
+    pub fn tick(&mut self, dt: f32) -> bool {
+        self.elapsed += dt;
+        if self.elapsed < self.interval {
+            return false;
+        }
+        self.elapsed = 0.0;
+        true
+    }
+
+    /// does nothing if the model is not saved
+    pub fn write(&self, m: &M) -> io::Result<()> {
+        match (self.save)(m) {
+            Some(data) => write_atomic(&self.path, &data),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::Context,
+        game::{Game, Model, Render},
+    };
+
+    #[derive(Default)]
+    struct Counter {
+        ticks: u32,
+    }
+
+    impl Model for Counter {
+        fn init(&mut self, _ctx: &mut Context) {}
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) {
+            self.ticks += 1;
+        }
+        fn handle_timer(&mut self, _ctx: &mut Context, _dt: f32) {}
+        fn handle_event(&mut self, _ctx: &mut Context, _dt: f32) {}
+        fn handle_input(&mut self, _ctx: &mut Context, _dt: f32) {}
+        fn handle_auto(&mut self, _ctx: &mut Context, _dt: f32) {}
+
+        fn save(&self) -> Option<Vec<u8>> {
+            Some(self.ticks.to_le_bytes().to_vec())
+        }
+
+        fn load(&mut self, data: &[u8]) -> Result<(), String> {
+            let b: [u8; 4] = data.try_into().map_err(|_| "bad counter data")?;
+            self.ticks = u32::from_le_bytes(b);
+            Ok(())
+        }
+
+        fn reset(&mut self, _ctx: &mut Context) {
+            self.ticks = 0;
+        }
+    }
+
+    struct NoRender;
+
+    impl Render for NoRender {
+        type Model = Counter;
+        fn init(&mut self, _ctx: &mut Context, _m: &mut Counter) {}
+        fn handle_event(&mut self, _ctx: &mut Context, _m: &mut Counter, _dt: f32) {}
+        fn handle_timer(&mut self, _ctx: &mut Context, _m: &mut Counter, _dt: f32) {}
+        fn draw(&mut self, _ctx: &mut Context, _m: &mut Counter, _dt: f32) {}
+    }
+
+    fn new_game() -> Game<Counter, NoRender> {
+        Game {
+            context: Context::new("autosave_test", "."),
+            model: Counter::default(),
+            render: NoRender,
+            recorder: None,
+            autosave: None,
+        }
+    }
+
+    #[test]
+    fn autosave_fires_at_interval_and_loads() {
+        let path = std::env::temp_dir().join("rust_pixel_autosave_test.sav");
+        let _ = fs::remove_file(&path);
+        let mut g = new_game();
+        g.set_autosave(1.0, &path);
+        for _ in 0..3 {
+            g.on_tick(0.25);
+        }
+        assert!(!path.exists());
+        g.on_tick(0.25);
+        assert_eq!(fs::read(&path).unwrap(), 4u32.to_le_bytes());
+        for _ in 0..6 {
+            g.on_tick(0.25);
+        }
+        // saved again at tick 8, ticks 9 and 10 are not saved yet
+        assert_eq!(fs::read(&path).unwrap(), 8u32.to_le_bytes());
+
+        let mut g2 = new_game();
+        assert_eq!(g2.load_checkpoint(&path), Ok(true));
+        assert_eq!(g2.model.ticks, 8);
+        let _ = fs::remove_file(&path);
+        assert_eq!(g2.load_checkpoint(&path), Ok(false));
+    }
+
+    #[test]
+    fn model_save_load_reset() {
+        let mut g = new_game();
+        for _ in 0..5 {
+            g.on_tick(0.1);
+        }
+        let data = g.model.save().unwrap();
+        g.reset();
+        assert_eq!(g.model.ticks, 0);
+        g.model.load(&data).unwrap();
+        assert_eq!(g.model.ticks, 5);
+        assert!(g.model.load(&[1, 2]).is_err());
+        assert_eq!(g.model.ticks, 5);
+    }
+}
@@ -0,0 +1,262 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! World is a minimal entity component store, an opt-in alternative
+//! to parallel arrays with MAX_* caps for entity heavy games
+//!
+//! Entities are ids with a generation, so an id of a despawned entity
+//! never reaches the component of a new one reusing its slot.
+//! Every component type has its own storage indexed by entity slot.
+//! Single threaded and without archetypes, keep it simple.
+//!
+//! # Example
+//!
+//! let mut w = World::new();
+//! let m = w.spawn();
+//! w.insert(m, Pos { x: 1, y: 2 });
+//! w.insert(m, Hp(10));
+//! for (e, p, hp) in w.query2::<Pos, Hp>() {
+//!     ...
+//! }
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    pub index: u32,
+    pub generation: u32,
+}
+
+trait AnyStorage {
+    fn remove_slot(&mut self, index: usize);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct Storage<T> {
+    slots: Vec<Option<T>>,
+}
+
+impl<T: 'static> AnyStorage for Storage<T> {
+    fn remove_slot(&mut self, index: usize) {
+        if let Some(s) = self.slots.get_mut(index) {
+            *s = None;
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[derive(Default)]
+pub struct World {
+    // generation of every slot, odd means alive
+    generations: Vec<u32>,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self) -> Entity {
+        let index = match self.free.pop() {
+            Some(i) => i,
+            None => {
+                self.generations.push(0);
+                (self.generations.len() - 1) as u32
+            }
+        };
+        let g = &mut self.generations[index as usize];
+        *g += 1;
+        Entity {
+            index,
+            generation: *g,
+        }
+    }
+
+    pub fn is_alive(&self, e: Entity) -> bool {
+        self.generations.get(e.index as usize) == Some(&e.generation) && e.generation % 2 == 1
+    }
+
+    /// removes the entity and all its components
+    pub fn despawn(&mut self, e: Entity) -> bool {
+        if !self.is_alive(e) {
+            return false;
+        }
+        for s in self.storages.values_mut() {
+            s.remove_slot(e.index as usize);
+        }
+        self.generations[e.index as usize] += 1;
+        self.free.push(e.index);
+        true
+    }
+
+    /// count of alive entities
+    pub fn len(&self) -> usize {
+        self.generations.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn storage<T: 'static>(&self) -> Option<&Storage<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .and_then(|s| s.as_any().downcast_ref())
+    }
+
+    fn storage_mut<T: 'static>(&mut self) -> Option<&mut Storage<T>> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|s| s.as_any_mut().downcast_mut())
+    }
+
+    /// attaches component to entity, returns the old one of the same type
+    pub fn insert<T: 'static>(&mut self, e: Entity, c: T) -> Option<T> {
+        if !self.is_alive(e) {
+            return None;
+        }
+        let s = self
+            .storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Storage::<T> { slots: vec![] }))
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+            .unwrap();
+        let i = e.index as usize;
+        if s.slots.len() <= i {
+            s.slots.resize_with(i + 1, || None);
+        }
+        s.slots[i].replace(c)
+    }
+
+    pub fn get<T: 'static>(&self, e: Entity) -> Option<&T> {
+        if !self.is_alive(e) {
+            return None;
+        }
+        self.storage::<T>()?.slots.get(e.index as usize)?.as_ref()
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, e: Entity) -> Option<&mut T> {
+        if !self.is_alive(e) {
+            return None;
+        }
+        self.storage_mut::<T>()?
+            .slots
+            .get_mut(e.index as usize)?
+            .as_mut()
+    }
+
+    pub fn remove<T: 'static>(&mut self, e: Entity) -> Option<T> {
+        if !self.is_alive(e) {
+            return None;
+        }
+        self.storage_mut::<T>()?
+            .slots
+            .get_mut(e.index as usize)?
+            .take()
+    }
+
+    fn entity(&self, index: usize) -> Entity {
+        Entity {
+            index: index as u32,
+            generation: self.generations[index],
+        }
+    }
+
+    /// entities having component T, in slot order
+    pub fn query<T: 'static>(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.storage::<T>()
+            .into_iter()
+            .flat_map(|s| s.slots.iter().enumerate())
+            .filter_map(move |(i, c)| c.as_ref().map(|c| (self.entity(i), c)))
+    }
+
+    pub fn query_mut<T: 'static>(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        let gens = &self.generations;
+        self.storages
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|s| s.as_any_mut().downcast_mut::<Storage<T>>())
+            .into_iter()
+            .flat_map(|s| s.slots.iter_mut().enumerate())
+            .filter_map(move |(i, c)| {
+                c.as_mut().map(|c| {
+                    let e = Entity {
+                        index: i as u32,
+                        generation: gens[i],
+                    };
+                    (e, c)
+                })
+            })
+    }
+
+    /// entities having both component A and B
+    pub fn query2<A: 'static, B: 'static>(&self) -> impl Iterator<Item = (Entity, &A, &B)> {
+        let sb = self.storage::<B>();
+        self.query::<A>().filter_map(move |(e, a)| {
+            let b = sb?.slots.get(e.index as usize)?.as_ref()?;
+            Some((e, a, b))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Pos(i32, i32);
+    #[derive(Debug, PartialEq)]
+    struct Hp(u32);
+
+    #[test]
+    fn query_entities_with_both() {
+        let mut w = World::new();
+        let a = w.spawn();
+        let b = w.spawn();
+        let c = w.spawn();
+        w.insert(a, Pos(1, 1));
+        w.insert(a, Hp(10));
+        w.insert(b, Pos(2, 2));
+        w.insert(c, Hp(30));
+        w.insert(c, Pos(3, 3));
+        assert_eq!(w.len(), 3);
+
+        let both: Vec<Entity> = w.query2::<Pos, Hp>().map(|(e, _, _)| e).collect();
+        assert_eq!(both, vec![a, c]);
+
+        for (_, p) in w.query_mut::<Pos>() {
+            p.0 += 10;
+        }
+        assert_eq!(w.get::<Pos>(b), Some(&Pos(12, 2)));
+        assert_eq!(w.remove::<Hp>(a), Some(Hp(10)));
+        assert_eq!(w.query2::<Pos, Hp>().count(), 1);
+    }
+
+    #[test]
+    fn despawned_id_is_stale() {
+        let mut w = World::new();
+        let a = w.spawn();
+        w.insert(a, Hp(1));
+        assert!(w.despawn(a));
+        assert!(!w.despawn(a));
+        let b = w.spawn();
+        assert_eq!(b.index, a.index);
+        assert!(w.get::<Hp>(b).is_none());
+        assert!(w.get::<Hp>(a).is_none());
+        assert!(w.insert(a, Hp(2)).is_none());
+        assert!(w.query::<Hp>().next().is_none());
+    }
+}
@@ -0,0 +1,205 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Replay records a game session so it can be played back exactly,
+//! e.g. attached to a bug report
+//!
+//! A replay holds the rng seed, engine version, an optional initial model
+//! snapshot and the input events and dt of every tick. Playback seeds
+//! context.rand again and feeds the same events with the same dt, so a model
+//! using only context.rand for randomness reaches the same state.
+//!
+//! # Example
+//!
+//! g.start_record(seed, vec![]);
+//! g.init();
+//! g.run()?;
+//! g.stop_record().unwrap().save("log/last.replay")?;
+//!
+//! // later...
+//! let r = Replay::load("log/last.replay")?;
+//! g.replay(&r);
+
+use crate::event::Event;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+pub const REPLAY_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// input events and dt of one tick
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    pub dt: f32,
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: String,
+    pub game_name: String,
+    pub seed: u64,
+    /// initial model snapshot serialized by the game itself, can be empty
+    pub state: Vec<u8>,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn new(game_name: &str, seed: u64, state: Vec<u8>) -> Self {
+        Self {
+            version: REPLAY_VERSION.to_string(),
+            game_name: game_name.to_string(),
+            seed,
+            state,
+            frames: vec![],
+        }
+    }
+
+    pub fn push_frame(&mut self, dt: f32, events: &[Event]) {
+        self.frames.push(ReplayFrame {
+            dt,
+            events: events.to_vec(),
+        });
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    /// fails if data is broken or recorded by another engine version
+    pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
+        let r: Replay = bincode::deserialize(data)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))?;
+        if r.version != REPLAY_VERSION {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "replay version {} mismatch engine version {}",
+                    r.version, REPLAY_VERSION
+                ),
+            ));
+        }
+        Ok(r)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::Context,
+        event::{KeyCode, KeyEvent, KeyModifiers},
+        game::{Game, Model, Render},
+        render::{buffer::Buffer, style::Style},
+        util::Rect,
+    };
+
+    const W: u16 = 20;
+    const H: u16 = 10;
+
+    struct Walker {
+        x: u16,
+        y: u16,
+    }
+
+    impl Model for Walker {
+        fn init(&mut self, _ctx: &mut Context) {}
+        fn handle_timer(&mut self, _ctx: &mut Context, _dt: f32) {}
+        fn handle_event(&mut self, _ctx: &mut Context, _dt: f32) {}
+        fn handle_input(&mut self, ctx: &mut Context, _dt: f32) {
+            for e in &ctx.input_events {
+                if let Event::Key(k) = e {
+                    match k.code {
+                        KeyCode::Right => self.x = (self.x + 3) % W,
+                        KeyCode::Down => self.y = (self.y + 2) % H,
+                        _ => {}
+                    }
+                }
+            }
+            ctx.input_events.clear();
+        }
+        fn handle_auto(&mut self, ctx: &mut Context, _dt: f32) {
+            self.x = (self.x + (ctx.rand.rand() % 2) as u16) % W;
+            self.y = (self.y + (ctx.rand.rand() % 2) as u16) % H;
+        }
+    }
+
+    struct WalkerRender {
+        buf: Buffer,
+    }
+
+    impl Render for WalkerRender {
+        type Model = Walker;
+        fn init(&mut self, _ctx: &mut Context, _m: &mut Walker) {}
+        fn handle_event(&mut self, _ctx: &mut Context, _m: &mut Walker, _dt: f32) {}
+        fn handle_timer(&mut self, _ctx: &mut Context, _m: &mut Walker, _dt: f32) {}
+        fn draw(&mut self, ctx: &mut Context, m: &mut Walker, _dt: f32) {
+            let c = char::from(b'a' + (ctx.stage % 26) as u8);
+            self.buf.set_str(m.x, m.y, c.to_string(), Style::default());
+        }
+    }
+
+    fn new_game() -> Game<Walker, WalkerRender> {
+        Game {
+            context: Context::new("replay_test", "."),
+            model: Walker { x: 0, y: 0 },
+            render: WalkerRender {
+                buf: Buffer::empty(Rect::new(0, 0, W, H)),
+            },
+            recorder: None,
+            autosave: None,
+        }
+    }
+
+    #[test]
+    fn replay_reproduces_final_buffer() {
+        let mut g = new_game();
+        g.start_record(20240601, vec![]);
+        g.init();
+        for i in 0..200 {
+            if i % 7 == 0 {
+                g.context.input_events.push(Event::Key(KeyEvent::new(
+                    KeyCode::Right,
+                    KeyModifiers::NONE,
+                )));
+            }
+            if i % 11 == 0 {
+                g.context
+                    .input_events
+                    .push(Event::Key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE)));
+            }
+            g.on_tick(1.0 / 60.0);
+        }
+        let r = g.stop_record().unwrap();
+        assert_eq!(r.frames.len(), 200);
+
+        let path = std::env::temp_dir().join("rust_pixel_replay_test.replay");
+        r.save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded, r);
+
+        let mut g2 = new_game();
+        g2.replay(&loaded);
+        assert_eq!(g2.render.buf, g.render.buf);
+        assert_ne!(g.render.buf, Buffer::empty(Rect::new(0, 0, W, H)));
+    }
+
+    #[test]
+    fn replay_rejects_other_version() {
+        let mut r = Replay::new("replay_test", 1, vec![]);
+        r.version = "0.0.0".to_string();
+        assert!(Replay::from_bytes(&r.to_bytes()).is_err());
+    }
+}
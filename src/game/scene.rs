@@ -0,0 +1,563 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Scene stack for menus, gameplay, pause and game-over screens
+//!
+//! Every scene owns its own model and drawing. SceneStack routes ticks, input
+//! and draw to the top scene only. An overlay scene (e.g. pause menu) keeps the
+//! scenes under it drawn but frozen, they are not updated until it is popped.
+//! A covered scene can opt in with ticks_when_covered, then its timers keep
+//! running (e.g. an animated background under a pause menu).
+//!
+//! Transitions come from the SceneOp returned by update, or from
+//! ctx.push_scene / pop_scene / replace_scene, hooks are called in this order:
+//!   push:    top.on_pause, new.init
+//!   pop:     top.on_exit, below.on_resume
+//!   replace: top.on_exit, new.init
+//!
+//! The stack is hosted by a Model, the Game loop is not changed.
+//! SceneModel and SceneRender wrap a SceneStack into the usual Model/Render pair,
+//! so a scene based game still runs with Game::new(...). A Model can also own
+//! a SceneStack and call its update in handle_input, see apps/template.
+//!
+//! All visible scenes draw into one Panel owned by SceneRender, from the lowest
+//! up to the top, so an overlay is composited over the frozen scene below it.
+//! The panel is flushed to screen once per frame, after the last scene.
+//!
+//! # Example
+//!
+//! let menu = ModelScene::new(MenuModel::new(), MenuDraw::new())
+//!     .with_transition(|m, _ctx| {
+//!         if m.start {
+//!             SceneOp::Push(Box::new(ModelScene::new(PlayModel::new(), PlayDraw::new())))
+//!         } else {
+//!             SceneOp::None
+//!         }
+//!     });
+//! let mut g = Game::new(SceneModel::new(Box::new(menu)), SceneRender::new(), "demo", &pp);
+
+use crate::{
+    context::Context,
+    event::timer_update,
+    game::{Model, Render},
+    render::panel::Panel,
+    LOGO_FRAME,
+};
+
+/// Transition requested by a scene after its update
+pub enum SceneOp {
+    None,
+    /// push a new scene on top, current scene is paused
+    Push(Box<dyn Scene>),
+    /// pop current scene, the one below resumes
+    Pop,
+    /// replace current scene
+    Replace(Box<dyn Scene>),
+}
+
+/// The Scene interface, a scene has its own data, logic and drawing
+pub trait Scene {
+    /// called when the scene enters the stack
+    fn init(&mut self, ctx: &mut Context);
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> SceneOp;
+    /// draws into the panel shared by all visible scenes, don't flush it
+    fn draw(&mut self, ctx: &mut Context, panel: &mut Panel, dt: f32);
+
+    /// overlay scenes keep the underlying scene drawn (frozen)
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    /// called when another scene is pushed over this one
+    fn on_pause(&mut self, _ctx: &mut Context) {}
+
+    /// called when this scene becomes the top again
+    fn on_resume(&mut self, _ctx: &mut Context) {}
+
+    /// called when the scene is popped or replaced
+    fn on_exit(&mut self, _ctx: &mut Context) {}
+
+    /// keep calling update_covered while other scenes are on top
+    fn ticks_when_covered(&self) -> bool {
+        false
+    }
+
+    /// timers of a covered scene, no input is seen here
+    fn update_covered(&mut self, _ctx: &mut Context, _dt: f32) {}
+}
+
+/// Drawing part of a ModelScene, unlike Render it never flushes the panel
+pub trait SceneDraw {
+    type Model: Model;
+
+    fn init(&mut self, _ctx: &mut Context, _m: &mut Self::Model) {}
+    fn draw(&mut self, ctx: &mut Context, m: &mut Self::Model, panel: &mut Panel, dt: f32);
+}
+
+type SceneTransition<M> = Box<dyn FnMut(&mut M, &mut Context) -> SceneOp>;
+
+/// Wraps a Model & SceneDraw pair as a Scene
+/// timer_update is done by SceneModel, so here model handle_* methods are called directly
+pub struct ModelScene<M, R>
+where
+    M: Model,
+    R: SceneDraw<Model = M>,
+{
+    pub model: M,
+    pub render: R,
+    pub overlay: bool,
+    pub covered_timer: bool,
+    transition: Option<SceneTransition<M>>,
+}
+
+impl<M, R> ModelScene<M, R>
+where
+    M: Model,
+    R: SceneDraw<Model = M>,
+{
+    pub fn new(m: M, r: R) -> Self {
+        Self {
+            model: m,
+            render: r,
+            overlay: false,
+            covered_timer: false,
+            transition: None,
+        }
+    }
+
+    pub fn with_overlay(mut self, overlay: bool) -> Self {
+        self.overlay = overlay;
+        self
+    }
+
+    /// keep handle_timer and handle_auto running under other scenes
+    pub fn with_covered_timer(mut self, covered_timer: bool) -> Self {
+        self.covered_timer = covered_timer;
+        self
+    }
+
+    /// closure checked after every update to decide scene switching
+    pub fn with_transition<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&mut M, &mut Context) -> SceneOp + 'static,
+    {
+        self.transition = Some(Box::new(f));
+        self
+    }
+}
+
+impl<M, R> Scene for ModelScene<M, R>
+where
+    M: Model,
+    R: SceneDraw<Model = M>,
+{
+    fn init(&mut self, ctx: &mut Context) {
+        self.model.init(ctx);
+        self.render.init(ctx, &mut self.model);
+    }
+
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> SceneOp {
+        self.model.handle_event(ctx, dt);
+        self.model.handle_timer(ctx, dt);
+        self.model.handle_input(ctx, dt);
+        self.model.handle_auto(ctx, dt);
+        match &mut self.transition {
+            Some(f) => f(&mut self.model, ctx),
+            None => SceneOp::None,
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context, panel: &mut Panel, dt: f32) {
+        self.render.draw(ctx, &mut self.model, panel, dt);
+    }
+
+    fn is_overlay(&self) -> bool {
+        self.overlay
+    }
+
+    fn ticks_when_covered(&self) -> bool {
+        self.covered_timer
+    }
+
+    fn update_covered(&mut self, ctx: &mut Context, dt: f32) {
+        self.model.handle_timer(ctx, dt);
+        self.model.handle_auto(ctx, dt);
+    }
+}
+
+/// Stack of scenes, only the top scene receives ticks and input
+#[derive(Default)]
+pub struct SceneStack {
+    pub scenes: Vec<Box<dyn Scene>>,
+}
+
+impl SceneStack {
+    pub fn new() -> Self {
+        Self { scenes: vec![] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    pub fn top(&mut self) -> Option<&mut Box<dyn Scene>> {
+        self.scenes.last_mut()
+    }
+
+    /// push and init a new scene, pausing the current top
+    pub fn push(&mut self, ctx: &mut Context, mut scene: Box<dyn Scene>) {
+        if let Some(s) = self.scenes.last_mut() {
+            s.on_pause(ctx);
+        }
+        scene.init(ctx);
+        self.scenes.push(scene);
+    }
+
+    /// pop the top scene, resuming the one below
+    pub fn pop(&mut self, ctx: &mut Context) -> Option<Box<dyn Scene>> {
+        let mut s = self.scenes.pop();
+        if let Some(t) = s.as_mut() {
+            t.on_exit(ctx);
+        }
+        if let Some(t) = self.scenes.last_mut() {
+            t.on_resume(ctx);
+        }
+        s
+    }
+
+    /// replace the top scene
+    pub fn replace(&mut self, ctx: &mut Context, mut scene: Box<dyn Scene>) {
+        if let Some(mut s) = self.scenes.pop() {
+            s.on_exit(ctx);
+        }
+        scene.init(ctx);
+        self.scenes.push(scene);
+    }
+
+    pub fn apply(&mut self, ctx: &mut Context, op: SceneOp) {
+        match op {
+            SceneOp::None => {}
+            SceneOp::Push(s) => self.push(ctx, s),
+            SceneOp::Pop => {
+                self.pop(ctx);
+            }
+            SceneOp::Replace(s) => self.replace(ctx, s),
+        }
+    }
+
+    /// tick covered scenes that opt in, update the top scene, then apply
+    /// its transition and the ops queued in ctx
+    pub fn update(&mut self, ctx: &mut Context, dt: f32) {
+        let n = self.scenes.len();
+        for s in self.scenes.iter_mut().take(n.saturating_sub(1)) {
+            if s.ticks_when_covered() {
+                s.update_covered(ctx, dt);
+            }
+        }
+        let op = match self.scenes.last_mut() {
+            Some(s) => s.update(ctx, dt),
+            None => SceneOp::None,
+        };
+        self.apply(ctx, op);
+        let ops: Vec<SceneOp> = ctx.scene_ops.drain(..).collect();
+        for op in ops {
+            self.apply(ctx, op);
+        }
+    }
+
+    /// draw from the lowest visible scene up to the top into panel
+    pub fn draw(&mut self, ctx: &mut Context, panel: &mut Panel, dt: f32) {
+        if self.scenes.is_empty() {
+            return;
+        }
+        let mut start = self.scenes.len() - 1;
+        while start > 0 && self.scenes[start].is_overlay() {
+            start -= 1;
+        }
+        for s in &mut self.scenes[start..] {
+            s.draw(ctx, panel, dt);
+        }
+    }
+}
+
+/// Model holding a SceneStack, so scenes can run in Game<M, R>
+pub struct SceneModel {
+    pub stack: SceneStack,
+    first: Option<Box<dyn Scene>>,
+}
+
+impl SceneModel {
+    pub fn new(first: Box<dyn Scene>) -> Self {
+        Self {
+            stack: SceneStack::new(),
+            first: Some(first),
+        }
+    }
+}
+
+impl Model for SceneModel {
+    fn init(&mut self, ctx: &mut Context) {
+        if let Some(s) = self.first.take() {
+            self.stack.push(ctx, s);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut Context, dt: f32) {
+        if ctx.stage <= LOGO_FRAME {
+            return;
+        }
+        timer_update();
+        self.stack.update(ctx, dt);
+    }
+
+    fn handle_timer(&mut self, _ctx: &mut Context, _dt: f32) {}
+    fn handle_event(&mut self, _ctx: &mut Context, _dt: f32) {}
+    fn handle_input(&mut self, _ctx: &mut Context, _dt: f32) {}
+    fn handle_auto(&mut self, _ctx: &mut Context, _dt: f32) {}
+}
+
+/// Render for SceneModel, composites the visible scenes into one panel
+#[derive(Default)]
+pub struct SceneRender {
+    pub panel: Panel,
+}
+
+impl SceneRender {
+    pub fn new() -> Self {
+        Self {
+            panel: Panel::new(),
+        }
+    }
+}
+
+impl Render for SceneRender {
+    type Model = SceneModel;
+
+    fn init(&mut self, ctx: &mut Context, _m: &mut Self::Model) {
+        self.panel.init(ctx);
+    }
+
+    fn handle_event(&mut self, _ctx: &mut Context, _m: &mut Self::Model, _dt: f32) {}
+    fn handle_timer(&mut self, _ctx: &mut Context, _m: &mut Self::Model, _dt: f32) {}
+
+    fn draw(&mut self, ctx: &mut Context, m: &mut Self::Model, dt: f32) {
+        m.stack.draw(ctx, &mut self.panel, dt);
+        self.panel.draw(ctx).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+    use crate::render::style::Style;
+    use std::{cell::RefCell, rc::Rc};
+
+    struct CountScene {
+        inputs: Rc<RefCell<u32>>,
+        pop_on_input: bool,
+    }
+
+    impl Scene for CountScene {
+        fn init(&mut self, _ctx: &mut Context) {}
+
+        fn update(&mut self, ctx: &mut Context, _dt: f32) -> SceneOp {
+            let n = ctx.input_events.len() as u32;
+            *self.inputs.borrow_mut() += n;
+            ctx.input_events.clear();
+            if n > 0 && self.pop_on_input {
+                return SceneOp::Pop;
+            }
+            SceneOp::None
+        }
+
+        fn draw(&mut self, _ctx: &mut Context, _panel: &mut Panel, _dt: f32) {}
+    }
+
+    fn key_event() -> Event {
+        Event::Key(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn push_redirects_input_and_pop_restores() {
+        let mut ctx = Context::new("scene_test", ".");
+        let mut stack = SceneStack::new();
+        let game = Rc::new(RefCell::new(0));
+        let pause = Rc::new(RefCell::new(0));
+
+        stack.push(
+            &mut ctx,
+            Box::new(CountScene {
+                inputs: game.clone(),
+                pop_on_input: false,
+            }),
+        );
+        ctx.input_events.push(key_event());
+        stack.update(&mut ctx, 0.0);
+        assert_eq!(*game.borrow(), 1);
+
+        stack.push(
+            &mut ctx,
+            Box::new(CountScene {
+                inputs: pause.clone(),
+                pop_on_input: true,
+            }),
+        );
+        ctx.input_events.push(key_event());
+        stack.update(&mut ctx, 0.0);
+        // only the pause scene saw the input, and it popped itself
+        assert_eq!(*game.borrow(), 1);
+        assert_eq!(*pause.borrow(), 1);
+        assert_eq!(stack.len(), 1);
+
+        ctx.input_events.push(key_event());
+        stack.update(&mut ctx, 0.0);
+        assert_eq!(*game.borrow(), 2);
+        assert_eq!(*pause.borrow(), 1);
+    }
+
+    struct LogScene {
+        name: &'static str,
+        log: Rc<RefCell<Vec<String>>>,
+        covered: bool,
+    }
+
+    impl LogScene {
+        fn boxed(name: &'static str, log: &Rc<RefCell<Vec<String>>>, covered: bool) -> Box<Self> {
+            Box::new(Self {
+                name,
+                log: log.clone(),
+                covered,
+            })
+        }
+
+        fn log(&self, hook: &str) {
+            self.log
+                .borrow_mut()
+                .push(format!("{}.{}", self.name, hook));
+        }
+    }
+
+    impl Scene for LogScene {
+        fn init(&mut self, _ctx: &mut Context) {
+            self.log("init");
+        }
+
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> SceneOp {
+            self.log("update");
+            SceneOp::None
+        }
+
+        fn draw(&mut self, _ctx: &mut Context, _panel: &mut Panel, _dt: f32) {}
+
+        fn on_pause(&mut self, _ctx: &mut Context) {
+            self.log("pause");
+        }
+
+        fn on_resume(&mut self, _ctx: &mut Context) {
+            self.log("resume");
+        }
+
+        fn on_exit(&mut self, _ctx: &mut Context) {
+            self.log("exit");
+        }
+
+        fn ticks_when_covered(&self) -> bool {
+            self.covered
+        }
+
+        fn update_covered(&mut self, _ctx: &mut Context, _dt: f32) {
+            self.log("timer");
+        }
+    }
+
+    #[test]
+    fn transitions_call_hooks_in_order() {
+        let mut ctx = Context::new("scene_test", ".");
+        let mut stack = SceneStack::new();
+        let log = Rc::new(RefCell::new(vec![]));
+
+        stack.push(&mut ctx, LogScene::boxed("menu", &log, false));
+        ctx.replace_scene(LogScene::boxed("play", &log, false));
+        stack.update(&mut ctx, 0.0);
+        ctx.push_scene(LogScene::boxed("pause", &log, false));
+        stack.update(&mut ctx, 0.0);
+        ctx.pop_scene();
+        stack.update(&mut ctx, 0.0);
+        assert!(ctx.scene_ops.is_empty());
+        assert_eq!(stack.len(), 1);
+        assert_eq!(
+            *log.borrow(),
+            [
+                "menu.init",
+                "menu.update",
+                "menu.exit",
+                "play.init",
+                "play.update",
+                "play.pause",
+                "pause.init",
+                "pause.update",
+                "pause.exit",
+                "play.resume",
+            ]
+        );
+    }
+
+    #[test]
+    fn only_opted_in_covered_scenes_tick() {
+        let mut ctx = Context::new("scene_test", ".");
+        let mut stack = SceneStack::new();
+        let log = Rc::new(RefCell::new(vec![]));
+
+        stack.push(&mut ctx, LogScene::boxed("back", &log, true));
+        stack.push(&mut ctx, LogScene::boxed("play", &log, false));
+        stack.push(&mut ctx, LogScene::boxed("pause", &log, false));
+        log.borrow_mut().clear();
+        stack.update(&mut ctx, 0.0);
+        assert_eq!(*log.borrow(), ["back.timer", "pause.update"]);
+    }
+    struct TextScene {
+        text: &'static str,
+        overlay: bool,
+    }
+
+    impl Scene for TextScene {
+        fn init(&mut self, _ctx: &mut Context) {}
+
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> SceneOp {
+            SceneOp::None
+        }
+
+        fn draw(&mut self, _ctx: &mut Context, panel: &mut Panel, _dt: f32) {
+            panel
+                .current_buffer_mut()
+                .set_str(0, 0, self.text, Style::default());
+        }
+
+        fn is_overlay(&self) -> bool {
+            self.overlay
+        }
+    }
+
+    #[test]
+    fn overlay_is_composited_over_frozen_scene() {
+        let mut ctx = Context::new("scene_test", ".");
+        let mut stack = SceneStack::new();
+        let mut panel = Panel::new();
+        let scene = |text, overlay| Box::new(TextScene { text, overlay });
+
+        stack.push(&mut ctx, scene("hidden", false));
+        stack.push(&mut ctx, scene("play", false));
+        stack.push(&mut ctx, scene("#", true));
+        stack.draw(&mut ctx, &mut panel, 0.0);
+        let row: String = (0..6)
+            .map(|x| panel.current_buffer_mut().get(x, 0).symbol.clone())
+            .collect();
+        // the scene under "play" is covered, the overlay only hides one cell
+        assert_eq!(row, "#lay  ");
+    }
+}
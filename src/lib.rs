@@ -17,9 +17,10 @@
 //! Various modules asset, audio, event, game, log, render, algorithm, util are offered to ease
 //! game development
 //!
-//! We also provide a base mode in which only algorithm, event and util modules are compiled.
+//! We also provide a base mode in which only algorithm, event and util modules are compiled,
+//! plus render::style for the color types (Color, ColorPro, delta_e...).
 //! Base mode requires fewer dependencies and therefore it is a good fit for compiling to ffi
-//! or wasm libs.
+//! or wasm libs. tests/base_features.rs checks that rust_pixel and the app libs build in it.
 
 /// framerate per second, set to moderate number to save CPUs
 pub const GAME_FRAME: u32 = 60;
@@ -78,3 +79,9 @@ pub mod log;
 #[cfg(not(feature = "base"))]
 pub mod render;
 
+/// base mode only has the color types of render::style, no adapter, buffer or sprite
+#[cfg(feature = "base")]
+pub mod render {
+    pub mod style;
+}
+
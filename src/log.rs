@@ -1,32 +1,192 @@
 // RustPixel
 // copyright zipxing@hotmail.com 2022~2024
 
-
 //! log provides various log functions, reference
 //! https://docs.rs/log4rs
-
+//!
+//! Levels can be set per target, the longest matching target wins:
+//!   set_module_level("rust_pixel::render", LevelFilter::Warn);
+//! or by env var RUST_PIXEL_LOG parsed in init_log, e.g.
+//!   RUST_PIXEL_LOG="info,rust_pixel::render=warn,tower=debug"
+//!
+//! enable_log_ring keeps the last N formatted lines in memory,
+//! so a console overlay can show them in graphics mode
+//! through log_ring_lines
 
 #[cfg(not(target_arch = "wasm32"))]
 use crate::util::get_abs_path;
-use log::LevelFilter;
+use lazy_static::lazy_static;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, RwLock},
+};
 
 #[cfg(not(target_arch = "wasm32"))]
 use log4rs::{
     append::file::FileAppender,
     config::{Appender, Config, Root},
     encode::pattern::PatternEncoder,
-    filter::threshold::ThresholdFilter,
 };
 
+pub const LOG_ENV: &str = "RUST_PIXEL_LOG";
+
+/// default level and per target levels
+#[derive(Debug, Clone)]
+pub struct LogFilter {
+    pub default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    pub fn new(default: LevelFilter) -> Self {
+        Self {
+            default,
+            modules: vec![],
+        }
+    }
+
+    pub fn set_module_level(&mut self, target: &str, level: LevelFilter) {
+        match self.modules.iter_mut().find(|(t, _)| t == target) {
+            Some(m) => m.1 = level,
+            None => self.modules.push((target.to_string(), level)),
+        }
+    }
+
+    /// level of the longest module path matching target
+    pub fn level(&self, target: &str) -> LevelFilter {
+        let mut best: Option<&(String, LevelFilter)> = None;
+        for m in &self.modules {
+            let hit = target == m.0
+                || (target.starts_with(&m.0) && target[m.0.len()..].starts_with("::"));
+            if hit && best.is_none_or(|b| m.0.len() > b.0.len()) {
+                best = Some(m);
+            }
+        }
+        best.map_or(self.default, |b| b.1)
+    }
+
+    /// parses "level,target=level,...", unknown items are skipped
+    pub fn parse(&mut self, spec: &str) {
+        for item in spec.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            match item.split_once('=') {
+                Some((t, l)) => {
+                    if let Ok(l) = l.trim().parse() {
+                        self.set_module_level(t.trim(), l);
+                    }
+                }
+                None => {
+                    if let Ok(l) = item.parse() {
+                        self.default = l;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// keeps the last cap lines, cap 0 means disabled
+#[derive(Debug, Default)]
+pub struct LogRing {
+    lines: VecDeque<String>,
+    cap: usize,
+}
+
+impl LogRing {
+    pub fn new(cap: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(cap),
+            cap,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.cap > 0
+    }
+
+    pub fn push(&mut self, line: String) {
+        if !self.is_enabled() {
+            return;
+        }
+        if self.lines.len() == self.cap {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.iter().cloned().collect()
+    }
+}
+
+lazy_static! {
+    static ref LOG_FILTER: RwLock<LogFilter> = RwLock::new(LogFilter::new(LevelFilter::Info));
+    static ref LOG_RING: Mutex<LogRing> = Mutex::new(LogRing::new(0));
+}
+
+pub fn set_module_level(target: &str, level: LevelFilter) {
+    LOG_FILTER.write().unwrap().set_module_level(target, level);
+}
+
+/// keeps the last cap log lines in memory, 0 disables it
+pub fn enable_log_ring(cap: usize) {
+    *LOG_RING.lock().unwrap() = LogRing::new(cap);
+}
+
+/// lines in the ring, oldest first
+pub fn log_ring_lines() -> Vec<String> {
+    LOG_RING.lock().unwrap().lines()
+}
+
+/// applies the per target filter, fills the ring and
+/// forwards to log4rs file or browser console
+struct PixelLogger {
+    #[cfg(not(target_arch = "wasm32"))]
+    file: log4rs::Logger,
+}
+
+impl Log for PixelLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= LOG_FILTER.read().unwrap().level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.file.log(record);
+        let mut ring = LOG_RING.lock().unwrap();
+        // the console always needs the line, the ring only when enabled
+        if !cfg!(target_arch = "wasm32") && !ring.is_enabled() {
+            return;
+        }
+        let line = format!("{} {} {}", record.level(), record.target(), record.args());
+        #[cfg(target_arch = "wasm32")]
+        web_sys::console::log_1(&line.as_str().into());
+        ring.push(line);
+    }
+
+    fn flush(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.file.flush();
+    }
+}
+
 /// init logs system
 #[allow(unused)]
 pub fn init_log(level: LevelFilter, file_path: &str) {
-    #[cfg(target_arch = "wasm32")]
     {
-        wasm_logger::init(wasm_logger::Config::default());
+        let mut f = LOG_FILTER.write().unwrap();
+        f.default = level;
+        if let Ok(spec) = std::env::var(LOG_ENV) {
+            f.parse(&spec);
+        }
     }
+    #[cfg(target_arch = "wasm32")]
+    let logger = PixelLogger {};
     #[cfg(not(target_arch = "wasm32"))]
-    {
+    let logger = {
         let fpstr = get_abs_path(file_path);
         let logfile = FileAppender::builder()
             .encoder(Box::new(PatternEncoder::new(
@@ -35,17 +195,57 @@ pub fn init_log(level: LevelFilter, file_path: &str) {
             .build(fpstr)
             .unwrap();
         let config = Config::builder()
-            .appender(
-                Appender::builder()
-                    .filter(Box::new(ThresholdFilter::new(level)))
-                    .build("logfile", Box::new(logfile)),
-            )
+            .appender(Appender::builder().build("logfile", Box::new(logfile)))
             .build(
                 Root::builder()
                     .appender("logfile")
                     .build(LevelFilter::Trace),
             )
             .unwrap();
-        let _handle = log4rs::init_config(config).unwrap();
+        PixelLogger {
+            file: log4rs::Logger::new(config),
+        }
+    };
+    if log::set_logger(Box::leak(Box::new(logger))).is_ok() {
+        log::set_max_level(LevelFilter::Trace);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_target_wins() {
+        let mut f = LogFilter::new(LevelFilter::Info);
+        f.parse("debug, rust_pixel::render=warn,bad=xx");
+        f.set_module_level("rust_pixel::render::adapter", LevelFilter::Trace);
+        f.set_module_level("rust_pixel", LevelFilter::Error);
+        assert_eq!(f.default, LevelFilter::Debug);
+        assert_eq!(f.level("tower::model"), LevelFilter::Debug);
+        assert_eq!(f.level("rust_pixel::game"), LevelFilter::Error);
+        assert_eq!(f.level("rust_pixel::render::cell"), LevelFilter::Warn);
+        assert_eq!(f.level("rust_pixel::render"), LevelFilter::Warn);
+        assert_eq!(
+            f.level("rust_pixel::render::adapter::sdl"),
+            LevelFilter::Trace
+        );
+        // prefix must end at a module boundary
+        assert_eq!(f.level("rust_pixel::renderer"), LevelFilter::Error);
+        f.set_module_level("rust_pixel::render", LevelFilter::Off);
+        assert_eq!(f.level("rust_pixel::render::cell"), LevelFilter::Off);
+    }
+
+    #[test]
+    fn ring_wraps_around() {
+        let mut r = LogRing::new(3);
+        for i in 0..5 {
+            r.push(format!("line{}", i));
+        }
+        assert_eq!(r.lines(), vec!["line2", "line3", "line4"]);
+        let mut off = LogRing::new(0);
+        off.push("x".to_string());
+        assert!(off.lines().is_empty());
+        assert!(!off.is_enabled() && r.is_enabled());
     }
 }
@@ -16,12 +16,24 @@ pub mod cell;
 /// Buffer is used to manage a set of Cell
 pub mod buffer;
 
+/// box drawing borders with junctions, text mode
+pub mod border;
+
 /// image, to read or write image files in pix or esc format
 pub mod image;
 
 /// sprite, basic drawing unit
 pub mod sprite;
 
+/// camera, scrolls a map bigger than the screen
+pub mod camera;
+
+/// debug overlay, grid, mouse cell and fps over the main buffer
+pub mod overlay;
+
+/// particle emitters for bursts of colored cells
+pub mod particles;
+
 /// defines attributes like fore- or back-ground colors
 pub mod style;
 
@@ -3,8 +3,8 @@
 
 #![allow(unused_variables)]
 use crate::{
-    event::Event,
-    render::{buffer::Buffer, sprite::Sprites},
+    event::{Event, GamepadInfo},
+    render::{buffer::Buffer, sprite::Sprites, style::BlendMode},
     util::{Rand, Rect},
 };
 #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
@@ -15,6 +15,7 @@ use crate::{
     LOGO_FRAME,
 };
 use std::any::Any;
+use std::ops::Range;
 use std::sync::OnceLock;
 use std::time::Duration;
 // use log::info;
@@ -26,6 +27,10 @@ pub mod gl;
 #[cfg(all(feature = "sdl", not(target_arch = "wasm32")))]
 pub mod sdl;
 
+/// record the screen to gif, sdl mode
+#[cfg(all(feature = "sdl", not(target_arch = "wasm32")))]
+pub mod record;
+
 /// web adapter
 #[cfg(target_arch = "wasm32")]
 pub mod web;
@@ -39,10 +44,18 @@ pub mod web;
 )))]
 pub mod cross;
 
+/// kitty graphics protocol, draws pixel sprites in the crossterm adapter
+#[cfg(not(any(
+    feature = "sdl",
+    target_os = "android",
+    target_os = "ios",
+    target_arch = "wasm32"
+)))]
+pub mod kitty;
+
 /// symbols texture contains 8x8 blocks
 /// each block contain 16x16 symbols
 /// total 128 * 128 symbols
-#[cfg(any(feature = "sdl", target_arch = "wasm32"))]
 pub const PIXEL_TEXTURE_FILE: &str = "assets/pix/symbols.png";
 
 /// symbol size is calculated based on the size of the texture
@@ -116,6 +129,18 @@ pub struct RenderCell {
     pub angle: f32,
     pub cx: f32,
     pub cy: f32,
+    /// gl blend func of the cell, from Sprite::set_blend of pixel sprites
+    pub blend: BlendMode,
+}
+
+impl RenderCell {
+    /// texture page of texsym, the symbols texture holds 8x8 pages
+    /// of 16x16 symbols, see push_render_buffer
+    pub fn texture_page(&self) -> usize {
+        let x = self.texsym % (16 * 8);
+        let y = self.texsym / (16 * 8);
+        (y / 16) * 8 + x / 16
+    }
 }
 
 pub struct AdapterBase {
@@ -133,6 +158,9 @@ pub struct AdapterBase {
     pub rflag: bool,
     #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
     pub rbuf: Vec<RenderCell>,
+    /// indices of rbuf cells changed since the last frame
+    #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
+    pub rdirty: Vec<usize>,
     #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
     pub gl: Option<glow::Context>,
     #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
@@ -157,6 +185,8 @@ impl AdapterBase {
             #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
             rbuf: vec![],
             #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
+            rdirty: vec![],
+            #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
             gl: None,
             #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
             gl_pixel: None,
@@ -168,6 +198,10 @@ pub trait Adapter {
     fn init(&mut self, w: u16, h: u16, rx: f32, ry: f32, s: String);
     fn reset(&mut self);
     fn get_base(&mut self) -> &mut AdapterBase;
+    /// appends pending input events to ev, every backend converts to the same
+    /// Event: key codes with modifiers, mouse positions in cells (ratio applied),
+    /// and Resize. The web adapter drains events queued by push_event.
+    /// Returns true when the game should quit
     fn poll_event(&mut self, timeout: Duration, ev: &mut Vec<Event>) -> bool;
 
     fn draw_all_to_screen(
@@ -232,6 +266,39 @@ pub trait Adapter {
         self
     }
 
+    /// start recording the screen to a gif at path, fps frames a second,
+    /// only the sdl adapter supports it, see render::adapter::record
+    fn start_recording(&mut self, path: &str, fps: u32) -> Result<(), String> {
+        Err("recording is not supported by this adapter".to_string())
+    }
+
+    /// stop recording and finish the gif, ok if not recording
+    fn stop_recording(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// connected gamepads, only the sdl adapter opens them
+    fn gamepads(&self) -> Vec<GamepadInfo> {
+        vec![]
+    }
+
+    /// changes the window title at runtime, e.g. to show the score
+    /// set_title above only sets the title used when the window is created
+    fn set_window_title(&mut self, title: &str) {
+        self.get_base().title = title.to_string();
+    }
+
+    /// sets the window icon from w * h rgba pixels, no-op in terminal and web mode
+    fn set_window_icon(&mut self, _rgba: &[u8], _w: u32, _h: u32) {}
+
+    /// text in the os clipboard, None if empty or not supported
+    fn clipboard_get(&mut self) -> Option<String> {
+        None
+    }
+
+    /// puts text into the os clipboard, no-op if not supported
+    fn clipboard_set(&mut self, _text: &str) {}
+
     fn cell_width(&self) -> f32;
     fn cell_height(&self) -> f32;
     fn hide_cursor(&mut self) -> Result<(), String>;
@@ -266,8 +333,10 @@ pub trait Adapter {
             // draw render_texture 2 & 3 to screen
             self.draw_render_textures_to_screen();
         } else {
-            // copy rbuf to base.rbuf
-            self.get_base().rbuf = rbuf;
+            // copy rbuf to base.rbuf, keeping the changed cells
+            let bs = self.get_base();
+            bs.rdirty = dirty_render_cells(&bs.rbuf, &rbuf);
+            bs.rbuf = rbuf;
             // info!("rbuf len...{}", self.get_base().rbuf.len());
         }
     }
@@ -277,12 +346,45 @@ pub trait Adapter {
         self.get_base().rflag = false;
     }
 
+    /// enables or disables a post pass (crt, scanline, bloom or user added),
+    /// false if there is no such pass or gl is not ready yet
+    #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
+    fn set_postfx(&mut self, name: &str, enabled: bool) -> bool {
+        match &mut self.get_base().gl_pixel {
+            Some(pix) => pix.set_postfx(name, enabled),
+            None => false,
+        }
+    }
+
+    /// sets the progress uniform of a post pass, e.g. effect strength
+    #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
+    fn set_postfx_progress(&mut self, name: &str, progress: f32) -> bool {
+        match &mut self.get_base().gl_pixel {
+            Some(pix) => pix.set_postfx_progress(name, progress),
+            None => false,
+        }
+    }
+
+    /// sets the time uniform of post passes, Panel::draw passes Context::time
+    #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
+    fn set_postfx_time(&mut self, time: f32) {
+        if let Some(pix) = &mut self.get_base().gl_pixel {
+            pix.set_postfx_time(time);
+        }
+    }
+
     #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
     fn draw_render_textures_to_screen(&mut self) {
         let bs = self.get_base();
 
         if let (Some(pix), Some(gl)) = (&mut bs.gl_pixel, &mut bs.gl) {
-            pix.bind_screen(gl);
+            // with post passes, draw the frame to a render texture first
+            let postfx = pix.postfx_enabled();
+            if postfx {
+                pix.bind_postfx_target(gl);
+            } else {
+                pix.bind_screen(gl);
+            }
             let c = GlColor::new(1.0, 1.0, 1.0, 1.0);
 
             // draw render_texture 2 ( main buffer )
@@ -310,6 +412,10 @@ pub trait Adapter {
                     &c,
                 );
             }
+
+            if postfx {
+                pix.draw_postfx(gl);
+            }
         }
     }
 
@@ -413,16 +519,22 @@ pub trait Adapter {
             render_main_buffer(cb, width, rx, ry, false, &mut rfunc);
         }
 
-        // render pixel_sprites...
+        // render pixel_sprites, lower z_index first...
         if stage > LOGO_FRAME {
-            for item in ps {
+            let mut zorder: Vec<usize> = (0..ps.len()).collect();
+            zorder.sort_by_key(|i| ps[*i].z_index);
+            for i in zorder {
+                let item = &mut ps[i];
                 if item.is_pixel && !item.is_hidden {
                     render_pixel_sprites(
                         item,
                         rx,
                         ry,
-                        |fc, bc, _s0, _s1, s2, texidx, symidx, angle, ccp| {
+                        |fc, bc, _s0, _s1, s2, texidx, symidx, angle, ccp, blend| {
                             push_render_buffer(&mut rbuf, fc, bc, texidx, symidx, s2, angle, &ccp);
+                            if let Some(c) = rbuf.last_mut() {
+                                c.blend = blend;
+                            }
                         },
                     );
                 }
@@ -434,7 +546,39 @@ pub trait Adapter {
     fn as_any(&mut self) -> &mut dyn Any;
 }
 
+/// indices of cells in cur that differ from prev,
+/// all cells are dirty if the length changed
+pub fn dirty_render_cells(prev: &[RenderCell], cur: &[RenderCell]) -> Vec<usize> {
+    if prev.len() != cur.len() {
+        return (0..cur.len()).collect();
+    }
+    cur.iter()
+        .zip(prev.iter())
+        .enumerate()
+        .filter(|(_, (c, p))| c != p)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// groups consecutive cells sharing the same texture page,
+/// returns (texture page, index range in rbuf) in drawing order.
+/// Sprites are already in z order, so the groups can be submitted
+/// one draw per group without changing overdraw, see
+/// GlRenderSymbols::render_rbuf
+pub fn batch_by_texture(rbuf: &[RenderCell]) -> Vec<(usize, Range<usize>)> {
+    let mut ret: Vec<(usize, Range<usize>)> = vec![];
+    for (i, c) in rbuf.iter().enumerate() {
+        let page = c.texture_page();
+        match ret.last_mut() {
+            Some((p, r)) if *p == page => r.end = i + 1,
+            _ => ret.push((page, i..i + 1)),
+        }
+    }
+    ret
+}
+
 #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
+#[allow(clippy::too_many_arguments)]
 fn push_render_buffer(
     rbuf: &mut Vec<RenderCell>,
     fc: &(u8, u8, u8, u8),
@@ -541,7 +685,7 @@ fn render_helper(
 #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
 pub fn render_pixel_sprites<F>(pixel_spt: &mut Sprites, rx: f32, ry: f32, mut f: F)
 where
-    // rgba, back rgba, back rect, sym rect, dst rect, tex, sym, angle, center point
+    // rgba, back rgba, back rect, sym rect, dst rect, tex, sym, angle, center point, blend
     F: FnMut(
         &(u8, u8, u8, u8),
         &Option<(u8, u8, u8, u8)>,
@@ -552,9 +696,20 @@ where
         usize,
         f64,
         PointI32,
+        BlendMode,
     ),
 {
-    // sort by render_weight...
+    let sym_w = *PIXEL_SYM_WIDTH.get().expect("lazylock init");
+    let sym_h = *PIXEL_SYM_HEIGHT.get().expect("lazylock init");
+    // camera shift in pixels, the fraction of a cell included
+    let (ox, oy) = match pixel_spt.camera_pos {
+        Some((cx, cy)) => (
+            (cx * sym_w / rx).round() as i32,
+            (cy * sym_h / ry).round() as i32,
+        ),
+        None => (0, 0),
+    };
+    // sort by z and render_weight...
     pixel_spt.update_render_index();
     for si in &pixel_spt.render_index {
         let s = &pixel_spt.sprites[si.0];
@@ -568,7 +723,7 @@ where
 
         for (i, cell) in s.content.content.iter().enumerate() {
             let sh = &cell.get_cell_info();
-            let (s0, s1, s2, texidx, symidx) = render_helper(
+            let (s0, s1, mut s2, texidx, symidx) = render_helper(
                 pw,
                 PointF32 { x: rx, y: ry },
                 i,
@@ -576,6 +731,8 @@ where
                 PointU16 { x: px, y: py },
                 false,
             );
+            s2.x -= ox;
+            s2.y -= oy;
             let x = i % pw as usize;
             let y = i / pw as usize;
             // center point ...
@@ -583,17 +740,17 @@ where
                 x: ((pw as f32 / 2.0 - x as f32) * PIXEL_SYM_WIDTH.get().expect("lazylock init") / rx) as i32,
                 y: ((ph as f32 / 2.0 - y as f32) * PIXEL_SYM_HEIGHT.get().expect("lazylock init") / ry) as i32,
             };
-            let mut fc = sh.2.get_rgba();
+            let mut fc = s.tint_color(sh.2).get_rgba();
             fc.3 = s.alpha;
             let bc;
             if sh.3 != Color::Reset {
-                let mut brgba = sh.3.get_rgba();
+                let mut brgba = s.tint_color(sh.3).get_rgba();
                 brgba.3 = s.alpha;
                 bc = Some(brgba);
             } else {
                 bc = None;
             }
-            f(&fc, &bc, s0, s1, s2, texidx, symidx, s.angle, ccp);
+            f(&fc, &bc, s0, s1, s2, texidx, symidx, s.angle, ccp, s.get_blend());
         }
     }
 }
@@ -723,3 +880,50 @@ where
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(tex: usize, sym: usize) -> RenderCell {
+        // same layout as push_render_buffer
+        let x = sym % 16 + (tex % 8) * 16;
+        let y = sym / 16 + (tex / 8) * 16;
+        RenderCell {
+            texsym: y * 16 * 8 + x,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn batch_groups_consecutive_pages() {
+        assert_eq!(cell(9, 200).texture_page(), 9);
+        let rbuf = vec![
+            cell(0, 1),
+            cell(0, 255),
+            cell(3, 7),
+            cell(3, 8),
+            cell(3, 9),
+            cell(0, 1),
+            cell(63, 0),
+        ];
+        assert_eq!(
+            batch_by_texture(&rbuf),
+            vec![(0, 0..2), (3, 2..5), (0, 5..6), (63, 6..7)]
+        );
+        assert!(batch_by_texture(&[]).is_empty());
+    }
+
+    #[test]
+    fn dirty_cells_between_frames() {
+        let prev = vec![cell(0, 1), cell(0, 2), cell(0, 3)];
+        let mut cur = prev.clone();
+        assert!(dirty_render_cells(&prev, &cur).is_empty());
+        cur[1] = cell(1, 2);
+        cur[2].fcolor = (1.0, 0.0, 0.0, 1.0);
+        assert_eq!(dirty_render_cells(&prev, &cur), vec![1, 2]);
+        // a length change marks every cell
+        cur.push(cell(0, 4));
+        assert_eq!(dirty_render_cells(&prev, &cur), vec![0, 1, 2, 3]);
+        assert!(dirty_render_cells(&[], &[]).is_empty());
+    }
+}
@@ -1,14 +1,27 @@
 // RustPixel
 // copyright zipxing@hotmail.com 2022~2024
 
+//! crossterm adapter renders in terminal
+//!
+//! Terminal state (raw mode, alternate screen, hidden cursor, mouse capture)
+//! is owned by TerminalGuard and restored when it drops. restore_terminal is
+//! also called by the panic hook installed in Game::init, so a panicking game
+//! leaves a usable terminal and a readable backtrace.
+//!
+//! In terminals speaking the kitty graphics protocol, pixel layers are drawn
+//! as an image over the cells, see render::adapter::kitty
+
 use crate::{
     event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
     render::{
-        adapter::{Adapter, AdapterBase},
+        adapter::{
+            kitty::{KittyLayer, TerminalGraphics},
+            Adapter, AdapterBase,
+        },
         buffer::Buffer,
         image::to_error,
         sprite::Sprites,
-        style::{Color, Modifier, ModifierDiff},
+        style::{Color, Modifier, ModifierDiff, TerminalColorCaps},
     },
     util::Rand,
     LOGO_FRAME,
@@ -29,14 +42,79 @@ use crossterm::{
 };
 use std::any::Any;
 use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
 use std::time::Duration;
 // use log::info;
 
+// set while the terminal is in game mode
+static TERMINAL_ACTIVE: AtomicBool = AtomicBool::new(false);
+static PANIC_HOOK: Once = Once::new();
+
+/// enables raw mode, alternate screen, hidden cursor and mouse capture,
+/// restores them on drop
+#[cfg(not(feature = "sdl"))]
+pub struct TerminalGuard;
+
+#[cfg(not(feature = "sdl"))]
+impl TerminalGuard {
+    pub fn new() -> io::Result<Self> {
+        enable_raw_mode()?;
+        TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture, Hide)?;
+        Ok(Self)
+    }
+}
+
+#[cfg(not(feature = "sdl"))]
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// restores terminal, does nothing if already restored
+#[cfg(not(feature = "sdl"))]
+pub fn restore_terminal() {
+    restore_terminal_to(&TERMINAL_ACTIVE, &mut io::stdout());
+}
+
+#[cfg(not(feature = "sdl"))]
+fn restore_terminal_to<W: Write>(active: &AtomicBool, w: &mut W) {
+    if active.swap(false, Ordering::SeqCst) {
+        let _ = disable_raw_mode();
+        let _ = execute!(w, LeaveAlternateScreen, DisableMouseCapture, Show);
+    }
+}
+
+// restores terminal first, then runs the previous hook to print the message
+#[cfg(not(feature = "sdl"))]
+fn chain_panic_hook() {
+    let prev = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        prev(info);
+    }));
+}
+
+/// installs the terminal restoring panic hook, only once
+#[cfg(not(feature = "sdl"))]
+pub fn install_panic_hook() {
+    PANIC_HOOK.call_once(chain_panic_hook);
+}
+
 #[cfg(not(feature = "sdl"))]
 pub struct CrosstermAdapter {
     pub writer: Box<dyn Write>,
     pub base: AdapterBase,
     pub rd: Rand,
+    /// rgb colors are downgraded to fit, detected at init
+    pub color_caps: TerminalColorCaps,
+    /// graphics protocol, detected at init
+    pub graphics: TerminalGraphics,
+    // Some if pixel layers are drawn by the kitty protocol
+    kitty: Option<KittyLayer>,
+    guard: Option<TerminalGuard>,
 }
 
 #[cfg(not(feature = "sdl"))]
@@ -47,6 +125,10 @@ impl CrosstermAdapter {
             writer: Box::new(stdout),
             base: AdapterBase::new(gn, project_path),
             rd: Rand::new(),
+            color_caps: TerminalColorCaps::default(),
+            graphics: TerminalGraphics::default(),
+            kitty: None,
+            guard: None,
         }
     }
 }
@@ -66,9 +148,17 @@ impl Adapter for CrosstermAdapter {
                 w, h, width, height
             );
         }
-        enable_raw_mode().unwrap();
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture).unwrap();
+        self.color_caps = TerminalColorCaps::detect();
+        self.graphics = TerminalGraphics::detect();
+        // falls back to cells if the symbols texture can't be loaded
+        #[cfg(feature = "image")]
+        if self.graphics == TerminalGraphics::Kitty {
+            match crate::render::adapter::kitty::SymbolTexture::load(&self.base.project_path) {
+                Ok(tex) => self.kitty = Some(KittyLayer::new(tex)),
+                Err(e) => log::warn!("kitty graphics off: {}", e),
+            }
+        }
+        self.guard = Some(TerminalGuard::new().unwrap());
     }
 
     fn get_base(&mut self) -> &mut AdapterBase {
@@ -76,9 +166,11 @@ impl Adapter for CrosstermAdapter {
     }
 
     fn reset(&mut self) {
-        disable_raw_mode().unwrap();
-        execute!(self.writer, LeaveAlternateScreen, DisableMouseCapture).unwrap();
-        self.show_cursor().unwrap();
+        if let Some(k) = &mut self.kitty {
+            let _ = k.clear(&mut self.writer);
+        }
+        self.guard = None;
+        restore_terminal();
     }
 
     fn cell_width(&self) -> f32 {
@@ -110,6 +202,10 @@ impl Adapter for CrosstermAdapter {
     fn poll_event(&mut self, timeout: Duration, es: &mut Vec<Event>) -> bool {
         if crossterm::event::poll(timeout).unwrap() {
             let e = crossterm::event::read().unwrap();
+            // panel follows the new size with Buffer::resize_preserving
+            if let CEvent::Resize(w, h) = e {
+                self.set_size(w, h);
+            }
             if let Some(et) = input_events_from_cross(&e) {
                 es.push(et);
             }
@@ -126,7 +222,7 @@ impl Adapter for CrosstermAdapter {
         &mut self,
         current_buffer: &Buffer,
         previous_buffer: &Buffer,
-        _pix: &mut Vec<Sprites>,
+        pix: &mut Vec<Sprites>,
         stage: u32,
     ) -> Result<(), String> {
         if stage <= LOGO_FRAME {
@@ -180,12 +276,12 @@ impl Adapter for CrosstermAdapter {
                 modifier = cell.modifier;
             }
             if cell.fg != fg {
-                let color = CColor::from(cell.fg);
+                let color = CColor::from(cell.fg.downgrade(self.color_caps));
                 to_error(queue!(self.writer, SetForegroundColor(color)))?;
                 fg = cell.fg;
             }
             if cell.bg != bg {
-                let color = CColor::from(cell.bg);
+                let color = CColor::from(cell.bg.downgrade(self.color_caps));
                 to_error(queue!(self.writer, SetBackgroundColor(color)))?;
                 bg = cell.bg;
             }
@@ -197,7 +293,13 @@ impl Adapter for CrosstermAdapter {
             SetForegroundColor(CColor::Reset),
             SetBackgroundColor(CColor::Reset),
             SetAttribute(CAttribute::Reset)
-        ))
+        ))?;
+        if let Some(k) = &mut self.kitty {
+            let area = current_buffer.area();
+            to_error(queue!(self.writer, MoveTo(0, 0)))?;
+            to_error(k.draw(&mut self.writer, pix, area.width, area.height))?;
+        }
+        Ok(())
     }
 
     fn as_any(&mut self) -> &mut dyn Any {
@@ -205,11 +307,26 @@ impl Adapter for CrosstermAdapter {
     }
 }
 
+#[cfg(not(feature = "sdl"))]
+fn modifiers_from_cross(m: crossterm::event::KeyModifiers) -> KeyModifiers {
+    use crossterm::event::KeyModifiers as CKeyModifiers;
+    let mut ret = KeyModifiers::NONE;
+    if m.contains(CKeyModifiers::SHIFT) {
+        ret.insert(KeyModifiers::SHIFT);
+    }
+    if m.contains(CKeyModifiers::CONTROL) {
+        ret.insert(KeyModifiers::CONTROL);
+    }
+    if m.contains(CKeyModifiers::ALT) {
+        ret.insert(KeyModifiers::ALT);
+    }
+    ret
+}
+
 /// Convert crossterm I/O events to RustPixel event, for the sake of unified event processing
 /// For keyboard and mouse event, please refer to the handle_input method in game/unblock/model.rs
 #[cfg(not(feature = "sdl"))]
 pub fn input_events_from_cross(e: &CEvent) -> Option<Event> {
-    let mut mcte: Option<MouseEvent> = None;
     match e {
         CEvent::Key(key) => {
             let kc = match key.code {
@@ -219,12 +336,23 @@ pub fn input_events_from_cross(e: &CEvent) -> Option<Event> {
                 CKeyCode::Left => KeyCode::Left,
                 CKeyCode::Right => KeyCode::Right,
                 CKeyCode::Tab => KeyCode::Tab,
+                CKeyCode::BackTab => KeyCode::BackTab,
+                CKeyCode::Enter => KeyCode::Enter,
+                CKeyCode::Backspace => KeyCode::Backspace,
+                CKeyCode::Esc => KeyCode::Esc,
+                CKeyCode::Home => KeyCode::Home,
+                CKeyCode::End => KeyCode::End,
+                CKeyCode::PageUp => KeyCode::PageUp,
+                CKeyCode::PageDown => KeyCode::PageDown,
+                CKeyCode::Delete => KeyCode::Delete,
+                CKeyCode::Insert => KeyCode::Insert,
+                CKeyCode::F(n) => KeyCode::F(n),
                 _ => {
                     return None;
                 }
             };
-            let cte = KeyEvent::new(kc, KeyModifiers::NONE);
-            return Some(Event::Key(cte));
+            let cte = KeyEvent::new(kc, modifiers_from_cross(key.modifiers));
+            Some(Event::Key(cte))
         }
         CEvent::Mouse(mouse) => {
             let mk = match mouse.kind {
@@ -259,14 +387,68 @@ pub fn input_events_from_cross(e: &CEvent) -> Option<Event> {
                 kind: mk,
                 column: mouse.column,
                 row: mouse.row,
-                modifiers: KeyModifiers::NONE,
+                modifiers: modifiers_from_cross(mouse.modifiers),
             };
-            mcte = Some(cte);
+            Some(Event::Mouse(cte))
         }
-        _ => {}
+        CEvent::Resize(w, h) => Some(Event::Resize(*w, *h)),
     }
-    if let Some(mc) = mcte {
-        return Some(Event::Mouse(mc));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synthetic_events_normalized() {
+        use crossterm::event::{
+            KeyEvent as CKeyEvent, KeyModifiers as CKeyModifiers, MouseEvent as CMouseEvent,
+            MouseEventKind as CMouseEventKind,
+        };
+        let k = CEvent::Key(CKeyEvent::new(CKeyCode::Char('s'), CKeyModifiers::CONTROL));
+        assert_eq!(
+            input_events_from_cross(&k),
+            Some(Event::Key(KeyEvent::new(
+                KeyCode::Char('s'),
+                KeyModifiers::CONTROL
+            )))
+        );
+        let f = CEvent::Key(CKeyEvent::new(CKeyCode::F(5), CKeyModifiers::NONE));
+        assert_eq!(
+            input_events_from_cross(&f),
+            Some(Event::Key(KeyEvent::new(KeyCode::F(5), KeyModifiers::NONE)))
+        );
+        let m = CEvent::Mouse(CMouseEvent {
+            kind: CMouseEventKind::Down(CMouseButton::Left),
+            column: 3,
+            row: 4,
+            modifiers: CKeyModifiers::SHIFT,
+        });
+        assert_eq!(
+            input_events_from_cross(&m),
+            Some(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 3,
+                row: 4,
+                modifiers: KeyModifiers::SHIFT,
+            }))
+        );
+        assert_eq!(
+            input_events_from_cross(&CEvent::Resize(80, 24)),
+            Some(Event::Resize(80, 24))
+        );
+    }
+
+    #[test]
+    fn restore_is_idempotent() {
+        let mut out: Vec<u8> = vec![];
+        let active = AtomicBool::new(true);
+        restore_terminal_to(&active, &mut out);
+        let n = out.len();
+        assert!(n > 0);
+        restore_terminal_to(&active, &mut out);
+        restore_terminal_to(&active, &mut out);
+        assert_eq!(out.len(), n);
+        assert!(!active.load(Ordering::SeqCst));
     }
-    None
 }
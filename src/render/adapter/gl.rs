@@ -6,6 +6,7 @@ pub mod pixel;
 pub mod shader_source;
 pub mod render_symbols;
 pub mod render_transition;
+pub mod render_postfx;
 pub mod render_general2d;
 
 use shader::GlShader;
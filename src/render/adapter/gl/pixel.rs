@@ -3,9 +3,14 @@
 
 use crate::render::adapter::{
     gl::{
-        color::GlColor, render_general2d::GlRenderGeneral2d, render_symbols::GlRenderSymbols,
-        render_transition::GlRenderTransition, texture::GlRenderTexture, transform::GlTransform,
-        GlRender, 
+        color::GlColor,
+        render_general2d::GlRenderGeneral2d,
+        render_postfx::{postfx_chain, GlRenderPostFx, POSTFX_RT},
+        render_symbols::GlRenderSymbols,
+        render_transition::GlRenderTransition,
+        texture::GlRenderTexture,
+        transform::GlTransform,
+        GlRender,
     },
     RenderCell,
 };
@@ -17,6 +22,7 @@ pub struct GlPixel {
     r_sym: GlRenderSymbols,
     r_g2d: GlRenderGeneral2d,
     r_trans: GlRenderTransition,
+    r_post: GlRenderPostFx,
 
     render_textures: Vec<GlRenderTexture>,
 
@@ -49,6 +55,10 @@ impl GlPixel {
         let mut r_trans = GlRenderTransition::new(canvas_width as u32, canvas_height as u32);
         r_trans.init(gl, ver);
 
+        // gl render postfx for post processing the final frame
+        let mut r_post = GlRenderPostFx::new(canvas_width as u32, canvas_height as u32);
+        r_post.init(gl, ver);
+
         unsafe {
             gl.enable(glow::BLEND);
             gl.disable(glow::DEPTH_TEST);
@@ -61,12 +71,13 @@ impl GlPixel {
         }
 
         // create 4 render texture for gl transition...
+        // and 2 more (4, 5) for post processing ping-pong
         let mut render_textures = vec![];
-        let rt_hidden = [true, true, false, false];
-        for i in 0..4 {
+        let rt_hidden = [true, true, false, false, true, true];
+        for hidden in rt_hidden {
             let w = canvas_width as u32;
             let h = canvas_height as u32;
-            let rt = GlRenderTexture::new(gl, w, h, rt_hidden[i]).unwrap();
+            let rt = GlRenderTexture::new(gl, w, h, hidden).unwrap();
             info!("rt...{:?}", rt.texture);
             render_textures.push(rt);
         }
@@ -77,6 +88,7 @@ impl GlPixel {
             r_sym,
             r_g2d,
             r_trans,
+            r_post,
             render_textures,
             clear_color: GlColor::new(0.0, 0.0, 0.0, 1.0),
         }
@@ -94,10 +106,7 @@ impl GlPixel {
     pub fn bind_target(&mut self, gl: &glow::Context, render_texture_idx: usize) {
         unsafe {
             let tex = &self.render_textures[render_texture_idx];
-            gl.bind_framebuffer(
-                glow::FRAMEBUFFER,
-                Some(tex.framebuffer),
-            );
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(tex.framebuffer));
             // info!("bind_target...{} {} {}", render_texture_idx, tex.width, tex.height);
             gl.viewport(0, 0, tex.width as i32, tex.height as i32);
         }
@@ -154,12 +163,7 @@ impl GlPixel {
         self.r_sym.render_rbuf(gl, rbuf, ratio_x, ratio_y);
     }
 
-    pub fn render_trans_frame(
-        &mut self,
-        gl: &glow::Context,
-        sidx: usize,
-        progress: f32,
-    ) {
+    pub fn render_trans_frame(&mut self, gl: &glow::Context, sidx: usize, progress: f32) {
         self.r_trans.set_texture(
             self.canvas_width,
             self.canvas_height,
@@ -168,4 +172,65 @@ impl GlPixel {
         );
         self.r_trans.draw_trans(gl, sidx, progress);
     }
+
+    /// adds a post pass from `vec4 effect(vec2 uv)` source, disabled by default
+    pub fn add_postfx(&mut self, gl: &glow::Context, name: &str, effect_src: &str) -> usize {
+        self.r_post.add_pass(gl, name, effect_src)
+    }
+
+    /// enables or disables a post pass (crt, scanline, bloom or user added)
+    pub fn set_postfx(&mut self, name: &str, enabled: bool) -> bool {
+        match self.r_post.find_pass(name) {
+            Some(p) => {
+                p.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// sets the progress uniform of a post pass
+    pub fn set_postfx_progress(&mut self, name: &str, progress: f32) -> bool {
+        match self.r_post.find_pass(name) {
+            Some(p) => {
+                p.progress = progress;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// sets the time uniform of all post passes, in seconds of game time
+    pub fn set_postfx_time(&mut self, time: f32) {
+        self.r_post.time = time;
+    }
+
+    pub fn postfx_enabled(&self) -> bool {
+        self.r_post.has_enabled()
+    }
+
+    // frame is drawn into render texture 4 when post passes are enabled
+    pub fn bind_postfx_target(&mut self, gl: &glow::Context) {
+        self.bind_target(gl, POSTFX_RT[0]);
+        self.clear(gl);
+    }
+
+    // runs enabled passes between render texture 4 and 5, the last one to screen
+    pub fn draw_postfx(&mut self, gl: &glow::Context) {
+        let passes = self.r_post.enabled_passes();
+        unsafe {
+            gl.disable(glow::BLEND);
+        }
+        for (p, src, dst) in postfx_chain(&passes) {
+            match dst {
+                Some(d) => self.bind_target(gl, d),
+                None => self.bind_screen(gl),
+            }
+            let tex = self.render_textures[src].texture;
+            self.r_post.draw_pass(gl, p, tex);
+        }
+        unsafe {
+            gl.enable(glow::BLEND);
+        }
+    }
 }
@@ -0,0 +1,310 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Post processing passes applied to the final frame in graphics mode
+//!
+//! Built-in passes: crt, scanline, bloom (all disabled by default)
+//! Users can add their own fragment shader with GlPixel::add_postfx,
+//! see shader_source.rs for the uniform contract.
+//! Enabled passes run in the order they were added, games toggle them
+//! at runtime with Adapter::set_postfx, e.g. ctx.adapter.set_postfx("crt", true)
+
+use crate::render::adapter::gl::{
+    shader::GlShader,
+    shader_source::{
+        get_postfx_fragment_src, POSTFX_BLOOM, POSTFX_CRT, POSTFX_SCANLINE, VERTEX_SRC_TRANS,
+    },
+    GlRender, GlRenderBase,
+};
+use glow::HasContext;
+
+/// render textures the post passes ping-pong between, the frame is drawn into the first
+pub const POSTFX_RT: [usize; 2] = [4, 5];
+
+/// steps of running passes in order as (pass, source rt, target rt),
+/// a None target is the screen, only the last pass draws to it
+pub fn postfx_chain(passes: &[usize]) -> Vec<(usize, usize, Option<usize>)> {
+    let mut src = POSTFX_RT[0];
+    let mut ret = vec![];
+    for (n, &p) in passes.iter().enumerate() {
+        let dst = if src == POSTFX_RT[0] {
+            POSTFX_RT[1]
+        } else {
+            POSTFX_RT[0]
+        };
+        if n + 1 == passes.len() {
+            ret.push((p, src, None));
+        } else {
+            ret.push((p, src, Some(dst)));
+        }
+        src = dst;
+    }
+    ret
+}
+
+pub struct PostFxPass {
+    pub name: String,
+    pub enabled: bool,
+    pub progress: f32,
+}
+
+pub struct GlRenderPostFx {
+    pub base: GlRenderBase,
+    pub passes: Vec<PostFxPass>,
+    /// seconds of game time, set every frame by Panel::draw
+    pub time: f32,
+    ver: String,
+}
+
+impl GlRender for GlRenderPostFx {
+    fn new(canvas_width: u32, canvas_height: u32) -> Self {
+        let base = GlRenderBase {
+            id: 0,
+            shader: vec![],
+            shader_binded: false,
+            vao: None,
+            gl_buffers: vec![],
+            textures: vec![],
+            textures_binded: false,
+            canvas_width,
+            canvas_height,
+        };
+
+        Self {
+            base,
+            passes: vec![],
+            time: 0.0,
+            ver: String::new(),
+        }
+    }
+
+    fn get_base(&mut self) -> &mut GlRenderBase {
+        &mut self.base
+    }
+
+    fn create_shader(&mut self, gl: &glow::Context, ver: &str) {
+        self.ver = ver.to_string();
+        for (name, src) in [
+            ("crt", POSTFX_CRT),
+            ("scanline", POSTFX_SCANLINE),
+            ("bloom", POSTFX_BLOOM),
+        ] {
+            self.add_pass(gl, name, src);
+        }
+    }
+
+    fn create_buffer(&mut self, gl: &glow::Context) {
+        let vertices: [f32; 16] = [
+            -1.0, -1.0, 0.0, 0.0, 1.0, -1.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0, -1.0, 1.0, 0.0, 1.0,
+        ];
+        let indices: [u32; 6] = [0, 1, 2, 2, 3, 0];
+
+        unsafe {
+            let vao = gl.create_vertex_array().unwrap();
+            gl.bind_vertex_array(Some(vao));
+
+            let vertex_buffer = gl.create_buffer().unwrap();
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
+            gl.buffer_data_u8_slice(
+                glow::ARRAY_BUFFER,
+                vertices.align_to::<u8>().1,
+                glow::STATIC_DRAW,
+            );
+
+            let index_buffer = gl.create_buffer().unwrap();
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(index_buffer));
+            gl.buffer_data_u8_slice(
+                glow::ELEMENT_ARRAY_BUFFER,
+                indices.align_to::<u8>().1,
+                glow::STATIC_DRAW,
+            );
+
+            // same layout as VERTEX_SRC_TRANS
+            gl.enable_vertex_attrib_array(0);
+            gl.enable_vertex_attrib_array(1);
+            gl.vertex_attrib_pointer_f32(0, 2, glow::FLOAT, false, 16, 0);
+            gl.vertex_attrib_pointer_f32(1, 2, glow::FLOAT, false, 16, 8);
+
+            gl.bind_vertex_array(None);
+
+            self.base.vao = Some(vao);
+            self.base.gl_buffers = vec![vertex_buffer, index_buffer];
+        }
+    }
+
+    fn prepare_draw(&mut self, _gl: &glow::Context) {}
+
+    fn draw(&mut self, gl: &glow::Context) {
+        unsafe {
+            gl.draw_elements(glow::TRIANGLES, 6, glow::UNSIGNED_INT, 0);
+        }
+    }
+
+    fn cleanup(&mut self, _gl: &glow::Context) {}
+}
+
+impl GlRenderPostFx {
+    /// compiles a pass from `vec4 effect(vec2 uv)` source, disabled by default
+    /// returns the pass index
+    pub fn add_pass(&mut self, gl: &glow::Context, name: &str, effect_src: &str) -> usize {
+        let fs = get_postfx_fragment_src(effect_src);
+        self.base
+            .shader
+            .push(GlShader::new(gl, &self.ver, VERTEX_SRC_TRANS, &fs));
+        self.passes.push(PostFxPass {
+            name: name.to_string(),
+            enabled: false,
+            progress: 1.0,
+        });
+        self.passes.len() - 1
+    }
+
+    pub fn find_pass(&mut self, name: &str) -> Option<&mut PostFxPass> {
+        self.passes.iter_mut().find(|p| p.name == name)
+    }
+
+    pub fn has_enabled(&self) -> bool {
+        self.passes.iter().any(|p| p.enabled)
+    }
+
+    pub fn enabled_passes(&self) -> Vec<usize> {
+        self.passes
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.enabled)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// draws texture with pass idx into the currently bound framebuffer
+    pub fn draw_pass(&mut self, gl: &glow::Context, idx: usize, tex: glow::Texture) {
+        let program = self.base.shader[idx].program;
+        self.base.shader[idx].bind(gl);
+        unsafe {
+            gl.bind_vertex_array(self.base.vao);
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D, Some(tex));
+            gl.uniform_1_i32(gl.get_uniform_location(program, "texture1").as_ref(), 0);
+            gl.uniform_2_f32(
+                gl.get_uniform_location(program, "resolution").as_ref(),
+                self.base.canvas_width as f32,
+                self.base.canvas_height as f32,
+            );
+            gl.uniform_1_f32(gl.get_uniform_location(program, "time").as_ref(), self.time);
+            gl.uniform_1_f32(
+                gl.get_uniform_location(program, "progress").as_ref(),
+                self.passes[idx].progress,
+            );
+        }
+        self.draw(gl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pass(name: &str, enabled: bool) -> PostFxPass {
+        PostFxPass {
+            name: name.to_string(),
+            enabled,
+            progress: 1.0,
+        }
+    }
+
+    #[test]
+    fn chain_passes_frame_through() {
+        // no gl needed to build the pass list
+        let mut post = GlRenderPostFx::new(4, 4);
+        post.passes = vec![
+            pass("crt", false),
+            pass("scanline", false),
+            pass("bloom", false),
+        ];
+        assert!(!post.has_enabled());
+        assert!(postfx_chain(&post.enabled_passes()).is_empty());
+
+        // a single pass reads the frame and draws straight to the screen
+        post.find_pass("scanline").unwrap().enabled = true;
+        assert_eq!(postfx_chain(&post.enabled_passes()), vec![(1, 4, None)]);
+
+        // several ping-pong in order, disabled ones are skipped
+        post.find_pass("crt").unwrap().enabled = true;
+        post.passes.push(pass("copy", true));
+        assert_eq!(
+            postfx_chain(&post.enabled_passes()),
+            vec![(0, 4, Some(5)), (1, 5, Some(4)), (3, 4, None)]
+        );
+        assert!(post.find_pass("none").is_none());
+    }
+
+    #[test]
+    fn effect_src_has_uniform_contract() {
+        let fs = get_postfx_fragment_src(crate::render::adapter::gl::shader_source::POSTFX_COPY);
+        for u in ["texture1", "resolution", "time", "progress"] {
+            assert!(fs.contains(&format!(" {};", u)), "{}", u);
+        }
+        assert!(fs.contains("FragColor = effect(TexCoord);"));
+    }
+
+    #[cfg(feature = "sdl")]
+    #[test]
+    #[ignore = "needs a display for the GL context"]
+    fn copy_pass_keeps_pixels() {
+        use crate::render::adapter::gl::{shader_source::POSTFX_COPY, texture::GlRenderTexture};
+        let sdl = sdl2::init().unwrap();
+        let video = sdl.video().unwrap();
+        let gl_attr = video.gl_attr();
+        gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
+        gl_attr.set_context_version(3, 3);
+        let window = video
+            .window("postfx", 4, 4)
+            .opengl()
+            .hidden()
+            .build()
+            .unwrap();
+        let _ctx = window.gl_create_context().unwrap();
+        let gl = unsafe {
+            glow::Context::from_loader_function(|s| video.gl_get_proc_address(s) as *const _)
+        };
+
+        let mut post = GlRenderPostFx::new(4, 4);
+        post.init(&gl, "#version 330 core");
+        let idx = post.add_pass(&gl, "copy", POSTFX_COPY);
+
+        let data: Vec<u8> = (0..64u8).map(|i| i * 4).collect();
+        let src = GlRenderTexture::new(&gl, 4, 4, true).unwrap();
+        let dst = GlRenderTexture::new(&gl, 4, 4, true).unwrap();
+        let mut out = vec![0u8; 64];
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D, Some(src.texture));
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA as i32,
+                4,
+                4,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                Some(&data),
+            );
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(dst.framebuffer));
+            gl.viewport(0, 0, 4, 4);
+            gl.disable(glow::BLEND);
+        }
+        post.draw_pass(&gl, idx, src.texture);
+        unsafe {
+            gl.read_pixels(
+                0,
+                0,
+                4,
+                4,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelPackData::Slice(&mut out),
+            );
+        }
+        assert_eq!(out, data);
+    }
+}
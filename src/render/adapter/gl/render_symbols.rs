@@ -9,10 +9,25 @@ use crate::render::adapter::gl::{
     transform::GlTransform,
     GlRender, GlRenderBase,
 };
-use crate::render::adapter::{RenderCell, PIXEL_SYM_HEIGHT, PIXEL_SYM_WIDTH};
+use crate::render::{
+    adapter::{batch_by_texture, RenderCell, PIXEL_SYM_HEIGHT, PIXEL_SYM_WIDTH},
+    style::BlendMode,
+};
 use glow::HasContext;
 use log::info;
 
+/// gl blend func (src rgb, dst rgb, src alpha, dst alpha) of a BlendMode,
+/// Normal is the alpha-over set up by GlPixel::new
+pub fn gl_blend_func(mode: BlendMode) -> (u32, u32, u32, u32) {
+    let (src, dst) = match mode {
+        BlendMode::Normal => (glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA),
+        BlendMode::Add => (glow::SRC_ALPHA, glow::ONE),
+        BlendMode::Multiply => (glow::DST_COLOR, glow::ONE_MINUS_SRC_ALPHA),
+        BlendMode::Screen => (glow::ONE, glow::ONE_MINUS_SRC_COLOR),
+    };
+    (src, dst, glow::ONE, glow::ONE_MINUS_SRC_ALPHA)
+}
+
 pub struct GlRenderSymbols {
     pub base: GlRenderBase,
     instance_buffer: Vec<f32>,
@@ -324,33 +339,60 @@ impl GlRenderSymbols {
         ratio_y: f32,
     ) {
         // info!("ratiox....{} ratioy....{}", ratio_x, ratio_y);
-        for r in rbuf {
-            let mut transform = GlTransform::new();
-
-            transform.translate(
-                r.x + r.cx - PIXEL_SYM_WIDTH.get().expect("lazylock init"),
-                r.y + r.cy - PIXEL_SYM_HEIGHT.get().expect("lazylock init"),
-            );
-            if r.angle != 0.0 {
-                transform.rotate(r.angle);
+        let mut blend = BlendMode::Normal;
+        for (_, range) in batch_by_texture(rbuf) {
+            // a texture page group is flushed only if its texture isn't the
+            // bound one, the pages share one texture for now so groups merge
+            let texture = self.symbols[rbuf[range.start].texsym].texture;
+            if self.base.textures[0] != texture {
+                self.draw(gl);
+                self.base.textures[0] = texture;
+                self.base.textures_binded = false;
             }
-            transform.translate(
-                -r.cx + PIXEL_SYM_WIDTH.get().expect("lazylock init") / ratio_x,
-                -r.cy + PIXEL_SYM_HEIGHT.get().expect("lazylock init") / ratio_y,
-            );
-            transform.scale(1.0 / ratio_x, 1.0 / ratio_y);
+            for r in &rbuf[range] {
+                // cells are in z order, a blend change flushes the cells before it
+                if r.blend != blend {
+                    self.draw(gl);
+                    self.set_blend(gl, r.blend);
+                    blend = r.blend;
+                }
+                let mut transform = GlTransform::new();
+
+                transform.translate(
+                    r.x + r.cx - PIXEL_SYM_WIDTH.get().expect("lazylock init"),
+                    r.y + r.cy - PIXEL_SYM_HEIGHT.get().expect("lazylock init"),
+                );
+                if r.angle != 0.0 {
+                    transform.rotate(r.angle);
+                }
+                transform.translate(
+                    -r.cx + PIXEL_SYM_WIDTH.get().expect("lazylock init") / ratio_x,
+                    -r.cy + PIXEL_SYM_HEIGHT.get().expect("lazylock init") / ratio_y,
+                );
+                transform.scale(1.0 / ratio_x, 1.0 / ratio_y);
 
-            if let Some(b) = r.bcolor {
-                let back_color = GlColor::new(b.0, b.1, b.2, b.3);
+                if let Some(b) = r.bcolor {
+                    let back_color = GlColor::new(b.0, b.1, b.2, b.3);
+                    // fill instance buffer for opengl instance rendering
+                    self.draw_symbol(gl, 1280, &transform, &back_color);
+                }
+
+                let color = GlColor::new(r.fcolor.0, r.fcolor.1, r.fcolor.2, r.fcolor.3);
                 // fill instance buffer for opengl instance rendering
-                self.draw_symbol(gl, 1280, &transform, &back_color);
+                self.draw_symbol(gl, r.texsym, &transform, &color);
             }
-
-            let color = GlColor::new(r.fcolor.0, r.fcolor.1, r.fcolor.2, r.fcolor.3);
-            // fill instance buffer for opengl instance rendering
-            self.draw_symbol(gl, r.texsym, &transform, &color);
         }
         self.draw(gl);
+        if blend != BlendMode::Normal {
+            self.set_blend(gl, BlendMode::Normal);
+        }
+    }
+
+    fn set_blend(&mut self, gl: &glow::Context, mode: BlendMode) {
+        let (s, d, sa, da) = gl_blend_func(mode);
+        unsafe {
+            gl.blend_func_separate(s, d, sa, da);
+        }
     }
 
     fn make_symbols_frame(&mut self, sheet: &mut GlTexture, x: f32, y: f32) -> GlCell {
@@ -377,3 +419,28 @@ impl GlRenderSymbols {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blend_funcs() {
+        // same as the alpha-over set up by GlPixel::new
+        assert_eq!(
+            gl_blend_func(BlendMode::Normal),
+            (
+                glow::SRC_ALPHA,
+                glow::ONE_MINUS_SRC_ALPHA,
+                glow::ONE,
+                glow::ONE_MINUS_SRC_ALPHA
+            )
+        );
+        assert_eq!(gl_blend_func(BlendMode::Add).1, glow::ONE);
+        assert_eq!(gl_blend_func(BlendMode::Multiply).0, glow::DST_COLOR);
+        assert_eq!(
+            gl_blend_func(BlendMode::Screen).1,
+            glow::ONE_MINUS_SRC_COLOR
+        );
+    }
+}
@@ -48,7 +48,10 @@ pub const VERTEX_SRC_TRANS: &str = r#"
             }
         "#;
 
-pub const TRANS_FS: [&str; 7] = [
+// transition effects, the index is the shader id passed to render_trans_frame
+// 0 squares, 1 heart, 2 noise, 3 rotate scale, 4 bounce, 5 displace,
+// 6 ripple, 7 crossfade, 8 edge detect
+pub const TRANS_FS: [&str; 9] = [
     r#"
           const ivec2 squaresMin = ivec2(20);
           const int steps = 50;
@@ -158,6 +161,27 @@ pub const TRANS_FS: [&str; 7] = [
               }
             }
     "#,
+    r#"
+            vec4 transition(vec2 uv) {
+              return mix(getFromColor(uv), getToColor(uv), progress);
+            }
+    "#,
+    r#"
+            float edgeLum(vec2 p) {
+              vec4 c = progress < 0.5 ? getFromColor(p) : getToColor(p);
+              return dot(c.rgb, vec3(0.299, 0.587, 0.114));
+            }
+            vec4 transition(vec2 uv) {
+              vec2 px = 1.0 / vec2(textureSize(texture1, 0));
+              float gx = edgeLum(uv + vec2(px.x, 0.0)) - edgeLum(uv - vec2(px.x, 0.0));
+              float gy = edgeLum(uv + vec2(0.0, px.y)) - edgeLum(uv - vec2(0.0, px.y));
+              float e = clamp(length(vec2(gx, gy)) * 4.0, 0.0, 1.0);
+              vec4 c = progress < 0.5 ? getFromColor(uv) : getToColor(uv);
+              // only edges are left in the middle of the transition
+              float k = 1.0 - abs(progress * 2.0 - 1.0);
+              return mix(c, vec4(vec3(e), 1.0), k);
+            }
+    "#,
 ];
 
 pub fn get_trans_fragment_src() -> Vec<String> {
@@ -211,3 +235,77 @@ pub const GENERAL2D_FRAGMENT_SRC: &str = r#"
                 FragColor = texColor * color;
             }
         "#;
+
+// post processing shader ...
+// uniform contract of every post pass:
+//   sampler2D texture1 : output of the previous pass (or the whole frame)
+//   vec2 resolution    : framebuffer size in pixels
+//   float time         : seconds of game time (Context::time), updated every frame
+//   float progress     : user value 0.0 ~ 1.0, e.g. effect strength
+// a pass only supplies `vec4 effect(vec2 uv)`, use getColor(uv) to sample
+pub const POSTFX_COPY: &str = r#"
+            vec4 effect(vec2 uv) {
+                return getColor(uv);
+            }
+    "#;
+
+pub const POSTFX_CRT: &str = r#"
+            const float curvature = 6.0;
+            vec4 effect(vec2 uv) {
+                vec2 cc = uv * 2.0 - 1.0;
+                vec2 offset = abs(cc.yx) / curvature;
+                cc = cc + cc * offset * offset;
+                vec2 p = cc * 0.5 + 0.5;
+                if (p.x < 0.0 || p.x > 1.0 || p.y < 0.0 || p.y > 1.0) {
+                    return vec4(0.0, 0.0, 0.0, 1.0);
+                }
+                // vignette
+                vec2 v = p * (1.0 - p.yx);
+                float vig = clamp(pow(v.x * v.y * 15.0, 0.25), 0.0, 1.0);
+                vec4 c = getColor(p);
+                return vec4(c.rgb * vig, c.a);
+            }
+    "#;
+
+pub const POSTFX_SCANLINE: &str = r#"
+            vec4 effect(vec2 uv) {
+                vec4 c = getColor(uv);
+                float line = sin(uv.y * resolution.y * 3.14159);
+                float s = 1.0 - 0.25 * progress * (1.0 - line * line);
+                return vec4(c.rgb * s, c.a);
+            }
+    "#;
+
+pub const POSTFX_BLOOM: &str = r#"
+            const float threshold = 0.6;
+            vec4 effect(vec2 uv) {
+                vec4 c = getColor(uv);
+                vec2 px = 1.0 / resolution;
+                vec3 glow = vec3(0.0);
+                for (int x = -2; x <= 2; x++) {
+                    for (int y = -2; y <= 2; y++) {
+                        vec3 s = getColor(uv + vec2(float(x), float(y)) * px * 2.0).rgb;
+                        glow += max(s - vec3(threshold), vec3(0.0));
+                    }
+                }
+                return vec4(c.rgb + glow / 25.0 * 2.0 * progress, c.a);
+            }
+    "#;
+
+pub fn get_postfx_fragment_src(effect: &str) -> String {
+    format!(
+        r#"
+            precision mediump float;
+            out vec4 FragColor;
+            in vec2 TexCoord;
+            uniform sampler2D texture1;
+            uniform vec2 resolution;
+            uniform float time;
+            uniform float progress;
+            vec4 getColor(vec2 uv) {{ return texture(texture1, uv); }}
+            {}
+            void main() {{ FragColor = effect(TexCoord); }}
+            "#,
+        effect
+    )
+}
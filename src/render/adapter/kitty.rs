@@ -0,0 +1,315 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Draws pixel sprites in terminals speaking the kitty graphics protocol
+//! (kitty, WezTerm, ghostty), other terminals keep plain cell rendering
+//!
+//! TerminalGraphics is detected from the environment:
+//! - RUST_PIXEL_GRAPHICS=kitty or none forces it on or off
+//! - TERM=tmux* or screen* is off, tmux doesn't pass the escapes through
+//! - KITTY_WINDOW_ID set, TERM=*kitty* or TERM_PROGRAM=WezTerm or ghostty is kitty
+//!
+//! iTerm2 only speaks its own OSC 1337 protocol and is not detected.
+//!
+//! Pixel layers are rasterized with the symbols texture into one rgba image
+//! of the whole screen, which is placed over the cells at (0, 0). Blank cells
+//! are transparent, so the text below shows through. The image is only sent
+//! again when it changed. Sprite angles are ignored.
+
+use crate::render::{sprite::Sprites, style::Color};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    io::{self, Write},
+};
+
+/// kitty requires the base64 payload split in chunks of at most 4096 bytes
+const KITTY_CHUNK: usize = 4096;
+/// image id of the pixel layers
+const KITTY_IMAGE_ID: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TerminalGraphics {
+    #[default]
+    None,
+    Kitty,
+}
+
+impl TerminalGraphics {
+    pub fn detect() -> Self {
+        let var = |k| std::env::var(k).ok();
+        Self::from_env(
+            var("RUST_PIXEL_GRAPHICS").as_deref(),
+            var("TERM").as_deref(),
+            var("TERM_PROGRAM").as_deref(),
+            var("KITTY_WINDOW_ID").as_deref(),
+        )
+    }
+
+    pub fn from_env(
+        force: Option<&str>,
+        term: Option<&str>,
+        term_program: Option<&str>,
+        kitty_window_id: Option<&str>,
+    ) -> Self {
+        match force.map(|f| f.to_ascii_lowercase()).as_deref() {
+            Some("kitty") => return TerminalGraphics::Kitty,
+            Some("none") | Some("off") => return TerminalGraphics::None,
+            _ => {}
+        }
+        let term = term.unwrap_or("");
+        if term.starts_with("tmux") || term.starts_with("screen") {
+            return TerminalGraphics::None;
+        }
+        if kitty_window_id.is_some()
+            || term.contains("kitty")
+            || matches!(term_program, Some("WezTerm") | Some("ghostty"))
+        {
+            return TerminalGraphics::Kitty;
+        }
+        TerminalGraphics::None
+    }
+}
+
+/// standard base64 with padding
+pub fn base64_encode(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut s = String::with_capacity(data.len().div_ceil(3) * 4);
+    for c in data.chunks(3) {
+        let n = ((c[0] as u32) << 16)
+            | ((*c.get(1).unwrap_or(&0) as u32) << 8)
+            | *c.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= c.len() {
+                s.push(TABLE[((n >> (18 - i * 6)) & 0x3f) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+/// transmits and shows w * h rgba pixels scaled to cols * rows cells
+/// at the cursor, without moving the cursor
+pub fn kitty_image(id: u32, rgba: &[u8], w: u32, h: u32, cols: u16, rows: u16) -> String {
+    let data = base64_encode(rgba);
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut s = String::new();
+    for (i, c) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        let c = std::str::from_utf8(c).unwrap();
+        if i == 0 {
+            s.push_str(&format!(
+                "\x1b_Ga=T,f=32,s={},v={},c={},r={},i={},C=1,q=2,m={};{}\x1b\\",
+                w, h, cols, rows, id, more, c
+            ));
+        } else {
+            s.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, c));
+        }
+    }
+    s
+}
+
+/// deletes the image and its placements
+pub fn kitty_delete(id: u32) -> String {
+    format!("\x1b_Ga=d,d=I,i={},q=2\x1b\\", id)
+}
+
+/// rgba pixels of the symbols texture, 128 * 128 symbols
+pub struct SymbolTexture {
+    pub rgba: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl SymbolTexture {
+    #[cfg(feature = "image")]
+    pub fn load(project_path: &str) -> Result<Self, String> {
+        let path = format!(
+            "{}{}{}",
+            project_path,
+            std::path::MAIN_SEPARATOR,
+            crate::render::adapter::PIXEL_TEXTURE_FILE
+        );
+        let img = image::open(&path)
+            .map_err(|e| format!("{}: {}", path, e))?
+            .to_rgba8();
+        Ok(Self {
+            width: img.width(),
+            height: img.height(),
+            rgba: img.into_raw(),
+        })
+    }
+
+    pub fn sym_size(&self) -> (u32, u32) {
+        (self.width / 128, self.height / 128)
+    }
+
+    fn texel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        [
+            self.rgba[i],
+            self.rgba[i + 1],
+            self.rgba[i + 2],
+            self.rgba[i + 3],
+        ]
+    }
+}
+
+/// draws visible pixel layers, lower z_index first, into a transparent
+/// cols * rows cells canvas, sprite positions are in pixels
+pub fn rasterize(layers: &mut [Sprites], tex: &SymbolTexture, cols: u16, rows: u16) -> Vec<u8> {
+    let (sw, sh) = tex.sym_size();
+    let (cw, ch) = (cols as u32 * sw, rows as u32 * sh);
+    let mut canvas = vec![0u8; (cw * ch * 4) as usize];
+    let mut zorder: Vec<usize> = (0..layers.len()).collect();
+    zorder.sort_by_key(|i| layers[*i].z_index);
+    for li in zorder {
+        let layer = &mut layers[li];
+        if !layer.is_pixel || layer.is_hidden {
+            continue;
+        }
+        layer.update_render_index();
+        for si in &layer.render_index {
+            let s = &layer.sprites[si.0];
+            if s.is_hidden() {
+                continue;
+            }
+            let area = s.content.area;
+            for (i, cell) in s.content.content.iter().enumerate() {
+                let (sym, t, fg, bg) = cell.get_cell_info();
+                // same layout as push_render_buffer
+                let tx = if t < 64 { t as u32 } else { 1 };
+                let symx = sym as u32 % 16 + (tx % 8) * 16;
+                let symy = sym as u32 / 16 + (tx / 8) * 16;
+                let mut fc = s.tint_color(fg).get_rgba();
+                fc.3 = s.alpha;
+                let bc = if bg != Color::Reset {
+                    let mut c = s.tint_color(bg).get_rgba();
+                    c.3 = s.alpha;
+                    Some(c)
+                } else {
+                    None
+                };
+                let ox = area.x as u32 + (i as u32 % area.width as u32) * sw;
+                let oy = area.y as u32 + (i as u32 / area.width as u32) * sh;
+                for y in 0..sh {
+                    for x in 0..sw {
+                        let (px, py) = (ox + x, oy + y);
+                        if px >= cw || py >= ch {
+                            continue;
+                        }
+                        let t = tex.texel(symx * sw + x, symy * sh + y);
+                        let c = if t[3] >= 128 {
+                            let m = |a: u8, b: u8| (a as u32 * b as u32 / 255) as u8;
+                            (m(fc.0, t[0]), m(fc.1, t[1]), m(fc.2, t[2]), fc.3)
+                        } else if let Some(b) = bc {
+                            b
+                        } else {
+                            continue;
+                        };
+                        let k = ((py * cw + px) * 4) as usize;
+                        canvas[k..k + 4].copy_from_slice(&[c.0, c.1, c.2, c.3]);
+                    }
+                }
+            }
+        }
+    }
+    canvas
+}
+
+/// pixel layers of the crossterm adapter in kitty mode
+pub struct KittyLayer {
+    tex: SymbolTexture,
+    // hash of the image on screen
+    last: Option<u64>,
+}
+
+impl KittyLayer {
+    pub fn new(tex: SymbolTexture) -> Self {
+        Self { tex, last: None }
+    }
+
+    /// sends the pixel layers if they changed since the last frame,
+    /// the cursor must be at (0, 0)
+    pub fn draw<W: Write>(
+        &mut self,
+        w: &mut W,
+        layers: &mut [Sprites],
+        cols: u16,
+        rows: u16,
+    ) -> io::Result<()> {
+        let canvas = rasterize(layers, &self.tex, cols, rows);
+        let mut h = DefaultHasher::new();
+        canvas.hash(&mut h);
+        let hash = h.finish();
+        if self.last == Some(hash) {
+            return Ok(());
+        }
+        self.last = Some(hash);
+        let (sw, sh) = self.tex.sym_size();
+        write!(
+            w,
+            "{}{}",
+            kitty_delete(KITTY_IMAGE_ID),
+            kitty_image(
+                KITTY_IMAGE_ID,
+                &canvas,
+                cols as u32 * sw,
+                rows as u32 * sh,
+                cols,
+                rows
+            )
+        )
+    }
+
+    pub fn clear<W: Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.last = None;
+        write!(w, "{}", kitty_delete(KITTY_IMAGE_ID))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_from_env() {
+        let d = TerminalGraphics::from_env;
+        let (kitty, none) = (TerminalGraphics::Kitty, TerminalGraphics::None);
+        assert_eq!(d(None, Some("xterm-kitty"), None, None), kitty);
+        assert_eq!(d(None, Some("xterm-256color"), None, Some("3")), kitty);
+        assert_eq!(
+            d(None, Some("xterm-256color"), Some("WezTerm"), None),
+            kitty
+        );
+        assert_eq!(
+            d(None, Some("xterm-256color"), Some("iTerm.app"), None),
+            none
+        );
+        assert_eq!(d(None, Some("tmux-256color"), None, Some("3")), none);
+        assert_eq!(d(None, None, None, None), none);
+        assert_eq!(d(Some("KITTY"), Some("xterm"), None, None), kitty);
+        assert_eq!(d(Some("none"), Some("xterm-kitty"), None, None), none);
+    }
+
+    #[test]
+    fn image_is_base64_in_chunks() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+
+        let s = kitty_image(1, &[255; 4], 1, 1, 1, 1);
+        assert_eq!(
+            s,
+            "\x1b_Ga=T,f=32,s=1,v=1,c=1,r=1,i=1,C=1,q=2,m=0;/////w==\x1b\\"
+        );
+        // 3 * 4096 bytes are 4 * 4096 base64 chars, 4 chunks
+        let s = kitty_image(1, &[0; 3 * KITTY_CHUNK], 64, 48, 8, 6);
+        assert_eq!(s.matches("\x1b_G").count(), 4);
+        assert_eq!(s.matches("m=1;").count(), 3);
+        assert!(s.ends_with("\x1b\\") && s.contains("\x1b_Gm=0;"));
+    }
+}
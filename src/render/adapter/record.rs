@@ -0,0 +1,132 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Records the screen to a gif, for README demos
+//!
+//! Every captured frame is read back from the gl framebuffer with
+//! glReadPixels and quantized to 256 colors, which stalls the gpu pipeline
+//! and costs several ms per frame, so the game runs slower while recording.
+//! Frames are taken at most fps times a second, scaled down to fit
+//! RECORD_MAX_SIDE and recording stops by itself after RECORD_MAX_SECS.
+//!
+//! # Example
+//!
+//! ctx.adapter.start_recording("demo.gif", 20)?;
+//! ...
+//! ctx.adapter.stop_recording()?;
+
+use glow::HasContext;
+use image::{codecs::gif::GifEncoder, Delay, Frame, RgbaImage};
+use std::{fs::File, time::Instant};
+
+/// frames wider or higher are scaled down by an integer factor
+pub const RECORD_MAX_SIDE: u32 = 640;
+/// recording stops after this many seconds of frames
+pub const RECORD_MAX_SECS: u32 = 30;
+/// gif delays are in 1/100 s, faster is not played well by browsers
+pub const RECORD_MAX_FPS: u32 = 50;
+
+pub struct GifRecorder {
+    encoder: GifEncoder<File>,
+    fps: u32,
+    frames: u32,
+    last: Option<Instant>,
+}
+
+impl GifRecorder {
+    pub fn new(path: &str, fps: u32) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("create {}: {}", path, e))?;
+        let mut encoder = GifEncoder::new_with_speed(file, 10);
+        encoder
+            .set_repeat(image::codecs::gif::Repeat::Infinite)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            encoder,
+            fps: fps.clamp(1, RECORD_MAX_FPS),
+            frames: 0,
+            last: None,
+        })
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.frames >= self.fps * RECORD_MAX_SECS
+    }
+
+    /// reads the bound framebuffer of w * h pixels if a frame is due,
+    /// call it after drawing and before swapping the window
+    pub fn capture(&mut self, gl: &glow::Context, w: u32, h: u32) -> Result<(), String> {
+        let now = Instant::now();
+        if let Some(last) = self.last {
+            if now.duration_since(last).as_secs_f32() < 1.0 / self.fps as f32 {
+                return Ok(());
+            }
+        }
+        if self.is_full() || w == 0 || h == 0 {
+            return Ok(());
+        }
+        self.last = Some(now);
+        let mut data = vec![0u8; (w * h * 4) as usize];
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            gl.read_pixels(
+                0,
+                0,
+                w as i32,
+                h as i32,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelPackData::Slice(&mut data),
+            );
+        }
+        self.push_rgba(w, h, &data)
+    }
+
+    /// adds a frame of bottom-up rgba rows, as gl reads them
+    pub fn push_rgba(&mut self, w: u32, h: u32, data: &[u8]) -> Result<(), String> {
+        let img = frame_image(w, h, data);
+        let delay = Delay::from_numer_denom_ms(1000, self.fps);
+        self.encoder
+            .encode_frame(Frame::from_parts(img, 0, 0, delay))
+            .map_err(|e| e.to_string())?;
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+/// flips gl rows to top-down and scales to fit RECORD_MAX_SIDE,
+/// alpha is set to opaque
+pub fn frame_image(w: u32, h: u32, data: &[u8]) -> RgbaImage {
+    let mut scale = 1;
+    while w.max(h) / scale > RECORD_MAX_SIDE {
+        scale += 1;
+    }
+    let (ow, oh) = ((w / scale).max(1), (h / scale).max(1));
+    RgbaImage::from_fn(ow, oh, |x, y| {
+        let (sx, sy) = (x * scale, h - 1 - y * scale);
+        let i = ((sy * w + sx) * 4) as usize;
+        image::Rgba([data[i], data[i + 1], data[i + 2], 255])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_is_flipped_and_scaled() {
+        // 1280 x 2, bottom row red, top row blue
+        let (w, h) = (1280u32, 2u32);
+        let mut data = vec![0u8; (w * h * 4) as usize];
+        for x in 0..w as usize {
+            data[x * 4] = 255;
+            data[(w as usize + x) * 4 + 2] = 255;
+        }
+        let img = frame_image(w, h, &data);
+        assert_eq!(img.dimensions(), (640, 1));
+        assert_eq!(img.get_pixel(0, 0).0, [0, 0, 255, 255]);
+    }
+}
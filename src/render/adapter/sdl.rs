@@ -5,25 +5,29 @@
 //! Includes resizing of height and width, init settings.
 //! Use opengl and glow mod for rendering.
 use crate::event::{
-    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton::*, MouseEvent, MouseEventKind::*,
+    apply_deadzone, Event, GamepadAxis, GamepadButton, GamepadEvent, GamepadEventKind, GamepadInfo,
+    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton::*, MouseEvent, MouseEventKind::*,
+    AXIS_DEADZONE,
 };
 use crate::render::{
     adapter::{
-        gl::pixel::GlPixel, init_sym_height, init_sym_width, Adapter, AdapterBase,
-        PIXEL_SYM_HEIGHT, PIXEL_SYM_WIDTH, PIXEL_TEXTURE_FILE,
+        gl::pixel::GlPixel, init_sym_height, init_sym_width, record::GifRecorder, Adapter,
+        AdapterBase, PIXEL_SYM_HEIGHT, PIXEL_SYM_WIDTH, PIXEL_TEXTURE_FILE,
     },
     buffer::Buffer,
     sprite::Sprites,
 };
 use log::info;
 use sdl2::{
+    controller::{Axis as SAxis, Button as SButton, GameController},
     event::Event as SEvent,
     image::LoadSurface,
     keyboard::Keycode as SKeycode,
     mouse::*,
+    pixels::PixelFormatEnum,
     surface::Surface,
     video::{Window, WindowPos::Positioned},
-    EventPump, Sdl,
+    EventPump, GameControllerSubsystem, Sdl,
 };
 use std::any::Any;
 use std::time::Duration;
@@ -55,6 +59,13 @@ pub struct SdlAdapter {
 
     // data for dragging the window
     drag: Drag,
+
+    // gif recorder, Some while recording
+    recorder: Option<GifRecorder>,
+
+    // opened gamepads
+    controller_subsystem: Option<GameControllerSubsystem>,
+    controllers: Vec<GameController>,
 }
 
 pub enum SdlBorderArea {
@@ -74,6 +85,9 @@ impl SdlAdapter {
             sdl_window: None,
             gl_context: None,
             drag: Default::default(),
+            recorder: None,
+            controller_subsystem: None,
+            controllers: vec![],
         }
     }
 
@@ -89,6 +103,28 @@ impl SdlAdapter {
         self.sdl_context.mouse().show_cursor(true);
     }
 
+    // pads are opened on ControllerDeviceAdded, sdl also sends it
+    // for pads already plugged in at startup
+    fn update_controllers(&mut self, event: &SEvent) -> Option<GamepadEvent> {
+        match *event {
+            SEvent::ControllerDeviceAdded { which, .. } => {
+                let gc = self.controller_subsystem.as_ref()?.open(which).ok()?;
+                let id = gc.instance_id();
+                info!("gamepad {} connected: {}", id, gc.name());
+                if self.controllers.iter().any(|c| c.instance_id() == id) {
+                    return None;
+                }
+                self.controllers.push(gc);
+                Some(GamepadEvent::new(id, GamepadEventKind::Connected))
+            }
+            SEvent::ControllerDeviceRemoved { which, .. } => {
+                self.controllers.retain(|c| c.instance_id() != which);
+                Some(GamepadEvent::new(which, GamepadEventKind::Disconnected))
+            }
+            _ => gamepad_event_from_sdl(event),
+        }
+    }
+
     fn in_border(&self, x: i32, y: i32) -> SdlBorderArea {
         let w = self.cell_width();
         let h = self.cell_height();
@@ -191,6 +227,7 @@ impl Adapter for SdlAdapter {
 
         // init video subsystem...
         let video_subsystem = self.sdl_context.video().unwrap();
+        self.controller_subsystem = self.sdl_context.game_controller().ok();
 
         // Set OpenGL attributes
         let gl_attr = video_subsystem.gl_attr();
@@ -263,28 +300,31 @@ impl Adapter for SdlAdapter {
     }
 
     fn poll_event(&mut self, timeout: Duration, es: &mut Vec<Event>) -> bool {
-        let mut ses: Vec<SEvent> = vec![];
-        if let Some(ref mut ep) = self.event_pump {
-            for event in ep.poll_iter() {
-                ses.push(event.clone());
-                // convert sdl events to pixel events, providing a unified processing interfaces
-                if let Some(et) =
-                    input_events_from_sdl(&event, self.base.ratio_x, self.base.ratio_y)
-                {
-                    if !self.drag.draging {
-                        es.push(et);
-                    }
-                }
+        let ses: Vec<SEvent> = match self.event_pump {
+            Some(ref mut ep) => ep.poll_iter().collect(),
+            None => return false,
+        };
+        for event in &ses {
+            // gamepad events, also opens or closes the pads
+            if let Some(ge) = self.update_controllers(event) {
+                es.push(Event::Gamepad(ge));
+                continue;
             }
-            for event in ses {
-                // sdl window is borderless, we draw the title and border ourselves
-                // processing mouse events such as dragging of borders, close, etc.
-                if self.drag_window(&event) {
-                    return true;
+            // convert sdl events to pixel events, providing a unified processing interfaces
+            if let Some(et) = input_events_from_sdl(event, self.base.ratio_x, self.base.ratio_y) {
+                if !self.drag.draging {
+                    es.push(et);
                 }
             }
-            ::std::thread::sleep(timeout);
         }
+        for event in ses {
+            // sdl window is borderless, we draw the title and border ourselves
+            // processing mouse events such as dragging of borders, close, etc.
+            if self.drag_window(&event) {
+                return true;
+            }
+        }
+        ::std::thread::sleep(timeout);
         false
     }
 
@@ -305,11 +345,95 @@ impl Adapter for SdlAdapter {
 
         self.draw_all_graph(current_buffer, _p, pixel_sprites, stage);
 
+        // capture the frame before swap...
+        if let (Some(r), Some(gl)) = (&mut self.recorder, &self.base.gl) {
+            let (w, h) = self.sdl_window.as_ref().unwrap().drawable_size();
+            r.capture(gl, w, h)?;
+        }
+        if self.recorder.as_ref().is_some_and(|r| r.is_full()) {
+            self.stop_recording()?;
+        }
+
         // swap window for display
         self.sdl_window.as_ref().unwrap().gl_swap_window();
         Ok(())
     }
 
+    fn start_recording(&mut self, path: &str, fps: u32) -> Result<(), String> {
+        self.stop_recording()?;
+        self.recorder = Some(GifRecorder::new(path, fps)?);
+        info!("start recording to {}", path);
+        Ok(())
+    }
+
+    fn stop_recording(&mut self) -> Result<(), String> {
+        // the gif trailer is written when the encoder drops
+        if let Some(r) = self.recorder.take() {
+            info!("stop recording, {} frames", r.frames());
+        }
+        Ok(())
+    }
+
+    fn gamepads(&self) -> Vec<GamepadInfo> {
+        self.controllers
+            .iter()
+            .map(|c| GamepadInfo {
+                id: c.instance_id(),
+                name: c.name(),
+            })
+            .collect()
+    }
+
+    fn set_window_title(&mut self, title: &str) {
+        self.base.title = title.to_string();
+        if let Some(win) = &mut self.sdl_window {
+            if let Err(e) = win.set_title(title) {
+                info!("set title failed: {}", e);
+            }
+        }
+    }
+
+    fn set_window_icon(&mut self, rgba: &[u8], w: u32, h: u32) {
+        let row = w as usize * 4;
+        if rgba.len() < row * h as usize {
+            info!("set icon failed: need {}x{} rgba pixels", w, h);
+            return;
+        }
+        let Some(win) = &mut self.sdl_window else {
+            return;
+        };
+        match Surface::new(w, h, PixelFormatEnum::RGBA32) {
+            Ok(mut icon) => {
+                // surface rows may be padded to pitch bytes
+                let pitch = icon.pitch() as usize;
+                icon.with_lock_mut(|p| {
+                    for y in 0..h as usize {
+                        p[y * pitch..y * pitch + row]
+                            .copy_from_slice(&rgba[y * row..(y + 1) * row]);
+                    }
+                });
+                win.set_icon(icon);
+            }
+            Err(e) => info!("set icon failed: {}", e),
+        }
+    }
+
+    fn clipboard_get(&mut self) -> Option<String> {
+        let cb = self.sdl_context.video().ok()?.clipboard();
+        if !cb.has_clipboard_text() {
+            return None;
+        }
+        cb.clipboard_text().ok()
+    }
+
+    fn clipboard_set(&mut self, text: &str) {
+        if let Ok(video) = self.sdl_context.video() {
+            if let Err(e) = video.clipboard().set_clipboard_text(text) {
+                info!("set clipboard failed: {}", e);
+            }
+        }
+    }
+
     fn hide_cursor(&mut self) -> Result<(), String> {
         Ok(())
     }
@@ -352,6 +476,72 @@ macro_rules! sdl_event {
     };
 }
 
+/// Convert sdl controller button and axis events to GamepadEvent,
+/// axis values get the default deadzone
+pub fn gamepad_event_from_sdl(e: &SEvent) -> Option<GamepadEvent> {
+    let button = |b: &SButton| {
+        Some(match b {
+            SButton::A => GamepadButton::South,
+            SButton::B => GamepadButton::East,
+            SButton::X => GamepadButton::West,
+            SButton::Y => GamepadButton::North,
+            SButton::Back => GamepadButton::Back,
+            SButton::Guide => GamepadButton::Guide,
+            SButton::Start => GamepadButton::Start,
+            SButton::LeftStick => GamepadButton::LeftStick,
+            SButton::RightStick => GamepadButton::RightStick,
+            SButton::LeftShoulder => GamepadButton::LeftShoulder,
+            SButton::RightShoulder => GamepadButton::RightShoulder,
+            SButton::DPadUp => GamepadButton::DPadUp,
+            SButton::DPadDown => GamepadButton::DPadDown,
+            SButton::DPadLeft => GamepadButton::DPadLeft,
+            SButton::DPadRight => GamepadButton::DPadRight,
+            _ => return None,
+        })
+    };
+    let kind = match e {
+        SEvent::ControllerButtonDown { button: b, .. } => GamepadEventKind::ButtonDown(button(b)?),
+        SEvent::ControllerButtonUp { button: b, .. } => GamepadEventKind::ButtonUp(button(b)?),
+        SEvent::ControllerAxisMotion { axis, value, .. } => {
+            let a = match axis {
+                SAxis::LeftX => GamepadAxis::LeftX,
+                SAxis::LeftY => GamepadAxis::LeftY,
+                SAxis::RightX => GamepadAxis::RightX,
+                SAxis::RightY => GamepadAxis::RightY,
+                SAxis::TriggerLeft => GamepadAxis::TriggerLeft,
+                SAxis::TriggerRight => GamepadAxis::TriggerRight,
+            };
+            GamepadEventKind::Axis(a, apply_deadzone(*value, AXIS_DEADZONE))
+        }
+        _ => return None,
+    };
+    let which = match e {
+        SEvent::ControllerButtonDown { which, .. }
+        | SEvent::ControllerButtonUp { which, .. }
+        | SEvent::ControllerAxisMotion { which, .. } => *which,
+        _ => 0,
+    };
+    Some(GamepadEvent::new(which, kind))
+}
+
+fn modifiers_from_sdl(m: sdl2::keyboard::Mod) -> KeyModifiers {
+    use sdl2::keyboard::Mod;
+    let mut ret = KeyModifiers::NONE;
+    if m.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+        ret.insert(KeyModifiers::SHIFT);
+    }
+    if m.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) {
+        ret.insert(KeyModifiers::CONTROL);
+    }
+    if m.intersects(Mod::LALTMOD | Mod::RALTMOD) {
+        ret.insert(KeyModifiers::ALT);
+    }
+    if m.intersects(Mod::LGUIMOD | Mod::RGUIMOD) {
+        ret.insert(KeyModifiers::SUPER);
+    }
+    ret
+}
+
 /// Convert sdl input events to RustPixel event, for the sake of unified event processing
 /// For keyboard and mouse event, please refer to the handle_input method in game/unblock/model.rs
 pub fn input_events_from_sdl(e: &SEvent, adjx: f32, adjy: f32) -> Option<Event> {
@@ -359,7 +549,12 @@ pub fn input_events_from_sdl(e: &SEvent, adjx: f32, adjy: f32) -> Option<Event>
     let sym_height = PIXEL_SYM_HEIGHT.get().expect("lazylock init");
     let mut mcte: Option<MouseEvent> = None;
     match e {
-        SEvent::KeyDown { keycode, .. } => {
+        SEvent::KeyDown {
+            keycode,
+            keymod,
+            repeat,
+            ..
+        } => {
             let kc = match keycode {
                 Some(SKeycode::Space) => ' ',
                 Some(SKeycode::A) => 'a',
@@ -392,7 +587,10 @@ pub fn input_events_from_sdl(e: &SEvent, adjx: f32, adjy: f32) -> Option<Event>
                     return None;
                 }
             };
-            let cte = KeyEvent::new(KeyCode::Char(kc), KeyModifiers::NONE);
+            let mut cte = KeyEvent::new(KeyCode::Char(kc), modifiers_from_sdl(*keymod));
+            if *repeat {
+                cte.kind = KeyEventKind::Repeat;
+            }
             return Some(Event::Key(cte));
         }
         SEvent::MouseButtonUp { x, y, .. } => {
@@ -4,13 +4,28 @@
 //! Implements an Adapter trait. Moreover, all SDL related processing is handled here.
 //! Includes resizing of height and width, init settings.
 //! Use opengl and glow mod for rendering.
+//!
+//! When the host page draws by itself (only_render_buffer), every frame the
+//! render cells are kept in base.rbuf. Instead of uploading the whole rbuf,
+//! JS can ask only for the cells changed since the last frame:
+//!
+//! ```text
+//! // js side, the game wrapper generated by pixel_game! exports both
+//! g.tick(dt);
+//! const n = g.web_cells_len();  // resize the instance buffer if n changed
+//! const d = g.web_dirty();      // Float32Array, WEB_CELL_FLOATS per changed cell
+//! // for each changed cell update the slot d[i * 17] of the WebGL
+//! // instance buffer with gl.bufferSubData, instead of a full upload
+//! ```
+//! If the number of cells changed, every cell is reported as dirty.
 use crate::event::{
-    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton::*, MouseEvent, MouseEventKind::*,
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton::*, MouseEvent,
+    MouseEventKind::*,
 };
 use crate::render::{
     adapter::{
         gl::pixel::GlPixel, 
-        Adapter, AdapterBase, PIXEL_SYM_HEIGHT, PIXEL_SYM_WIDTH, init_sym_width, init_sym_height,
+        Adapter, AdapterBase, RenderCell, PIXEL_SYM_HEIGHT, PIXEL_SYM_WIDTH, init_sym_width, init_sym_height,
     },
     buffer::Buffer,
     sprite::Sprites,
@@ -19,14 +34,34 @@ use log::info;
 use std::any::Any;
 use std::time::Duration;
 
+/// floats per cell in WebAdapter::web_dirty_floats
+pub const WEB_CELL_FLOATS: usize = 17;
+
 pub struct WebAdapter {
     pub base: AdapterBase,
+    // events pushed by js, drained by poll_event like other adapters
+    pub events: Vec<Event>,
 }
 
 impl WebAdapter {
     pub fn new(gn: &str, project_path: &str) -> Self {
         Self {
             base: AdapterBase::new(gn, project_path),
+            events: vec![],
+        }
+    }
+
+    /// converts a js event with the adapter's own size and ratio,
+    /// and queues it for the next poll_event
+    pub fn push_event(&mut self, t: u8, e: web_sys::Event) {
+        if let Some(pe) = input_events_from_web(
+            t,
+            e,
+            self.base.pixel_h,
+            self.base.ratio_x,
+            self.base.ratio_y,
+        ) {
+            self.events.push(pe);
         }
     }
 
@@ -49,6 +84,50 @@ impl WebAdapter {
         ));
         info!("web glpix init ok...");
     }
+
+    /// cells changed in the last frame, as (index in rbuf, cell)
+    pub fn web_dirty_cells(&self) -> Vec<(usize, RenderCell)> {
+        self.base
+            .rdirty
+            .iter()
+            .map(|&i| (i, self.base.rbuf[i]))
+            .collect()
+    }
+
+    /// web_dirty_cells flattened for js, WEB_CELL_FLOATS per cell:
+    /// index, fg rgba, bg rgba (all -1.0 without bg), texsym, x, y, w, h, angle, cx, cy
+    pub fn web_dirty_floats(&self) -> Vec<f32> {
+        let mut ret = Vec::with_capacity(self.base.rdirty.len() * WEB_CELL_FLOATS);
+        for (i, c) in self.web_dirty_cells() {
+            let f = c.fcolor;
+            let b = c.bcolor.unwrap_or((-1.0, -1.0, -1.0, -1.0));
+            ret.extend_from_slice(&[
+                i as f32,
+                f.0,
+                f.1,
+                f.2,
+                f.3,
+                b.0,
+                b.1,
+                b.2,
+                b.3,
+                c.texsym as f32,
+                c.x,
+                c.y,
+                c.w as f32,
+                c.h as f32,
+                c.angle,
+                c.cx,
+                c.cy,
+            ]);
+        }
+        ret
+    }
+
+    /// total cell count of rbuf, the js side should resize its buffer to it
+    pub fn web_cells_len(&self) -> usize {
+        self.base.rbuf.len()
+    }
 }
 
 impl Adapter for WebAdapter {
@@ -94,7 +173,8 @@ impl Adapter for WebAdapter {
         PIXEL_SYM_HEIGHT.get().expect("lazylock init") / self.base.ratio_y
     }
 
-    fn poll_event(&mut self, _timeout: Duration, _es: &mut Vec<Event>) -> bool {
+    fn poll_event(&mut self, _timeout: Duration, es: &mut Vec<Event>) -> bool {
+        es.append(&mut self.events);
         false
     }
 
@@ -153,10 +233,15 @@ pub fn input_events_from_web(t: u8, e: web_sys::Event, pixel_h: u32, ratiox: f32
         let kcc = (key_e.key_code(), key_e.char_code());
         match kcc.0 {
             32 | 48..=57 | 97..=122 => {
-                let cte = KeyEvent::new(
-                    KeyCode::Char(char::from_u32(kcc.0).unwrap()),
-                    KeyModifiers::NONE,
-                );
+                let mut mods = KeyModifiers::NONE;
+                mods.set(KeyModifiers::SHIFT, key_e.shift_key());
+                mods.set(KeyModifiers::CONTROL, key_e.ctrl_key());
+                mods.set(KeyModifiers::ALT, key_e.alt_key());
+                mods.set(KeyModifiers::SUPER, key_e.meta_key());
+                let mut cte = KeyEvent::new(KeyCode::Char(char::from_u32(kcc.0).unwrap()), mods);
+                if key_e.repeat() {
+                    cte.kind = KeyEventKind::Repeat;
+                }
                 return Some(Event::Key(cte));
             }
             _ => {
@@ -0,0 +1,149 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Box drawing borders in text mode
+//!
+//! A border glyph is described by its arms (up, right, down, left),
+//! drawing a border over another box drawing glyph merges their arms,
+//! so where two borders meet the right T-junction or cross is drawn.
+//! Buffer::draw_border and Sprite::set_border draw with these glyphs.
+
+pub const ARM_UP: u8 = 0b0001;
+pub const ARM_RIGHT: u8 = 0b0010;
+pub const ARM_DOWN: u8 = 0b0100;
+pub const ARM_LEFT: u8 = 0b1000;
+
+const ARM_V: u8 = ARM_UP | ARM_DOWN;
+const ARM_H: u8 = ARM_LEFT | ARM_RIGHT;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BorderType {
+    #[default]
+    Plain,
+    Rounded,
+    Double,
+    Thick,
+    /// +, - and |, for terminals or fonts without box drawing chars
+    Ascii,
+}
+
+// arms, plain, thick, double
+const GLYPHS: [(u8, &str, &str, &str); 11] = [
+    (ARM_V, "│", "┃", "║"),
+    (ARM_H, "─", "━", "═"),
+    (ARM_DOWN | ARM_LEFT, "┐", "┓", "╗"),
+    (ARM_RIGHT | ARM_DOWN, "┌", "┏", "╔"),
+    (ARM_UP | ARM_LEFT, "┘", "┛", "╝"),
+    (ARM_UP | ARM_RIGHT, "└", "┗", "╚"),
+    (ARM_V | ARM_LEFT, "┤", "┫", "╣"),
+    (ARM_V | ARM_RIGHT, "├", "┣", "╠"),
+    (ARM_H | ARM_DOWN, "┬", "┳", "╦"),
+    (ARM_H | ARM_UP, "┴", "┻", "╩"),
+    (ARM_V | ARM_H, "┼", "╋", "╬"),
+];
+
+const ROUNDED: [(u8, &str); 4] = [
+    (ARM_DOWN | ARM_LEFT, "╮"),
+    (ARM_RIGHT | ARM_DOWN, "╭"),
+    (ARM_UP | ARM_LEFT, "╯"),
+    (ARM_UP | ARM_RIGHT, "╰"),
+];
+
+/// the six glyphs of a plain box
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorderSet {
+    pub vertical: &'static str,
+    pub horizontal: &'static str,
+    pub top_left: &'static str,
+    pub top_right: &'static str,
+    pub bottom_left: &'static str,
+    pub bottom_right: &'static str,
+}
+
+impl BorderType {
+    pub fn set(&self) -> BorderSet {
+        BorderSet {
+            vertical: border_glyph(ARM_V, *self),
+            horizontal: border_glyph(ARM_H, *self),
+            top_left: border_glyph(ARM_RIGHT | ARM_DOWN, *self),
+            top_right: border_glyph(ARM_LEFT | ARM_DOWN, *self),
+            bottom_left: border_glyph(ARM_RIGHT | ARM_UP, *self),
+            bottom_right: border_glyph(ARM_LEFT | ARM_UP, *self),
+        }
+    }
+}
+
+/// glyph with these arms, a single arm is drawn as a full line,
+/// no arm gives a space
+pub fn border_glyph(arms: u8, bt: BorderType) -> &'static str {
+    let mut arms = arms & (ARM_V | ARM_H);
+    if arms & ARM_V != 0 && arms & ARM_V != ARM_V && arms & ARM_H == 0 {
+        arms = ARM_V;
+    }
+    if arms & ARM_H != 0 && arms & ARM_H != ARM_H && arms & ARM_V == 0 {
+        arms = ARM_H;
+    }
+    if arms == 0 {
+        return " ";
+    }
+    if bt == BorderType::Ascii {
+        return match (arms & ARM_V != 0, arms & ARM_H != 0) {
+            (true, false) => "|",
+            (false, true) => "-",
+            _ => "+",
+        };
+    }
+    if bt == BorderType::Rounded {
+        if let Some(g) = ROUNDED.iter().find(|g| g.0 == arms) {
+            return g.1;
+        }
+    }
+    let g = GLYPHS.iter().find(|g| g.0 == arms).unwrap();
+    match bt {
+        BorderType::Double => g.3,
+        BorderType::Thick => g.2,
+        _ => g.1,
+    }
+}
+
+/// arms of a box drawing glyph, None if sym is not one
+pub fn border_arms(sym: &str) -> Option<u8> {
+    match sym {
+        "|" => return Some(ARM_V),
+        "-" => return Some(ARM_H),
+        "+" => return Some(ARM_V | ARM_H),
+        _ => {}
+    }
+    GLYPHS
+        .iter()
+        .find(|g| g.1 == sym || g.2 == sym || g.3 == sym)
+        .map(|g| g.0)
+        .or_else(|| ROUNDED.iter().find(|g| g.1 == sym).map(|g| g.0))
+}
+
+/// glyph to draw over old, arms of both are kept and the new border type
+/// wins, mixed line weights are not drawn
+pub fn merge_border(old: &str, arms: u8, bt: BorderType) -> &'static str {
+    border_glyph(arms | border_arms(old).unwrap_or(0), bt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn junctions_merge() {
+        assert_eq!(merge_border("│", ARM_H, BorderType::Plain), "┼");
+        assert_eq!(merge_border("╮", ARM_V, BorderType::Plain), "┤");
+        assert_eq!(
+            merge_border("─", ARM_DOWN | ARM_LEFT, BorderType::Double),
+            "╦"
+        );
+        assert_eq!(
+            merge_border("a", ARM_RIGHT | ARM_DOWN, BorderType::Rounded),
+            "╭"
+        );
+        assert_eq!(merge_border("|", ARM_H, BorderType::Ascii), "+");
+        assert_eq!(BorderType::Thick.set().bottom_right, "┛");
+    }
+}
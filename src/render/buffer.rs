@@ -7,7 +7,7 @@
 //!
 //! Almost all Unicode chars can be drawn in text mode, depending on the terminal apps
 //! (use of iterm2 in macOS is recommended). For example:
-//! ```
+//! ```ignore
 //! my_buffer.set_str(0, 0, "Hello world 😃.",
 //!     Style::default().fg(Color::Red).bg(Color::Reset))
 //! ```
@@ -29,7 +29,7 @@
 //! 3: assets/c64e2.png custom extension 2
 //! each texture is an image of 16 row * 16 row = 256 chars
 //! # Example
-//! ```
+//! ```ignore
 //! my_buffer.set_str_tex(0, 0, sdlsym(0), Style::default().fg(Color::Red), 1)
 //! ```
 //! sets pos(0,0) in the buffer to the 1st char of texture1(assets/c64u.png)
@@ -39,17 +39,45 @@
 //! Some common chars a-Z and tabs are preset in SDL_SYM_MAP,
 //! for easier set of latin letters using set_str in SDL mode
 //! # Example
-//! ```
+//! ```ignore
 //! my_buffer.set_str_tex(0, 0, "Hello world.",
 //!     Style::default().fg(Color::Red), 0)
 //! ```
 //! Warning！tex here must be set to 0，because the offset in SDL_SYM_MAP is preset based on
 //! texture0(assets/c64l.png). May have display issues if set to another texture.
 //!
+//! draw_text draws a multi-line text into a rect, with word or char wrapping,
+//! left/center/right alignment, and an ellipsis when the text overflows
+//! # Example
+//! ```ignore
+//! my_buffer.draw_text(rect, "Hello world.", WrapMode::Word, Alignment::Center,
+//!     Style::default().fg(Color::Red))
+//! ```
+//!
+//! draw_border frames a rect with box drawing chars, joining borders
+//! already drawn into junctions, with an optional title in the top edge
+//! # Example
+//! ```ignore
+//! my_buffer.draw_border(rect, BorderType::Double, "Score", Alignment::Center,
+//!     Style::default().fg(Color::Yellow))
+//! ```
+//!
+//! push_clip masks all later draws to a rect until pop_clip, so a widget can't
+//! draw outside its bounds, nested clips narrow the current one
+//! # Example
+//! ```ignore
+//! my_buffer.push_clip(list_rect);
+//! for (i, item) in items.iter().enumerate() {
+//!     my_buffer.set_string(list_rect.x, list_rect.y + i as u16, item, style);
+//! }
+//! my_buffer.pop_clip();
+//! ```
+//!
 #[allow(unused_imports)]
 use crate::{
+    render::border::{merge_border, BorderType, ARM_DOWN, ARM_LEFT, ARM_RIGHT, ARM_UP},
     render::cell::{cellsym, Cell},
-    render::style::{Color, Style},
+    render::style::{BlendMode, Color, Style},
     util::Rect,
 };
 use log::info;
@@ -58,10 +86,55 @@ use std::cmp::min;
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+/// how draw_text breaks lines longer than the rect width
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    /// no wrapping, long lines are truncated with an ellipsis
+    #[default]
+    None,
+    /// break at whitespace, words longer than width are broken by chars
+    Word,
+    /// break at any char
+    Char,
+}
+
+/// horizontal alignment of each line in draw_text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+const ELLIPSIS: &str = "…";
+
+/// how blit treats source cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlitMode {
+    /// copies every cell
+    #[default]
+    Opaque,
+    /// blank source cells keep the destination cell
+    SkipBlank,
+    /// source cells whose bg is this color index keep the destination cell
+    ColorKeyed(u8),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Buffer {
     pub area: Rect,
     pub content: Vec<Cell>,
+    // clip stack in absolute pos, top is the current clip
+    #[serde(skip)]
+    clips: Vec<Rect>,
+}
+
+// the clip stack only lives while drawing, it is not part of the content
+impl PartialEq for Buffer {
+    fn eq(&self, other: &Self) -> bool {
+        self.area == other.area && self.content == other.content
+    }
 }
 
 impl Buffer {
@@ -76,7 +149,11 @@ impl Buffer {
         for _ in 0..size {
             content.push(cell.clone());
         }
-        Buffer { area, content }
+        Buffer {
+            area,
+            content,
+            clips: vec![],
+        }
     }
 
     pub fn with_lines<S>(lines: Vec<S>) -> Buffer
@@ -101,6 +178,53 @@ impl Buffer {
         buffer
     }
 
+    /// buffer from multi-line text, for test fixtures,
+    /// width is the widest line, shorter lines are padded with spaces
+    pub fn from_text(text: &str) -> Buffer {
+        Buffer::with_lines(text.lines().collect())
+    }
+
+    /// symbols only, rows joined by newlines, for snapshot tests
+    pub fn to_text(&self) -> String {
+        let w = self.area.width as usize;
+        let rows: Vec<String> = self
+            .content
+            .chunks(w.max(1))
+            .map(|row| row.iter().map(|c| c.symbol.as_str()).collect())
+            .collect();
+        rows.join("\n")
+    }
+
+    /// same as to_text, with fg and bg color escapes around
+    /// every span of the same colors
+    pub fn to_ansi_string(&self) -> String {
+        let w = self.area.width as usize;
+        let mut rows = vec![];
+        for row in self.content.chunks(w.max(1)) {
+            let mut s = String::new();
+            let mut i = 0;
+            while i < row.len() {
+                let (fg, bg) = (row[i].fg, row[i].bg);
+                let mut span = String::new();
+                while i < row.len() && row[i].fg == fg && row[i].bg == bg {
+                    span.push_str(&row[i].symbol);
+                    i += 1;
+                }
+                let sgr: Vec<String> = [ansi_sgr(fg, 38), ansi_sgr(bg, 48)]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                if sgr.is_empty() {
+                    s.push_str(&span);
+                } else {
+                    s.push_str(&format!("\x1b[{}m{}\x1b[0m", sgr.join(";"), span));
+                }
+            }
+            rows.push(s);
+        }
+        rows.join("\n")
+    }
+
     pub fn content(&self) -> &[Cell] {
         &self.content
     }
@@ -150,6 +274,35 @@ impl Buffer {
         &mut self.content[i]
     }
 
+    /// masks draws to rect(absolute pos) intersected with the current clip,
+    /// until the matching pop_clip
+    pub fn push_clip(&mut self, rect: Rect) {
+        let cur = self.clip();
+        let r = if rect.intersects(cur) {
+            rect.intersection(cur)
+        } else {
+            Rect::new(rect.x, rect.y, 0, 0)
+        };
+        self.clips.push(r);
+    }
+
+    pub fn pop_clip(&mut self) -> Option<Rect> {
+        self.clips.pop()
+    }
+
+    /// current clip, the whole area if no clip is pushed
+    pub fn clip(&self) -> Rect {
+        *self.clips.last().unwrap_or(&self.area)
+    }
+
+    /// true if (x, y)(absolute pos) is inside the current clip
+    pub fn in_clip(&self, x: u16, y: u16) -> bool {
+        match self.clips.last() {
+            Some(c) => x >= c.left() && x < c.right() && y >= c.top() && y < c.bottom(),
+            None => true,
+        }
+    }
+
     //global offset
     pub fn index_of(&self, x: u16, y: u16) -> usize {
         debug_assert!(
@@ -246,8 +399,13 @@ impl Buffer {
     {
         let mut index = self.index_of(x, y);
         let mut x_offset = x as usize;
+        let row_start = index - (x - self.area.x) as usize;
+        let row_end = row_start + self.area.width as usize;
         let graphemes = UnicodeSegmentation::graphemes(string.as_ref(), true);
         let max_offset = min(self.area.right() as usize, width.saturating_add(x as usize));
+        // glyphs out of the clip are skipped, but still advance x_offset
+        let clip = self.clip();
+        let row_visible = self.in_clip(clip.x, y);
         for s in graphemes {
             let width = s.width();
             if width == 0 {
@@ -258,25 +416,173 @@ impl Buffer {
             if width > max_offset.saturating_sub(x_offset) {
                 break;
             }
+            if !row_visible
+                || x_offset < clip.left() as usize
+                || x_offset + width > clip.right() as usize
+            {
+                index += width;
+                x_offset += width;
+                continue;
+            }
+
+            // writing over the tail of a wide glyph, its head would be cut
+            self.clear_wide_head(index, row_start);
 
             self.content[index].set_symbol(s);
             self.content[index].set_style(style);
             self.content[index].set_texture(tex);
 
-            // Reset following cells if multi-width (they would be hidden by the grapheme),
+            // Following cells of a wide glyph are continuation cells (they are hidden
+            // by the grapheme), they keep the style so background stays the same
             for i in index + 1..index + width {
                 self.content[i].reset();
+                self.content[i].set_continuation();
+                self.content[i].set_style(style);
             }
             index += width;
             x_offset += width;
+
+            // tail of an overwritten wide glyph is left alone
+            self.clear_wide_tail(index, row_end);
         }
         (x_offset as u16, y)
     }
 
+    /// resets the wide glyph whose continuation cell is at index
+    fn clear_wide_head(&mut self, index: usize, row_start: usize) {
+        if !self.content[index].is_continuation() {
+            return;
+        }
+        let mut i = index;
+        while i > row_start && self.content[i].is_continuation() {
+            self.content[i].reset();
+            i -= 1;
+        }
+        self.content[i].reset();
+    }
+
+    /// resets orphan continuation cells starting at index
+    fn clear_wide_tail(&mut self, index: usize, row_end: usize) {
+        let mut i = index;
+        while i < row_end && self.content[i].is_continuation() {
+            self.content[i].reset();
+            i += 1;
+        }
+    }
+
+    /// draws text into area(absolute pos), returns the number of lines drawn
+    /// lines exceeding area height are dropped, and the last visible line
+    /// ends with an ellipsis
+    pub fn draw_text(
+        &mut self,
+        area: Rect,
+        text: &str,
+        wrap: WrapMode,
+        align: Alignment,
+        style: Style,
+    ) -> u16 {
+        if !area.intersects(self.area) {
+            return 0;
+        }
+        let area = area.intersection(self.area);
+        let width = area.width as usize;
+        let height = area.height as usize;
+        if width == 0 || height == 0 {
+            return 0;
+        }
+
+        let mut lines = wrap_text(text, width, wrap);
+        if lines.len() > height {
+            lines.truncate(height);
+            let last = lines.pop().unwrap();
+            lines.push(truncate_with_ellipsis(&last, width, true));
+        }
+
+        for (row, line) in lines.iter().enumerate() {
+            let lw = line.width().min(width);
+            let offset = match align {
+                Alignment::Left => 0,
+                Alignment::Center => (width - lw) / 2,
+                Alignment::Right => width - lw,
+            };
+            self.set_stringn(
+                area.x + offset as u16,
+                area.y + row as u16,
+                line,
+                width - offset,
+                style,
+                0,
+            );
+        }
+        lines.len() as u16
+    }
+
+    /// draws a border on the edges of area(absolute pos), cells out of
+    /// the buffer are skipped, title is cut to fit between the corners
+    pub fn draw_border(
+        &mut self,
+        area: Rect,
+        border: BorderType,
+        title: &str,
+        title_align: Alignment,
+        style: Style,
+    ) {
+        if area.width < 2 || area.height < 2 {
+            return;
+        }
+        let (l, t) = (area.left(), area.top());
+        let (r, b) = (area.right() - 1, area.bottom() - 1);
+        let edges = (l..=r)
+            .flat_map(|x| [(x, t), (x, b)])
+            .chain((t + 1..b).flat_map(|y| [(l, y), (r, y)]));
+        for (x, y) in edges {
+            if !self.area.intersects(Rect::new(x, y, 1, 1)) {
+                continue;
+            }
+            let mut arms = 0;
+            if y == t || y == b {
+                if x > l {
+                    arms |= ARM_LEFT;
+                }
+                if x < r {
+                    arms |= ARM_RIGHT;
+                }
+            }
+            if x == l || x == r {
+                if y > t {
+                    arms |= ARM_UP;
+                }
+                if y < b {
+                    arms |= ARM_DOWN;
+                }
+            }
+            let sym = merge_border(&self.get(x, y).symbol, arms, border);
+            self.set_stringn(x, y, sym, 1, style, 1);
+        }
+        let inner = area.width as usize - 2;
+        if title.is_empty() || inner == 0 {
+            return;
+        }
+        let title = truncate_with_ellipsis(title, inner, false);
+        let tw = title.width().min(inner);
+        let offset = match title_align {
+            Alignment::Left => 0,
+            Alignment::Center => (inner - tw) / 2,
+            Alignment::Right => inner - tw,
+        };
+        let tarea = Rect::new(l + 1 + offset as u16, t, tw as u16, 1);
+        if self.area.intersects(tarea) {
+            let tarea = tarea.intersection(self.area);
+            self.set_stringn(tarea.x, t, &title, tarea.width as usize, style, 0);
+        }
+    }
+
     pub fn set_style(&mut self, area: Rect, style: Style) {
         for y in area.top()..area.bottom() {
             for x in area.left()..area.right() {
-                self.get_mut(x, y).set_style(style);
+                if self.in_clip(x, y) {
+                    self.get_mut(x, y).set_style(style);
+                }
             }
         }
     }
@@ -291,6 +597,21 @@ impl Buffer {
         self.area = area;
     }
 
+    /// resizes to width x height keeping the top left corner,
+    /// cells inside both the old and new area keep their content, new cells are blank
+    pub fn resize_preserving(&mut self, width: u16, height: u16) {
+        let area = Rect::new(self.area.x, self.area.y, width, height);
+        let mut content = vec![Cell::default(); area.area() as usize];
+        let w = width.min(self.area.width) as usize;
+        for y in 0..height.min(self.area.height) as usize {
+            let from = y * self.area.width as usize;
+            let to = y * width as usize;
+            content[to..to + w].clone_from_slice(&self.content[from..from + w]);
+        }
+        self.content = content;
+        self.area = area;
+    }
+
     pub fn reset(&mut self) {
         for c in &mut self.content {
             c.reset();
@@ -319,7 +640,44 @@ impl Buffer {
         }
     }
 
+    /// copies src_rect of src (whole src if None, in src local coordinates)
+    /// to dst_pos of self, clipped at both edges, returns the copied size
     pub fn blit(
+        &mut self,
+        dst_pos: (u16, u16),
+        src: &Buffer,
+        src_rect: Option<Rect>,
+        mode: BlitMode,
+    ) -> (u16, u16) {
+        let sa = Rect::new(0, 0, src.area.width, src.area.height);
+        let sr = src_rect.unwrap_or(sa);
+        let (dx, dy) = dst_pos;
+        if !sr.intersects(sa) || dx >= self.area.width || dy >= self.area.height {
+            return (0, 0);
+        }
+        let sr = sr.intersection(sa);
+        let bw = min(sr.width, self.area.width - dx);
+        let bh = min(sr.height, self.area.height - dy);
+        for i in 0..bh {
+            for j in 0..bw {
+                let cell = &src.content[((sr.y + i) * src.area.width + sr.x + j) as usize];
+                let skip = match mode {
+                    BlitMode::Opaque => false,
+                    BlitMode::SkipBlank => cell.is_blank(),
+                    BlitMode::ColorKeyed(k) => Color::index_of(cell.bg) == Some(k),
+                };
+                let (x, y) = (self.area.x + dx + j, self.area.y + dy + i);
+                if !skip && self.in_clip(x, y) {
+                    self.content[((dy + i) * self.area.width + dx + j) as usize] = cell.clone();
+                }
+            }
+        }
+        (bw, bh)
+    }
+
+    /// same as blit, but every cell is copied with copy_cell,
+    /// which applies alpha in graphics mode
+    pub fn blit_alpha(
         &mut self,
         dstx: u16,
         dsty: u16,
@@ -351,7 +709,9 @@ impl Buffer {
                     // (other.area.width * other_part.y + other_part.x + i * bw + j) as usize;
                     (other.area.width * other_part.y + other_part.x + i * other.area.width + j) as usize;
                 // info!("blit...ps{:?} po{:?}", pos_self, pos_other);
-                self.copy_cell(pos_self, other, alpha, pos_other);
+                if self.in_clip(self.area.x + dstx + j, self.area.y + dsty + i) {
+                    self.copy_cell(pos_self, other, alpha, pos_other);
+                }
             }
         }
 
@@ -359,6 +719,15 @@ impl Buffer {
     }
 
     pub fn merge(&mut self, other: &Buffer, alpha: u8, fast: bool) {
+        self.merge_inner(other, alpha, fast, BlendMode::Normal);
+    }
+
+    /// merges other with its colors blended over the cells below, see BlendMode
+    pub fn merge_blend(&mut self, other: &Buffer, alpha: u8, blend: BlendMode) {
+        self.merge_inner(other, alpha, true, blend);
+    }
+
+    fn merge_inner(&mut self, other: &Buffer, alpha: u8, fast: bool, blend: BlendMode) {
         let area = self.area.union(other.area);
         let cell: Cell = Default::default();
         self.content.resize(area.area() as usize, cell.clone());
@@ -379,8 +748,14 @@ impl Buffer {
             let (x, y) = other.pos_of(i);
             let k = ((y - area.y) * area.width + x - area.x) as usize;
             // add transparent support...
-            if !other.content[i].is_blank() {
+            if !other.content[i].is_blank() && self.in_clip(x, y) {
+                let (fg, bg) = (self.content[k].fg, self.content[k].bg);
                 self.copy_cell(k, other, alpha, i);
+                if blend != BlendMode::Normal {
+                    let c = &mut self.content[k];
+                    c.fg = blend.blend(fg, c.fg);
+                    c.bg = blend.blend(bg, c.bg);
+                }
             }
         }
         self.area = area;
@@ -415,6 +790,93 @@ impl Buffer {
     }
 }
 
+/// sgr params of a color, base is 38 for fg and 48 for bg,
+/// None for Reset
+fn ansi_sgr(color: Color, base: u8) -> Option<String> {
+    match color {
+        Color::Reset => None,
+        Color::Rgba(r, g, b, _) => Some(format!("{};2;{};{};{}", base, r, g, b)),
+        _ => Color::index_of(color).map(|i| format!("{};5;{}", base, i)),
+    }
+}
+
+/// splits text into lines no wider than width
+fn wrap_text(text: &str, width: usize, wrap: WrapMode) -> Vec<String> {
+    let mut lines = vec![];
+    for para in text.lines() {
+        match wrap {
+            WrapMode::None => lines.push(truncate_with_ellipsis(para, width, false)),
+            WrapMode::Char => wrap_chars(para, width, &mut lines),
+            WrapMode::Word => {
+                let mut line = String::new();
+                for word in para.split_whitespace() {
+                    let ww = word.width();
+                    let lw = line.width();
+                    if lw == 0 {
+                        if ww > width {
+                            // word longer than a line, break it by chars
+                            wrap_chars(word, width, &mut lines);
+                            line = lines.pop().unwrap_or_default();
+                        } else {
+                            line.push_str(word);
+                        }
+                    } else if lw + 1 + ww <= width {
+                        line.push(' ');
+                        line.push_str(word);
+                    } else {
+                        lines.push(std::mem::take(&mut line));
+                        if ww > width {
+                            wrap_chars(word, width, &mut lines);
+                            line = lines.pop().unwrap_or_default();
+                        } else {
+                            line.push_str(word);
+                        }
+                    }
+                }
+                lines.push(line);
+            }
+        }
+    }
+    lines
+}
+
+fn wrap_chars(text: &str, width: usize, lines: &mut Vec<String>) {
+    let mut line = String::new();
+    let mut lw = 0;
+    for g in UnicodeSegmentation::graphemes(text, true) {
+        let gw = g.width();
+        if lw + gw > width && lw > 0 {
+            lines.push(std::mem::take(&mut line));
+            lw = 0;
+        }
+        line.push_str(g);
+        lw += gw;
+    }
+    lines.push(line);
+}
+
+/// cuts text to width, ending with an ellipsis if cut (or forced)
+fn truncate_with_ellipsis(text: &str, width: usize, force: bool) -> String {
+    if !force && text.width() <= width {
+        return text.to_string();
+    }
+    let max = width.saturating_sub(ELLIPSIS.width());
+    let mut out = String::new();
+    let mut lw = 0;
+    for g in UnicodeSegmentation::graphemes(text, true) {
+        let gw = g.width();
+        if lw + gw > max {
+            break;
+        }
+        out.push_str(g);
+        lw += gw;
+    }
+    if width >= ELLIPSIS.width() {
+        out.push_str(ELLIPSIS);
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -438,4 +900,209 @@ mod tests {
         assert_eq!(buf.pos_of(buf.content.len() - 1), (249, 179));
         assert_eq!(buf.index_of(249, 179), buf.content.len() - 1);
     }
+
+    fn row(buf: &Buffer, y: u16) -> String {
+        (0..buf.area.width)
+            .map(|x| buf.get(x, y).symbol.clone())
+            .collect()
+    }
+
+    #[test]
+    fn resize_preserving_keeps_top_left() {
+        let mut buf = Buffer::with_lines(vec!["abc", "def"]);
+        buf.resize_preserving(4, 3);
+        assert_eq!(buf.area, Rect::new(0, 0, 4, 3));
+        assert_eq!(row(&buf, 0), "abc ");
+        assert_eq!(row(&buf, 1), "def ");
+        assert_eq!(row(&buf, 2), "    ");
+
+        buf.resize_preserving(2, 1);
+        assert_eq!(buf.content.len(), 2);
+        assert_eq!(row(&buf, 0), "ab");
+    }
+
+    #[test]
+    fn draw_text_word_wraps_at_boundary() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 10, 3));
+        let n = buf.draw_text(
+            Rect::new(0, 0, 10, 3),
+            "hello world again",
+            WrapMode::Word,
+            Alignment::Left,
+            Style::default(),
+        );
+        // "hello world" is 11 wide, so "world" goes to the next line
+        assert_eq!(n, 3);
+        assert_eq!(row(&buf, 0), "hello     ");
+        assert_eq!(row(&buf, 1), "world     ");
+        assert_eq!(row(&buf, 2), "again     ");
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 11, 1));
+        buf.draw_text(
+            Rect::new(0, 0, 11, 1),
+            "hello world again",
+            WrapMode::Word,
+            Alignment::Left,
+            Style::default(),
+        );
+        // overflowing lines are dropped, last visible line gets an ellipsis
+        assert_eq!(row(&buf, 0), "hello worl…");
+    }
+
+    #[test]
+    fn draw_text_center_short_line() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 8, 1));
+        buf.draw_text(
+            Rect::new(0, 0, 8, 1),
+            "ab",
+            WrapMode::None,
+            Alignment::Center,
+            Style::default(),
+        );
+        assert_eq!(row(&buf, 0), "   ab   ");
+    }
+
+    #[test]
+    fn wide_glyph_takes_two_cells() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 6, 1));
+        let (x, _) = buf.set_stringn(0, 0, "😀", usize::MAX, Style::default(), 0);
+        assert_eq!(x, 2);
+        buf.set_str(x, 0, "a", Style::default());
+        assert_eq!(buf.get(0, 0).symbol, "😀");
+        assert!(buf.get(1, 0).is_continuation());
+        assert_eq!(buf.get(2, 0).symbol, "a");
+        assert_eq!(row(&buf, 0), "😀a   ");
+
+        // no room for the second column
+        let (x, _) = buf.set_stringn(5, 0, "中", usize::MAX, Style::default(), 0);
+        assert_eq!(x, 5);
+    }
+
+    #[test]
+    fn overwrite_half_of_wide_glyph() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 6, 1));
+        buf.set_str(0, 0, "中文", Style::default());
+        assert_eq!(row(&buf, 0), "中文  ");
+        // writing on the continuation cell clears its head
+        buf.set_str(1, 0, "x", Style::default());
+        assert_eq!(row(&buf, 0), " x文  ");
+        // narrow glyph on the head leaves no orphan continuation
+        buf.set_str(2, 0, "y", Style::default());
+        assert_eq!(row(&buf, 0), " xy   ");
+        assert!(!buf.get(3, 0).is_continuation());
+    }
+
+    #[test]
+    fn blit_clips_at_edges() {
+        let src = Buffer::from_text("abc\ndef\nghi");
+        let mut dst = Buffer::from_text("......\n......\n......");
+        // right and bottom edges of destination
+        assert_eq!(dst.blit((4, 1), &src, None, BlitMode::Opaque), (2, 2));
+        assert_eq!(dst.to_text(), "......\n....ab\n....de");
+        // left and top edges of source
+        let mut dst = Buffer::from_text("....\n....");
+        let part = Rect::new(1, 2, 5, 5);
+        assert_eq!(dst.blit((0, 0), &src, Some(part), BlitMode::Opaque), (2, 1));
+        assert_eq!(dst.to_text(), "hi..\n....");
+        // out of range
+        assert_eq!(dst.blit((4, 0), &src, None, BlitMode::Opaque), (0, 0));
+        let far = Some(Rect::new(3, 0, 2, 2));
+        assert_eq!(dst.blit((0, 0), &src, far, BlitMode::Opaque), (0, 0));
+    }
+
+    #[test]
+    fn blit_skip_blank_keeps_destination() {
+        let src = Buffer::from_text("a c\n b ");
+        let mut dst = Buffer::from_text("xxxx\nxxxx");
+        dst.blit((1, 0), &src, None, BlitMode::SkipBlank);
+        assert_eq!(dst.to_text(), "xaxc\nxxbx");
+
+        let mut keyed = src.clone();
+        keyed.get_mut(0, 0).set_bg(Color::Indexed(5));
+        let mut dst = Buffer::from_text("xxxx\nxxxx");
+        dst.blit((0, 0), &keyed, None, BlitMode::ColorKeyed(5));
+        assert_eq!(dst.to_text(), "x cx\n b x");
+    }
+
+    #[test]
+    fn double_border_with_title() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 12, 4));
+        let area = Rect::new(0, 0, 12, 4);
+        buf.draw_border(
+            area,
+            BorderType::Double,
+            "Hi",
+            Alignment::Center,
+            Style::default(),
+        );
+        assert_eq!(row(&buf, 0), "╔════Hi════╗");
+        assert_eq!(row(&buf, 1), "║          ║");
+        assert_eq!(row(&buf, 3), "╚══════════╝");
+
+        // a box sharing the left edge turns its right corners into junctions
+        let left = Rect::new(0, 0, 4, 4);
+        buf.draw_border(
+            left,
+            BorderType::Double,
+            "",
+            Alignment::Left,
+            Style::default(),
+        );
+        assert_eq!(row(&buf, 0), "╔══╦═Hi════╗");
+        assert_eq!(row(&buf, 1), "║  ║       ║");
+        assert_eq!(row(&buf, 3), "╚══╩═══════╝");
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let text = "+--+\n|中|\n+--+";
+        let buf = Buffer::from_text(text);
+        assert_eq!(buf.area, Rect::new(0, 0, 4, 3));
+        assert!(buf.get(2, 1).is_continuation());
+        assert_eq!(buf.to_text(), text);
+        assert_eq!(Buffer::from_text(&buf.to_text()), buf);
+    }
+
+    #[test]
+    fn drawn_scene_snapshot() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 6, 3));
+        buf.set_str(0, 0, "score", Style::default().fg(Color::Red));
+        buf.set_str(2, 2, "@", Style::default());
+        let expected = "score \n      \n  @   ";
+        assert_eq!(buf.to_text(), expected);
+        assert_eq!(
+            buf.to_ansi_string().lines().next().unwrap(),
+            "\x1b[38;5;1mscore\x1b[0m "
+        );
+        assert_eq!(buf.to_ansi_string().lines().nth(2).unwrap(), "  @   ");
+    }
+
+    #[test]
+    fn clip_masks_draws() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 10, 3));
+        buf.push_clip(Rect::new(2, 0, 4, 2));
+        let end = buf.set_stringn(0, 1, "abcdefgh", usize::MAX, Style::default(), 0);
+        assert_eq!(end, (8, 1));
+        buf.set_string(0, 2, "outside", Style::default());
+        // nested clip narrows the outer one
+        buf.push_clip(Rect::new(4, 0, 6, 1));
+        buf.set_string(0, 0, "0123456789", Style::default());
+        assert_eq!(buf.clip(), Rect::new(4, 0, 2, 1));
+        buf.pop_clip();
+        buf.pop_clip();
+        assert_eq!(buf.clip(), buf.area);
+        assert_eq!(buf.to_text(), "    45    \n  cdef    \n          ");
+    }
+
+    #[test]
+    fn eq_ignores_clips() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 4, 2));
+        buf.set_string(0, 0, "ab", Style::default());
+        let copy = buf.clone();
+        buf.push_clip(Rect::new(1, 0, 2, 1));
+        assert_eq!(buf, copy);
+        // draws inside the clip still change the content
+        buf.set_string(1, 0, "x", Style::default());
+        assert_ne!(buf, copy);
+    }
 }
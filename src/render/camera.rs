@@ -0,0 +1,160 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Camera shows a view_w * view_h window of a bigger world_w * world_h map,
+//! all sizes in cells
+//!
+//! The position is the top left of the view in world cells. It is kept as
+//! f32, so follow can move it smoothly: terminal mode can only scroll by
+//! whole cells (cell_pos), graphics mode may add the remaining fraction as a
+//! pixel offset (pixel_offset).
+//!
+//! Layers marked by Panel::set_layer_camera are drawn through the camera.
+//! In text layers sprites out of the view are skipped, the rest is cut at
+//! the view edges. Pixel layers are shifted by the camera position in pixels,
+//! pixel_offset included, graphics mode clips them at the screen edges.
+//!
+//! # Example
+//! ```
+//! use rust_pixel::render::camera::Camera;
+//! let mut cam = Camera::new(200, 100, 80, 40);
+//! // every frame, centers on the player with some lag
+//! cam.follow((150.0, 20.0), 0.2);
+//! if let Some((sx, sy)) = cam.world_to_screen(150, 20) {
+//!     // the player is on screen at (sx, sy)
+//! }
+//! ```
+
+use crate::util::Rect;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Camera {
+    pub world_w: u16,
+    pub world_h: u16,
+    pub view_w: u16,
+    pub view_h: u16,
+    /// top left of the view in world cells
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Camera {
+    pub fn new(world_w: u16, world_h: u16, view_w: u16, view_h: u16) -> Self {
+        Self {
+            world_w,
+            world_h,
+            view_w,
+            view_h,
+            x: 0.0,
+            y: 0.0,
+        }
+    }
+
+    /// moves the top left to (x, y), clamped to the world
+    pub fn set_pos(&mut self, x: f32, y: f32) {
+        self.x = x;
+        self.y = y;
+        self.clamp();
+    }
+
+    /// keeps the view inside the world, a world smaller than the view
+    /// is shown from 0
+    pub fn clamp(&mut self) {
+        let mx = self.world_w.saturating_sub(self.view_w) as f32;
+        let my = self.world_h.saturating_sub(self.view_h) as f32;
+        self.x = self.x.clamp(0.0, mx);
+        self.y = self.y.clamp(0.0, my);
+    }
+
+    /// centers the view on target at once
+    pub fn center_on(&mut self, target: (f32, f32)) {
+        self.set_pos(
+            target.0 - self.view_w as f32 / 2.0,
+            target.1 - self.view_h as f32 / 2.0,
+        );
+    }
+
+    /// moves lerp (0.0~1.0) of the way to center target, call it every frame,
+    /// 1.0 centers at once
+    pub fn follow(&mut self, target: (f32, f32), lerp: f32) {
+        let (ox, oy) = (self.x, self.y);
+        self.center_on(target);
+        let t = lerp.clamp(0.0, 1.0);
+        self.x = ox + (self.x - ox) * t;
+        self.y = oy + (self.y - oy) * t;
+    }
+
+    /// whole cells scrolled, used by terminal mode and cell culling
+    pub fn cell_pos(&self) -> (u16, u16) {
+        (self.x.floor() as u16, self.y.floor() as u16)
+    }
+
+    /// visible part of the world in whole cells
+    pub fn view_rect(&self) -> Rect {
+        let (cx, cy) = self.cell_pos();
+        Rect::new(cx, cy, self.view_w, self.view_h)
+    }
+
+    /// fraction of a cell scrolled in pixels, graphics mode shifts
+    /// the world layers left and up by this for smooth scrolling
+    pub fn pixel_offset(&self, sym_w: f32, sym_h: f32) -> (f32, f32) {
+        (self.x.fract() * sym_w, self.y.fract() * sym_h)
+    }
+
+    /// screen cell of world cell (x, y), None if out of the view
+    pub fn world_to_screen(&self, x: u16, y: u16) -> Option<(u16, u16)> {
+        let (cx, cy) = self.cell_pos();
+        if x < cx || y < cy || x - cx >= self.view_w || y - cy >= self.view_h {
+            return None;
+        }
+        Some((x - cx, y - cy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamps_at_edges_and_culls() {
+        let mut cam = Camera::new(100, 50, 40, 20);
+        cam.set_pos(-5.0, 70.0);
+        assert_eq!((cam.x, cam.y), (0.0, 30.0));
+        cam.center_on((99.0, 0.0));
+        assert_eq!(cam.cell_pos(), (60, 0));
+        assert_eq!(cam.world_to_screen(60, 0), Some((0, 0)));
+        assert_eq!(cam.world_to_screen(99, 19), Some((39, 19)));
+        assert_eq!(cam.world_to_screen(59, 5), None);
+        assert_eq!(cam.world_to_screen(70, 20), None);
+
+        // world smaller than view stays at 0
+        let mut small = Camera::new(10, 10, 40, 20);
+        small.center_on((9.0, 9.0));
+        assert_eq!(small.cell_pos(), (0, 0));
+        assert_eq!(small.world_to_screen(9, 9), Some((9, 9)));
+
+        cam.set_pos(10.25, 3.5);
+        assert_eq!(cam.cell_pos(), (10, 3));
+        assert_eq!(cam.pixel_offset(16.0, 16.0), (4.0, 8.0));
+    }
+
+    #[test]
+    fn follow_converges() {
+        let mut cam = Camera::new(200, 100, 40, 20);
+        let target = (70.0, 60.0);
+        cam.follow(target, 0.5);
+        assert_eq!((cam.x, cam.y), (25.0, 25.0));
+        for _ in 0..20 {
+            cam.follow(target, 0.5);
+        }
+        assert!((cam.x - 50.0).abs() < 0.001 && (cam.y - 50.0).abs() < 0.001);
+        // never passes the target
+        let mut c2 = Camera::new(200, 100, 40, 20);
+        let mut last = 0.0;
+        for _ in 0..50 {
+            c2.follow(target, 0.3);
+            assert!(c2.x >= last && c2.x <= 50.0);
+            last = c2.x;
+        }
+    }
+}
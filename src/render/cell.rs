@@ -10,11 +10,16 @@
 //! Please refer to the code (cellsym, symidx, get_cell_info, CELL_SYM_MAP) for 
 //! how to use cell.
 //!
+//! A wide glyph (CJK, most emoji) takes 2 columns in terminal, it is stored in
+//! its first cell and the following cell is a continuation cell with an empty
+//! symbol, which is never drawn. Buffer::set_stringn keeps this layout.
+//!
 
 use crate::render::style::{Color, Modifier, Style};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use lazy_static::lazy_static;
+use unicode_width::UnicodeWidthStr;
 // use log::info;
 
 lazy_static! {
@@ -123,6 +128,21 @@ impl Cell {
         (symidx(&self.symbol), self.tex, self.fg, self.bg)
     }
 
+    /// marks the cell as covered by the wide glyph before it
+    pub fn set_continuation(&mut self) -> &mut Cell {
+        self.symbol.clear();
+        self
+    }
+
+    pub fn is_continuation(&self) -> bool {
+        self.symbol.is_empty()
+    }
+
+    /// display columns of the symbol, 2 for wide glyphs, 0 for continuation
+    pub fn width(&self) -> usize {
+        self.symbol.width()
+    }
+
     pub fn set_char(&mut self, ch: char) -> &mut Cell {
         self.symbol.clear();
         self.symbol.push(ch);
@@ -174,7 +194,8 @@ impl Cell {
 
     #[cfg(any(target_arch = "wasm32", feature = "sdl"))]
     pub fn is_blank(&self) -> bool {
-        (self.symbol == " " || self.symbol == cellsym(32)) && (self.tex == 0 || self.tex == 1)
+        ((self.symbol == " " || self.symbol == cellsym(32)) && (self.tex == 0 || self.tex == 1))
+            || self.is_continuation()
     }
 
     #[cfg(all(not(target_arch = "wasm32"), not(feature = "sdl")))]
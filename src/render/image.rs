@@ -70,10 +70,15 @@ fn find_vaild_area(content: &Buffer) -> (u16, u16, u16, u16) {
 }
 
 pub mod pix;
-pub use pix::PixAsset;
+pub use pix::{PaletteRemap, PixAsset, PIX_VERSION};
 
 pub mod esc;
 pub use esc::EscAsset;
 
 pub mod seq_frame;
 pub use seq_frame::SeqFrameAsset;
+
+pub mod c64;
+
+#[cfg(feature = "image")]
+pub mod petii;
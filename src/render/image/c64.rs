@@ -1,3 +1,8 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! C64 PETSCII charset bitmaps, 128 chars of 8x8 bits each
+
 pub const C64LOW: [[u8; 8]; 128] = [
     [0x3c, 0x66, 0x6e, 0x6e, 0x60, 0x62, 0x3c, 0x00],
     [0x00, 0x00, 0x3c, 0x06, 0x3e, 0x66, 0x3e, 0x00],
@@ -259,4 +264,3 @@ pub const C64UP: [[u8; 8]; 128] = [
     [0xf0, 0xf0, 0xf0, 0xf0, 0x00, 0x00, 0x00, 0x00],
     [0xf0, 0xf0, 0xf0, 0xf0, 0x0f, 0x0f, 0x0f, 0x0f],
 ];
-
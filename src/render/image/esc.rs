@@ -8,7 +8,7 @@
 //! $ cat assets/tetris/back.txt
 
 use crate::{
-    asset::{Asset, AssetBase},
+    asset::{Asset, AssetBase, AssetError},
     render::image::find_vaild_area,
     render::buffer::Buffer,
     render::style::{Color, Style},
@@ -33,7 +33,7 @@ impl Asset for EscAsset {
         &mut self.base
     }
 
-    fn parse(&mut self) {
+    fn parse(&mut self) -> Result<(), AssetError> {
         self.base.parsed_buffers.clear();
         let size = Rect::new(0, 0, 500, 300);
         let mut sp = Buffer::empty(size);
@@ -50,8 +50,9 @@ impl Asset for EscAsset {
         }
         let nsize = Rect::new(0, 0, max_width, row);
         let mut nsp = Buffer::empty(nsize);
-        let _ = nsp.blit(0, 0, &sp, nsize, 255);
+        let _ = nsp.blit_alpha(0, 0, &sp, nsize, 255);
         self.base.parsed_buffers.push(nsp);
+        Ok(())
     }
 
     fn save(&mut self, content: &Buffer) {
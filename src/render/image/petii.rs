@@ -0,0 +1,370 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Converts an image to PETSCII cells, which can be saved as pix or ssf frames
+//! Used by tools/pixel_petii and cargo pixel convert_gif
+//!
+//! https://github.com/JuliaPoo/AsciiArtist
+//! https://github.com/EgonOlsen71/petsciiator
+//!
+//! The image is resized to width*8 x height*8, every 8x8 block is matched
+//! against the 256 PETSCII symbols by a simple eigenvector distance,
+//! and the block color is mapped to the nearest ANSI 256 color by CIEDE2000
+
+use crate::render::{
+    image::c64::{C64LOW, C64UP},
+    image::pix::PIX_VERSION,
+    symbols::{
+        binarize_block, find_background_color, find_best_color, find_best_color_u32,
+        BinarizationConfig, RGB,
+    },
+};
+use image::codecs::gif::GifDecoder;
+use image::{AnimationDecoder, DynamicImage, GenericImageView, ImageBuffer, ImageResult, Luma};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// gray 8x8 image...
+pub type Image8x8 = Vec<Vec<u8>>;
+
+pub type GrayImage = ImageBuffer<Luma<u8>, Vec<u8>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PetiiConfig {
+    /// output width in cells
+    pub width: u32,
+    /// output height in cells
+    pub height: u32,
+    /// source is a petscii art image (2 colors per block)
+    pub is_petii: bool,
+    /// crop (x, y, w, h) applied before resizing
+    pub crop: Option<(u32, u32, u32, u32)>,
+}
+
+impl Default for PetiiConfig {
+    fn default() -> Self {
+        Self {
+            width: 40,
+            height: 25,
+            is_petii: false,
+            crop: None,
+        }
+    }
+}
+
+/// sym, fg, tex, bg
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PetiiCell {
+    pub sym: u8,
+    pub fg: u8,
+    pub tex: u8,
+    pub bg: u8,
+}
+
+/// convert image to width * height cells, row by row
+pub fn petii_convert(img: &DynamicImage, cfg: &PetiiConfig) -> Vec<PetiiCell> {
+    let (width, height) = (cfg.width, cfg.height);
+    let src = match cfg.crop {
+        Some((cx, cy, cw, ch)) => img.crop_imm(cx, cy, cw, ch),
+        None => img.clone(),
+    };
+    let resized_img =
+        src.resize_exact(width * 8, height * 8, image::imageops::FilterType::Lanczos3);
+    let gray_img = resized_img.clone().into_luma8();
+
+    // get petscii images...
+    let vcs = gen_charset_images(false);
+
+    // find background color...
+    let back = find_background_color(&resized_img, width * 8, height * 8);
+    let (back_gray, back_rgb) = (back.gray, back.rgba);
+
+    let mut cells = Vec::with_capacity((width * height) as usize);
+    for i in 0..height {
+        for j in 0..width {
+            let block_at = get_block_at(&gray_img, j, i);
+            let bm = find_best_match(&block_at, &vcs, back_gray, cfg.is_petii) as u8;
+            if !cfg.is_petii {
+                let block_color = get_block_color(&resized_img, j, i);
+                let bc = find_best_color(block_color) as u8;
+                cells.push(PetiiCell {
+                    sym: bm,
+                    fg: bc,
+                    tex: 1,
+                    bg: 0,
+                });
+            } else {
+                let bc = get_petii_block_color(&resized_img, &gray_img, j, i, back_rgb);
+                cells.push(PetiiCell {
+                    sym: bm,
+                    fg: bc.1 as u8,
+                    tex: 1,
+                    bg: bc.0 as u8,
+                });
+            }
+        }
+    }
+    cells
+}
+
+/// format cells as pix text, same as pixel_petii output
+pub fn petii_to_pix(cells: &[PetiiCell], cfg: &PetiiConfig) -> String {
+    let mut s = format!(
+        "version={}\nwidth={},height={},texture=255\n",
+        PIX_VERSION, cfg.width, cfg.height
+    );
+    for row in cells.chunks(cfg.width as usize) {
+        for c in row {
+            if cfg.is_petii {
+                // sym, fg, tex, bg
+                s.push_str(&format!("{},{},{},{} ", c.sym, c.fg, c.tex, c.bg));
+            } else {
+                s.push_str(&format!("{},{},{} ", c.sym, c.fg, c.tex));
+            }
+        }
+        s.push('\n');
+    }
+    s
+}
+
+/// gif frames with 0 delay are shown as 100ms by most viewers
+pub const GIF_DEFAULT_DELAY_MS: f64 = 100.0;
+
+/// decode gif frames with their delay in ms,
+/// shared by pixel_petii and cargo pixel convert_gif
+pub fn decode_gif_frames(data: &[u8]) -> ImageResult<Vec<(DynamicImage, f64)>> {
+    let frames = GifDecoder::new(Cursor::new(data))?
+        .into_frames()
+        .collect_frames()?;
+    Ok(frames
+        .into_iter()
+        .map(|f| {
+            let (numer, denom) = f.delay().numer_denom_ms();
+            let mut ms = numer as f64 / denom as f64;
+            if ms <= 0.0 {
+                ms = GIF_DEFAULT_DELAY_MS;
+            }
+            (DynamicImage::ImageRgba8(f.into_buffer()), ms)
+        })
+        .collect())
+}
+
+/// generate 256 petscii image with 0 and 255
+/// 128~255 are the reversed images of 0~127
+#[allow(clippy::needless_range_loop)]
+pub fn gen_charset_images(low_up: bool) -> Vec<Image8x8> {
+    let data = if low_up { &C64LOW } else { &C64UP };
+    let mut vcs = vec![vec![vec![0u8; 8]; 8]; 256];
+
+    for i in 0..128 {
+        for row in 0..8 {
+            for bit in 0..8 {
+                if data[i][row] >> bit & 1 == 1 {
+                    vcs[i][row][7 - bit] = 255;
+                    vcs[128 + i][row][7 - bit] = 0;
+                } else {
+                    vcs[i][row][7 - bit] = 0;
+                    vcs[128 + i][row][7 - bit] = 255;
+                }
+            }
+        }
+    }
+    vcs
+}
+
+// get petscii block color, returns (back, fore)
+fn get_petii_block_color(
+    image: &DynamicImage,
+    img: &GrayImage,
+    x: u32,
+    y: u32,
+    back_rgb: u32,
+) -> (usize, usize) {
+    let mut cc: HashMap<u32, (u32, u32)> = HashMap::new();
+    for i in 0..8u32 {
+        for j in 0..8u32 {
+            let pixel_x = x * 8 + j;
+            let pixel_y = y * 8 + i;
+            if pixel_x < image.width() && pixel_y < image.height() {
+                let p = image.get_pixel(pixel_x, pixel_y);
+                let k: u32 = ((p[0] as u32) << 24)
+                    + ((p[1] as u32) << 16)
+                    + ((p[2] as u32) << 8)
+                    + (p[3] as u32);
+                cc.entry(k).or_insert((pixel_x, pixel_y));
+            }
+        }
+    }
+    let cv: Vec<_> = cc.iter().collect();
+    let clen = cv.len();
+    let include_back = cv.iter().any(|c| *c.0 == back_rgb);
+    let mut ret = None;
+    if include_back {
+        if clen == 1 {
+            ret = Some((back_rgb, back_rgb));
+        } else if clen == 2 {
+            let mut r = (back_rgb, back_rgb);
+            if *cv[0].0 != back_rgb {
+                r.1 = *cv[0].0;
+            }
+            if *cv[1].0 != back_rgb {
+                r.1 = *cv[1].0;
+            }
+            ret = Some(r);
+        }
+    } else if clen == 1 {
+        ret = Some((*cv[0].0, *cv[0].0));
+    } else if clen == 2 {
+        let g0 = img.get_pixel(cv[0].1 .0, cv[0].1 .1).0[0];
+        let g1 = img.get_pixel(cv[1].1 .0, cv[1].1 .1).0[0];
+        if g0 <= g1 {
+            ret = Some((*cv[0].0, *cv[1].0));
+        } else {
+            ret = Some((*cv[1].0, *cv[0].0));
+        }
+    }
+    match ret {
+        Some(r) => (find_best_color_u32(r.0), find_best_color_u32(r.1)),
+        _ => (0, 0),
+    }
+}
+
+// get block average color(for not petscii image)
+fn get_block_color(image: &DynamicImage, x: u32, y: u32) -> RGB {
+    let mut r = 0u32;
+    let mut g = 0u32;
+    let mut b = 0u32;
+    let mut count = 0u32;
+
+    for i in 0..8u32 {
+        for j in 0..8u32 {
+            let pixel_x = x * 8 + j;
+            let pixel_y = y * 8 + i;
+
+            if pixel_x < image.width() && pixel_y < image.height() {
+                let p = image.get_pixel(pixel_x, pixel_y);
+                if p[0] != 0 || p[1] != 0 || p[2] != 0 {
+                    r += p[0] as u32;
+                    g += p[1] as u32;
+                    b += p[2] as u32;
+                    count += 1;
+                }
+            }
+        }
+    }
+
+    if count == 0 {
+        return RGB { r: 0, g: 0, b: 0 };
+    }
+
+    RGB {
+        r: (r / count) as u8,
+        g: (g / count) as u8,
+        b: (b / count) as u8,
+    }
+}
+
+/// get 8x8 gray block at cell (x, y)
+pub fn get_block_at(image: &GrayImage, x: u32, y: u32) -> Image8x8 {
+    let mut block = vec![vec![0u8; 8]; 8];
+
+    for (i, row) in block.iter_mut().enumerate() {
+        for (j, v) in row.iter_mut().enumerate() {
+            let pixel_x = x * 8 + j as u32;
+            let pixel_y = y * 8 + i as u32;
+
+            if pixel_x < image.width() && pixel_y < image.height() {
+                *v = image.get_pixel(pixel_x, pixel_y).0[0];
+            }
+        }
+    }
+
+    block
+}
+
+/// find the nearest symbol of a 8x8 gray block
+pub fn find_best_match(
+    input_image: &Image8x8,
+    char_images: &[Image8x8],
+    back: u8,
+    is_petii: bool,
+) -> usize {
+    let mut min_mse = f64::MAX;
+    let mut best_match = 0;
+
+    for (i, char_image) in char_images.iter().enumerate() {
+        let mse = calculate_mse(input_image, char_image, back, is_petii);
+        if mse < min_mse {
+            min_mse = mse;
+            best_match = i;
+        }
+    }
+
+    best_match
+}
+
+fn calc_eigenvector(img: &Image8x8, back: u8, is_petii: bool, is_source: bool) -> Vec<i32> {
+    let mut v = vec![0i32; 10];
+
+    let block = if !is_petii {
+        // normal image...
+        img.clone()
+    } else if is_source {
+        // for petscii source...
+        img.iter()
+            .map(|row| row.iter().map(|&p| (p != 0) as u8).collect())
+            .collect()
+    } else {
+        // gray image8x8 binarization...
+        binarize_block(img, &BinarizationConfig::background(back))
+    };
+
+    for (y, row) in block.iter().enumerate() {
+        for (x, &p) in row.iter().enumerate() {
+            let p = p as i32;
+
+            if x < 4 && y < 4 {
+                v[0] += p;
+            }
+            if x > 3 && y < 4 {
+                v[1] += p;
+            }
+            if x < 4 && y > 3 {
+                v[2] += p;
+            }
+            if x > 3 && y > 3 {
+                v[3] += p;
+            }
+            if x > 2 && x < 6 && y > 2 && y < 6 {
+                v[4] += p;
+            }
+            if x == y || x == (7 - y) {
+                v[5] += p;
+            }
+            if x == 0 {
+                v[6] += p;
+            }
+            if x == 7 {
+                v[7] += p;
+            }
+            if y == 0 {
+                v[8] += p;
+            }
+            if y == 7 {
+                v[9] += p;
+            }
+        }
+    }
+    v
+}
+
+fn calculate_mse(img1: &Image8x8, img2: &Image8x8, back: u8, is_petii: bool) -> f64 {
+    let v1 = calc_eigenvector(img1, back, is_petii, false);
+    let v2 = calc_eigenvector(img2, back, is_petii, true);
+    let mut mse = 0.0f64;
+    for i in 0..10usize {
+        mse += ((v1[i] - v2[i]) * (v1[i] - v2[i])) as f64;
+    }
+    mse.sqrt()
+}
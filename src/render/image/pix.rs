@@ -7,9 +7,12 @@
 //! cell: symbol index, fore-color, texture_id 
 //! $ cat assets/snake/back.pix
 //!
+//! The first line "version=N" marks the format version, files without it are
+//! treated as version 0 (same layout as version 1). Files of an unknown version
+//! fail to parse with AssetError::UnsupportedVersion instead of rendering garbage.
 
 use crate::{
-    asset::{Asset, AssetBase},
+    asset::{Asset, AssetBase, AssetError, AssetType},
     render::buffer::Buffer,
     render::cell::cellsym,
     render::image::find_vaild_area,
@@ -20,10 +23,84 @@ use log::info;
 use regex::Regex;
 use std::io::{BufRead, BufReader, Write};
 
+/// current pix format version, written by save and the asset tools
+pub const PIX_VERSION: u32 = 1;
+
+/// Color index remap table, used to recolor a pix image cell by cell
+/// e.g. team-colored units from one source image
+/// Only Indexed fore- and background colors are remapped
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteRemap {
+    pub table: [u8; 256],
+}
+
+impl Default for PaletteRemap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PaletteRemap {
+    /// identity remap
+    pub fn new() -> Self {
+        let mut table = [0u8; 256];
+        for (i, t) in table.iter_mut().enumerate() {
+            *t = i as u8;
+        }
+        Self { table }
+    }
+
+    pub fn from_fn(f: &dyn Fn(u8) -> u8) -> Self {
+        let mut r = Self::new();
+        for t in r.table.iter_mut() {
+            *t = f(*t);
+        }
+        r
+    }
+
+    /// build from a swap list, e.g. vec![(old, new)]
+    pub fn from_swaps(swaps: &[(u8, u8)]) -> Self {
+        let mut r = Self::new();
+        for (old, new) in swaps {
+            r.table[*old as usize] = *new;
+        }
+        r
+    }
+
+    pub fn map(&self, idx: u8) -> u8 {
+        self.table[idx as usize]
+    }
+
+    pub fn map_color(&self, c: Color) -> Color {
+        match c {
+            Color::Indexed(i) => Color::Indexed(self.map(i)),
+            _ => c,
+        }
+    }
+
+    /// rewrites fg/bg colors of every cell, symbols are untouched
+    pub fn apply(&self, buf: &mut Buffer) {
+        for c in &mut buf.content {
+            c.fg = self.map_color(c.fg);
+            c.bg = self.map_color(c.bg);
+        }
+    }
+}
+
 pub struct PixAsset {
     base: AssetBase,
 }
 
+impl PixAsset {
+    /// parse pix data in memory, no asset manager needed
+    pub fn from_data(data: &[u8]) -> Result<Self, AssetError> {
+        let mut pa = PixAsset::new(AssetBase::new(AssetType::ImgPix, ""));
+        pa.set_data(data);
+        pa.parse()?;
+        Ok(pa)
+    }
+}
+
 impl Asset for PixAsset {
     fn new(ab: AssetBase) -> Self {
         Self { base: ab }
@@ -33,12 +110,13 @@ impl Asset for PixAsset {
         &mut self.base
     }
 
-    fn parse(&mut self) {
+    fn parse(&mut self) -> Result<(), AssetError> {
         self.base.parsed_buffers.clear();
         let size = Rect::new(0, 0, 0, 0);
         let mut sp = Buffer::empty(size);
 
         let reader = BufReader::new(&self.base.raw_data[..]);
+        let rev = Regex::new(r"^version=(\d+)").unwrap();
         let re = Regex::new(r"width=(\d+),height=(\d+),texture=(\d+)").unwrap();
         let rel0 = Regex::new(r"(\d+),(\d+)(.*?)").unwrap();
         let rel1 = Regex::new(r"(\d+),(\d+),(\d+)(.*?)").unwrap();
@@ -53,6 +131,15 @@ impl Asset for PixAsset {
             let l = line.unwrap();
             // skip garbage lines...
             if !start {
+                if let Some(cap) = rev.captures(&l) {
+                    let version = cap[1].parse::<u32>().unwrap_or(u32::MAX);
+                    if version > PIX_VERSION {
+                        return Err(AssetError::UnsupportedVersion {
+                            location: self.base.location.clone(),
+                            version,
+                        });
+                    }
+                }
                 if !l.starts_with("width") {
                     continue;
                 } else {
@@ -125,6 +212,7 @@ impl Asset for PixAsset {
             lineidx += 1;
         }
         self.base.parsed_buffers.push(sp);
+        Ok(())
     }
 
     fn save(&mut self, content: &Buffer) {
@@ -132,6 +220,7 @@ impl Asset for PixAsset {
         let mut ptr = std::io::Cursor::new(&mut self.base.raw_data);
         let (x1, x2, y1, y2) = find_vaild_area(content);
         let width = content.area.width;
+        let _ = writeln!(ptr, "version={}", PIX_VERSION);
         let _ = writeln!(
             ptr,
             "width={},height={},texture={}",
@@ -150,3 +239,74 @@ impl Asset for PixAsset {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::sprite::Sprite;
+
+    const TINY_PIX: &str = "width=2,height=1,texture=255\n1,5,0,6 2,7,0,6\n";
+
+    #[test]
+    fn remap_swaps_colors_keeps_symbols() {
+        let remap = PaletteRemap::from_swaps(&[(5, 9), (6, 10)]);
+        let sp = Sprite::from_pix_with_remap(TINY_PIX.as_bytes(), &remap).unwrap();
+        let c0 = sp.content.get(0, 0);
+        let c1 = sp.content.get(1, 0);
+        assert_eq!(c0.fg, Color::Indexed(9));
+        assert_eq!(c0.bg, Color::Indexed(10));
+        assert_eq!(c0.symbol, cellsym(1));
+        assert_eq!(c1.fg, Color::Indexed(7));
+        assert_eq!(c1.bg, Color::Indexed(10));
+        assert_eq!(c1.symbol, cellsym(2));
+    }
+
+    #[test]
+    fn remap_from_fn() {
+        let remap = PaletteRemap::from_fn(&|c| c.wrapping_add(1));
+        let sp = Sprite::from_pix_with_remap(TINY_PIX.as_bytes(), &remap).unwrap();
+        assert_eq!(sp.content.get(0, 0).fg, Color::Indexed(6));
+        assert_eq!(sp.content.get(1, 0).fg, Color::Indexed(8));
+    }
+
+    #[test]
+    fn current_version_parses() {
+        let data = format!("version={}\n{}", PIX_VERSION, TINY_PIX);
+        let mut pa = PixAsset::from_data(data.as_bytes()).unwrap();
+        let buf = &pa.get_base().parsed_buffers[0];
+        assert_eq!(buf.area, Rect::new(0, 0, 2, 1));
+        assert_eq!(buf.get(1, 0).symbol, cellsym(2));
+
+        // saved data carries the version and loads back
+        let saved = buf.clone();
+        pa.save(&saved);
+        let raw = pa.get_base().raw_data.clone();
+        assert!(raw.starts_with(format!("version={}\n", PIX_VERSION).as_bytes()));
+        assert!(PixAsset::from_data(&raw).is_ok());
+    }
+
+    #[test]
+    fn unknown_version_rejected() {
+        let data = format!("version={}\n{}", PIX_VERSION + 1, TINY_PIX);
+        let mut am = crate::asset::AssetManager::new();
+        let p = std::env::temp_dir().join("rust_pixel_bumped_version.pix");
+        std::fs::write(&p, &data).unwrap();
+        let loc = p.to_str().unwrap().to_string();
+        am.load(AssetType::ImgPix, &loc);
+        let _ = std::fs::remove_file(&p);
+        let ast = am.get(&loc).unwrap();
+        assert!(ast.get_state() == crate::asset::AssetState::Failed);
+        assert!(ast.get_base().parsed_buffers.is_empty());
+
+        match PixAsset::from_data(data.as_bytes()) {
+            Err(e) => assert_eq!(
+                e,
+                AssetError::UnsupportedVersion {
+                    location: "".to_string(),
+                    version: PIX_VERSION + 1
+                }
+            ),
+            Ok(_) => panic!("bumped version should be rejected"),
+        }
+    }
+}
@@ -9,7 +9,7 @@
 //! file content is compressed frame data
 
 use crate::{
-    asset::{Asset, AssetBase, AssetState},
+    asset::{Asset, AssetBase, AssetError, AssetState},
     render::buffer::Buffer,
     render::cell::cellsym,
     render::image::esc::escstr_to_buffer,
@@ -73,9 +73,9 @@ impl Asset for SeqFrameAsset {
     /// cell的数据长度为2字节: fg cellsym
     /// texture_id < 255 means SDL frame data，each cell's texture is texture_id,
     /// the length of each cell data is 2 bytes : fg cellsym
-    fn parse(&mut self) {
+    fn parse(&mut self) -> Result<(), AssetError> {
         if self.get_state() != AssetState::Parsing {
-            return;
+            return Ok(());
         }
         self.frame_len = vec![];
         self.frame_offset = vec![];
@@ -184,6 +184,7 @@ impl Asset for SeqFrameAsset {
             }
             self.base.parsed_buffers.push(sp);
         }
+        Ok(())
     }
 
     fn save(&mut self, _content: &Buffer) {}
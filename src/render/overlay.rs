@@ -0,0 +1,139 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! DebugOverlay draws layout and input aids over the main buffer:
+//! a grid every DEBUG_GRID_STEP cells, the cell under the mouse highlighted,
+//! and its coordinates with the fps in the top right corner
+//!
+//! It lives in context.debug, Game::on_tick feeds it the input events before
+//! the model takes them, toggling on key (F12 in debug builds, None in release).
+//! Panel::draw draws it after the layers, nothing is drawn while it is off.
+//! In graphics mode pixel layers are still drawn over it.
+
+use crate::{
+    event::{Event, KeyCode, KeyEventKind},
+    render::{
+        buffer::Buffer,
+        style::{Color, Style},
+    },
+};
+
+/// a grid line every this many cells
+pub const DEBUG_GRID_STEP: u16 = 10;
+
+pub struct DebugOverlay {
+    pub enabled: bool,
+    /// toggles enabled
+    pub key: Option<KeyCode>,
+    /// last mouse cell
+    pub mouse: Option<(u16, u16)>,
+    /// smoothed frames per second
+    pub fps: f32,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key: if cfg!(debug_assertions) {
+                Some(KeyCode::F(12))
+            } else {
+                None
+            },
+            mouse: None,
+            fps: 0.0,
+        }
+    }
+}
+
+impl DebugOverlay {
+    /// checks the hotkey, and while enabled tracks mouse and fps
+    pub fn update(&mut self, events: &[Event], dt: f32) {
+        for e in events {
+            match e {
+                Event::Key(k) if Some(k.code) == self.key && k.kind == KeyEventKind::Press => {
+                    self.enabled = !self.enabled;
+                }
+                Event::Mouse(m) if self.enabled => self.mouse = Some((m.column, m.row)),
+                _ => {}
+            }
+        }
+        if self.enabled && dt > 0.0 {
+            self.fps = if self.fps == 0.0 {
+                1.0 / dt
+            } else {
+                self.fps * 0.9 + 0.1 / dt
+            };
+        }
+    }
+
+    pub fn draw(&self, buf: &mut Buffer) {
+        let area = *buf.area();
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        for y in 0..area.height {
+            for x in 0..area.width {
+                if x % DEBUG_GRID_STEP == 0 || y % DEBUG_GRID_STEP == 0 {
+                    buf.get_mut(area.x + x, area.y + y)
+                        .set_bg(Color::Indexed(236));
+                }
+            }
+        }
+        let pos = match self.mouse {
+            Some((x, y)) => {
+                if x >= area.x && x < area.right() && y >= area.y && y < area.bottom() {
+                    buf.get_mut(x, y).set_fg(Color::Black).set_bg(Color::Yellow);
+                }
+                format!("{},{}", x, y)
+            }
+            None => "-,-".to_string(),
+        };
+        let text = format!("{} {:.0}fps", pos, self.fps);
+        let x = area.width.saturating_sub(text.len() as u16);
+        buf.set_str(
+            x,
+            0,
+            text,
+            Style::default().fg(Color::White).bg(Color::Blue),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::{KeyEvent, KeyModifiers, MouseEvent, MouseEventKind},
+        util::Rect,
+    };
+
+    #[test]
+    fn draws_coords_in_top_right() {
+        let mut d = DebugOverlay {
+            key: Some(KeyCode::F(12)),
+            ..Default::default()
+        };
+        let f12 = Event::Key(KeyEvent::new(KeyCode::F(12), KeyModifiers::NONE));
+        let mouse = Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Moved,
+            column: 3,
+            row: 2,
+            modifiers: KeyModifiers::NONE,
+        });
+        d.update(&[f12.clone(), mouse], 0.5);
+        assert!(d.enabled);
+        assert_eq!(d.mouse, Some((3, 2)));
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 5));
+        d.draw(&mut buf);
+        // "3,2 2fps" is 8 cells, right aligned
+        assert_eq!(buf.get(12, 0).symbol, "3");
+        assert_eq!(buf.get(19, 0).symbol, "s");
+        assert_eq!(buf.get(3, 2).bg, Color::Yellow);
+        assert_eq!(buf.get(10, 3).bg, Color::Indexed(236));
+
+        d.update(&[f12], 0.5);
+        assert!(!d.enabled);
+    }
+}
@@ -20,11 +20,22 @@
 //! in WEB mode, RustPixel renders buffer to a shared memory block and shared it
 //! with JavaScript in WEB, then JS calls webgl in the browser to render this memory block.
 //! Refer to the implementation in pixel.js
+//!
+//! Layers are composited by z_index (lower first, higher drawn over), so a game can keep
+//! UI over gameplay and effects over entities without ordering draw calls by hand.
+//! Layers with the same z_index fall back to render_weight. Text layers are merged into
+//! the main buffer, blank cells of an upper layer leave the lower layers visible.
+//! In graphics mode pixel layers are always drawn over the main buffer, also ordered by z_index.
 
+#[cfg(any(feature = "sdl", target_arch = "wasm32"))]
+use crate::render::adapter::{PIXEL_SYM_HEIGHT, PIXEL_SYM_WIDTH};
 use crate::{
+    asset::AssetManager,
     context::Context,
     render::{
         buffer::Buffer,
+        camera::Camera,
+        particles::Emitter,
         sprite::{Sprite, Sprites},
     },
     util::{
@@ -37,14 +48,29 @@ use log::info;
 use std::{collections::HashMap, io};
 use std::cmp::Reverse;
 
+/// pixel layer of particle sprites in graphics mode, over other pixel layers
+#[cfg(any(feature = "sdl", target_arch = "wasm32"))]
+const PARTICLE_LAYER: &str = "particles";
+#[cfg(any(feature = "sdl", target_arch = "wasm32"))]
+const PARTICLE_LAYER_Z: i32 = 1000;
+
 pub struct Panel {
     pub buffers: [Buffer; 2],
     pub current: usize,
     pub layer_tag_index: HashMap<String, usize>,
     pub layers: Vec<Sprites>,
 
-    // layer index, render weight...
-    pub render_index: Vec<(usize, i32)>,
+    // layer index, z_index, render weight...
+    pub render_index: Vec<(usize, i32, i32)>,
+
+    // scrolls layers set by set_layer_camera
+    pub camera: Option<Camera>,
+
+    // particle emitters, dropped when finished
+    pub emitters: Vec<Emitter>,
+
+    // adapter size the buffers were last sized to
+    size: Rect,
 }
 
 #[allow(unused)]
@@ -78,6 +104,9 @@ impl Panel {
             layer_tag_index,
             layers,
             render_index: vec![],
+            camera: None,
+            emitters: vec![],
+            size,
         }
     }
 
@@ -85,30 +114,80 @@ impl Panel {
         let size = ctx.adapter.size();
         self.buffers[0].resize(size);
         self.buffers[1].resize(size);
+        self.size = size;
         info!("panel init size...{:?}", size);
     }
 
+    /// follows an adapter size change, e.g. a terminal window resize,
+    /// both buffers keep what fits so only changed cells are redrawn
+    pub fn resize(&mut self, size: Rect) {
+        for b in &mut self.buffers {
+            b.resize_preserving(size.width, size.height);
+        }
+        self.size = size;
+    }
+
     pub fn current_buffer_mut(&mut self) -> &mut Buffer {
         &mut self.buffers[self.current]
     }
 
-    fn add_layer_inner(&mut self, name: &str, is_pixel: bool) {
-        let sps = if is_pixel {
+    /// clips both buffers, so layers are composited inside rect
+    /// until pop_clip, see Buffer::push_clip
+    pub fn push_clip(&mut self, rect: Rect) {
+        for b in &mut self.buffers {
+            b.push_clip(rect);
+        }
+    }
+
+    pub fn pop_clip(&mut self) {
+        for b in &mut self.buffers {
+            b.pop_clip();
+        }
+    }
+
+    fn add_layer_inner(&mut self, name: &str, is_pixel: bool, z: i32) {
+        let mut sps = if is_pixel {
             Sprites::new_pixel(name)
         } else {
             Sprites::new(name)
         };
+        sps.z_index = z;
         self.layers.push(sps);
         self.layer_tag_index
             .insert(name.to_string(), self.layers.len() - 1);
+        self.render_index.clear();
     }
 
     pub fn add_layer(&mut self, name: &str) {
-        self.add_layer_inner(name, false);
+        self.add_layer_inner(name, false, 0);
     }
 
     pub fn add_layer_pixel(&mut self, name: &str) {
-        self.add_layer_inner(name, true);
+        self.add_layer_inner(name, true, 0);
+    }
+
+    /// add a text layer with z-index, bigger z draws over smaller z
+    pub fn add_layer_z(&mut self, name: &str, z: i32) {
+        self.add_layer_inner(name, false, z);
+    }
+
+    /// add a pixel layer with z-index, bigger z draws over smaller z
+    pub fn add_layer_pixel_z(&mut self, name: &str, z: i32) {
+        self.add_layer_inner(name, true, z);
+    }
+
+    pub fn set_layer_z(&mut self, layer_name: &str, z: i32) {
+        let idx = self.layer_tag_index.get(layer_name).unwrap();
+        self.layers[*idx].z_index = z;
+        self.render_index.clear();
+    }
+
+    /// draws a layer through self.camera, text sprites are placed in world
+    /// cells and culled to the view, pixel sprites in world pixels are shifted
+    /// by the camera in pixels, so they scroll smoothly
+    pub fn set_layer_camera(&mut self, layer_name: &str, use_camera: bool) {
+        let idx = self.layer_tag_index.get(layer_name).unwrap();
+        self.layers[*idx].use_camera = use_camera;
     }
 
     pub fn add_layer_sprite(&mut self, sp: Sprite, layer_name: &str, tag: &str) {
@@ -158,24 +237,124 @@ impl Panel {
     }
 
     pub fn update_render_index(&mut self) {
+        // lower z_index first, same z_index sorted by render_weight
         if self.render_index.is_empty() {
             for (i, s) in self.layers.iter().enumerate() {
-                self.render_index.push((i, s.render_weight));
+                self.render_index.push((i, s.z_index, s.render_weight));
+            }
+            self.render_index.sort_by_key(|d| (d.1, Reverse(d.2)));
+        }
+    }
+
+    /// advances the motions of all sprites, called by draw
+    pub fn update_motions(&mut self, dt: f32) {
+        for layer in &mut self.layers {
+            for s in &mut layer.sprites {
+                s.update_motion(dt);
+            }
+        }
+    }
+
+    /// fire-and-forget, the emitter is advanced and drawn by draw until it finishes
+    pub fn add_emitter(&mut self, e: Emitter) {
+        self.emitters.push(e);
+    }
+
+    /// advances all emitters and drops the finished ones, called by draw
+    pub fn update_particles(&mut self, dt: f32) {
+        for e in &mut self.emitters {
+            e.update(dt);
+        }
+        self.emitters.retain(|e| !e.is_finished());
+    }
+
+    // terminal mode draws particles as cells over the layers
+    #[cfg(not(any(feature = "sdl", target_arch = "wasm32")))]
+    fn draw_particles(&mut self) {
+        for e in &self.emitters {
+            e.draw(&mut self.buffers[self.current]);
+        }
+    }
+
+    // graphics mode places a sprite of the particles pixel layer
+    // at each particle's pixel position, spare sprites are hidden
+    #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
+    fn draw_particles(&mut self) {
+        if !self.layer_tag_index.contains_key(PARTICLE_LAYER) {
+            if self.emitters.is_empty() {
+                return;
+            }
+            self.add_layer_pixel_z(PARTICLE_LAYER, PARTICLE_LAYER_Z);
+        }
+        let sw = *PIXEL_SYM_WIDTH.get().expect("lazylock init");
+        let sh = *PIXEL_SYM_HEIGHT.get().expect("lazylock init");
+        let layer = &mut self.layers[self.layer_tag_index[PARTICLE_LAYER]];
+        let mut n = 0;
+        for e in &self.emitters {
+            for o in e.particles.pool.iter().filter(|o| o.active) {
+                let (x, y) = (o.obj.pos.0 * sw, o.obj.pos.1 * sh);
+                if x < 0.0 || y < 0.0 {
+                    continue;
+                }
+                if n == layer.sprites.len() {
+                    let tag = format!("{}{}", PARTICLE_LAYER, n);
+                    layer.add_by_tag(Sprite::new(0, 0, 1, 1), &tag);
+                }
+                let sp = &mut layer.sprites[n];
+                sp.set_hidden(false);
+                sp.set_pos(x as u16, y as u16);
+                sp.content.content[0]
+                    .set_symbol(e.symbol_of(&o.obj))
+                    .set_fg(e.color_of(&o.obj));
+                n += 1;
+            }
+        }
+        for sp in &mut layer.sprites[n..] {
+            sp.set_hidden(true);
+        }
+    }
+
+    /// composite all visible layers into current buffer in z order
+    pub fn draw_layers(&mut self, am: &mut AssetManager) {
+        self.update_render_index();
+        for idx in &self.render_index {
+            let layer = &mut self.layers[idx.0];
+            if layer.is_hidden {
+                continue;
+            }
+            let buf = &mut self.buffers[self.current];
+            layer.camera_pos = match &self.camera {
+                Some(cam) if layer.use_camera && layer.is_pixel => Some((cam.x, cam.y)),
+                _ => None,
+            };
+            match &self.camera {
+                Some(cam) if layer.use_camera && !layer.is_pixel => {
+                    layer.render_all_to_buffer_camera(am, buf, cam)
+                }
+                _ => layer.render_all_to_buffer(am, buf),
             }
-            self.render_index.sort_by_key(|d| Reverse(d.1));
         }
     }
 
     pub fn draw(&mut self, ctx: &mut Context) -> io::Result<()> {
+        let size = ctx.adapter.size();
+        if size != self.size {
+            self.resize(size);
+        }
         if ctx.stage > LOGO_FRAME {
-            self.update_render_index();
-            for idx in &self.render_index {
-                if !self.layers[idx.0].is_hidden {
-                    self.layers[idx.0]
-                        .render_all_to_buffer(&mut ctx.asset_manager, &mut self.buffers[self.current]);
-                }
+            self.update_motions(ctx.dt);
+            self.update_particles(ctx.dt);
+            #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
+            self.draw_particles();
+            self.draw_layers(&mut ctx.asset_manager);
+            #[cfg(not(any(feature = "sdl", target_arch = "wasm32")))]
+            self.draw_particles();
+            if ctx.debug.enabled {
+                ctx.debug.draw(&mut self.buffers[self.current]);
             }
         }
+        #[cfg(any(feature = "sdl", target_arch = "wasm32"))]
+        ctx.adapter.set_postfx_time(ctx.time as f32);
         let cb = &self.buffers[self.current];
         let pb = &self.buffers[1 - self.current];
         ctx.adapter
@@ -192,6 +371,36 @@ impl Panel {
         Ok(())
     }
 
+    // objpool sprites live in a layer named by pool prefix if exists,
+    // otherwise in the default pixel layer
+    fn objpool_layer(&self, prefix: &str) -> usize {
+        *self.layer_tag_index.get(prefix).unwrap_or(&1)
+    }
+
+    fn get_objpool_sprite(&mut self, prefix: &str, idx: usize) -> &mut Sprite {
+        let li = self.objpool_layer(prefix);
+        self.layers[li].get_by_tag(&format!("{}{}", prefix, idx))
+    }
+
+    /// create a max number of sprites in a pixel layer with z-index
+    /// the layer is named by pool prefix, draw_objpool finds it automatically
+    pub fn creat_objpool_sprites_z<T, F>(
+        &mut self,
+        pool: &GameObjPool<T>,
+        size_x: u16,
+        size_y: u16,
+        z: i32,
+        f: F,
+    ) where
+        T: GObj,
+        F: FnMut(&mut Sprite),
+    {
+        if !self.layer_tag_index.contains_key(&pool.prefix) {
+            self.add_layer_pixel_z(&pool.prefix, z);
+        }
+        self.creat_objpool_sprites(pool, size_x, size_y, f);
+    }
+
     /// create a max number of sprites
     /// and calls f closure to init
     pub fn creat_objpool_sprites<T, F>(
@@ -204,11 +413,12 @@ impl Panel {
         T: GObj,
         F: FnMut(&mut Sprite),
     {
+        let li = self.objpool_layer(&pool.prefix);
         for i in 0..pool.max_count {
             let mut bl = Sprite::new(0, 0, size_x, size_y);
             f(&mut bl);
             bl.set_hidden(true);
-            self.add_pixel_sprite(bl, &format!("{}{}", &pool.prefix, i));
+            self.layers[li].add_by_tag(bl, &format!("{}{}", &pool.prefix, i));
         }
     }
 
@@ -224,8 +434,7 @@ impl Panel {
             if !o.active {
                 if let Some(oid) = os.map.remove(&o.id) {
                     //info!("render set hidden true...");
-                    self.get_pixel_sprite(&format!("{}{}", os.prefix, oid))
-                        .set_hidden(true);
+                    self.get_objpool_sprite(&os.prefix, oid).set_hidden(true);
                 }
                 continue;
             }
@@ -236,7 +445,7 @@ impl Panel {
                     let mut mi = 0;
                     // find an available sprite
                     for i in 0..os.max_count {
-                        let pp = self.get_pixel_sprite(&format!("{}{}", os.prefix, i));
+                        let pp = self.get_objpool_sprite(&os.prefix, i);
                         if pp.is_hidden() {
                             mi = i;
                             break;
@@ -248,9 +457,87 @@ impl Panel {
                 }
             };
             // concatenate pre and psid to get the sprite, set visible and draw
-            let pl = self.get_pixel_sprite(&format!("{}{}", os.prefix, psid));
+            let pl = self.get_objpool_sprite(&os.prefix, psid);
             pl.set_hidden(false);
             f(pl, o);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn higher_z_layer_overwrites_lower_z() {
+        let mut panel = Panel::new();
+        let mut am = AssetManager::new();
+        // add the upper layer first, order must come from z not insertion
+        panel.add_layer_z("ui", 10);
+        panel.add_layer_z("game", 1);
+        let mut top = Sprite::new(0, 0, 2, 1);
+        top.set_default_str("U");
+        panel.add_layer_sprite(top, "ui", "top");
+        let mut bottom = Sprite::new(0, 0, 2, 1);
+        bottom.set_default_str("GG");
+        panel.add_layer_sprite(bottom, "game", "bottom");
+
+        panel.draw_layers(&mut am);
+        let buf = &panel.buffers[panel.current];
+        assert_eq!(buf.get(0, 0).symbol, "U");
+        // blank cell of upper layer keeps lower layer visible
+        assert_eq!(buf.get(1, 0).symbol, "G");
+
+        // swap z, lower layer now on top
+        panel.set_layer_z("game", 20);
+        panel.buffers[panel.current].reset();
+        panel.draw_layers(&mut am);
+        assert_eq!(panel.buffers[panel.current].get(0, 0).symbol, "G");
+    }
+
+    #[test]
+    fn camera_layers_scroll_and_blend() {
+        use crate::render::style::{BlendMode, Color};
+        let mut panel = Panel::new();
+        let mut am = AssetManager::new();
+        panel.add_layer_z("map", 1);
+        panel.add_layer_pixel_z("actors", 2);
+        panel.set_layer_camera("map", true);
+        panel.set_layer_camera("actors", true);
+        let mut cam = Camera::new(400, 200, 180, 80);
+        cam.set_pos(100.5, 10.0);
+        panel.camera = Some(cam);
+
+        // something on screen cell (2, 2) before the map layer
+        let gray = Color::Rgba(100, 100, 100, 255);
+        let mut under = Sprite::new(2, 2, 1, 1);
+        under.set_color_str(0, 0, "u", gray, Color::Reset);
+        panel.add_layer_sprite(under, "main", "under");
+        panel.set_layer_z("main", 0);
+
+        // a glowing tree at world cell (102, 12), out of view one at (0, 0)
+        let mut tree = Sprite::new(102, 12, 1, 1);
+        tree.set_color_str(0, 0, "T", gray, Color::Reset);
+        tree.set_blend(BlendMode::Add);
+        panel.add_layer_sprite(tree, "map", "tree");
+        let mut far = Sprite::new(0, 0, 1, 1);
+        far.set_default_str("F");
+        panel.add_layer_sprite(far, "map", "far");
+        panel.add_layer_sprite(Sprite::new(0, 0, 1, 1), "actors", "hero");
+
+        panel.draw_layers(&mut am);
+        let buf = &panel.buffers[panel.current];
+        let c = buf.get(2, 2);
+        assert_eq!(c.symbol, "T");
+        // merged with blend like Sprite::render, not copied over
+        assert_eq!(c.fg, Color::Rgba(200, 200, 200, 255));
+        assert_eq!(buf.get(0, 0).symbol, " ");
+
+        // the pixel layer gets the camera position with its fraction
+        let actors = panel.layer_tag_index["actors"];
+        assert_eq!(panel.layers[actors].camera_pos, Some((100.5, 10.0)));
+        panel.set_layer_camera("actors", false);
+        panel.draw_layers(&mut am);
+        assert_eq!(panel.layers[actors].camera_pos, None);
+    }
+}
@@ -0,0 +1,361 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! particles gives fire-and-forget bursts for cell based effects, such as
+//! explosions, card sparkles or cleared blocks
+//!
+//! An Emitter spawns particles at pos, all at once (burst) or at rate per second
+//! for duration seconds (stream). Each particle gets a life from the life range
+//! and a velocity inside the cone, then falls with gravity. Its symbol and color
+//! are picked from the symbols and colors tables by the part of its life gone.
+//!
+//! Particles live in a util::GameObjPool capped at max, dead ones are reused,
+//! so once warmed up an emitter doesn't allocate. Positions are in cells.
+//!
+//! Give it to Panel::add_emitter and forget it, Panel::draw advances emitters
+//! by context.dt and drops the finished ones. In terminal mode particles are
+//! drawn as colored cells over the layers, in graphics mode as sprites of the
+//! particles pixel layer, at their exact position instead of a whole cell.
+//!
+//! With a seeded Rand an emitter gives the same particles every run.
+//!
+//! # Example
+//! ```
+//! use rust_pixel::render::{particles::Emitter, style::Color};
+//! let mut e = Emitter::burst(20.0, 10.0, 30)
+//!     .life(0.4, 0.8)
+//!     .speed(6.0, 12.0)
+//!     .gravity(20.0)
+//!     .symbols(&["*", "+", "."])
+//!     .colors(&[Color::Yellow, Color::Red, Color::DarkGray]);
+//! e.update(0.1);
+//! assert_eq!(e.len(), 30);
+//! ```
+
+use crate::{
+    render::{buffer::Buffer, style::Color},
+    util::{
+        objpool::{GObj, GameObjPool},
+        Rand,
+    },
+};
+use std::f32::consts::PI;
+
+/// default cap of live particles per emitter
+pub const MAX_EMITTER_PARTICLES: usize = 256;
+
+#[derive(Default, Debug, Clone)]
+pub struct CellParticle {
+    /// position in cells
+    pub pos: (f32, f32),
+    /// velocity in cells per second
+    pub v: (f32, f32),
+    pub age: f32,
+    pub life: f32,
+}
+
+impl GObj for CellParticle {
+    fn new() -> Self {
+        Default::default()
+    }
+
+    fn reset(&mut self, _t: u8, _ps: &[u32]) {}
+}
+
+impl CellParticle {
+    /// part of life gone, 0.0 at birth
+    pub fn progress(&self) -> f32 {
+        if self.life <= 0.0 {
+            1.0
+        } else {
+            (self.age / self.life).min(1.0)
+        }
+    }
+}
+
+pub struct Emitter {
+    /// position in cells
+    pub pos: (f32, f32),
+    /// particles spawned on the first update
+    pub burst: usize,
+    /// particles per second while emitting
+    pub rate: f32,
+    /// seconds of emitting at rate
+    pub duration: f32,
+    /// life range in seconds
+    pub life: (f32, f32),
+    /// cone center in radians, 0 to the right, PI / 2 down
+    pub direction: f32,
+    /// cone width in radians
+    pub spread: f32,
+    /// speed range in cells per second
+    pub speed: (f32, f32),
+    /// downward acceleration in cells per second squared
+    pub gravity: f32,
+    /// symbols over life, the first at birth
+    pub symbols: Vec<String>,
+    /// colors over life, the first at birth
+    pub colors: Vec<Color>,
+    pub particles: GameObjPool<CellParticle>,
+    rand: Rand,
+    age: f32,
+    residue: f32,
+    started: bool,
+}
+
+impl Emitter {
+    fn new(x: f32, y: f32, burst: usize, rate: f32, duration: f32) -> Self {
+        let mut rand = Rand::new();
+        rand.srand_now();
+        Self {
+            pos: (x, y),
+            burst,
+            rate,
+            duration,
+            life: (0.5, 1.0),
+            direction: -PI / 2.0,
+            spread: 2.0 * PI,
+            speed: (4.0, 8.0),
+            gravity: 0.0,
+            symbols: vec!["*".to_string()],
+            colors: vec![Color::White],
+            particles: GameObjPool::new("PARTICLE", MAX_EMITTER_PARTICLES),
+            rand,
+            age: 0.0,
+            residue: 0.0,
+            started: false,
+        }
+    }
+
+    /// count particles at once
+    pub fn burst(x: f32, y: f32, count: usize) -> Self {
+        Self::new(x, y, count, 0.0, 0.0)
+    }
+
+    /// rate particles per second for duration seconds
+    pub fn stream(x: f32, y: f32, rate: f32, duration: f32) -> Self {
+        Self::new(x, y, 0, rate, duration)
+    }
+
+    pub fn life(mut self, min: f32, max: f32) -> Self {
+        self.life = (min, max);
+        self
+    }
+
+    pub fn cone(mut self, direction: f32, spread: f32) -> Self {
+        self.direction = direction;
+        self.spread = spread;
+        self
+    }
+
+    pub fn speed(mut self, min: f32, max: f32) -> Self {
+        self.speed = (min, max);
+        self
+    }
+
+    pub fn gravity(mut self, g: f32) -> Self {
+        self.gravity = g;
+        self
+    }
+
+    pub fn symbols(mut self, symbols: &[&str]) -> Self {
+        self.symbols = symbols.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn colors(mut self, colors: &[Color]) -> Self {
+        self.colors = colors.to_vec();
+        self
+    }
+
+    /// caps live particles, spawns over the cap are dropped
+    pub fn max(mut self, max: usize) -> Self {
+        self.particles.max_count = max;
+        self
+    }
+
+    /// random source, seed it for the same particles every run
+    pub fn rand(mut self, rand: Rand) -> Self {
+        self.rand = rand;
+        self
+    }
+
+    /// moves and ages particles, then spawns the new ones
+    pub fn update(&mut self, dt: f32) {
+        let dt = dt.max(0.0);
+        let g = self.gravity;
+        self.particles.update_active(|po| {
+            let p = &mut po.obj;
+            p.age += dt;
+            if p.age >= p.life {
+                po.active = false;
+                return;
+            }
+            p.v.1 += g * dt;
+            p.pos.0 += p.v.0 * dt;
+            p.pos.1 += p.v.1 * dt;
+        });
+
+        let mut n = 0;
+        if !self.started {
+            self.started = true;
+            n += self.burst;
+        }
+        if self.age < self.duration {
+            let need = self.rate * dt.min(self.duration - self.age) + self.residue;
+            n += need as usize;
+            self.residue = need.fract();
+        }
+        self.age += dt;
+        let room = self.particles.max_count.saturating_sub(self.len());
+        for _ in 0..n.min(room) {
+            self.spawn();
+        }
+    }
+
+    fn spawn(&mut self) {
+        let r = &mut self.rand;
+        let life = r.gen_range(self.life.0 as f64, self.life.1 as f64) as f32;
+        let a = self.direction - self.spread / 2.0 + r.gen_range(0.0, self.spread as f64) as f32;
+        let speed = r.gen_range(self.speed.0 as f64, self.speed.1 as f64) as f32;
+        let pos = self.pos;
+        self.particles.create_with_func(0, |_, po| {
+            po.obj = CellParticle {
+                pos,
+                v: (a.cos() * speed, a.sin() * speed),
+                age: 0.0,
+                life,
+            };
+        });
+    }
+
+    /// live particles
+    pub fn len(&self) -> usize {
+        self.particles.pool.iter().filter(|o| o.active).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// done emitting and every particle is dead
+    pub fn is_finished(&self) -> bool {
+        self.started && self.age >= self.duration && self.is_empty()
+    }
+
+    pub fn symbol_of(&self, p: &CellParticle) -> &str {
+        match self.symbols.len() {
+            0 => " ",
+            n => &self.symbols[table_index(p, n)],
+        }
+    }
+
+    pub fn color_of(&self, p: &CellParticle) -> Color {
+        match self.colors.len() {
+            0 => Color::Reset,
+            n => self.colors[table_index(p, n)],
+        }
+    }
+
+    /// draws live particles into buf as colored cells, the ones outside are skipped
+    pub fn draw(&self, buf: &mut Buffer) {
+        let area = *buf.area();
+        for o in self.particles.pool.iter().filter(|o| o.active) {
+            let (x, y) = (o.obj.pos.0.round(), o.obj.pos.1.round());
+            if x < area.x as f32
+                || y < area.y as f32
+                || x >= area.right() as f32
+                || y >= area.bottom() as f32
+            {
+                continue;
+            }
+            buf.get_mut(x as u16, y as u16)
+                .set_symbol(self.symbol_of(&o.obj))
+                .set_fg(self.color_of(&o.obj));
+        }
+    }
+}
+
+fn table_index(p: &CellParticle, n: usize) -> usize {
+    ((p.progress() * n as f32) as usize).min(n - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Rect;
+
+    fn seeded(seed: u64) -> Rand {
+        let mut r = Rand::new();
+        r.srand(seed);
+        r
+    }
+
+    #[test]
+    fn counts_over_life_are_repeatable() {
+        let run = || {
+            let mut e = Emitter::stream(5.0, 5.0, 20.0, 0.5)
+                .life(0.2, 0.4)
+                .rand(seeded(7));
+            let mut counts = vec![];
+            for _ in 0..20 {
+                e.update(0.05);
+                counts.push(e.len());
+            }
+            (counts, e.is_finished())
+        };
+        let (counts, finished) = run();
+        assert_eq!(run().0, counts);
+        // one per 0.05s while emitting, none live after the last one dies
+        assert_eq!(counts[0], 1);
+        assert!(counts[..10].iter().all(|&n| n > 0 && n <= 8));
+        assert_eq!(counts[19], 0);
+        assert!(finished);
+
+        let mut b = Emitter::burst(0.0, 0.0, 10).life(1.0, 1.0);
+        b.update(0.0);
+        assert_eq!(b.len(), 10);
+        b.update(0.5);
+        assert_eq!(b.len(), 10);
+        assert!(!b.is_finished());
+        b.update(0.5);
+        assert!(b.is_finished());
+    }
+
+    #[test]
+    fn pool_is_reused_after_warm_up() {
+        let mut e = Emitter::stream(0.0, 0.0, 100.0, 5.0)
+            .life(0.1, 0.1)
+            .max(8)
+            .rand(seeded(1));
+        for _ in 0..10 {
+            e.update(0.02);
+        }
+        let (len, cap) = (e.particles.pool.len(), e.particles.pool.capacity());
+        assert!(len <= 8);
+        for _ in 0..100 {
+            e.update(0.02);
+            assert!(e.len() <= 8);
+        }
+        assert_eq!(e.particles.pool.len(), len);
+        assert_eq!(e.particles.pool.capacity(), cap);
+    }
+
+    #[test]
+    fn draws_tables_by_life() {
+        let mut e = Emitter::burst(2.0, 1.0, 1)
+            .life(1.0, 1.0)
+            .speed(0.0, 0.0)
+            .symbols(&["*", "."])
+            .colors(&[Color::Yellow, Color::Red]);
+        e.update(0.0);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 4, 3));
+        e.draw(&mut buf);
+        assert_eq!(buf.get(2, 1).symbol, "*");
+        assert_eq!(buf.get(2, 1).fg, Color::Yellow);
+        e.update(0.6);
+        e.draw(&mut buf);
+        assert_eq!(buf.get(2, 1).symbol, ".");
+        assert_eq!(buf.get(2, 1).fg, Color::Red);
+    }
+}
@@ -7,11 +7,12 @@
 //! Refer to util/shape.rs for an example of how to draw a line
 
 use crate::{
-    asset::{AssetManager, AssetState, AssetType},
-    render::buffer::Buffer,
+    asset::{Asset, AssetError, AssetManager, AssetState, AssetType},
+    render::buffer::{BlitMode, Buffer},
+    render::camera::Camera,
     render::cell::cellsym,
-    // render::image::*,
-    render::style::{Color, Style},
+    render::image::{PaletteRemap, PixAsset},
+    render::style::{BlendMode, Color, Style},
     util::shape::{circle, line, prepare_line},
     util::{PointU16, PointF32, Rect},
 };
@@ -21,6 +22,8 @@ use bitflags::bitflags;
 
 mod sprites;
 pub use sprites::Sprites;
+mod motion;
+pub use motion::{Motion, MotionTarget};
 
 /// Defines some common tabs symbol (in text mode)
 pub const SYMBOL_LINE: [&str; 37] = [
@@ -40,12 +43,50 @@ bitflags! {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum BorderType {
-    Plain,
-    Rounded,
-    Double,
-    Thick,
+pub use crate::render::border::BorderType;
+
+/// widths of the fixed border of a 9-slice source, in cells
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Margins {
+    pub left: u16,
+    pub top: u16,
+    pub right: u16,
+    pub bottom: u16,
+}
+
+impl Margins {
+    pub fn new(left: u16, top: u16, right: u16, bottom: u16) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub fn all(m: u16) -> Self {
+        Self::new(m, m, m, m)
+    }
+}
+
+/// source cell for target cell t on one axis of a 9-slice,
+/// a and b are the fixed margins, None if the source has no middle
+fn nine_slice_map(t: u16, tlen: u16, slen: u16, a: u16, b: u16) -> Option<u16> {
+    // margins are cut when the target is smaller than both of them
+    let ta = a.min(tlen);
+    let tb = b.min(tlen - ta);
+    if t < ta {
+        return Some(t);
+    }
+    if t >= tlen - tb {
+        return Some(slen - (tlen - t));
+    }
+    let sm = slen.saturating_sub(a + b);
+    if sm == 0 {
+        return None;
+    }
+    let tm = tlen - ta - tb;
+    Some(a + ((t - ta) as u32 * sm as u32 / tm as u32) as u16)
 }
 
 /// Used to simplify the call to set_content_by_asset method
@@ -111,7 +152,14 @@ pub struct Sprite {
     pub angle: f64,
     pub alpha: u8,
     pub asset_request: Option<(AssetType, String, usize, u16, u16)>,
+    pub asset_remap: Option<PaletteRemap>,
     render_weight: i32,
+    z: i32,
+    tint: Color,
+    tint_strength: f32,
+    nine_slice: Option<(Buffer, Margins)>,
+    blend: BlendMode,
+    motion: Option<Motion>,
 }
 
 impl Widget for Sprite {
@@ -119,7 +167,13 @@ impl Widget for Sprite {
         if !self.is_hidden() {
             self.check_asset_request(am);
             if !is_pixel {
-                buf.merge(&self.content, self.alpha, true);
+                if self.tint_strength > 0.0 {
+                    let mut content = self.content.clone();
+                    self.apply_tint(&mut content);
+                    buf.merge_blend(&content, self.alpha, self.blend);
+                } else {
+                    buf.merge_blend(&self.content, self.alpha, self.blend);
+                }
             }
         }
     }
@@ -134,14 +188,93 @@ impl Sprite {
             angle: 0.0,
             alpha: 255,
             asset_request: None,
+            asset_remap: None,
             render_weight: 1,
+            z: 0,
+            tint: Color::White,
+            tint_strength: 0.0,
+            nine_slice: None,
+            blend: BlendMode::Normal,
+            motion: None,
         }
     }
 
+    /// create a sprite from pix data, color indices rewritten by remap
+    pub fn from_pix_with_remap(data: &[u8], remap: &PaletteRemap) -> Result<Self, AssetError> {
+        let mut pa = PixAsset::from_data(data)?;
+        let area = pa.get_base().parsed_buffers[0].area;
+        let mut sp = Sprite::new(0, 0, area.width, area.height);
+        pa.set_sprite_remap(&mut sp, 0, 0, 0, remap);
+        Ok(sp)
+    }
+
     pub fn set_alpha(&mut self, a: u8) {
         self.alpha = a;
     }
 
+    /// tints colors at draw time, without changing content
+    /// cell colors are multiplied by color, then blended by strength(0.0~1.0)
+    /// e.g. flash red when hit, gray out disabled ui, strength 0.0 removes tint
+    pub fn set_tint(&mut self, color: Color, strength: f32) {
+        self.tint = color;
+        self.tint_strength = strength.clamp(0.0, 1.0);
+    }
+
+    /// how sprite colors combine with the layers below, e.g. BlendMode::Add
+    /// for glows, pixel sprites are blended by the gl blend func
+    pub fn set_blend(&mut self, blend: BlendMode) {
+        self.blend = blend;
+    }
+
+    pub fn get_blend(&self) -> BlendMode {
+        self.blend
+    }
+
+    /// animates pos or alpha, advanced by Panel::draw, see Motion
+    pub fn set_motion(&mut self, m: Motion) {
+        self.motion = Some(m);
+    }
+
+    pub fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+
+    /// true if a motion was set and has ended
+    pub fn motion_finished(&self) -> bool {
+        self.motion.as_ref().is_some_and(|m| m.is_finished())
+    }
+
+    /// advances the motion dt seconds and applies it
+    pub fn update_motion(&mut self, dt: f32) {
+        if let Some(mut m) = self.motion.take() {
+            m.update(dt);
+            m.apply(self);
+            self.motion = Some(m);
+        }
+    }
+
+    /// color after tint, Reset is kept as transparent
+    pub fn tint_color(&self, c: Color) -> Color {
+        if self.tint_strength == 0.0 || c == Color::Reset {
+            return c;
+        }
+        let (r, g, b, a) = c.get_rgba();
+        let (tr, tg, tb, _) = self.tint.get_rgba();
+        let s = self.tint_strength;
+        let mix = |v: u8, t: u8| -> u8 {
+            let m = v as f32 * t as f32 / 255.0;
+            (v as f32 * (1.0 - s) + m * s).round() as u8
+        };
+        Color::Rgba(mix(r, tr), mix(g, tg), mix(b, tb), a)
+    }
+
+    fn apply_tint(&self, buf: &mut Buffer) {
+        for c in &mut buf.content {
+            c.fg = self.tint_color(c.fg);
+            c.bg = self.tint_color(c.bg);
+        }
+    }
+
     pub fn set_fg(&mut self, color: Color) {
         self.content.set_fg(color);
     }
@@ -188,11 +321,35 @@ impl Sprite {
         self.check_asset_request(am);
     }
 
+    /// same as set_content_by_asset, recolors with a palette remap table
+    /// e.g. PaletteRemap::from_swaps(&[(old, new)])
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_content_by_asset_remap(
+        &mut self,
+        am: &mut AssetManager,
+        atype: AssetType,
+        location: &str,
+        frame_idx: usize,
+        off_x: u16,
+        off_y: u16,
+        remap: PaletteRemap,
+    ) {
+        self.asset_remap = Some(remap);
+        self.set_content_by_asset(am, atype, location, frame_idx, off_x, off_y);
+    }
+
     pub fn check_asset_request(&mut self, am: &mut AssetManager) -> bool {
         if let Some(req) = &self.asset_request {
+            // evicted before this sprite took it, load it again
+            if am.get(&req.1).is_none() {
+                am.load(req.0, &req.1);
+            }
             if let Some(ast) = am.get(&req.1) {
                 if ast.get_state() == AssetState::Ready {
-                    ast.set_sprite(self, req.2, req.3, req.4);
+                    match self.asset_remap.take() {
+                        Some(remap) => ast.set_sprite_remap(self, req.2, req.3, req.4, &remap),
+                        None => ast.set_sprite(self, req.2, req.3, req.4),
+                    }
                     self.asset_request = None;
                     return true;
                 }
@@ -203,6 +360,38 @@ impl Sprite {
         false
     }
 
+    /// draws the part of a text sprite inside the camera view, merged with
+    /// alpha and blend like render, content.area is in world cells,
+    /// sprites out of the view are skipped
+    pub fn render_camera(&mut self, am: &mut AssetManager, buf: &mut Buffer, cam: &Camera) {
+        if self.is_hidden() {
+            return;
+        }
+        self.check_asset_request(am);
+        let area = self.content.area;
+        let view = cam.view_rect();
+        if !area.intersects(view) {
+            return;
+        }
+        let vis = area.intersection(view);
+        let src = Rect::new(vis.x - area.x, vis.y - area.y, vis.width, vis.height);
+        // visible part moved to its screen position
+        let mut part = Buffer::empty(Rect::new(
+            buf.area.x + vis.x - view.x,
+            buf.area.y + vis.y - view.y,
+            vis.width,
+            vis.height,
+        ));
+        if self.tint_strength > 0.0 {
+            let mut content = self.content.clone();
+            self.apply_tint(&mut content);
+            part.blit((0, 0), &content, Some(src), BlitMode::Opaque);
+        } else {
+            part.blit((0, 0), &self.content, Some(src), BlitMode::Opaque);
+        }
+        buf.merge_blend(&part, self.alpha, self.blend);
+    }
+
     pub fn set_angle(&mut self, a: f64) {
         self.angle = a;
     }
@@ -226,73 +415,50 @@ impl Sprite {
         self.render_weight < 0
     }
 
+    /// z order in its Sprites, bigger z draws over smaller z
+    pub fn set_z(&mut self, z: i32) {
+        self.z = z;
+    }
+
+    pub fn z(&self) -> i32 {
+        self.z
+    }
+
     pub fn set_border(&mut self, borders: Borders, border_type: BorderType, style: Style) {
-        // vertical horizontal
-        // top_right top_left bottom_right bottom_left
-        // vertical_left vertical_right horizontal_down horizontal_up
-        // cross
-        let lineidx: [usize; 11] = match border_type {
-            BorderType::Plain => [0, 3, 6, 10, 14, 18, 22, 25, 28, 31, 34],
-            BorderType::Rounded => [0, 3, 7, 11, 15, 19, 22, 25, 28, 31, 34],
-            BorderType::Double => [1, 4, 8, 12, 16, 20, 23, 26, 29, 33, 35],
-            BorderType::Thick => [2, 5, 9, 13, 17, 21, 24, 27, 30, 34, 36],
-        };
+        let bs = border_type.set();
+        let (w, h) = (self.content.area.width, self.content.area.height);
         if borders.intersects(Borders::LEFT) {
-            for y in 0..self.content.area.height {
-                self.content
-                    .set_str_tex(0, y, SYMBOL_LINE[lineidx[0]], style, 1);
+            for y in 0..h {
+                self.content.set_str_tex(0, y, bs.vertical, style, 1);
             }
         }
         if borders.intersects(Borders::TOP) {
-            for x in 0..self.content.area.width {
-                self.content
-                    .set_str_tex(x, 0, SYMBOL_LINE[lineidx[1]], style, 1);
+            for x in 0..w {
+                self.content.set_str_tex(x, 0, bs.horizontal, style, 1);
             }
         }
         if borders.intersects(Borders::RIGHT) {
-            let x = self.content.area.width - 1;
-            for y in 0..self.content.area.height {
-                self.content
-                    .set_str_tex(x, y, SYMBOL_LINE[lineidx[0]], style, 1);
+            for y in 0..h {
+                self.content.set_str_tex(w - 1, y, bs.vertical, style, 1);
             }
         }
         if borders.intersects(Borders::BOTTOM) {
-            let y = self.content.area.height - 1;
-            for x in 0..self.content.area.width {
-                self.content
-                    .set_str_tex(x, y, SYMBOL_LINE[lineidx[1]], style, 1);
+            for x in 0..w {
+                self.content.set_str_tex(x, h - 1, bs.horizontal, style, 1);
             }
         }
         if borders.contains(Borders::RIGHT | Borders::BOTTOM) {
-            self.content.set_str_tex(
-                self.content.area.width - 1,
-                self.content.area.height - 1,
-                SYMBOL_LINE[lineidx[4]],
-                style,
-                1,
-            );
+            self.content
+                .set_str_tex(w - 1, h - 1, bs.bottom_right, style, 1);
         }
         if borders.contains(Borders::RIGHT | Borders::TOP) {
-            self.content.set_str_tex(
-                self.content.area.width - 1,
-                0,
-                SYMBOL_LINE[lineidx[2]],
-                style,
-                1,
-            );
+            self.content.set_str_tex(w - 1, 0, bs.top_right, style, 1);
         }
         if borders.contains(Borders::LEFT | Borders::BOTTOM) {
-            self.content.set_str_tex(
-                0,
-                self.content.area.height - 1,
-                SYMBOL_LINE[lineidx[5]],
-                style,
-                1,
-            );
+            self.content.set_str_tex(0, h - 1, bs.bottom_left, style, 1);
         }
         if borders.contains(Borders::LEFT | Borders::TOP) {
-            self.content
-                .set_str_tex(0, 0, SYMBOL_LINE[lineidx[3]], style, 1);
+            self.content.set_str_tex(0, 0, bs.top_left, style, 1);
         }
     }
 
@@ -307,6 +473,35 @@ impl Sprite {
         self.content.area = backup_area;
     }
 
+    /// keeps the current content as a 9-slice source, later set_nine_slice_area
+    /// keeps corners fixed, stretches edges along them and the center both ways
+    /// e.g. a decorated panel background drawn once and fitted to any size
+    pub fn set_nine_slice(&mut self, borders: Margins) {
+        self.nine_slice = Some((self.content.clone(), borders));
+    }
+
+    /// moves and resizes to area, redrawing the 9-slice source into it
+    /// only moves and resizes if set_nine_slice was not called
+    pub fn set_nine_slice_area(&mut self, area: Rect) {
+        self.content.resize(area);
+        self.content.reset();
+        let Some((src, m)) = &self.nine_slice else {
+            return;
+        };
+        let (sw, sh) = (src.area.width, src.area.height);
+        for y in 0..area.height {
+            let Some(sy) = nine_slice_map(y, area.height, sh, m.top, m.bottom) else {
+                continue;
+            };
+            for x in 0..area.width {
+                if let Some(sx) = nine_slice_map(x, area.width, sw, m.left, m.right) {
+                    let c = src.content[(sy * sw + sx) as usize].clone();
+                    self.content.content[(y * area.width + x) as usize] = c;
+                }
+            }
+        }
+    }
+
     pub fn set_pos(&mut self, x: u16, y: u16) {
         self.content.area = Rect::new(x, y, self.content.area.width, self.content.area.height);
     }
@@ -368,3 +563,73 @@ impl Sprite {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tinted(color: Color, strength: f32) -> Buffer {
+        let mut sp = Sprite::new(0, 0, 2, 1);
+        sp.content.set_str(
+            0,
+            0,
+            "ab",
+            Style::default().fg(Color::Indexed(250)).bg(Color::Indexed(33)),
+        );
+        sp.set_tint(color, strength);
+        let mut am = AssetManager::new();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 2, 1));
+        sp.render(false, &mut am, &mut buf);
+        buf
+    }
+
+    #[test]
+    fn nine_slice_keeps_corners() {
+        let mut sp = Sprite::new(0, 0, 3, 3);
+        for (i, s) in ["abc", "def", "ghi"].iter().enumerate() {
+            sp.set_color_str(0, i as u16, s, Color::Indexed(i as u8), Color::Reset);
+        }
+        sp.set_nine_slice(Margins::all(1));
+        sp.set_nine_slice_area(Rect::new(2, 3, 9, 9));
+        assert_eq!(sp.content.area, Rect::new(2, 3, 9, 9));
+        let sym = |x: u16, y: u16| sp.content.content[(y * 9 + x) as usize].symbol.clone();
+        assert_eq!(
+            [sym(0, 0), sym(8, 0), sym(0, 8), sym(8, 8)],
+            ["a", "c", "g", "i"]
+        );
+        assert_eq!(sp.content.content[8].fg, Color::Indexed(0));
+        assert_eq!(sp.content.content[80].fg, Color::Indexed(2));
+        for i in 1..8 {
+            assert_eq!(sym(i, 0), "b");
+            assert_eq!(sym(0, i), "d");
+            assert_eq!(sym(i, 8), "h");
+            assert_eq!(sym(8, i), "f");
+            assert_eq!(sym(i, 4), "e");
+        }
+        // smaller than the margins, corners are cut
+        sp.set_nine_slice_area(Rect::new(0, 0, 1, 2));
+        assert_eq!(sp.content.content[0].symbol, "a");
+        assert_eq!(sp.content.content[1].symbol, "g");
+    }
+
+    #[test]
+    fn white_tint_keeps_colors() {
+        let buf = tinted(Color::Rgba(255, 255, 255, 255), 1.0);
+        let c = buf.get(0, 0);
+        assert_eq!(c.fg.get_rgba(), Color::Indexed(250).get_rgba());
+        assert_eq!(c.bg.get_rgba(), Color::Indexed(33).get_rgba());
+        assert_eq!(c.symbol, "a");
+    }
+
+    #[test]
+    fn red_tint_shifts_to_red() {
+        let (r0, g0, b0, _) = Color::Indexed(250).get_rgba();
+        let buf = tinted(Color::Rgba(255, 0, 0, 255), 0.5);
+        let (r, g, b, _) = buf.get(1, 0).fg.get_rgba();
+        assert_eq!(r, r0);
+        assert!(g < g0 && b < b0);
+        // strength 0 resets
+        let c = tinted(Color::Red, 0.0).get(1, 0).fg;
+        assert_eq!(c.get_rgba(), (r0, g0, b0, 255));
+    }
+}
@@ -0,0 +1,193 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Motion animates a sprite's position or alpha with a chain of util::Tween
+//! steps, e.g. a pop-in that overshoots and settles, then fades out
+//!
+//! Set it with Sprite::set_motion, Panel::draw advances every sprite's motion
+//! by context.dt before drawing the layers. A long frame carries the time left
+//! over into the next step, so the chain stays on time under uneven dt.
+//!
+//! Pos is content.area x and y, pixels in pixel layers and cells in text layers.
+//! Values are rounded, so terminal mode moves whole cells, and clamped at 0.
+//!
+//! When the last step ends is_finished turns true, and on_finish is emitted
+//! through event_emit, so a model can event_check it like other events.
+//!
+//! # Example
+//! ```
+//! use rust_pixel::render::sprite::{Motion, Sprite};
+//! use rust_pixel::util::Easing;
+//! let mut sp = Sprite::new(0, 0, 1, 1);
+//! sp.set_motion(
+//!     Motion::pos((10.0, 0.0), (10.0, 8.0), 0.3, Easing::Bounce)
+//!         .then((10.0, 0.0), 0.2, Easing::InQuad)
+//!         .on_finish("Food.Popped"),
+//! );
+//! ```
+
+use crate::{
+    event::event_emit,
+    render::sprite::Sprite,
+    util::{Easing, Tween},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MotionTarget {
+    /// content.area x and y
+    Pos,
+    /// alpha, the first value only
+    Alpha,
+}
+
+#[derive(Debug, Clone)]
+pub struct Motion {
+    pub target: MotionTarget,
+    steps: Vec<Tween<(f32, f32)>>,
+    current: usize,
+    on_finish: Option<String>,
+    emitted: bool,
+}
+
+impl Motion {
+    pub fn new(
+        target: MotionTarget,
+        from: (f32, f32),
+        to: (f32, f32),
+        duration: f32,
+        easing: Easing,
+    ) -> Self {
+        Self {
+            target,
+            steps: vec![Tween::new(from, to, duration, easing)],
+            current: 0,
+            on_finish: None,
+            emitted: false,
+        }
+    }
+
+    pub fn pos(from: (f32, f32), to: (f32, f32), duration: f32, easing: Easing) -> Self {
+        Self::new(MotionTarget::Pos, from, to, duration, easing)
+    }
+
+    pub fn alpha(from: u8, to: u8, duration: f32, easing: Easing) -> Self {
+        let (f, t) = ((from as f32, 0.0), (to as f32, 0.0));
+        Self::new(MotionTarget::Alpha, f, t, duration, easing)
+    }
+
+    /// adds a step from where the last step ends to to
+    pub fn then(mut self, to: (f32, f32), duration: f32, easing: Easing) -> Self {
+        let from = self.steps[self.steps.len() - 1].to;
+        self.steps.push(Tween::new(from, to, duration, easing));
+        self
+    }
+
+    pub fn then_alpha(self, to: u8, duration: f32, easing: Easing) -> Self {
+        self.then((to as f32, 0.0), duration, easing)
+    }
+
+    /// event emitted once when the last step ends
+    pub fn on_finish(mut self, event: &str) -> Self {
+        self.on_finish = Some(event.to_string());
+        self
+    }
+
+    /// advances dt seconds, time left by a finished step goes to the next one
+    pub fn update(&mut self, dt: f32) -> (f32, f32) {
+        let mut left = dt.max(0.0);
+        loop {
+            let last = self.current + 1 == self.steps.len();
+            let t = &mut self.steps[self.current];
+            let remain = t.duration.max(0.0) * (1.0 - t.progress());
+            if left < remain || last {
+                t.update(left);
+                break;
+            }
+            t.update(remain);
+            left -= remain;
+            self.current += 1;
+        }
+        if self.is_finished() && !self.emitted {
+            self.emitted = true;
+            if let Some(e) = &self.on_finish {
+                event_emit(e);
+            }
+        }
+        self.value()
+    }
+
+    pub fn value(&self) -> (f32, f32) {
+        self.steps[self.current].value()
+    }
+
+    /// index of the running step
+    pub fn step(&self) -> usize {
+        self.current
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current + 1 == self.steps.len() && self.steps[self.current].is_finished()
+    }
+
+    /// writes the current value to sp
+    pub fn apply(&self, sp: &mut Sprite) {
+        let v = self.value();
+        match self.target {
+            MotionTarget::Pos => {
+                let q = |f: f32| f.round().clamp(0.0, u16::MAX as f32) as u16;
+                sp.set_pos(q(v.0), q(v.1));
+            }
+            MotionTarget::Alpha => sp.set_alpha(v.0.round().clamp(0.0, 255.0) as u8),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{event_check, event_register};
+
+    #[test]
+    fn easing_known_values() {
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert_eq!(Easing::InQuad.apply(0.5), 0.25);
+        assert_eq!(Easing::OutQuad.apply(0.5), 0.75);
+        assert_eq!(Easing::InOutCubic.apply(0.25), 0.0625);
+        // the first bounce lands on the target
+        assert!((Easing::Bounce.apply(1.0 / 2.75) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn chain_runs_in_order_under_uneven_dt() {
+        let mut m = Motion::pos((0.0, 0.0), (4.0, 0.0), 0.5, Easing::Linear)
+            .then((4.0, 8.0), 1.0, Easing::Linear)
+            .then((0.0, 8.0), 0.0, Easing::Linear);
+        assert_eq!(m.update(0.25), (2.0, 0.0));
+        assert_eq!(m.step(), 0);
+        // 0.25 ends step 0, 0.5 goes to step 1
+        assert_eq!(m.update(0.75), (4.0, 4.0));
+        assert_eq!(m.step(), 1);
+        assert!(!m.is_finished());
+        m.update(0.125);
+        m.update(0.25);
+        assert!(!m.is_finished());
+        // the zero duration step jumps to its target
+        assert_eq!(m.update(0.125), (0.0, 8.0));
+        assert!(m.is_finished());
+        assert_eq!(m.update(1.0), (0.0, 8.0));
+
+        event_register("Motion.Test", "faded");
+        let mut sp = Sprite::new(0, 0, 1, 1);
+        let mut a = Motion::alpha(255, 0, 1.0, Easing::Linear).on_finish("Motion.Test");
+        a.update(0.5);
+        a.apply(&mut sp);
+        assert_eq!(sp.alpha, 128);
+        sp.set_motion(a);
+        sp.update_motion(3.0);
+        assert_eq!(sp.alpha, 0);
+        assert!(sp.motion_finished());
+        assert!(event_check("Motion.Test", "faded"));
+        sp.update_motion(1.0);
+        assert!(!event_check("Motion.Test", "faded"));
+    }
+}
@@ -3,12 +3,16 @@
 
 //! sprites.rs implements a set of Sprites. Those Sprites are stored in a vector
 //! Sprite can be accessed via offset in the vector or by tag in the hashmap
-//! render_all method draws the sprites in a sorted order indicated by render_weight
+//! render_all method draws the sprites in a sorted order indicated by z and render_weight
+//! lower z first, same z sorted by render_weight, same both keeps adding order.
+//! The order is cached in render_index and only sorted again when sprites are
+//! added or a sprite's z changed
 
 use crate::{
     asset::AssetManager,
     render::sprite::Sprite,
     render::buffer::Buffer,
+    render::camera::Camera,
     util::PointU16,
 };
 use crate::render::sprite::Widget;
@@ -28,11 +32,21 @@ pub struct Sprites {
     pub sprites: Vec<Sprite>,
     pub tag_index: HashMap<String, usize>,
 
-    // sprite index, render weight...
-    pub render_index: Vec<(usize, i32)>,
+    // sprite index, z, render weight...
+    pub render_index: Vec<(usize, i32, i32)>,
 
     // render weight as layers in panel...
     pub render_weight: i32,
+
+    // z-index as layers in panel, bigger z is composited later (upper level)...
+    pub z_index: i32,
+
+    // layer drawn through the panel camera, see Panel::set_layer_camera
+    pub use_camera: bool,
+
+    // camera top left in world cells, set by Panel::draw_layers for pixel layers,
+    // graphics mode shifts the sprites by it in pixels, fraction included
+    pub camera_pos: Option<(f32, f32)>,
 }
 
 /// 实现Index，IndexMut协议
@@ -60,6 +74,9 @@ impl Sprites {
             tag_index: HashMap::new(),
             render_index: vec![],
             render_weight: 1, 
+            z_index: 0,
+            use_camera: false,
+            camera_pos: None,
         }
     }
 
@@ -72,6 +89,9 @@ impl Sprites {
             tag_index: HashMap::new(),
             render_index: vec![],
             render_weight: 1, 
+            z_index: 0,
+            use_camera: false,
+            camera_pos: None,
         }
     }
 
@@ -128,19 +148,33 @@ impl Sprites {
         self.render_index.clear();
     }
 
+    pub fn set_z_by_tag(&mut self, name: &str, z: i32) {
+        let idx = self.tag_index.get(name).unwrap();
+        self.sprites[*idx].set_z(z);
+    }
+
     pub fn set_hidden_by_tag(&mut self, name: &str, hidden: bool) {
         let idx = self.tag_index.get(name).unwrap();
         self.sprites[*idx].set_hidden(hidden);
     }
 
+    // z of a sprite may be changed directly by Sprite::set_z,
+    // so compare the cached z without sorting
+    fn render_index_stale(&self) -> bool {
+        self.render_index.len() != self.sprites.len()
+            || self.render_index.iter().any(|d| self.sprites[d.0].z != d.1)
+    }
+
     pub fn update_render_index(&mut self) {
-        // renders in an order by render_weight
-        // bigger render_weight is rendered later（upper level)
-        if self.render_index.is_empty() {
+        // renders in an order by z, then render_weight
+        // bigger z is rendered later（upper level)
+        if self.render_index_stale() {
+            self.render_index.clear();
             for (i, s) in self.sprites.iter().enumerate() {
-                self.render_index.push((i, s.render_weight));
+                self.render_index.push((i, s.z, s.render_weight));
             }
-            self.render_index.sort_by_key(|d| Reverse(d.1));
+            // stable sort, keeps adding order
+            self.render_index.sort_by_key(|d| (d.1, Reverse(d.2)));
             // info!("render_index...{:?}", self.render_index);
         }
     }
@@ -151,4 +185,53 @@ impl Sprites {
             self.sprites[v.0].render(self.is_pixel, am, buffer);
         }
     }
+
+    /// like render_all_to_buffer, sprites positions are world cells of cam
+    pub fn render_all_to_buffer_camera(
+        &mut self,
+        am: &mut AssetManager,
+        buffer: &mut Buffer,
+        cam: &Camera,
+    ) {
+        self.update_render_index();
+        for v in &self.render_index {
+            self.sprites[v.0].render_camera(am, buffer, cam);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Rect;
+
+    #[test]
+    fn higher_z_sprite_draws_over() {
+        let mut am = AssetManager::new();
+        let mut sps = Sprites::new("test");
+        let mut hud = Sprite::new(0, 0, 3, 1);
+        hud.set_default_str("HUD");
+        hud.set_z(10);
+        let mut monster = Sprite::new(1, 0, 2, 1);
+        monster.set_default_str("MM");
+        let mut tower = Sprite::new(2, 0, 2, 1);
+        tower.set_default_str("TT");
+        tower.set_z(5);
+        // hud added first, order comes from z
+        sps.add_by_tag(hud, "hud");
+        sps.add_by_tag(monster, "monster");
+        sps.add_by_tag(tower, "tower");
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 4, 1));
+        sps.render_all_to_buffer(&mut am, &mut buf);
+        let row: String = (0..4).map(|x| buf.get(x, 0).symbol.clone()).collect();
+        assert_eq!(row, "HUDT");
+
+        // z changed through the sprite directly, monster now on top
+        sps.get_by_tag("monster").set_z(20);
+        buf.reset();
+        sps.render_all_to_buffer(&mut am, &mut buf);
+        let row: String = (0..4).map(|x| buf.get(x, 0).symbol.clone()).collect();
+        assert_eq!(row, "HMMT");
+    }
 }
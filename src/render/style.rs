@@ -4,10 +4,22 @@
 //! Defines styles such as color, bold or italics.
 //! Only foreground color is supported in Graph mode, as background color is used for texture.
 
-#[cfg(not(any(feature = "sdl", target_os = "android", target_os = "ios", target_arch = "wasm32")))]
+#[cfg(not(any(
+    feature = "base",
+    feature = "sdl",
+    target_os = "android",
+    target_os = "ios",
+    target_arch = "wasm32"
+)))]
 use crate::render::image::io_error;
 use bitflags::bitflags;
-#[cfg(not(any(feature = "sdl", target_os = "android", target_os = "ios", target_arch = "wasm32")))]
+#[cfg(not(any(
+    feature = "base",
+    feature = "sdl",
+    target_os = "android",
+    target_os = "ios",
+    target_arch = "wasm32"
+)))]
 use crossterm::{
     queue,
     style::{Attribute as CAttribute, SetAttribute},
@@ -20,6 +32,12 @@ pub use color::*;
 mod color_pro;
 pub use color_pro::*;
 
+mod downgrade;
+pub use downgrade::*;
+
+mod blend;
+pub use blend::*;
+
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
     pub struct Modifier: u16 {
@@ -101,13 +119,25 @@ impl Style {
 }
 
 #[derive(Debug)]
-#[cfg(not(any(feature = "sdl", target_os = "android", target_os = "ios", target_arch = "wasm32")))]
+#[cfg(not(any(
+    feature = "base",
+    feature = "sdl",
+    target_os = "android",
+    target_os = "ios",
+    target_arch = "wasm32"
+)))]
 pub struct ModifierDiff {
     pub from: Modifier,
     pub to: Modifier,
 }
 
-#[cfg(not(any(feature = "sdl", target_os = "android", target_os = "ios", target_arch = "wasm32")))]
+#[cfg(not(any(
+    feature = "base",
+    feature = "sdl",
+    target_os = "android",
+    target_os = "ios",
+    target_arch = "wasm32"
+)))]
 impl ModifierDiff {
     pub fn queue<W>(&self, mut w: W) -> std::io::Result<()>
     where
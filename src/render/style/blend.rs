@@ -0,0 +1,85 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! BlendMode tells how a sprite's colors combine with the colors under it
+//! when merged into a buffer, see Buffer::merge_blend and Sprite::set_blend
+//!
+//! Add brightens, for glows and fire. Multiply darkens, for shadows.
+//! Screen brightens softer than Add and never gets darker.
+//!
+//! Channels are mixed as rgb, so named and indexed colors become Rgba.
+//! Terminal mode shows them as true colors, Add clamps the summed channels.
+//! Reset has no color to mix with, the other color is taken as is.
+//!
+//! Pixel sprites in graphics mode are mixed by the gpu instead, each mode
+//! maps to a gl blend func, see gl::render_symbols::gl_blend_func.
+
+use crate::render::style::Color;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BlendMode {
+    /// src replaces dst, alpha-over in graphics mode
+    #[default]
+    Normal,
+    Add,
+    Multiply,
+    Screen,
+}
+
+impl BlendMode {
+    /// src drawn over dst, keeps the alpha of src
+    pub fn blend(self, dst: Color, src: Color) -> Color {
+        if self == BlendMode::Normal || dst == Color::Reset || src == Color::Reset {
+            return src;
+        }
+        let d = dst.get_rgba();
+        let s = src.get_rgba();
+        let f = |a: u8, b: u8| -> u8 {
+            let (a, b) = (a as u16, b as u16);
+            match self {
+                BlendMode::Add => (a + b).min(255) as u8,
+                BlendMode::Multiply => (a * b / 255) as u8,
+                BlendMode::Screen => (255 - (255 - a) * (255 - b) / 255) as u8,
+                BlendMode::Normal => b as u8,
+            }
+        };
+        Color::Rgba(f(d.0, s.0), f(d.1, s.1), f(d.2, s.2), s.3)
+    }
+}
+
+#[cfg(all(test, not(feature = "base")))]
+mod tests {
+    use super::*;
+    use crate::{render::buffer::Buffer, util::Rect};
+
+    #[test]
+    fn add_brightens_multiply_darkens() {
+        let gray = Color::Rgba(128, 128, 128, 255);
+        let cell_with = |mode: BlendMode| {
+            let mut dst = Buffer::empty(Rect::new(0, 0, 1, 1));
+            dst.get_mut(0, 0).set_symbol("a").set_fg(gray).set_bg(gray);
+            let mut src = Buffer::empty(Rect::new(0, 0, 1, 1));
+            src.get_mut(0, 0).set_symbol("b").set_fg(gray).set_bg(gray);
+            dst.merge_blend(&src, 255, mode);
+            dst.get(0, 0).clone()
+        };
+        let add = cell_with(BlendMode::Add);
+        assert_eq!(add.symbol, "b");
+        assert_eq!(add.fg, Color::Rgba(255, 255, 255, 255));
+        assert_eq!(add.bg, Color::Rgba(255, 255, 255, 255));
+        assert_eq!(
+            cell_with(BlendMode::Multiply).bg,
+            Color::Rgba(64, 64, 64, 255)
+        );
+        assert_eq!(
+            cell_with(BlendMode::Screen).bg,
+            Color::Rgba(192, 192, 192, 255)
+        );
+        assert_eq!(cell_with(BlendMode::Normal).bg, gray);
+
+        // nothing under, src is kept
+        assert_eq!(BlendMode::Add.blend(Color::Reset, gray), gray);
+        assert_eq!(BlendMode::Multiply.blend(gray, Color::Reset), Color::Reset);
+    }
+}
@@ -4,7 +4,13 @@
 //! Defines styles color
 
 use crate::render::style::ColorPro;
-#[cfg(not(any(feature = "sdl", target_os = "android", target_os = "ios", target_arch = "wasm32")))]
+#[cfg(not(any(
+    feature = "base",
+    feature = "sdl",
+    target_os = "android",
+    target_os = "ios",
+    target_arch = "wasm32"
+)))]
 use crossterm::style::Color as CColor;
 use serde::{Deserialize, Serialize};
 
@@ -31,28 +37,61 @@ pub enum Color {
     Indexed(u8),
 }
 
+/// the 16 standard ansi colors, in the order of their index
+/// in the 256 color palette (ANSI_COLOR_RGB)
+pub const ANSI_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
 impl Color {
+    /// color of index in the 256 color ansi palette,
+    /// 0~15 are the named standard colors
+    pub fn ansi256(index: u8) -> Color {
+        if index < 16 {
+            ANSI_COLORS[index as usize]
+        } else {
+            Color::Indexed(index)
+        }
+    }
+
+    /// index in the 256 color ansi palette, rgba colors must match exactly,
+    /// returns None for Reset and rgba colors not in the palette
+    pub fn index_of(color: Color) -> Option<u8> {
+        match color {
+            Color::Reset => None,
+            Color::Indexed(i) => Some(i),
+            Color::Rgba(r, g, b, _a) => ANSI_COLOR_RGB
+                .iter()
+                .position(|c| *c == [r, g, b])
+                .map(|i| i as u8),
+            _ => ANSI_COLORS
+                .iter()
+                .position(|c| *c == color)
+                .map(|i| i as u8),
+        }
+    }
+
     pub fn get_rgba(self) -> (u8, u8, u8, u8) {
         let cidx: usize = match self {
+            // Reset draws as DarkGray in graphics mode
             Color::Reset => 8,
-            Color::Black => 0,
-            Color::Red => 1,
-            Color::Green => 2,
-            Color::Yellow => 3,
-            Color::Blue => 4,
-            Color::Magenta => 5,
-            Color::Cyan => 6,
-            Color::Gray => 7,
-            Color::DarkGray => 8,
-            Color::LightRed => 9,
-            Color::LightGreen => 10,
-            Color::LightYellow => 11,
-            Color::LightBlue => 12,
-            Color::LightMagenta => 13,
-            Color::LightCyan => 14,
-            Color::White => 15,
-            Color::Indexed(i) => i as usize,
             Color::Rgba(r, g, b, a) => return (r, g, b, a),
+            _ => Color::index_of(self).unwrap() as usize,
         };
         (
             ANSI_COLOR_RGB[cidx][0],
@@ -63,7 +102,13 @@ impl Color {
     }
 }
 
-#[cfg(not(any(feature = "sdl", target_os = "android", target_os = "ios", target_arch = "wasm32")))]
+#[cfg(not(any(
+    feature = "base",
+    feature = "sdl",
+    target_os = "android",
+    target_os = "ios",
+    target_arch = "wasm32"
+)))]
 impl From<Color> for CColor {
     fn from(color: Color) -> Self {
         match color {
@@ -92,27 +137,7 @@ impl From<Color> for CColor {
 
 impl From<Color> for u8 {
     fn from(color: Color) -> Self {
-        match color {
-            Color::Reset => 0,
-            Color::Black => 0,
-            Color::Red => 1,
-            Color::Green => 2,
-            Color::Yellow => 3,
-            Color::Blue => 4,
-            Color::Magenta => 5,
-            Color::Cyan => 6,
-            Color::Gray => 7,
-            Color::DarkGray => 8,
-            Color::LightRed => 9,
-            Color::LightGreen => 10,
-            Color::LightBlue => 11,
-            Color::LightYellow => 12,
-            Color::LightMagenta => 13,
-            Color::LightCyan => 14,
-            Color::White => 15,
-            Color::Indexed(i) => i,
-            Color::Rgba(r, g, b, _a) => get_u8_rgb(r, g, b),
-        }
+        Color::index_of(color).unwrap_or(0)
     }
 }
 
@@ -123,16 +148,6 @@ impl From<ColorPro> for Color {
     }
 }
 
-fn get_u8_rgb(r: u8, g: u8, b: u8) -> u8 {
-    let ret = 0;
-    for (i, item) in ANSI_COLOR_RGB.iter().enumerate() {
-        if item[0] == r && item[1] == g && item[2] == b {
-            return i as u8;
-        }
-    }
-    ret
-}
-
 pub const ANSI_COLOR_RGB: [[u8; 3]; 256] = [
     [0, 0, 0],
     [128, 0, 0],
@@ -391,3 +406,24 @@ pub const ANSI_COLOR_RGB: [[u8; 3]; 256] = [
     [228, 228, 228],
     [238, 238, 238],
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ansi256_round_trip() {
+        let red = Color::ansi256(1);
+        assert_eq!(red, Color::Red);
+        assert_eq!(red.get_rgba(), (128, 0, 0, 255));
+        assert_eq!(Color::index_of(red), Some(1));
+        assert_eq!(Color::index_of(Color::Rgba(128, 0, 0, 255)), Some(1));
+        assert_eq!(Color::ansi256(196), Color::Indexed(196));
+        for i in 0..=255u8 {
+            assert_eq!(Color::index_of(Color::ansi256(i)), Some(i));
+            assert_eq!(u8::from(Color::ansi256(i)), i);
+        }
+        assert_eq!(Color::index_of(Color::Reset), None);
+        assert_eq!(Color::index_of(Color::Rgba(1, 2, 3, 255)), None);
+    }
+}
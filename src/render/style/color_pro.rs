@@ -80,6 +80,14 @@ pub use delta::*;
 mod gradient;
 pub use gradient::*;
 
+/// color blindness simulation and contrast ratio
+mod cvd;
+pub use cvd::*;
+
+/// nearest palette color by delta_e
+mod index;
+pub use index::*;
+
 // 0.3127 / 0.3290  (1.0 - 0.3127 - 0.3290) / 0.3290
 pub const WHITE: [f64; 3] = [0.9504559270516716, 1.0, 1.0890577507598784];
 pub const EPSILON_LSTAR: f64 = 216.0 / 24389.0;
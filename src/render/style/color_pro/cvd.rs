@@ -0,0 +1,147 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Color vision deficiency (color blindness) simulation and contrast check
+//!
+//! simulate_cvd uses the model of Machado, Oliveira and Fernandes 2009,
+//! which simulates cone (LMS) responses and is expressed as a
+//! linear rgb matrix for every deficiency, severity 1.0 is used here
+//!
+//! Refer:
+//!   https://www.inf.ufrgs.br/~oliveira/pubs_files/CVD_Simulation/CVD_Simulation.html
+//!   https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio
+
+use crate::render::style::color_pro::*;
+
+/// colors closer than this after simulation are hard to tell apart
+pub const CVD_CONFUSE_DELTA_E: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CvdKind {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+const PROTANOPIA: [[f64; 3]; 3] = [
+    [0.152286, 1.052583, -0.204868],
+    [0.114503, 0.786281, 0.099216],
+    [-0.003882, -0.048116, 1.051998],
+];
+
+const DEUTERANOPIA: [[f64; 3]; 3] = [
+    [0.367322, 0.860646, -0.227968],
+    [0.280085, 0.672501, 0.047413],
+    [-0.011820, 0.042940, 0.968881],
+];
+
+const TRITANOPIA: [[f64; 3]; 3] = [
+    [1.255528, -0.076749, -0.178779],
+    [-0.078411, 0.930809, 0.147602],
+    [0.004733, 0.691367, 0.303900],
+];
+
+/// how color looks to people with the given deficiency
+pub fn simulate_cvd(color: ColorPro, kind: CvdKind) -> ColorPro {
+    let m = match kind {
+        CvdKind::Protanopia => &PROTANOPIA,
+        CvdKind::Deuteranopia => &DEUTERANOPIA,
+        CvdKind::Tritanopia => &TRITANOPIA,
+    };
+    let c = color[LinearRGBA].unwrap();
+    let mut v = [0.0, 0.0, 0.0, c.v[3]];
+    for i in 0..3 {
+        v[i] = (m[i][0] * c.v[0] + m[i][1] * c.v[1] + m[i][2] * c.v[2]).clamp(0.0, 1.0);
+    }
+    ColorPro::from_space(LinearRGBA, ColorData { v })
+}
+
+/// WCAG contrast ratio, from 1.0 (same luminance) to 21.0 (black and white)
+pub fn contrast_ratio(a: ColorPro, b: ColorPro) -> f64 {
+    let la = a.luminance();
+    let lb = b.luminance();
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+/// pairs of indices in colors which are confusable for the given deficiency,
+/// i.e. ciede2000 distance after simulation is under CVD_CONFUSE_DELTA_E
+pub fn pick_distinguishable(colors: &[ColorPro], kind: CvdKind) -> Vec<(usize, usize)> {
+    let labs: Vec<ColorData> = colors
+        .iter()
+        .map(|c| simulate_cvd(*c, kind)[LabA].unwrap())
+        .collect();
+    let mut ret = vec![];
+    for i in 0..labs.len() {
+        for j in i + 1..labs.len() {
+            if delta_e_ciede2000(labs[i], labs[j]) < CVD_CONFUSE_DELTA_E {
+                ret.push((i, j));
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linear(c: ColorPro) -> [f64; 3] {
+        let v = c[LinearRGBA].unwrap().v;
+        [v[0], v[1], v[2]]
+    }
+
+    fn assert_close(a: [f64; 3], b: [f64; 3]) {
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < 1e-4, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn cvd_reference_values() {
+        let red = ColorPro::from_space_f64(LinearRGBA, 1.0, 0.0, 0.0, 1.0);
+        let green = ColorPro::from_space_f64(LinearRGBA, 0.0, 1.0, 0.0, 1.0);
+        let blue = ColorPro::from_space_f64(LinearRGBA, 0.0, 0.0, 1.0, 1.0);
+        // primaries map to the clamped matrix columns of Machado 2009
+        assert_close(
+            linear(simulate_cvd(red, CvdKind::Protanopia)),
+            [0.152286, 0.114503, 0.0],
+        );
+        assert_close(
+            linear(simulate_cvd(green, CvdKind::Deuteranopia)),
+            [0.860646, 0.672501, 0.042940],
+        );
+        assert_close(
+            linear(simulate_cvd(blue, CvdKind::Tritanopia)),
+            [0.0, 0.147602, 0.303900],
+        );
+        // grays are seen the same
+        let gray = ColorPro::from_space_f64(LinearRGBA, 0.5, 0.5, 0.5, 1.0);
+        for k in [
+            CvdKind::Protanopia,
+            CvdKind::Deuteranopia,
+            CvdKind::Tritanopia,
+        ] {
+            assert_close(linear(simulate_cvd(gray, k)), [0.5, 0.5, 0.5]);
+        }
+    }
+
+    #[test]
+    fn contrast_black_white() {
+        let black = ColorPro::from_space_f64(SRGBA, 0.0, 0.0, 0.0, 1.0);
+        let white = ColorPro::from_space_f64(SRGBA, 1.0, 1.0, 1.0, 1.0);
+        assert!((contrast_ratio(black, white) - 21.0).abs() < 1e-3);
+        assert!((contrast_ratio(white, black) - 21.0).abs() < 1e-3);
+        assert!((contrast_ratio(white, white) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn red_green_confusable_for_deutan() {
+        let colors = [
+            ColorPro::from_space_u8(SRGBA, 200, 60, 40, 255),
+            ColorPro::from_space_u8(SRGBA, 110, 110, 40, 255),
+            ColorPro::from_space_u8(SRGBA, 20, 40, 220, 255),
+        ];
+        assert!(pick_distinguishable(&colors, CvdKind::Deuteranopia).contains(&(0, 1)));
+        assert!(!pick_distinguishable(&colors, CvdKind::Deuteranopia).contains(&(0, 2)));
+    }
+}
@@ -0,0 +1,170 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Downgrades rgb colors for terminals without truecolor support
+//!
+//! TerminalColorCaps is detected from COLORTERM and TERM, rgb colors are
+//! mapped to the nearest color of the 256 or 16 color ansi palette by
+//! CIEDE2000. Mappings are cached by rgb, so drawing the same colors every
+//! frame does not compute delta e again.
+
+use crate::render::style::{laba_from_rgb, Color, ColorIndex, ColorPro, ANSI_COLOR_RGB};
+use lazy_static::lazy_static;
+use std::{collections::HashMap, ops::Range, sync::Mutex};
+
+/// cache is cleared when it grows to this size
+const DOWNGRADE_CACHE_SIZE: usize = 4096;
+
+lazy_static! {
+    static ref ANSI_INDEX: ColorIndex = ColorIndex::from_labs(
+        ANSI_COLOR_RGB
+            .iter()
+            .map(|c| laba_from_rgb(c[0], c[1], c[2]))
+            .collect()
+    );
+    static ref DOWNGRADE_CACHE: Mutex<HashMap<(u8, u8, u8, TerminalColorCaps), u8>> =
+        Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TerminalColorCaps {
+    #[default]
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl TerminalColorCaps {
+    pub fn detect() -> Self {
+        Self::from_env(
+            std::env::var("COLORTERM").ok().as_deref(),
+            std::env::var("TERM").ok().as_deref(),
+        )
+    }
+
+    /// COLORTERM=truecolor or 24bit means truecolor, TERM=*256color* means 256,
+    /// no TERM at all (e.g. windows console) keeps truecolor, else 16
+    pub fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if matches!(colorterm, Some("truecolor") | Some("24bit")) {
+            return TerminalColorCaps::TrueColor;
+        }
+        match term {
+            None => TerminalColorCaps::TrueColor,
+            Some(t) if t.contains("direct") || t.contains("truecolor") => {
+                TerminalColorCaps::TrueColor
+            }
+            Some(t) if t.contains("256color") => TerminalColorCaps::Ansi256,
+            Some(_) => TerminalColorCaps::Ansi16,
+        }
+    }
+
+    // palette indexes to search, the 16 base colors are left out
+    // in 256 mode because terminal themes often change them
+    fn palette(&self) -> Option<Range<usize>> {
+        match self {
+            TerminalColorCaps::TrueColor => None,
+            TerminalColorCaps::Ansi256 => Some(16..256),
+            TerminalColorCaps::Ansi16 => Some(0..16),
+        }
+    }
+}
+
+/// index of the nearest color in ANSI_COLOR_RGB[range] by CIEDE2000,
+/// not cached, the first one wins on a tie
+pub fn find_nearest_ansi(r: u8, g: u8, b: u8, range: Range<usize>) -> u8 {
+    ANSI_INDEX.nearest_in(laba_from_rgb(r, g, b), range).0 as u8
+}
+
+/// rgb as a color the terminal can show, cached
+pub fn downgrade_rgb(r: u8, g: u8, b: u8, caps: TerminalColorCaps) -> Color {
+    let range = match caps.palette() {
+        Some(range) => range,
+        None => return Color::Rgba(r, g, b, 255),
+    };
+    let mut cache = DOWNGRADE_CACHE.lock().unwrap();
+    let idx = match cache.get(&(r, g, b, caps)) {
+        Some(i) => *i,
+        None => {
+            let i = find_nearest_ansi(r, g, b, range);
+            if cache.len() >= DOWNGRADE_CACHE_SIZE {
+                cache.clear();
+            }
+            cache.insert((r, g, b, caps), i);
+            i
+        }
+    };
+    Color::ansi256(idx)
+}
+
+pub fn downgrade(color: ColorPro, caps: TerminalColorCaps) -> Color {
+    let (r, g, b, _a) = color.get_srgba_u8();
+    downgrade_rgb(r, g, b, caps)
+}
+
+impl Color {
+    /// rgba and 256 palette colors out of caps are mapped to the
+    /// nearest color, named colors and Reset are kept
+    pub fn downgrade(self, caps: TerminalColorCaps) -> Color {
+        match self {
+            Color::Rgba(r, g, b, _a) => downgrade_rgb(r, g, b, caps),
+            Color::Indexed(i) if caps == TerminalColorCaps::Ansi16 => {
+                let c = ANSI_COLOR_RGB[i as usize];
+                downgrade_rgb(c[0], c[1], c[2], caps)
+            }
+            _ => self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::style::ColorSpace::SRGBA;
+
+    #[test]
+    fn detect_caps_from_env() {
+        use TerminalColorCaps::*;
+        assert_eq!(
+            TerminalColorCaps::from_env(Some("truecolor"), Some("xterm")),
+            TrueColor
+        );
+        assert_eq!(
+            TerminalColorCaps::from_env(None, Some("xterm-256color")),
+            Ansi256
+        );
+        assert_eq!(
+            TerminalColorCaps::from_env(None, Some("xterm-direct")),
+            TrueColor
+        );
+        assert_eq!(TerminalColorCaps::from_env(None, Some("linux")), Ansi16);
+        assert_eq!(TerminalColorCaps::from_env(None, None), TrueColor);
+    }
+
+    #[test]
+    fn downgrade_spot_check_and_cache() {
+        use TerminalColorCaps::*;
+        let red = ColorPro::from_space_u8(SRGBA, 255, 0, 0, 255);
+        assert_eq!(downgrade(red, TrueColor), Color::Rgba(255, 0, 0, 255));
+        assert_eq!(downgrade(red, Ansi256), Color::Indexed(196));
+        assert_eq!(downgrade(red, Ansi16), Color::LightRed);
+
+        assert_eq!(downgrade_rgb(128, 128, 128, Ansi256), Color::Indexed(244));
+        assert_eq!(downgrade_rgb(10, 10, 10, Ansi16), Color::Black);
+        assert_eq!(downgrade_rgb(200, 200, 200, Ansi16), Color::Gray);
+        assert_eq!(Color::Indexed(196).downgrade(Ansi256), Color::Indexed(196));
+        assert_eq!(Color::Indexed(196).downgrade(Ansi16), Color::LightRed);
+        assert_eq!(Color::Cyan.downgrade(Ansi16), Color::Cyan);
+
+        for c in [(12u8, 34u8, 56u8), (250, 128, 3), (90, 200, 180), (1, 1, 1)] {
+            for (caps, range) in [(Ansi256, 16..256), (Ansi16, 0..16)] {
+                let cold = Color::ansi256(find_nearest_ansi(c.0, c.1, c.2, range));
+                assert_eq!(downgrade_rgb(c.0, c.1, c.2, caps), cold);
+                assert!(DOWNGRADE_CACHE
+                    .lock()
+                    .unwrap()
+                    .contains_key(&(c.0, c.1, c.2, caps)));
+                assert_eq!(downgrade_rgb(c.0, c.1, c.2, caps), cold);
+            }
+        }
+    }
+}
@@ -4,22 +4,35 @@
 //!
 //! A symbol comprises a point vector with width * height elements
 //!
+//! Background detection and block binarization are shared here by
+//! pixel_petii (render::image::petii) and pixel_symbol
 //!
 
-use crate::render::style::ANSI_COLOR_RGB;
+use crate::render::style::find_nearest_ansi;
 use deltae::*;
-use image::{DynamicImage, GenericImageView};
+use image::{DynamicImage, GenericImageView, Pixel};
 use lab::Lab;
 use std::collections::HashMap;
 
 pub struct RGB {
-    r: u8,
-    g: u8,
-    b: u8,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
 }
 
-// find big image background colors...
-pub fn find_background_color(img: &DynamicImage, w: u32, h: u32) -> u32 {
+/// Background color of an image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackgroundColor {
+    /// gray level of background, as in the luma8 image
+    pub gray: u8,
+    /// rgba packed as 0xRRGGBBAA
+    pub rgba: u32,
+    /// ratio of pixels with the background color, 0.0 ~ 1.0
+    pub confidence: f32,
+}
+
+// find big image background colors, the most used rgba color in the w * h area
+pub fn find_background_color(img: &DynamicImage, w: u32, h: u32) -> BackgroundColor {
     // color_u32 : (first_x, first_y, count)
     let mut cc: HashMap<u32, (u32, u32, u32)> = HashMap::new();
     for i in 0..h {
@@ -29,33 +42,132 @@ pub fn find_background_color(img: &DynamicImage, w: u32, h: u32) -> u32 {
                 + ((p[1] as u32) << 16)
                 + ((p[2] as u32) << 8)
                 + (p[3] as u32);
-            (*cc.entry(k).or_insert((j, i, 0))).2 += 1;
+            cc.entry(k).or_insert((j, i, 0)).2 += 1;
         }
     }
     let mut cv: Vec<_> = cc.iter().collect();
-    cv.sort_by(|b, a| (&a.1 .2).cmp(&b.1 .2));
-    *cv[0].0
+    // sort by count desc, then by color to be deterministic
+    cv.sort_by(|b, a| a.1 .2.cmp(&b.1 .2).then(b.0.cmp(a.0)));
+    let (bx, by, count) = *cv[0].1;
+    BackgroundColor {
+        gray: img.get_pixel(bx, by).to_luma().0[0],
+        rgba: *cv[0].0,
+        confidence: count as f32 / (w * h) as f32,
+    }
 }
 
-pub fn find_best_color(color: RGB) -> usize {
-    let mut min_mse = f32::MAX;
-    let mut best_match = 0;
-
-    for (i, pcolor) in ANSI_COLOR_RGB.iter().enumerate() {
-        let pcrgb = RGB {
-            r: pcolor[0],
-            g: pcolor[1],
-            b: pcolor[2],
-        };
-        let mse = color_distance_rgb(&pcrgb, &color);
+/// How binarize_block splits a gray block into 0 and 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinarizationMode {
+    /// pixels equal to the background gray level are 0, others 1,
+    /// blocks without background map their darkest gray to 0 (used by petii)
+    Background(u8),
+    /// Otsu threshold on the block histogram, pixels above threshold are 1
+    Otsu,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BinarizationConfig {
+    pub mode: BinarizationMode,
+    /// blocks with a contrast ratio (max+0.05)/(min+0.05) of gray/255 below this
+    /// are taken as uniform and set to all 1, Otsu mode only
+    pub min_contrast: f32,
+}
+
+impl Default for BinarizationConfig {
+    fn default() -> Self {
+        Self {
+            mode: BinarizationMode::Otsu,
+            min_contrast: 1.2,
+        }
+    }
+}
+
+impl BinarizationConfig {
+    pub fn background(gray: u8) -> Self {
+        Self {
+            mode: BinarizationMode::Background(gray),
+            ..Default::default()
+        }
+    }
+}
 
-        if mse < min_mse {
-            min_mse = mse;
-            best_match = i;
+/// binarize a gray block into 0 / 1 values
+pub fn binarize_block(img: &[Vec<u8>], cfg: &BinarizationConfig) -> Vec<Vec<u8>> {
+    let pixels = img.iter().flat_map(|row| row.iter().copied());
+    let min = pixels.clone().min().unwrap_or(0);
+    let max = pixels.clone().max().unwrap_or(0);
+
+    let threshold = match cfg.mode {
+        BinarizationMode::Background(back) => {
+            if pixels.clone().any(|p| p == back) {
+                // if block include back colors...
+                return img
+                    .iter()
+                    .map(|row| row.iter().map(|&p| (p != back) as u8).collect())
+                    .collect();
+            }
+            // only 1 color is all 1, else min to 0 and others to 1
+            if min == max {
+                return uniform_block(img, 1);
+            }
+            min
+        }
+        BinarizationMode::Otsu => {
+            let l = |g: u8| g as f32 / 255.0 + 0.05;
+            if min == max || l(max) / l(min) < cfg.min_contrast {
+                return uniform_block(img, 1);
+            }
+            otsu_threshold(pixels)
+        }
+    };
+    img.iter()
+        .map(|row| row.iter().map(|&p| (p > threshold) as u8).collect())
+        .collect()
+}
+
+fn uniform_block(img: &[Vec<u8>], v: u8) -> Vec<Vec<u8>> {
+    img.iter().map(|row| vec![v; row.len()]).collect()
+}
+
+// threshold maximizing between-class variance
+fn otsu_threshold(pixels: impl Iterator<Item = u8>) -> u8 {
+    let mut hist = [0u32; 256];
+    let mut total = 0u32;
+    for p in pixels {
+        hist[p as usize] += 1;
+        total += 1;
+    }
+    let sum: f64 = hist
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| i as f64 * c as f64)
+        .sum();
+    let mut sum_b = 0.0f64;
+    let mut w_b = 0u32;
+    let mut best = (0.0f64, 0u8);
+    for (t, &c) in hist.iter().enumerate() {
+        w_b += c;
+        if w_b == 0 {
+            continue;
+        }
+        let w_f = total - w_b;
+        if w_f == 0 {
+            break;
+        }
+        sum_b += t as f64 * c as f64;
+        let m_b = sum_b / w_b as f64;
+        let m_f = (sum - sum_b) / w_f as f64;
+        let var = w_b as f64 * w_f as f64 * (m_b - m_f) * (m_b - m_f);
+        if var > best.0 {
+            best = (var, t as u8);
         }
     }
+    best.1
+}
 
-    best_match
+pub fn find_best_color(color: RGB) -> usize {
+    find_nearest_ansi(color.r, color.g, color.b, 0..256) as usize
 }
 
 pub fn find_best_color_u32(c: u32) -> usize {
@@ -80,7 +192,7 @@ pub fn color_distance_rgb(e1: &RGB, e2: &RGB) -> f32 {
         a: l2.a,
         b: l2.b,
     };
-    *DeltaE::new(&lab1, &lab2, DE2000).value()
+    *DeltaE::new(lab1, lab2, DE2000).value()
 }
 
 pub fn luminance(e1: u32) -> f32 {
@@ -111,10 +223,9 @@ pub fn color_distance(e1: u32, e2: u32) -> f32 {
         a: l2.a,
         b: l2.b,
     };
-    *DeltaE::new(&lab1, &lab2, DE2000).value()
+    *DeltaE::new(lab1, lab2, DE2000).value()
 }
 
-
 #[repr(C)]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Symbol {
@@ -158,15 +269,12 @@ impl Symbol {
 
     pub fn make_binary(&mut self, back_rgb: u32) {
         let mut cc: HashMap<u32, (u32, u32)> = HashMap::new();
-        let mut cm: Vec<u32> = vec![];
-        self.binary_data = vec![vec![0u8; self.width as usize]; self.height as usize];
         for i in 0..self.height {
             for j in 0..self.width {
                 let pixel_x = j as u32;
                 let pixel_y = i as u32;
                 let k = self.data[pixel_y as usize][pixel_x as usize];
                 cc.entry(k).or_insert((pixel_x, pixel_y));
-                cm.push(k);
             }
         }
         let mut cv: Vec<_> = cc.iter().collect();
@@ -180,7 +288,7 @@ impl Symbol {
                 // fix simliar color to back
                 if cd < 1.0 {
                     // println!("cd={} c1={} c2={}", cd, *c.0, back_rgb);
-                    (*c).0 = &back_rgb;
+                    c.0 = &back_rgb;
                     include_back = true;
                 }
             }
@@ -234,12 +342,12 @@ impl Symbol {
                 // println!("ERROR2!!! clen={} cv={:?}", clen, cv);
                 let mut base = *cv[0].0;
                 ccv.push(cv[0]);
-                for i in 1..clen {
-                    let cd = color_distance(*cv[i].0, base);
+                for c in &cv[1..] {
+                    let cd = color_distance(*c.0, base);
                     if cd > 1.0 {
-                        ccv.push(cv[i]);
+                        ccv.push(*c);
                     }
-                    base = *cv[i].0;
+                    base = *c.0;
                 }
                 let l1 = luminance(*ccv[0].0);
                 let l2 = luminance(*ccv[1].0);
@@ -252,18 +360,24 @@ impl Symbol {
             }
         }
 
-        for i in 0..self.height as usize {
-            for j in 0..self.width as usize {
-                let color = cm[i * self.width as usize + j];
-                let cd0 = color_distance(color, ret.unwrap().0);
-                let cd1 = color_distance(color, ret.unwrap().1);
-                if cd0 <= cd1 {
-                    self.binary_data[i][j] = 0;
-                } else {
-                    self.binary_data[i][j] = 1;
-                }
-            }
-        }
+        let (back, fore) = ret.unwrap();
+        self.binary_data = self
+            .data
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&color| {
+                        let cd0 = color_distance(color, back);
+                        let cd1 = color_distance(color, fore);
+                        if cd0 <= cd1 {
+                            0
+                        } else {
+                            1
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
 
         match ret {
             Some(r) => {
@@ -277,3 +391,120 @@ impl Symbol {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // left half dark, right half light
+    fn half_block(dark: u8, light: u8) -> Vec<Vec<u8>> {
+        (0..8)
+            .map(|_| (0..8).map(|x| if x < 4 { dark } else { light }).collect())
+            .collect()
+    }
+
+    fn half_mask() -> Vec<Vec<u8>> {
+        half_block(0, 1)
+    }
+
+    #[test]
+    fn binarize_half_block() {
+        let b = half_block(10, 200);
+        assert_eq!(
+            binarize_block(&b, &BinarizationConfig::background(10)),
+            half_mask()
+        );
+        assert_eq!(
+            binarize_block(&b, &BinarizationConfig::default()),
+            half_mask()
+        );
+        // background not in block, darkest gray goes to 0
+        assert_eq!(
+            binarize_block(&b, &BinarizationConfig::background(0)),
+            half_mask()
+        );
+    }
+
+    #[test]
+    fn binarize_uniform_block() {
+        let b = vec![vec![100u8; 8]; 8];
+        let ones = vec![vec![1u8; 8]; 8];
+        assert_eq!(binarize_block(&b, &BinarizationConfig::background(0)), ones);
+        assert_eq!(binarize_block(&b, &BinarizationConfig::default()), ones);
+        // background block is all 0
+        assert_eq!(
+            binarize_block(&b, &BinarizationConfig::background(100)),
+            vec![vec![0u8; 8]; 8]
+        );
+    }
+
+    #[test]
+    fn binarize_noisy_block() {
+        let mut b = half_block(10, 200);
+        for (y, row) in b.iter_mut().enumerate() {
+            for (x, p) in row.iter_mut().enumerate() {
+                *p += ((x * 3 + y * 5) % 7) as u8;
+            }
+        }
+        // otsu still splits the two halves
+        assert_eq!(
+            binarize_block(&b, &BinarizationConfig::default()),
+            half_mask()
+        );
+        // exact background match sees the dark noise as foreground
+        let bg = binarize_block(&b, &BinarizationConfig::background(10));
+        assert_ne!(bg, half_mask());
+        assert!(bg.iter().all(|row| row[4..].iter().all(|&p| p == 1)));
+    }
+
+    #[test]
+    fn background_color_confidence() {
+        let mut img = image::RgbaImage::from_pixel(4, 4, image::Rgba([0, 0, 255, 255]));
+        img.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+        let bc = find_background_color(&DynamicImage::ImageRgba8(img), 4, 4);
+        assert_eq!(bc.rgba, 0x0000ffff);
+        assert_eq!(bc.confidence, 15.0 / 16.0);
+    }
+
+    #[test]
+    fn symbol_binary_splits_by_color_distance() {
+        // dark left half on a white background, a mid gray pixel in the dark half
+        let mut img = image::RgbaImage::from_pixel(8, 8, image::Rgba([255, 255, 255, 255]));
+        for y in 0..8 {
+            for x in 0..4 {
+                img.put_pixel(x, y, image::Rgba([20, 20, 20, 255]));
+            }
+        }
+        img.put_pixel(1, 1, image::Rgba([90, 90, 90, 255]));
+        let mut sym = Symbol::new(8, 8, true, &DynamicImage::ImageRgba8(img));
+        sym.make_binary(0xffffffff);
+        for bits in &sym.binary_data {
+            assert_eq!(bits[..4], [1; 4]);
+            assert_eq!(bits[4..], [0; 4]);
+        }
+    }
+
+    #[test]
+    fn symbol_binary_iso_luminant_colors() {
+        // red left half, green right half, both with luma 150
+        let red = image::Rgba([255, 126, 0, 255]);
+        let green = image::Rgba([0, 240, 80, 255]);
+        let mut img = image::RgbaImage::from_pixel(8, 8, green);
+        for y in 0..8 {
+            for x in 0..4 {
+                img.put_pixel(x, y, red);
+            }
+        }
+        let mut sym = Symbol::new(8, 8, true, &DynamicImage::ImageRgba8(img));
+        let l0 = luminance(sym.data[0][0]);
+        let l1 = luminance(sym.data[0][7]);
+        assert!((l0 - l1).abs() < 2.0);
+        sym.make_binary(0);
+        let left = sym.binary_data[0][0];
+        assert_ne!(left, sym.binary_data[0][7]);
+        for bits in &sym.binary_data {
+            assert_eq!(bits[..4], [left; 4]);
+            assert_eq!(bits[4..], [1 - left; 4]);
+        }
+    }
+}
@@ -4,6 +4,11 @@
 //! Utils of random rect PointU16...
 //! and a simple object pool: objpool.rs
 //! some primitive algorithm: shape.rs
+//! grid map with flood fill and line of sight: grid.rs
+//! uniform grid spatial hash for broad phase collision: spatial.rs
+//! easing curves and value tweens: tween.rs
+//! 4-way direction with key mapping: direction.rs
+//! packed bit board for occupancy and state keys: bitgrid.rs
 
 use serde::{Deserialize, Serialize};
 use std::{
@@ -18,10 +23,22 @@ use keyframe_derive::CanTween;
 
 pub mod objpool;
 pub mod shape;
+pub mod grid;
+pub use grid::GridMap;
+pub mod spatial;
+pub use spatial::SpatialHash;
 mod particle;
 pub use particle::*;
 mod rand;
 pub use rand::*;
+mod tween;
+pub use tween::*;
+mod spring;
+pub use spring::*;
+mod direction;
+pub use direction::*;
+mod bitgrid;
+pub use bitgrid::*;
 
 /// smart get project path function
 pub fn get_project_path() -> String {
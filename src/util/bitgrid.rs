@@ -0,0 +1,189 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! BitGrid packs a width x height board of bools into u64 words,
+//! for occupancy checks and as a cheap key of visited states in solvers
+//!
+//! bits past width * height are always 0, so equal boards are equal
+//! words and the derived Hash only hashes a few u64
+//!
+//! # Example
+//! ```
+//! use rust_pixel::util::BitGrid;
+//! let mut a = BitGrid::new(10, 10);
+//! a.set(3, 4, true);
+//! let b = a.not();
+//! assert_eq!(a.and(&b).count_ones(), 0);
+//! assert_eq!(a.or(&b).count_ones(), 100);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BitGrid {
+    pub width: u16,
+    pub height: u16,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn new(width: u16, height: u16) -> Self {
+        let n = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            words: vec![0; n.div_ceil(64)],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(y as usize * self.width as usize + x as usize)
+    }
+
+    /// false if out of the grid
+    pub fn get(&self, x: u16, y: u16) -> bool {
+        match self.index(x, y) {
+            Some(i) => self.words[i / 64] >> (i % 64) & 1 == 1,
+            None => false,
+        }
+    }
+
+    /// ignored if out of the grid
+    pub fn set(&mut self, x: u16, y: u16, v: bool) {
+        if let Some(i) = self.index(x, y) {
+            if v {
+                self.words[i / 64] |= 1 << (i % 64);
+            } else {
+                self.words[i / 64] &= !(1 << (i % 64));
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    pub fn count_ones(&self) -> u32 {
+        self.words.iter().map(|w| w.count_ones()).sum()
+    }
+
+    /// (x, y) of every set bit, row by row
+    pub fn iter_ones(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        let w = self.width as usize;
+        (0..self.len())
+            .filter(move |i| self.words[i / 64] >> (i % 64) & 1 == 1)
+            .map(move |i| ((i % w) as u16, (i / w) as u16))
+    }
+
+    fn zip(&self, other: &BitGrid, f: impl Fn(u64, u64) -> u64) -> BitGrid {
+        assert!(
+            self.width == other.width && self.height == other.height,
+            "BitGrid size mismatch"
+        );
+        BitGrid {
+            width: self.width,
+            height: self.height,
+            words: self
+                .words
+                .iter()
+                .zip(other.words.iter())
+                .map(|(a, b)| f(*a, *b))
+                .collect(),
+        }
+    }
+
+    /// panics if the sizes differ, same for or and xor
+    pub fn and(&self, other: &BitGrid) -> BitGrid {
+        self.zip(other, |a, b| a & b)
+    }
+
+    pub fn or(&self, other: &BitGrid) -> BitGrid {
+        self.zip(other, |a, b| a | b)
+    }
+
+    pub fn xor(&self, other: &BitGrid) -> BitGrid {
+        self.zip(other, |a, b| a ^ b)
+    }
+
+    pub fn not(&self) -> BitGrid {
+        let mut g = self.zip(self, |a, _| !a);
+        // keep the bits past the grid 0
+        let rem = self.len() % 64;
+        if rem != 0 {
+            if let Some(last) = g.words.last_mut() {
+                *last &= (1u64 << rem) - 1;
+            }
+        }
+        g
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of(g: &BitGrid) -> u64 {
+        let mut h = DefaultHasher::new();
+        g.hash(&mut h);
+        h.finish()
+    }
+
+    #[test]
+    fn set_get_and_ops() {
+        let mut g = BitGrid::new(9, 8);
+        let pts = [(0, 0), (8, 0), (3, 5), (8, 7), (7, 7)];
+        for (x, y) in pts {
+            g.set(x, y, true);
+        }
+        g.set(9, 0, true);
+        g.set(0, 8, true);
+        assert_eq!(g.count_ones(), 5);
+        for y in 0..8 {
+            for x in 0..9 {
+                assert_eq!(g.get(x, y), pts.contains(&(x, y)), "{} {}", x, y);
+            }
+        }
+        assert_eq!(
+            g.iter_ones().collect::<Vec<_>>(),
+            [(0, 0), (8, 0), (3, 5), (7, 7), (8, 7)]
+        );
+        g.set(3, 5, false);
+        assert!(!g.get(3, 5));
+
+        let n = g.not();
+        assert_eq!(n.count_ones(), 72 - 4);
+        assert_eq!(g.and(&n).count_ones(), 0);
+        assert_eq!(g.or(&n).count_ones(), 72);
+        assert_eq!(g.xor(&g).count_ones(), 0);
+        assert_eq!(n.not(), g);
+    }
+
+    #[test]
+    fn equal_grids_hash_equal() {
+        let mut a = BitGrid::new(20, 20);
+        let mut b = BitGrid::new(20, 20);
+        a.set(1, 2, true);
+        a.set(19, 19, true);
+        b.set(19, 19, true);
+        b.set(5, 5, true);
+        b.set(5, 5, false);
+        b.set(1, 2, true);
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+        // not() must not leave garbage bits past the grid
+        assert_eq!(hash_of(&a.not().not()), hash_of(&b));
+    }
+}
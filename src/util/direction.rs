@@ -0,0 +1,117 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Direction4 is the 4-way direction shared by games and input handling
+//!
+//! screen coordinates are used everywhere, y grows downward,
+//! so Up is (0, -1), same order as GridMap::neighbors4
+//!
+//! # Example
+//! ```
+//! use rust_pixel::{event::KeyCode, util::Direction4};
+//! let d = Direction4::from_key(KeyCode::Left).unwrap();
+//! assert_eq!(d.delta(), (-1, 0));
+//! assert_eq!(d.rotate_cw(), Direction4::Up);
+//! ```
+
+use crate::event::KeyCode;
+use serde::{Deserialize, Serialize};
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Direction4 {
+    #[default]
+    Up = 0,
+    Right = 1,
+    Down = 2,
+    Left = 3,
+}
+
+impl Direction4 {
+    /// clockwise from Up
+    pub const ALL: [Direction4; 4] = [
+        Direction4::Up,
+        Direction4::Right,
+        Direction4::Down,
+        Direction4::Left,
+    ];
+
+    /// None if v > 3, for values coming from ffi or saved data
+    pub fn from_u8(v: u8) -> Option<Self> {
+        Self::ALL.get(v as usize).copied()
+    }
+
+    /// (dx, dy) of one step
+    pub fn delta(&self) -> (i8, i8) {
+        match self {
+            Direction4::Up => (0, -1),
+            Direction4::Right => (1, 0),
+            Direction4::Down => (0, 1),
+            Direction4::Left => (-1, 0),
+        }
+    }
+
+    pub fn opposite(&self) -> Self {
+        Self::ALL[(*self as usize + 2) % 4]
+    }
+
+    pub fn rotate_cw(&self) -> Self {
+        Self::ALL[(*self as usize + 1) % 4]
+    }
+
+    pub fn rotate_ccw(&self) -> Self {
+        Self::ALL[(*self as usize + 3) % 4]
+    }
+
+    /// arrow keys only, games add their own wasd or hjkl keys
+    pub fn from_key(key: KeyCode) -> Option<Self> {
+        match key {
+            KeyCode::Up => Some(Direction4::Up),
+            KeyCode::Right => Some(Direction4::Right),
+            KeyCode::Down => Some(Direction4::Down),
+            KeyCode::Left => Some(Direction4::Left),
+            _ => None,
+        }
+    }
+
+    pub fn arrow_char(&self) -> char {
+        match self {
+            Direction4::Up => '↑',
+            Direction4::Right => '→',
+            Direction4::Down => '↓',
+            Direction4::Left => '←',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotate_and_opposite_algebra() {
+        for d in Direction4::ALL {
+            let (dx, dy) = d.delta();
+            assert_eq!(d.opposite().delta(), (-dx, -dy));
+            assert_eq!(d.opposite().opposite(), d);
+            assert_eq!(d.rotate_cw().rotate_ccw(), d);
+            assert_eq!(d.rotate_cw().rotate_cw(), d.opposite());
+            assert_eq!(d.rotate_cw().rotate_cw().rotate_cw().rotate_cw(), d);
+            assert_eq!(Direction4::from_u8(d as u8), Some(d));
+        }
+        assert_eq!(Direction4::from_u8(4), None);
+    }
+
+    #[test]
+    fn arrow_keys_map() {
+        assert_eq!(Direction4::from_key(KeyCode::Up), Some(Direction4::Up));
+        assert_eq!(Direction4::from_key(KeyCode::Down), Some(Direction4::Down));
+        assert_eq!(Direction4::from_key(KeyCode::Left), Some(Direction4::Left));
+        assert_eq!(
+            Direction4::from_key(KeyCode::Right),
+            Some(Direction4::Right)
+        );
+        assert_eq!(Direction4::from_key(KeyCode::Char('w')), None);
+        assert_eq!(Direction4::Up.delta(), (0, -1));
+    }
+}
@@ -0,0 +1,276 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! GridMap is a 2d grid shared by games and algorithm crates
+//! for bounds checks, 4/8 neighbors, flood fill and line of sight
+//!
+//! cells are stored row by row as cells[y][x], so they can be passed
+//! to algorithm::astar::a_star directly
+//!
+//! # Example
+//! ```
+//! use rust_pixel::util::GridMap;
+//! let mut g = GridMap::new(8, 8, 0u8);
+//! g.set(3, 0, 1);
+//! let area = g.flood_fill((0, 0), |v| *v == 0);
+//! let see = g.visible((0, 0), (7, 0), |v| *v != 0);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+const DIR4: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const DIR8: [(i32, i32); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridMap<T> {
+    pub width: u16,
+    pub height: u16,
+    pub cells: Vec<Vec<T>>,
+}
+
+impl<T: Clone> GridMap<T> {
+    pub fn new(width: u16, height: u16, v: T) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![vec![v; width as usize]; height as usize],
+        }
+    }
+
+    /// set all cells to v
+    pub fn fill(&mut self, v: T) {
+        for row in self.cells.iter_mut() {
+            for c in row.iter_mut() {
+                *c = v.clone();
+            }
+        }
+    }
+}
+
+impl<T> From<Vec<Vec<T>>> for GridMap<T> {
+    fn from(cells: Vec<Vec<T>>) -> Self {
+        let height = cells.len() as u16;
+        let width = cells.first().map(|r| r.len()).unwrap_or(0) as u16;
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+}
+
+impl<T> GridMap<T> {
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32
+    }
+
+    pub fn get(&self, x: u16, y: u16) -> Option<&T> {
+        self.cells.get(y as usize).and_then(|r| r.get(x as usize))
+    }
+
+    pub fn get_mut(&mut self, x: u16, y: u16) -> Option<&mut T> {
+        self.cells
+            .get_mut(y as usize)
+            .and_then(|r| r.get_mut(x as usize))
+    }
+
+    /// returns false if (x, y) is out of bounds
+    pub fn set(&mut self, x: u16, y: u16, v: T) -> bool {
+        match self.get_mut(x, y) {
+            Some(c) => {
+                *c = v;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn neighbors<'a>(
+        &'a self,
+        x: u16,
+        y: u16,
+        dirs: &'a [(i32, i32)],
+    ) -> impl Iterator<Item = (u16, u16)> + 'a {
+        dirs.iter().filter_map(move |(dx, dy)| {
+            let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+            if self.in_bounds(nx, ny) {
+                Some((nx as u16, ny as u16))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// up, right, down, left neighbors inside the grid
+    pub fn neighbors4(&self, x: u16, y: u16) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.neighbors(x, y, &DIR4)
+    }
+
+    /// 8 neighbors inside the grid, clockwise from up
+    pub fn neighbors8(&self, x: u16, y: u16) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.neighbors(x, y, &DIR8)
+    }
+
+    /// 4-connected cells reachable from start, start included if passable
+    pub fn flood_fill<F>(&self, start: (u16, u16), passable: F) -> Vec<(u16, u16)>
+    where
+        F: Fn(&T) -> bool,
+    {
+        let mut ret = vec![];
+        match self.get(start.0, start.1) {
+            Some(v) if passable(v) => {}
+            _ => return ret,
+        }
+        let mut visited = vec![vec![false; self.width as usize]; self.height as usize];
+        let mut queue = VecDeque::new();
+        visited[start.1 as usize][start.0 as usize] = true;
+        queue.push_back(start);
+        while let Some((x, y)) = queue.pop_front() {
+            ret.push((x, y));
+            for (nx, ny) in self.neighbors4(x, y) {
+                if !visited[ny as usize][nx as usize]
+                    && passable(&self.cells[ny as usize][nx as usize])
+                {
+                    visited[ny as usize][nx as usize] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        ret
+    }
+
+    /// true if no cell strictly between start and end blocks the sight
+    pub fn visible<F>(&self, start: (u16, u16), end: (u16, u16), blocks: F) -> bool
+    where
+        F: Fn(&T) -> bool,
+    {
+        line(start, end)
+            .filter(|p| *p != start && *p != end)
+            .all(|(x, y)| match self.get(x, y) {
+                Some(v) => !blocks(v),
+                None => false,
+            })
+    }
+}
+
+/// Bresenham line from start to end, both included
+/// the same cells are returned for (a, b) and (b, a), so line of sight is symmetric
+pub fn line(start: (u16, u16), end: (u16, u16)) -> impl Iterator<Item = (u16, u16)> {
+    let swap = end < start;
+    let (a, b) = if swap { (end, start) } else { (start, end) };
+    let (mut x0, mut y0) = (a.0 as i32, a.1 as i32);
+    let (x1, y1) = (b.0 as i32, b.1 as i32);
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let mut points = vec![];
+    loop {
+        points.push((x0 as u16, y0 as u16));
+        if x0 == x1 && y0 == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x0 += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y0 += sy;
+        }
+    }
+    if swap {
+        points.reverse();
+    }
+    points.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 7x7, a passable ring at distance 1..=2 from center, walls elsewhere
+    fn donut() -> GridMap<u8> {
+        let mut g = GridMap::new(7, 7, 1u8);
+        for y in 0..7u16 {
+            for x in 0..7u16 {
+                let d = (x as i32 - 3).abs().max((y as i32 - 3).abs());
+                if d == 1 || d == 2 {
+                    g.set(x, y, 0);
+                }
+            }
+        }
+        g
+    }
+
+    #[test]
+    fn flood_fill_donut() {
+        let g = donut();
+        let ring: Vec<_> = (0..7u16)
+            .flat_map(|y| (0..7u16).map(move |x| (x, y)))
+            .filter(|&(x, y)| g.cells[y as usize][x as usize] == 0)
+            .collect();
+        assert_eq!(ring.len(), 24);
+        // same region from any start on the ring, hole and border excluded
+        for &s in &ring {
+            let mut f = g.flood_fill(s, |v| *v == 0);
+            f.sort();
+            let mut r = ring.clone();
+            r.sort();
+            assert_eq!(f, r);
+        }
+        assert!(g.flood_fill((3, 3), |v| *v == 0).is_empty());
+        assert!(g.flood_fill((9, 9), |v| *v == 0).is_empty());
+    }
+
+    #[test]
+    fn line_is_contiguous_and_los_symmetric() {
+        let mut g = GridMap::new(9, 9, 0u8);
+        for (x, y) in [(4, 4), (2, 6), (6, 1), (5, 5), (1, 3)] {
+            g.set(x, y, 1);
+        }
+        for y0 in 0..9u16 {
+            for x0 in 0..9u16 {
+                for y1 in 0..9u16 {
+                    for x1 in 0..9u16 {
+                        let pts: Vec<_> = line((x0, y0), (x1, y1)).collect();
+                        assert_eq!(pts[0], (x0, y0));
+                        assert_eq!(*pts.last().unwrap(), (x1, y1));
+                        for w in pts.windows(2) {
+                            let dx = (w[0].0 as i32 - w[1].0 as i32).abs();
+                            let dy = (w[0].1 as i32 - w[1].1 as i32).abs();
+                            assert_eq!(dx.max(dy), 1);
+                        }
+                        assert_eq!(
+                            g.visible((x0, y0), (x1, y1), |v| *v != 0),
+                            g.visible((x1, y1), (x0, y0), |v| *v != 0)
+                        );
+                    }
+                }
+            }
+        }
+        assert!(!g.visible((0, 4), (8, 4), |v| *v != 0));
+        assert!(g.visible((0, 0), (8, 0), |v| *v != 0));
+    }
+
+    #[test]
+    fn neighbors_stay_in_bounds() {
+        let g = GridMap::new(3, 3, 0u8);
+        assert_eq!(g.neighbors4(0, 0).count(), 2);
+        assert_eq!(g.neighbors8(0, 0).count(), 3);
+        assert_eq!(g.neighbors4(1, 1).count(), 4);
+        assert_eq!(g.neighbors8(1, 1).count(), 8);
+    }
+}
@@ -68,6 +68,24 @@ impl Rand {
     pub fn shuffle<T: Copy>(&mut self, v: &mut Vec<T>) {
         v.shuffle(&mut self.rng);
     }
+
+    /// advances n * 2^128 numbers
+    pub fn jump(&mut self, n: u32) {
+        for _ in 0..n {
+            self.rng.jump();
+        }
+    }
+
+    /// returns a substream starting at the current state and jumps self
+    /// 2^128 numbers ahead, so streams never overlap, e.g. one per worker
+    /// thread, all reproducible from the seed of the parent
+    pub fn split(&mut self) -> Rand {
+        let child = Rand {
+            rng: self.rng.clone(),
+        };
+        self.rng.jump();
+        child
+    }
 }
 
 /// 封装LCG随机数生成器, 随机效果不好
@@ -140,3 +158,38 @@ impl RandLCG {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take(r: &mut Rand) -> Vec<u64> {
+        (0..16).map(|_| r.rand64()).collect()
+    }
+
+    #[test]
+    fn split_streams_reproducible() {
+        let mut p1 = Rand::new();
+        p1.srand(2024);
+        let mut p2 = Rand::new();
+        p2.srand(2024);
+        let mut a1 = p1.split();
+        let mut b1 = p1.split();
+        let mut a2 = p2.split();
+        let mut b2 = p2.split();
+        let (sa, sb) = (take(&mut a1), take(&mut b1));
+        assert_eq!(sa, take(&mut a2));
+        assert_eq!(sb, take(&mut b2));
+        assert_ne!(sa, sb);
+        assert!(sa.iter().all(|v| !sb.contains(v)));
+        assert_eq!(take(&mut p1), take(&mut p2));
+
+        // the first split continues the parent stream, the second is one jump ahead
+        let mut q = Rand::new();
+        q.srand(2024);
+        assert_eq!(take(&mut q), sa);
+        q.srand(2024);
+        q.jump(1);
+        assert_eq!(take(&mut q), sb);
+    }
+}
@@ -0,0 +1,146 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! SpatialHash is a uniform grid for broad phase collision
+//! objects are inserted with their bounding rect every frame,
+//! then query only returns objects near the query rect,
+//! instead of comparing every pair of objects
+//!
+//! # Example
+//! ```
+//! use rust_pixel::util::{Rect, SpatialHash};
+//! let mut sh = SpatialHash::new(16);
+//! sh.insert(0, Rect::new(10, 10, 4, 4));
+//! sh.insert(1, Rect::new(200, 200, 4, 4));
+//! assert_eq!(sh.query(Rect::new(8, 8, 4, 4)), vec![0]);
+//! sh.clear();
+//! ```
+
+use crate::util::Rect;
+use std::collections::HashMap;
+
+pub struct SpatialHash {
+    cell_size: u16,
+    cells: HashMap<(u16, u16), Vec<usize>>,
+    rects: HashMap<usize, Rect>,
+}
+
+impl SpatialHash {
+    /// cell_size is better close to the size of common objects
+    pub fn new(cell_size: u16) -> Self {
+        Self {
+            cell_size: cell_size.max(1),
+            cells: HashMap::new(),
+            rects: HashMap::new(),
+        }
+    }
+
+    /// cells covered by rect, empty rect takes its top left cell
+    fn cell_range(&self, r: Rect) -> (u16, u16, u16, u16) {
+        let cs = self.cell_size;
+        let x1 = r.right().max(r.x.saturating_add(1)) - 1;
+        let y1 = r.bottom().max(r.y.saturating_add(1)) - 1;
+        (r.x / cs, r.y / cs, x1 / cs, y1 / cs)
+    }
+
+    /// inserts object id with its bounding rect,
+    /// insert the same id again without clear is not supported
+    pub fn insert(&mut self, id: usize, rect: Rect) {
+        let (x0, y0, x1, y1) = self.cell_range(rect);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                self.cells.entry((x, y)).or_default().push(id);
+            }
+        }
+        self.rects.insert(id, rect);
+    }
+
+    /// ids sharing a cell with rect, sorted and deduped,
+    /// this is the broad phase result, they may not overlap rect
+    pub fn candidates(&self, rect: Rect) -> Vec<usize> {
+        let (x0, y0, x1, y1) = self.cell_range(rect);
+        let mut ret = vec![];
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                if let Some(ids) = self.cells.get(&(x, y)) {
+                    ret.extend_from_slice(ids);
+                }
+            }
+        }
+        ret.sort_unstable();
+        ret.dedup();
+        ret
+    }
+
+    /// ids whose rect overlaps rect, sorted
+    pub fn query(&self, rect: Rect) -> Vec<usize> {
+        self.candidates(rect)
+            .into_iter()
+            .filter(|id| self.rects[id].intersects(rect))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    /// removes all objects, usually called at the start of every frame
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.rects.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_only_overlapping() {
+        let mut sh = SpatialHash::new(8);
+        sh.insert(1, Rect::new(0, 0, 4, 4));
+        sh.insert(2, Rect::new(6, 6, 4, 4));
+        sh.insert(3, Rect::new(100, 100, 4, 4));
+        // spans several cells
+        sh.insert(4, Rect::new(2, 12, 30, 2));
+        assert_eq!(sh.query(Rect::new(3, 3, 4, 4)), vec![1, 2]);
+        assert_eq!(sh.query(Rect::new(20, 10, 2, 3)), vec![4]);
+        assert_eq!(sh.query(Rect::new(99, 99, 2, 2)), vec![3]);
+        // same cell, but no overlap
+        assert_eq!(sh.candidates(Rect::new(5, 0, 1, 1)), vec![1, 2]);
+        assert!(sh.query(Rect::new(5, 0, 1, 1)).is_empty());
+        assert!(sh.query(Rect::new(60, 60, 4, 4)).is_empty());
+        sh.clear();
+        assert!(sh.is_empty());
+        assert!(sh.query(Rect::new(0, 0, 200, 200)).is_empty());
+    }
+
+    #[test]
+    fn fewer_comparisons_than_brute_force() {
+        // 100 monsters and 500 bullets on a 400x200 pixel map
+        let monsters: Vec<Rect> = (0..100u16)
+            .map(|i| Rect::new((i % 20) * 20, (i / 20) * 40, 8, 8))
+            .collect();
+        let bullets: Vec<Rect> = (0..500u16)
+            .map(|i| Rect::new((i * 37) % 400, (i * 53) % 200, 2, 2))
+            .collect();
+        let mut sh = SpatialHash::new(16);
+        for (id, m) in monsters.iter().enumerate() {
+            sh.insert(id, *m);
+        }
+        let mut compares = 0;
+        for b in &bullets {
+            let c = sh.candidates(*b);
+            compares += c.len();
+            let brute: Vec<usize> = (0..monsters.len())
+                .filter(|i| monsters[*i].intersects(*b))
+                .collect();
+            assert_eq!(sh.query(*b), brute);
+        }
+        assert!(compares * 10 < bullets.len() * monsters.len());
+    }
+}
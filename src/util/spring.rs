@@ -0,0 +1,109 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Spring moves a value toward a target like a damped spring of mass 1,
+//! for snappy UI settles and camera follow without a fixed duration
+//!
+//! update integrates with semi-implicit euler: velocity is updated first and
+//! the new velocity moves the value. It stays stable where explicit euler
+//! gains energy and blows up. Big dt are split into steps of at most
+//! SPRING_MAX_STEP, so a slow frame can't make a stiff spring explode.
+//!
+//! damping 2 * sqrt(stiffness) is critical: the fastest settle without
+//! overshoot. Less overshoots and oscillates, more creeps in slowly.
+//!
+//! # Example
+//! ```
+//! use rust_pixel::render::camera::Camera;
+//! use rust_pixel::util::Spring;
+//! let mut cam = Camera::new(200, 100, 80, 40);
+//! let mut sx = Spring::critical(60.0, 0.0);
+//! // every frame, follows the player x
+//! cam.set_pos(sx.update(150.0 - 40.0, 1.0 / 60.0), 0.0);
+//! ```
+
+/// longest integration step in seconds
+pub const SPRING_MAX_STEP: f32 = 1.0 / 120.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Spring {
+    pub stiffness: f32,
+    pub damping: f32,
+    pub value: f32,
+    pub velocity: f32,
+}
+
+impl Spring {
+    pub fn new(stiffness: f32, damping: f32, value: f32) -> Self {
+        Self {
+            stiffness,
+            damping,
+            value,
+            velocity: 0.0,
+        }
+    }
+
+    /// critically damped spring
+    pub fn critical(stiffness: f32, value: f32) -> Self {
+        Self::new(stiffness, 2.0 * stiffness.max(0.0).sqrt(), value)
+    }
+
+    /// advances dt seconds toward target, returns the new value
+    pub fn update(&mut self, target: f32, dt: f32) -> f32 {
+        let mut left = dt.max(0.0);
+        while left > 0.0 {
+            let h = left.min(SPRING_MAX_STEP);
+            let a = self.stiffness * (target - self.value) - self.damping * self.velocity;
+            self.velocity += a * h;
+            self.value += self.velocity * h;
+            left -= h;
+        }
+        self.value
+    }
+
+    /// close to target and nearly still
+    pub fn is_settled(&self, target: f32, eps: f32) -> bool {
+        (target - self.value).abs() <= eps && self.velocity.abs() <= eps
+    }
+
+    /// jumps to value and stops
+    pub fn reset(&mut self, value: f32) {
+        self.value = value;
+        self.velocity = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overdamped_never_overshoots() {
+        let mut s = Spring::new(100.0, 40.0, 0.0);
+        let mut last = 0.0;
+        for _ in 0..300 {
+            let v = s.update(1.0, 1.0 / 60.0);
+            assert!(v >= last && v <= 1.0, "{}", v);
+            last = v;
+        }
+        assert!(s.is_settled(1.0, 0.01));
+    }
+
+    #[test]
+    fn critical_settles_in_time() {
+        // omega 10, 1 - (1 + 10t) * e^(-10t) is within 2% after about 0.58s
+        let mut s = Spring::critical(100.0, 0.0);
+        s.update(1.0, 0.4);
+        assert!(1.0 - s.value > 0.02);
+        s.update(1.0, 0.3);
+        assert!((1.0 - s.value).abs() < 0.02);
+        // one big frame gives the same as small ones
+        let mut a = Spring::critical(100.0, 0.0);
+        let mut b = a;
+        a.update(1.0, 0.5);
+        for _ in 0..60 {
+            b.update(1.0, 0.5 / 60.0);
+        }
+        assert!((a.value - b.value).abs() < 0.01);
+    }
+}
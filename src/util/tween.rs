@@ -0,0 +1,194 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! Easing curves and Tween, which interpolates a value over a duration,
+//! advanced every tick with dt, e.g. context.dt
+//!
+//! # Example
+//! ```
+//! use rust_pixel::util::{Easing, Tween};
+//! let mut t = Tween::new(0.0f32, 10.0, 0.5, Easing::OutQuad);
+//! let x = t.update(1.0 / 60.0);
+//! assert!(x > 0.0 && !t.is_finished());
+//! ```
+
+use std::f32::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    InQuad,
+    OutQuad,
+    InOutQuad,
+    InCubic,
+    OutCubic,
+    InOutCubic,
+    /// bounces at the end, like a ball dropped on the target
+    Bounce,
+    /// overshoots and springs back to the target
+    Elastic,
+}
+
+impl Easing {
+    /// maps progress t in 0.0..=1.0, 0 gives 0 and 1 gives 1
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::InQuad => t * t,
+            Easing::OutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::InOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - 2.0 * (1.0 - t) * (1.0 - t)
+                }
+            }
+            Easing::InCubic => t * t * t,
+            Easing::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::InOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - 4.0 * (1.0 - t).powi(3)
+                }
+            }
+            Easing::Bounce => bounce_out(t),
+            Easing::Elastic => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+                }
+            }
+        }
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// values a Tween can interpolate
+pub trait Lerp: Copy {
+    fn lerp(a: Self, b: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+}
+
+impl Lerp for (f32, f32) {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        (f32::lerp(a.0, b.0, t), f32::lerp(a.1, b.1, t))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Tween<T: Lerp> {
+    pub from: T,
+    pub to: T,
+    pub duration: f32,
+    pub easing: Easing,
+    elapsed: f32,
+}
+
+impl<T: Lerp> Tween<T> {
+    /// duration in seconds, 0 jumps to the target at the first update
+    pub fn new(from: T, to: T, duration: f32, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing,
+            elapsed: 0.0,
+        }
+    }
+
+    /// advances dt seconds, returns the new value
+    pub fn update(&mut self, dt: f32) -> T {
+        self.elapsed = (self.elapsed + dt).min(self.duration.max(0.0));
+        self.value()
+    }
+
+    /// 0.0..=1.0 before easing
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            1.0
+        } else {
+            self.elapsed / self.duration
+        }
+    }
+
+    pub fn value(&self) -> T {
+        T::lerp(self.from, self.to, self.easing.apply(self.progress()))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Easing; 9] = [
+        Easing::Linear,
+        Easing::InQuad,
+        Easing::OutQuad,
+        Easing::InOutQuad,
+        Easing::InCubic,
+        Easing::OutCubic,
+        Easing::InOutCubic,
+        Easing::Bounce,
+        Easing::Elastic,
+    ];
+
+    #[test]
+    fn easing_ends_fixed() {
+        for e in ALL {
+            assert!(e.apply(0.0).abs() < 1e-6, "{:?}", e);
+            assert!((e.apply(1.0) - 1.0).abs() < 1e-6, "{:?}", e);
+        }
+        for e in [Easing::InOutQuad, Easing::InOutCubic] {
+            for i in 0..=20 {
+                let t = i as f32 / 20.0;
+                assert!((e.apply(t) + e.apply(1.0 - t) - 1.0).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn tween_reaches_target() {
+        let mut t = Tween::new((0.0, 10.0), (4.0, 2.0), 1.0, Easing::Linear);
+        assert_eq!(t.update(0.25), (1.0, 8.0));
+        for _ in 0..10 {
+            t.update(0.25);
+        }
+        assert!(t.is_finished());
+        assert_eq!(t.value(), (4.0, 2.0));
+        t.reset();
+        assert_eq!(t.value(), (0.0, 10.0));
+    }
+}
@@ -0,0 +1,43 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! base mode keeps rust_pixel and the app libs free of render deps,
+//! the ffi and wasm crates of the apps depend on it
+//!
+//! Runs cargo check with only the base feature for every crate below,
+//! in its own target dir so it doesn't wait on the lock of this test run.
+
+use std::{env, process::Command};
+
+const BASE_CRATES: [&str; 9] = [
+    "rust_pixel",
+    "city_lib",
+    "ginrummy_lib",
+    "palette_lib",
+    "petview_lib",
+    "snake_lib",
+    "template_lib",
+    "tetris_lib",
+    "tower_lib",
+];
+
+#[test]
+fn base_builds_check() {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let root = env!("CARGO_MANIFEST_DIR");
+    for c in BASE_CRATES {
+        let out = Command::new(&cargo)
+            .current_dir(root)
+            .env("CARGO_TARGET_DIR", format!("{}/target/base-check", root))
+            .args(["check", "-q", "-p", c])
+            .args(["--no-default-features", "--features", "base"])
+            .output()
+            .expect("run cargo check");
+        assert!(
+            out.status.success(),
+            "{} base build failed:\n{}",
+            c,
+            String::from_utf8_lossy(&out.stderr)
+        );
+    }
+}
@@ -0,0 +1,32 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+//! install_panic_hook swaps the process wide panic hook, so it is tested
+//! in its own binary where no other test can panic meanwhile
+
+#![cfg(not(any(
+    feature = "sdl",
+    target_os = "android",
+    target_os = "ios",
+    target_arch = "wasm32"
+)))]
+
+use rust_pixel::render::adapter::cross::install_panic_hook;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+#[test]
+fn panic_hook_keeps_previous() {
+    let called = Arc::new(AtomicUsize::new(0));
+    let c = called.clone();
+    std::panic::set_hook(Box::new(move |_| {
+        c.fetch_add(1, Ordering::SeqCst);
+    }));
+    install_panic_hook();
+    let r = std::panic::catch_unwind(|| panic!("boom"));
+    assert!(r.is_err());
+    let _ = std::panic::take_hook();
+    assert_eq!(called.load(Ordering::SeqCst), 1);
+}
@@ -63,7 +63,8 @@ pub fn make_parser() -> ArgMatches {
             SubCommand::with_name("creat")
                 .alias("c")
                 .arg(Arg::with_name("mod_name").required(true))
-                .arg(Arg::with_name("standalone_dir_name").required(false)),
+                .arg(Arg::with_name("standalone_dir_name").required(false))
+                .arg(Arg::with_name("dep").long("dep").takes_value(true)),
         ))
         .subcommand(common_arg(
             SubCommand::with_name("convert_gif")
@@ -71,8 +72,10 @@ pub fn make_parser() -> ArgMatches {
                 .arg(Arg::with_name("gif").required(true))
                 .arg(Arg::with_name("ssf").required(true))
                 .arg(Arg::with_name("width").required(true))
-                .arg(Arg::with_name("height").required(true)),
+                .arg(Arg::with_name("height").required(true))
+                .arg(Arg::with_name("fps").long("fps").takes_value(true)),
         ))
+        .subcommand(SubCommand::with_name("doctor").alias("d"))
         .get_matches();
 
     matches
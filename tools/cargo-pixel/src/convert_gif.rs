@@ -15,74 +15,99 @@
 /// cargo pixel r snake w
 /// ...
 ///
+/// convert_gif decodes gif frames in process and converts every frame
+/// to petscii cells by rust_pixel::render::image::petii, then writes ssf:
+///
+/// cargo pixel cg a.gif a.ssf 40 25 [--fps 10]
+///
 use clap::ArgMatches;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use regex::Regex;
+use image::DynamicImage;
+use rust_pixel::render::image::petii::{decode_gif_frames, petii_convert, PetiiConfig};
 use std::fs;
 use std::io::{self, Write};
-use std::process::Command;
-use std::process::Stdio;
-use std::str;
 
 use crate::PixelContext;
-use crate::exec_cmd;
 
 pub fn pixel_convert_gif(_ctx: &PixelContext, args: &ArgMatches) {
     let gif = args.value_of("gif").unwrap();
     let ssf = args.value_of("ssf").unwrap();
-    let width: usize = args.value_of("width").unwrap().parse().unwrap();
-    let height: usize = args.value_of("height").unwrap().parse().unwrap();
-
-    println!("🍀 extract pngs use ffmpeg...");
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg(format!("ffmpeg -i {} -vsync 0 tmp/t%d.png", gif))
-        .stderr(Stdio::piped())
-        .output()
-        .expect("failed to execute process");
-
-    let stderr = str::from_utf8(&output.stderr).unwrap();
-    let rf = Regex::new(r"(.*)frame=(.*?)(\d+)(.*)").unwrap();
-    let fg = rf.captures(stderr).unwrap();
-    let frame_count: usize = fg.get(3).unwrap().as_str().parse().unwrap();
-    println!("    frame_count = {}", frame_count);
-
-    println!("🍀 pixel_petii convert png to pix...");
-    for x in 0..frame_count {
-        print!("\r{}  ", x + 1);
+    let width: u32 = args.value_of("width").unwrap().parse().unwrap();
+    let height: u32 = args.value_of("height").unwrap().parse().unwrap();
+    let fps: Option<f64> = args
+        .value_of("fps")
+        .map(|f| f.parse().expect("fps must be a number"));
+
+    let data = fs::read(gif).expect("failed to read gif file");
+    println!("🍀 pixel_petii convert gif frames to pix...");
+    let ssf_data = gif_to_ssf(&data, width, height, fps, |idx, count| {
+        print!("\r    frame {}/{}  ", idx + 1, count);
         io::stdout().flush().unwrap();
-        let cmd = format!(
-            "cargo r --bin pixel_petii --release tmp/t{}.png  {} {} > tmp/t{}.pix 2>/dev/null",
-            x + 1,
-            width,
-            height,
-            x + 1
-        );
-        exec_cmd(&cmd);
+    });
+    fs::write(ssf, ssf_data).expect("failed to write ssf file");
+    println!("\n🍀 {} write ok!", ssf);
+}
+
+/// decode gif frames, resampled to fps if given
+fn resample_gif_frames(data: &[u8], fps: Option<f64>) -> Vec<DynamicImage> {
+    let (images, delays): (Vec<_>, Vec<_>) = decode_gif_frames(data)
+        .expect("failed to decode gif frames")
+        .into_iter()
+        .unzip();
+
+    let fps = match fps {
+        Some(f) if f > 0.0 => f,
+        _ => return images,
+    };
+
+    // pick the source frame shown at every output tick
+    let total: f64 = delays.iter().sum();
+    let step = 1000.0 / fps;
+    let count = ((total / step).round() as usize).max(1);
+    let mut out = vec![];
+    let mut src = 0;
+    let mut src_end = delays[0];
+    for i in 0..count {
+        let t = i as f64 * step;
+        while t >= src_end && src + 1 < images.len() {
+            src += 1;
+            src_end += delays[src];
+        }
+        out.push(images[src].clone());
     }
+    out
+}
 
-    let mut fsdq = fs::File::create(ssf).unwrap();
-    writeln!(
-        fsdq,
-        "width={},height={},texture=255,frame_count={}",
-        width, height, frame_count
-    )
-    .unwrap();
+/// converts gif data to ssf file content
+/// progress is called with (frame_index, frame_count) before each frame
+pub fn gif_to_ssf<F>(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    fps: Option<f64>,
+    mut progress: F,
+) -> Vec<u8>
+where
+    F: FnMut(usize, usize),
+{
+    let images = resample_gif_frames(data, fps);
+    let frame_count = images.len();
+    let cfg = PetiiConfig {
+        width,
+        height,
+        ..Default::default()
+    };
 
-    let rds = Regex::new(r"(\d+),(\d+),(\d+)(.*?)").unwrap();
     let mut datas = Vec::new();
     let mut flens = Vec::new();
-    for x in 0..frame_count {
-        let pix_file = format!("tmp/t{}.pix", x + 1);
-        let content = fs::read_to_string(pix_file).unwrap();
+    for (idx, img) in images.iter().enumerate() {
+        progress(idx, frame_count);
         let mut sdatas = Vec::new();
-        for line in content.lines().skip(1) {
-            for cap in rds.captures_iter(line) {
-                sdatas.push(cap[1].parse::<u8>().unwrap());
-                sdatas.push(cap[2].parse::<u8>().unwrap());
-                sdatas.push(cap[3].parse::<u8>().unwrap());
-            }
+        for c in petii_convert(img, &cfg) {
+            sdatas.push(c.sym);
+            sdatas.push(c.fg);
+            sdatas.push(c.tex);
         }
         let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
         encoder.write_all(&sdatas).unwrap();
@@ -91,13 +116,81 @@ pub fn pixel_convert_gif(_ctx: &PixelContext, args: &ArgMatches) {
         datas.extend_from_slice(&compressed);
     }
 
+    let mut out = Vec::new();
+    writeln!(
+        out,
+        "width={},height={},texture=255,frame_count={}",
+        width, height, frame_count
+    )
+    .unwrap();
     for len in flens {
-        write!(fsdq, "{},", len).unwrap();
+        write!(out, "{},", len).unwrap();
     }
-    writeln!(fsdq).unwrap();
-    fsdq.write_all(&datas).unwrap();
-
-    println!("\n🍀 {} write ok!", ssf);
-    exec_cmd("rm tmp/t*.p*");
+    writeln!(out).unwrap();
+    out.extend_from_slice(&datas);
+    out
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Frame, Rgba, RgbaImage};
+    use std::io::Read;
+
+    fn make_gif(frames: u32) -> Vec<u8> {
+        let mut buf = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut buf);
+            for i in 0..frames {
+                let img = RgbaImage::from_pixel(16, 16, Rgba([(i * 80) as u8, 0, 0, 255]));
+                let frame = Frame::from_parts(img, 0, 0, Delay::from_numer_denom_ms(100, 1));
+                encoder.encode_frame(frame).unwrap();
+            }
+        }
+        buf
+    }
+
+    #[test]
+    fn gif_frames_to_ssf() {
+        let gif = make_gif(3);
+        let mut progressed = 0;
+        let ssf = gif_to_ssf(&gif, 2, 2, None, |_, _| progressed += 1);
+        assert_eq!(progressed, 3);
+
+        let mut lines = ssf.splitn(3, |b| *b == b'\n');
+        let header = std::str::from_utf8(lines.next().unwrap()).unwrap();
+        assert_eq!(header, "width=2,height=2,texture=255,frame_count=3");
+        let lens: Vec<usize> = std::str::from_utf8(lines.next().unwrap())
+            .unwrap()
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert_eq!(lens.len(), 3);
+
+        let mut body = lines.next().unwrap();
+        for len in lens {
+            let mut cells = Vec::new();
+            GzDecoder::new(&body[..len])
+                .read_to_end(&mut cells)
+                .unwrap();
+            // sym, fg, tex for every cell
+            assert_eq!(cells.len(), 2 * 2 * 3);
+            body = &body[len..];
+        }
+    }
+
+    #[test]
+    fn gif_fps_resample() {
+        let gif = make_gif(3);
+        // 300ms at 20fps
+        let ssf = gif_to_ssf(&gif, 1, 1, Some(20.0), |_, _| {});
+        let header = ssf.split(|b| *b == b'\n').next().unwrap();
+        assert_eq!(
+            std::str::from_utf8(header).unwrap(),
+            "width=1,height=1,texture=255,frame_count=6"
+        );
+    }
+}
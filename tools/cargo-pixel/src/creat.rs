@@ -7,6 +7,8 @@
 /// cargo pixel run snake term
 /// cargo pixel run snake sdl
 /// cargo pixel creat games mygame
+/// cargo pixel creat mygame .. --dep crates
+/// cargo pixel creat mygame .. --dep git=https://github.com/zipxing/rust_pixel#<rev>
 /// cargo pixel build snake web
 ///
 /// shortcut:
@@ -23,9 +25,72 @@ use std::path::Path;
 use crate::PixelContext;
 use crate::PState;
 use crate::exec_cmd;
-use crate::replace_in_files;
+use crate::{replace_in_files, ReplaceOpts};
 use crate::write_config;
 use crate::capitalize;
+use crate::pixel_root_version;
+
+// how a standalone project depends on rust_pixel, --dep path|crates|git=<url>[#rev]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PixelDep {
+    // local rust_pixel repo
+    Path(String),
+    // crates.io version
+    Crates(String),
+    Git { url: String, rev: Option<String> },
+}
+
+impl PixelDep {
+    // root is the rust_pixel repo, crates takes its version
+    pub fn parse(s: &str, root: &str) -> Result<Self, String> {
+        match s {
+            "path" => Ok(PixelDep::Path(root.to_string())),
+            "crates" => pixel_root_version(root).map(PixelDep::Crates),
+            _ => match s.strip_prefix("git=") {
+                Some(g) if !g.is_empty() => {
+                    let (url, rev) = match g.split_once('#') {
+                        Some((u, r)) => (u, Some(r.to_string())),
+                        None => (g, None),
+                    };
+                    Ok(PixelDep::Git {
+                        url: url.to_string(),
+                        rev,
+                    })
+                }
+                _ => Err(format!(
+                    "bad --dep {:?}, use path, crates or git=<url>[#rev]",
+                    s
+                )),
+            },
+        }
+    }
+
+    // recorded in [package.metadata.rust_pixel] for cargo pixel doctor
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PixelDep::Path(_) => "path",
+            PixelDep::Crates(_) => "crates",
+            PixelDep::Git { .. } => "git",
+        }
+    }
+
+    // source keys of the rust_pixel line in Cargo.toml
+    pub fn dep_line(&self) -> String {
+        match self {
+            PixelDep::Path(p) => format!("path = \"{}\"", p),
+            PixelDep::Crates(v) => format!("version = \"{}\"", v),
+            PixelDep::Git { url, rev: Some(r) } => format!("git = \"{}\", rev = \"{}\"", url, r),
+            PixelDep::Git { url, rev: None } => format!("git = \"{}\"", url),
+        }
+    }
+
+    // fills $RUST_PIXEL_DEP and $RUST_PIXEL_DEP_KIND of stand-alone templates
+    pub fn fill(&self, content: &str) -> String {
+        content
+            .replace("$RUST_PIXEL_DEP_KIND", self.kind())
+            .replace("$RUST_PIXEL_DEP", &self.dep_line())
+    }
+}
 
 // crate subcommand entry...
 pub fn pixel_creat(ctx: &PixelContext, args: &ArgMatches) {
@@ -40,6 +105,14 @@ pub fn pixel_creat(ctx: &PixelContext, args: &ArgMatches) {
     let upname = mod_name.to_uppercase();
     let loname = mod_name.to_lowercase();
     let capname = capitalize(mod_name);
+    let root = &ctx.rust_pixel_dir[ctx.rust_pixel_idx];
+    let dep = match PixelDep::parse(args.value_of("dep").unwrap_or("path"), root) {
+        Ok(d) => d,
+        Err(e) => {
+            println!("🚫 {}", e);
+            return;
+        }
+    };
 
     let cdir;
     if let Some(sdir) = sa_dir {
@@ -71,13 +144,16 @@ pub fn pixel_creat(ctx: &PixelContext, args: &ArgMatches) {
     exec_cmd("rm -fr tmp/pixel_game_template/stand-alone");
 
     replace_in_files(
-        is_standalone,
         Path::new("tmp/pixel_game_template"),
-        &ctx.rust_pixel_dir[ctx.rust_pixel_idx],
-        &dir_name,
-        &capname,
-        &upname,
-        &loname,
+        &ReplaceOpts {
+            is_standalone,
+            rust_pixel_path: root,
+            dep: &dep,
+            dirname: &dir_name,
+            capname: &capname,
+            upname: &upname,
+            loname: &loname,
+        },
     );
 
     let mut new_path;
@@ -125,3 +201,47 @@ pub fn pixel_creat(ctx: &PixelContext, args: &ArgMatches) {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMP: &str = include_str!("../../../apps/template/stand-alone/Cargo.toml.temp");
+
+    fn rust_pixel_line(toml: &str) -> &str {
+        toml.lines()
+            .find(|l| l.starts_with("rust_pixel ="))
+            .unwrap()
+    }
+
+    #[test]
+    fn dep_fills_cargo_toml() {
+        let p = PixelDep::parse("path", "/home/a/rust_pixel").unwrap();
+        let ct = p.fill(TEMP);
+        assert_eq!(
+            rust_pixel_line(&ct),
+            "rust_pixel = { path = \"/home/a/rust_pixel\", default-features = false }"
+        );
+        assert!(ct.contains("dep = \"path\""));
+
+        let ct = PixelDep::Crates("0.5.3".to_string()).fill(TEMP);
+        assert_eq!(
+            rust_pixel_line(&ct),
+            "rust_pixel = { version = \"0.5.3\", default-features = false }"
+        );
+        assert!(ct.contains("dep = \"crates\""));
+
+        let g = PixelDep::parse("git=https://github.com/zipxing/rust_pixel#a1b2c3", "").unwrap();
+        let ct = g.fill(TEMP);
+        assert_eq!(
+            rust_pixel_line(&ct),
+            "rust_pixel = { git = \"https://github.com/zipxing/rust_pixel\", rev = \"a1b2c3\", default-features = false }"
+        );
+        assert!(ct.contains("dep = \"git\""));
+        assert!(!ct.contains('$'));
+        let g = PixelDep::parse("git=https://x.org/p.git", "").unwrap();
+        assert_eq!(g.dep_line(), "git = \"https://x.org/p.git\"");
+
+        assert!(PixelDep::parse("git=", "").is_err());
+        assert!(PixelDep::parse("svn", "").is_err());
+    }
+}
@@ -0,0 +1,324 @@
+// RustPixel
+// copyright zipxing@hotmail.com 2022~2024
+
+/// rust_pixel cargo build tools...
+///
+/// Usage:
+/// cargo pixel doctor
+///
+/// checks toolchain, sdl2, wasm-pack, the saved rust_pixel.toml
+/// and the rust_pixel dependency of the current standalone project,
+/// prints a pass/fail report with fix suggestions
+///
+use clap::ArgMatches;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::is_pixel_root;
+use crate::PixelContext;
+use crate::PState;
+
+// same as MSRV in README.md
+const MIN_RUST: (u32, u32) = (1, 71);
+
+enum Level {
+    Pass,
+    // optional tool missing
+    Warn,
+    Fail,
+}
+
+struct Check {
+    level: Level,
+    name: String,
+    info: String,
+    fix: String,
+}
+
+impl Check {
+    fn pass(name: &str, info: &str) -> Self {
+        Self {
+            level: Level::Pass,
+            name: name.to_string(),
+            info: info.to_string(),
+            fix: String::new(),
+        }
+    }
+
+    fn fail(name: &str, info: &str, fix: &str) -> Self {
+        Self {
+            level: Level::Fail,
+            name: name.to_string(),
+            info: info.to_string(),
+            fix: fix.to_string(),
+        }
+    }
+
+    fn warn(name: &str, info: &str, fix: &str) -> Self {
+        Self {
+            level: Level::Warn,
+            ..Self::fail(name, info, fix)
+        }
+    }
+}
+
+// first line of stdout, None if the command is missing or fails
+fn cmd_output(cmd: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(cmd).args(args).output().ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let s = String::from_utf8_lossy(&out.stdout);
+    Some(s.lines().next().unwrap_or("").trim().to_string())
+}
+
+// "rustc 1.75.0 (82e1608df 2023-12-21)" => (1, 75)
+fn parse_rust_version(s: &str) -> Option<(u32, u32)> {
+    let ver = s.split_whitespace().nth(1)?;
+    let mut it = ver.split('.');
+    let major = it.next()?.parse().ok()?;
+    let minor = it.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+fn check_rust() -> Check {
+    let name = "rust toolchain";
+    match cmd_output("rustc", &["--version"]) {
+        Some(v) => match parse_rust_version(&v) {
+            Some(mv) if mv >= MIN_RUST => Check::pass(name, &v),
+            _ => Check::fail(
+                name,
+                &format!("{}, need {}.{}+", v, MIN_RUST.0, MIN_RUST.1),
+                "rustup update stable",
+            ),
+        },
+        None => Check::fail(
+            name,
+            "rustc not found",
+            "curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs | sh",
+        ),
+    }
+}
+
+fn check_sdl() -> Check {
+    let name = "sdl2";
+    let fix = if cfg!(target_os = "macos") {
+        "brew install sdl2 sdl2_image sdl2_gfx sdl2_ttf sdl2_mixer, see doc/mac.md"
+    } else if cfg!(target_os = "windows") {
+        "see doc/win.md"
+    } else {
+        "sudo apt-get install libsdl2-dev libsdl2-image-dev libsdl2-gfx-dev, see doc/linux.md"
+    };
+    match cmd_output("sdl2-config", &["--version"])
+        .or_else(|| cmd_output("pkg-config", &["--modversion", "sdl2"]))
+    {
+        Some(v) => Check::pass(name, &v),
+        None => Check::fail(name, "not found, needed by sdl mode", fix),
+    }
+}
+
+fn check_wasm_pack() -> Check {
+    let name = "wasm-pack";
+    match cmd_output("wasm-pack", &["--version"]) {
+        Some(v) => Check::pass(name, &v),
+        None => Check::fail(
+            name,
+            "not found, needed by web mode",
+            "curl https://rustwasm.github.io/wasm-pack/installer/init.sh -sSf | sh",
+        ),
+    }
+}
+
+// version of the rust_pixel repo at dir
+pub fn pixel_root_version(dir: &str) -> Result<String, String> {
+    let ct = fs::read_to_string(Path::new(dir).join("Cargo.toml"))
+        .map_err(|_| "Cargo.toml not found".to_string())?;
+    let doc = ct
+        .parse::<toml::Value>()
+        .map_err(|e| format!("bad Cargo.toml: {}", e))?;
+    if !is_pixel_root(&doc) {
+        return Err("not a rust_pixel repo".to_string());
+    }
+    Ok(doc
+        .get("package")
+        .and_then(|p| p.get("version"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string())
+}
+
+fn check_pixel_dirs(ctx: &PixelContext) -> Vec<Check> {
+    let mut cs = vec![];
+    if ctx.rust_pixel_dir.is_empty() {
+        cs.push(Check::fail(
+            "rust_pixel_dir",
+            "no rust_pixel repo configured",
+            "run cargo pixel in your rust_pixel checkout",
+        ));
+        return cs;
+    }
+    let current = env!("CARGO_PKG_VERSION");
+    for (i, dir) in ctx.rust_pixel_dir.iter().enumerate() {
+        let name = format!("rust_pixel_dir[{}]", i);
+        match pixel_root_version(dir) {
+            Ok(v) if v == current || i != ctx.rust_pixel_idx => {
+                cs.push(Check::pass(&name, &format!("{} ({})", dir, v)))
+            }
+            Ok(v) => cs.push(Check::fail(
+                &name,
+                &format!("{} is {}, cargo-pixel is {}", dir, v, current),
+                &format!("cd {} && cargo install --path . --force", dir),
+            )),
+            Err(e) => cs.push(Check::fail(
+                &name,
+                &format!("{}: {}", dir, e),
+                "fix or remove it in rust_pixel.toml",
+            )),
+        }
+    }
+    cs
+}
+
+fn check_context(ctx: &PixelContext) -> Vec<Check> {
+    let name = "rust_pixel.toml";
+    let mut cs = vec![];
+    if !ctx.rust_pixel_dir.is_empty() && ctx.rust_pixel_idx >= ctx.rust_pixel_dir.len() {
+        cs.push(Check::fail(
+            name,
+            &format!("rust_pixel_idx {} out of range", ctx.rust_pixel_idx),
+            "set rust_pixel_idx to 0",
+        ));
+    }
+    if ctx.cdir_state == PState::PixelProject && ctx.project_idx >= ctx.projects.len() {
+        cs.push(Check::fail(
+            name,
+            &format!("project_idx {} out of range", ctx.project_idx),
+            "set project_idx to 0",
+        ));
+    }
+    for p in ctx.projects.iter() {
+        if !Path::new(p).join("Cargo.toml").exists() {
+            cs.push(Check::fail(
+                name,
+                &format!("project {} is gone", p),
+                "remove it from projects",
+            ));
+        }
+    }
+    if cs.is_empty() {
+        cs.push(Check::pass(
+            name,
+            &format!(
+                "{} repo, {} projects, current dir {:?}",
+                ctx.rust_pixel_dir.len(),
+                ctx.projects.len(),
+                ctx.cdir_state
+            ),
+        ));
+    }
+    cs
+}
+
+// rust_pixel dependency of the current standalone project against
+// the kind recorded by cargo pixel creat --dep
+fn check_project_dep(ctx: &PixelContext) -> Option<Check> {
+    if ctx.cdir_state != PState::PixelProject {
+        return None;
+    }
+    let dir = Path::new(ctx.projects.get(ctx.project_idx)?);
+    let name = "rust_pixel dependency";
+    let doc = fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()?
+        .parse::<toml::Value>()
+        .ok()?;
+    let kind = doc
+        .get("package")
+        .and_then(|p| p.get("metadata"))
+        .and_then(|m| m.get("rust_pixel"))
+        .and_then(|r| r.get("dep"))
+        .and_then(|d| d.as_str());
+    let dep = doc.get("dependencies").and_then(|d| d.get("rust_pixel"));
+    let field = |k: &str| dep.and_then(|d| d.get(k)).and_then(|v| v.as_str());
+    let current = env!("CARGO_PKG_VERSION");
+    let missing = |k: &str| {
+        Check::fail(
+            name,
+            &format!("dep is {} but rust_pixel has no {} in Cargo.toml", k, k),
+            "fix the rust_pixel line in Cargo.toml",
+        )
+    };
+    Some(match kind {
+        None => Check::warn(
+            name,
+            "not recorded, project created before creat --dep",
+            "add dep = \"path\", \"crates\" or \"git\" to [package.metadata.rust_pixel]",
+        ),
+        Some("path") => match field("path") {
+            Some(p) => match pixel_root_version(dir.join(p).to_str().unwrap_or(p)) {
+                Ok(v) => Check::pass(name, &format!("path {} ({})", p, v)),
+                Err(e) => Check::fail(
+                    name,
+                    &format!("path {}: {}", p, e),
+                    "fix the path, or use a crates or git dependency",
+                ),
+            },
+            None => missing("path"),
+        },
+        Some("crates") => match field("version") {
+            Some(v) if v.trim_start_matches(['=', '^']) == current => {
+                Check::pass(name, &format!("crates {}", v))
+            }
+            Some(v) => Check::warn(
+                name,
+                &format!("crates {}, cargo-pixel is {}", v, current),
+                &format!("cargo install cargo-pixel --version {}", v),
+            ),
+            None => missing("version"),
+        },
+        Some("git") => match (field("git"), field("rev")) {
+            (Some(u), Some(r)) => Check::pass(name, &format!("git {}#{}", u, r)),
+            (Some(u), None) => Check::warn(
+                name,
+                &format!("git {} is not pinned", u),
+                "add rev = \"<commit>\" to the rust_pixel line",
+            ),
+            (None, _) => missing("git"),
+        },
+        Some(k) => Check::fail(
+            name,
+            &format!("unknown dep {:?}", k),
+            "use path, crates or git",
+        ),
+    })
+}
+
+// doctor subcommand entry...
+pub fn pixel_doctor(ctx: &PixelContext, _args: &ArgMatches) {
+    let mut checks = vec![check_rust(), check_sdl(), check_wasm_pack()];
+    checks.extend(check_pixel_dirs(ctx));
+    checks.extend(check_context(ctx));
+    checks.extend(check_project_dep(ctx));
+
+    let mut fails = 0;
+    for c in checks.iter() {
+        let mark = match c.level {
+            Level::Pass => "✅",
+            Level::Warn => "⚠️ ",
+            Level::Fail => {
+                fails += 1;
+                "🚫"
+            }
+        };
+        println!("{} {}: {}", mark, c.name, c.info);
+        if !c.fix.is_empty() {
+            println!("   fix: {}", c.fix);
+        }
+    }
+    if fails == 0 {
+        println!("🍭 Everything looks good.");
+    } else {
+        println!("🍭 {} problem(s) found.", fails);
+        std::process::exit(1);
+    }
+}
@@ -8,6 +8,7 @@
 /// cargo pixel run snake sdl
 /// cargo pixel creat games mygame
 /// cargo pixel build snake web
+/// cargo pixel doctor
 ///
 /// shortcut:
 /// cargo pixel r snake t
@@ -32,8 +33,13 @@ mod build_run;
 use build_run::*;
 mod creat;
 use creat::*;
+// convert_gif needs image and render::image::petii, not in base builds
+#[cfg(all(feature = "image", not(feature = "base")))]
 mod convert_gif;
+#[cfg(all(feature = "image", not(feature = "base")))]
 use convert_gif::*;
+mod doctor;
+use doctor::*;
 
 // current dir state
 // not pixel dir, rust_pixel root dir, depend rust_pixel project
@@ -71,29 +77,34 @@ fn write_config(pc: &PixelContext, config_path: &Path) {
     println!("🍭 Configuration saved to {}", config_path.display());
 }
 
-fn replace_in_files(
+// how replace_in_files fills the template app
+struct ReplaceOpts<'a> {
     is_standalone: bool,
-    dir: &Path,
-    rust_pixel_path: &str,
-    dirname: &str,
-    capname: &str,
-    upname: &str,
-    loname: &str,
-) {
+    rust_pixel_path: &'a str,
+    dep: &'a PixelDep,
+    dirname: &'a str,
+    // app name as Template, TEMPLATE and template
+    capname: &'a str,
+    upname: &'a str,
+    loname: &'a str,
+}
+
+fn replace_in_files(dir: &Path, opts: &ReplaceOpts) {
+    let ReplaceOpts {
+        is_standalone,
+        rust_pixel_path,
+        dep,
+        dirname,
+        capname,
+        upname,
+        loname,
+    } = *opts;
     if dir.is_dir() {
         for entry in fs::read_dir(dir).unwrap() {
             let entry = entry.unwrap();
             let path = entry.path();
             if path.is_dir() {
-                replace_in_files(
-                    is_standalone,
-                    &path,
-                    rust_pixel_path,
-                    dirname,
-                    capname,
-                    upname,
-                    loname,
-                );
+                replace_in_files(&path, opts);
             } else if path.is_file() {
                 let fname = path.file_name().and_then(OsStr::to_str);
                 let ext = path.extension().and_then(OsStr::to_str);
@@ -116,6 +127,7 @@ fn replace_in_files(
                         }
                     } else {
                         content_str = content_str.replace("$RUST_PIXEL_ROOT", rust_pixel_path);
+                        content_str = dep.fill(&content_str);
                         content_str = content_str.replace(
                             "pixel_game!(Template)",
                             &format!("pixel_game!(Template, \"app\", \".\")"),
@@ -155,7 +167,13 @@ fn main() {
         Some(("run", sub_m)) => pixel_run(&ctx, sub_m),
         Some(("build", sub_m)) => pixel_build(&ctx, sub_m),
         Some(("creat", sub_m)) => pixel_creat(&ctx, sub_m),
+        #[cfg(all(feature = "image", not(feature = "base")))]
         Some(("convert_gif", sub_m)) => pixel_convert_gif(&ctx, sub_m),
+        #[cfg(not(all(feature = "image", not(feature = "base"))))]
+        Some(("convert_gif", _)) => {
+            println!("🚫 convert_gif needs the image feature, not available in base builds")
+        }
+        Some(("doctor", sub_m)) => pixel_doctor(&ctx, sub_m),
         _ => {}
     }
 }
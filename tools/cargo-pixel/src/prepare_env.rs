@@ -119,8 +119,8 @@ pub fn check_pixel_env() -> PixelContext {
         let doc = ct.parse::<toml::Value>().unwrap();
 
         if let Some(package) = doc.get("package") {
-            if let Some(name) = package.get("name") {
-                if &name.to_string() == "\"rust_pixel\"" {
+            if package.get("name").is_some() {
+                if is_pixel_root(&doc) {
                     if pc.cdir_state == PState::NotPixel {
                         println!("🍭 Found a new pixel root:{:?}", cdir_s);
                         pc.cdir_state = PState::PixelRoot;
@@ -159,3 +159,10 @@ pub fn check_pixel_env() -> PixelContext {
     pc
 }
 
+// Cargo.toml of the rust_pixel repo itself
+pub fn is_pixel_root(doc: &toml::Value) -> bool {
+    doc.get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        == Some("rust_pixel")
+}
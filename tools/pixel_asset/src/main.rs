@@ -1,13 +1,18 @@
 use image::imageops::FilterType;
 use image::GenericImage;
 use image::{DynamicImage, GenericImageView, RgbaImage};
+use rust_pixel::render::image::PIX_VERSION;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::fs;
 use std::env;
 use std::io::Write;
 use std::fs::File;
 use std::path::Path;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize)]
 struct Rectangle {
     x: u32,
     y: u32,
@@ -186,11 +191,140 @@ fn adjust_size_to_multiple_of_eight(width: u32, height: u32) -> (u32, u32) {
 }
 
 struct ImageRect {
-    path: String,
     image: DynamicImage,
     rect: Rectangle,
 }
 
+/// one input image, duplicated images share the rect of the first one
+/// page is always 0, the engine loads a single texture_atlas.png
+#[derive(Serialize, Debug)]
+struct ManifestEntry {
+    file: String,
+    page: u32,
+    rect: Rectangle,
+    width: u32,
+    height: u32,
+    dedup: bool,
+}
+
+/// hash of padded image pixels, used to pack identical images only once
+fn image_hash(img: &RgbaImage) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    img.dimensions().hash(&mut hasher);
+    img.as_raw().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// padded pixels and index in packed images, several per hash on collisions
+type DedupMap = HashMap<u64, Vec<(RgbaImage, usize)>>;
+
+/// packs images into bin, returns unique packed images and a manifest entry
+/// for every input image, or the name of the first image that doesn't fit
+fn pack_images(
+    images: Vec<(String, DynamicImage)>,
+    bin: &mut MaxRectsBin,
+    dedup: &mut DedupMap,
+) -> Result<(Vec<ImageRect>, Vec<ManifestEntry>), String> {
+    let mut image_rects: Vec<ImageRect> = Vec::new();
+    let mut entries = Vec::new();
+    for img in images {
+        let (orig_width, orig_height) = img.1.dimensions();
+        let (adjusted_width, adjusted_height) =
+            adjust_size_to_multiple_of_eight(orig_width, orig_height);
+
+        let padded_image = if adjusted_width != orig_width || adjusted_height != orig_height {
+            let mut padded_image = DynamicImage::new_rgba8(adjusted_width, adjusted_height);
+            padded_image.copy_from(&img.1, 0, 0).unwrap();
+            (img.0, padded_image)
+        } else {
+            img
+        };
+
+        // equal hashes are confirmed by comparing the pixels
+        let pixels = padded_image.1.to_rgba8();
+        let hash = image_hash(&pixels);
+        let same = dedup
+            .get(&hash)
+            .and_then(|v| v.iter().find(|(p, _)| *p == pixels))
+            .map(|(_, idx)| *idx);
+        if let Some(idx) = same {
+            entries.push(ManifestEntry {
+                file: padded_image.0,
+                page: 0,
+                rect: image_rects[idx].rect,
+                width: orig_width,
+                height: orig_height,
+                dedup: true,
+            });
+            continue;
+        }
+
+        let padded_image = (
+            padded_image.0,
+            padded_image.1.resize_exact(
+                adjusted_width / 2,
+                adjusted_height / 2,
+                FilterType::Lanczos3,
+            ),
+        );
+
+        let rect = bin
+            .insert(adjusted_width / 2, adjusted_height / 2)
+            .ok_or(padded_image.0.clone())?;
+        dedup
+            .entry(hash)
+            .or_default()
+            .push((pixels, image_rects.len()));
+        entries.push(ManifestEntry {
+            file: padded_image.0,
+            page: 0,
+            rect,
+            width: orig_width,
+            height: orig_height,
+            dedup: false,
+        });
+        image_rects.push(ImageRect {
+            image: padded_image.1,
+            rect,
+        });
+    }
+    Ok((image_rects, entries))
+}
+
+/// writes a .pix file for every input image, duplicates point to the shared rect
+fn generate_pix_files(dst_dir: &str, entries: &[ManifestEntry]) {
+    for entry in entries {
+        let x0 = entry.rect.x / 8;
+        let y0 = entry.rect.y / 8;
+        let w = entry.rect.width / 8;
+        let h = entry.rect.height / 8;
+        let pathp = Path::new(&format!("{}/{}", dst_dir, entry.file)).with_extension("pix");
+        let mut file = File::create(pathp).unwrap();
+        let line = &format!(
+            "version={}\nwidth={},height={},texture=255\n",
+            PIX_VERSION, w, h
+        );
+        file.write_all(line.as_bytes()).unwrap();
+
+        for a in 0..h {
+            for b in 0..w {
+                let x = x0 + b;
+                let y = y0 + a;
+                let s = y % 16 * 16 + x % 16;
+                let t = y / 16 * 8 + x / 16;
+                let line = &format!("{},{},{},{} ", s, 15, t + 8, 0);
+                file.write_all(line.as_bytes()).unwrap();
+            }
+            file.write_all("\n".as_bytes()).unwrap();
+        }
+    }
+}
+
+fn write_manifest(dst_dir: &str, entries: &[ManifestEntry]) {
+    let json = serde_json::to_string_pretty(entries).unwrap();
+    fs::write(format!("{}/manifest.json", dst_dir), json).unwrap();
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
 
@@ -225,70 +359,133 @@ fn main() {
     }
 
     let mut bin = MaxRectsBin::new(atlas_width, atlas_height);
-    let mut image_rects = Vec::new();
-    for img in images {
-        let (orig_width, orig_height) = img.1.dimensions();
-        let (adjusted_width, adjusted_height) =
-            adjust_size_to_multiple_of_eight(orig_width, orig_height);
-
-        let padded_image = if adjusted_width != orig_width || adjusted_height != orig_height {
-            let mut padded_image = DynamicImage::new_rgba8(adjusted_width, adjusted_height);
-            padded_image.copy_from(&img.1, 0, 0).unwrap();
-            (img.0, padded_image)
-        } else {
-            img
-        };
-
-        let padded_image = (
-            padded_image.0,
-            padded_image.1.resize_exact(
-                adjusted_width / 2,
-                adjusted_height / 2,
-                FilterType::Lanczos3,
-            ),
-        );
-
-        if let Some(rect) = bin.insert(adjusted_width / 2, adjusted_height / 2) {
-            image_rects.push(ImageRect {
-                path: padded_image.0.to_string(),
-                image: padded_image.1,
-                rect,
-            });
-        } else {
-            println!("No Space");
+    let mut dedup = HashMap::new();
+    let (image_rects, entries) = match pack_images(images, &mut bin, &mut dedup) {
+        Ok(packed) => packed,
+        Err(file) => {
+            eprintln!("No Space: {} doesn't fit in the texture atlas", file);
+            std::process::exit(1);
         }
-    }
+    };
 
     let mut atlas = RgbaImage::new(atlas_width, atlas_height + 128);
     atlas.copy_from(&rawimage, 0, 0).unwrap();
 
     for image_rect in &image_rects {
-        atlas
-            .copy_from(&image_rect.image, image_rect.rect.x, image_rect.rect.y + 128)
-            .unwrap();
+        let (x, y) = (image_rect.rect.x, image_rect.rect.y + 128);
+        atlas.copy_from(&image_rect.image, x, y).unwrap();
     }
-    atlas.save(&format!("{}/texture_atlas.png", dst_dir)).unwrap();
-
-    for (_i, image_rect) in image_rects.iter().enumerate() {
-        let x0 = image_rect.rect.x / 8;
-        let y0 = image_rect.rect.y / 8;
-        let w = image_rect.rect.width / 8;
-        let h = image_rect.rect.height / 8;
-        let pathp = Path::new(&format!("{}/{}", dst_dir, image_rect.path)).with_extension("pix");
-        let mut file = File::create(pathp).unwrap();
-        let line = &format!("width={},height={},texture=255\n", w, h);
-        file.write_all(line.as_bytes()).unwrap();
+    atlas
+        .save(format!("{}/texture_atlas.png", dst_dir))
+        .unwrap();
 
-        for a in 0..h {
-            for b in 0..w {
-                let x = x0 + b;
-                let y = y0 + a;
-                let s = y % 16 * 16 + x % 16;
-                let t = y / 16 * 8 + x / 16;
-                let line = &format!("{},{},{},{} ", s, 15, t + 8, 0);
-                file.write_all(line.as_bytes()).unwrap();
-            }
-            file.write_all("\n".as_bytes()).unwrap();
+    generate_pix_files(dst_dir, &entries);
+    write_manifest(dst_dir, &entries);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiny(seed: u8) -> DynamicImage {
+        let mut img = RgbaImage::new(8, 8);
+        for (i, p) in img.pixels_mut().enumerate() {
+            *p = image::Rgba([seed, i as u8, 255 - seed, 255]);
         }
+        DynamicImage::ImageRgba8(img)
+    }
+
+    #[test]
+    fn identical_images_packed_once() {
+        let images = vec![
+            ("a.png".to_string(), tiny(1)),
+            ("b.png".to_string(), tiny(1)),
+            ("c.png".to_string(), tiny(2)),
+        ];
+        let mut bin = MaxRectsBin::new(64, 64);
+        let mut dedup = HashMap::new();
+        let (image_rects, entries) = pack_images(images, &mut bin, &mut dedup).unwrap();
+        assert_eq!(image_rects.len(), 2);
+        assert_eq!(bin.used_rects.len(), 2);
+        assert_eq!(entries.len(), 3);
+        assert!(!entries[0].dedup);
+        assert!(entries[1].dedup);
+        assert!(!entries[2].dedup);
+        assert_eq!(entries[1].rect.x, entries[0].rect.x);
+        assert_eq!(entries[1].rect.y, entries[0].rect.y);
+
+        let dir = env::temp_dir().join("pixel_asset_dedup_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let dst = dir.to_str().unwrap();
+        generate_pix_files(dst, &entries);
+        write_manifest(dst, &entries);
+        let pa = fs::read_to_string(dir.join("a.pix")).unwrap();
+        let pb = fs::read_to_string(dir.join("b.pix")).unwrap();
+        assert_eq!(pa, pb);
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("manifest.json")).unwrap()).unwrap();
+        assert_eq!(manifest[1]["file"], "b.png");
+        assert_eq!(manifest[1]["dedup"], true);
+        assert_eq!(manifest[0]["dedup"], false);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn hash_collision_is_not_dedup() {
+        let (a, b) = (tiny(1), tiny(2));
+        let mut dedup: DedupMap = HashMap::new();
+        // fake a collision: b's hash already holds a's pixels
+        dedup
+            .entry(image_hash(&b.to_rgba8()))
+            .or_default()
+            .push((a.to_rgba8(), 0));
+        let mut bin = MaxRectsBin::new(64, 64);
+        let images = vec![("b.png".to_string(), b)];
+        let (_, entries) = pack_images(images, &mut bin, &mut dedup).unwrap();
+        assert!(!entries[0].dedup);
+    }
+
+    #[test]
+    fn full_atlas_fails_loudly() {
+        let cell = |seed: u8| tiny(seed).resize_exact(16, 16, FilterType::Nearest);
+        // a 16x16 image packs to one 8x8 cell, the atlas holds only one
+        let images = vec![
+            ("a.png".to_string(), cell(1)),
+            ("c.png".to_string(), cell(1)),
+            ("b.png".to_string(), cell(2)),
+        ];
+        let mut bin = MaxRectsBin::new(8, 8);
+        let mut dedup = HashMap::new();
+        assert_eq!(
+            pack_images(images[..2].to_vec(), &mut bin, &mut dedup)
+                .map(|(r, e)| (r.len(), e.len())),
+            Ok((1, 2))
+        );
+
+        let mut bin = MaxRectsBin::new(8, 8);
+        let mut dedup = HashMap::new();
+        assert_eq!(
+            pack_images(images.clone(), &mut bin, &mut dedup).err(),
+            Some("b.png".to_string())
+        );
+
+        // the image that fits points at the first cell after symbols.png
+        let mut bin = MaxRectsBin::new(8, 8);
+        let mut dedup = HashMap::new();
+        let (_, entries) = pack_images(images[..1].to_vec(), &mut bin, &mut dedup).unwrap();
+        let dir = env::temp_dir().join("pixel_asset_full_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        generate_pix_files(dir.to_str().unwrap(), &entries);
+        let pa = fs::read_to_string(dir.join("a.pix")).unwrap();
+        assert_eq!(
+            pa,
+            format!(
+                "version={}\nwidth=1,height=1,texture=255\n0,15,8,0 \n",
+                PIX_VERSION
+            )
+        );
+        let _ = fs::remove_dir_all(&dir);
     }
 }
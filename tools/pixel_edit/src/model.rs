@@ -185,6 +185,7 @@ impl Model for TeditModel {
                         _ => {}
                     }
                 }
+                _ => {}
             }
         }
         context.input_events.clear();
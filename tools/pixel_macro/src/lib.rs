@@ -57,7 +57,9 @@ pub fn pixel_game(input: TokenStream) -> TokenStream {
             use rust_pixel::util::get_project_path;
 
             #[cfg(target_arch = "wasm32")]
-            use rust_pixel::render::adapter::web::{input_events_from_web, WebAdapter};
+            use rust_pixel::audio::web_audio_take;
+            #[cfg(target_arch = "wasm32")]
+            use rust_pixel::render::adapter::web::WebAdapter;
             use wasm_bindgen::prelude::*;
             #[cfg(target_arch = "wasm32")]
             use wasm_bindgen_futures::js_sys;
@@ -87,21 +89,22 @@ pub fn pixel_game(input: TokenStream) -> TokenStream {
                 }
 
                 pub fn tick(&mut self, dt: f32) {
+                    // events pushed by key_event since the last tick
+                    self.g
+                        .context
+                        .adapter
+                        .poll_event(std::time::Duration::ZERO, &mut self.g.context.input_events);
                     self.g.on_tick(dt);
                 }
 
                 pub fn key_event(&mut self, t: u8, e: web_sys::Event) {
-                    let abase = &self
-                        .g
+                    self.g
                         .context
                         .adapter
                         .as_any()
-                        .downcast_ref::<WebAdapter>()
+                        .downcast_mut::<WebAdapter>()
                         .unwrap()
-                        .base;
-                    if let Some(pe) = input_events_from_web(t, e, abase.pixel_h, abase.ratio_x, abase.ratio_y) {
-                        self.g.context.input_events.push(pe);
-                    }
+                        .push_event(t, e);
                 }
 
                 pub fn upload_imgdata(&mut self, w: i32, h: i32, d: &js_sys::Uint8ClampedArray) {
@@ -126,6 +129,40 @@ pub fn pixel_game(input: TokenStream) -> TokenStream {
                     self.g.context.asset_manager.set_data(url, data);
                 }
 
+                /// clips queued by Audio::play_file since the last call,
+                /// flat pairs [clip, is_loop, clip, is_loop, ...] for the web audio api
+                pub fn take_audio(&mut self) -> js_sys::Array {
+                    let arr = js_sys::Array::new();
+                    for a in web_audio_take() {
+                        arr.push(&JsValue::from_str(&a.clip));
+                        arr.push(&JsValue::from_bool(a.is_loop));
+                    }
+                    arr
+                }
+
+                /// cells changed in the last tick, see WebAdapter::web_dirty_floats
+                pub fn web_dirty(&mut self) -> js_sys::Float32Array {
+                    let wa = self
+                        .g
+                        .context
+                        .adapter
+                        .as_any()
+                        .downcast_mut::<WebAdapter>()
+                        .unwrap();
+                    js_sys::Float32Array::from(&wa.web_dirty_floats()[..])
+                }
+
+                /// total render cell count, the js instance buffer size
+                pub fn web_cells_len(&mut self) -> usize {
+                    self.g
+                        .context
+                        .adapter
+                        .as_any()
+                        .downcast_mut::<WebAdapter>()
+                        .unwrap()
+                        .web_cells_len()
+                }
+
                 pub fn get_ratiox(&mut self) -> f32 {
                     self.g.context.adapter.get_base().ratio_x
                 }
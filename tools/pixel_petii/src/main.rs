@@ -1,443 +1,244 @@
 // https://github.com/JuliaPoo/AsciiArtist
 // https://github.com/EgonOlsen71/petsciiator
-
-mod c64;
-use c64::{C64LOW, C64UP};
-use deltae::*;
-use image::{DynamicImage, GenericImageView, ImageBuffer, Luma};
-use lab::Lab;
-use rust_pixel::render::style::ANSI_COLOR_RGB;
-use std::collections::HashMap;
+//
+// conversion is done in rust_pixel::render::image::petii,
+// shared with cargo pixel convert_gif
+//
+// an animated gif input is converted frame by frame, writing
+// <name>_<n>.pix and manifest.json into <name>/ beside the input,
+// or into the --output directory, the written paths are printed
+//
+// --json prints {"width","height","cells":[{"sym","fg","tex","bg"}]}
+// instead of pix (the manifest for a gif), --output writes it to a file
+// (for a gif it is the frame directory),
+// usage and errors go to stderr so stdout stays clean for scripts
+
+use rust_pixel::render::image::petii::{
+    decode_gif_frames, petii_convert, petii_to_pix, PetiiCell, PetiiConfig,
+};
 use std::env;
-use std::path::Path;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// converts every gif frame to a pix file in out_dir,
+/// returns the written pix file names in frame order
+fn gif_to_pix_frames(
+    data: &[u8],
+    cfg: &PetiiConfig,
+    out_dir: &Path,
+    name: &str,
+) -> io::Result<Vec<String>> {
+    let frames =
+        decode_gif_frames(data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::create_dir_all(out_dir)?;
+    let mut files = vec![];
+    let mut manifest = vec![];
+    for (i, (img, delay_ms)) in frames.into_iter().enumerate() {
+        let cells = petii_convert(&img, cfg);
+        let file = format!("{}_{}.pix", name, i);
+        fs::write(out_dir.join(&file), petii_to_pix(&cells, cfg))?;
+        manifest.push(serde_json::json!({
+            "file": file,
+            "delay_ms": delay_ms,
+        }));
+        files.push(file);
+    }
+    let manifest = serde_json::json!({
+        "width": cfg.width,
+        "height": cfg.height,
+        "frames": manifest,
+    });
+    fs::write(
+        out_dir.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest).unwrap(),
+    )?;
+    Ok(files)
+}
+
+/// gif frames go to --output if given, else to <name>/ beside the input
+fn gif_out_dir(input: &Path, output: &Option<String>) -> PathBuf {
+    match output {
+        Some(dir) => PathBuf::from(dir),
+        None => input.with_extension(""),
+    }
+}
+
+fn petii_json(cells: &[PetiiCell], cfg: &PetiiConfig) -> serde_json::Value {
+    serde_json::json!({
+        "width": cfg.width,
+        "height": cfg.height,
+        "cells": cells,
+    })
+}
 
-// gray 8x8 image...
-type Image8x8 = Vec<Vec<u8>>;
-struct RGB {
-    r: u8,
-    g: u8,
-    b: u8,
+/// --json, --version and --output <file> are taken out of args
+#[derive(Default)]
+struct Flags {
+    json: bool,
+    version: bool,
+    output: Option<String>,
+}
+
+fn take_flags(args: &mut Vec<String>) -> Flags {
+    let mut flags = Flags::default();
+    let mut it = std::mem::take(args).into_iter();
+    while let Some(a) = it.next() {
+        match a.as_str() {
+            "--json" => flags.json = true,
+            "--version" => flags.version = true,
+            "--output" => flags.output = it.next(),
+            _ => args.push(a),
+        }
+    }
+    flags
+}
+
+fn write_output(output: &Option<String>, s: &str) {
+    match output {
+        Some(f) => fs::write(f, s).expect("Failed to write the output file"),
+        None => print!("{}", s),
+    }
 }
 
 fn main() {
-    let input_image_path;
-    let mut width: u32 = 40;
-    let mut height: u32 = 25;
-    let mut is_petii: bool = false;
+    let mut cfg = PetiiConfig::default();
 
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let flags = take_flags(&mut args);
+    if flags.version {
+        println!("pixel_petii {}", env!("CARGO_PKG_VERSION"));
+        return;
+    }
 
     match args.len() {
         2 | 4 | 5 | 9 => {}
         _ => {
-            println!("Usage: pixel_petii <image file path> [<width>] [<height>] [<is_petscii>]");
+            eprintln!("Usage: pixel_petii [--json] [--output <file>] <image file path> [<width>] [<height>] [<is_petscii>]");
+            eprintln!("       animated gif writes <name>_<n>.pix and manifest.json into <name>/ beside it, or into --output <dir>");
             return;
         }
     }
-    input_image_path = Path::new(&args[1]);
-    let mut img = image::open(&input_image_path).expect("Failed to open the input image");
+    let input_image_path = Path::new(&args[1]);
     if args.len() > 2 {
-        width = args[2].parse().unwrap();
-        height = args[3].parse().unwrap();
+        cfg.width = args[2].parse().unwrap();
+        cfg.height = args[3].parse().unwrap();
     }
     if args.len() > 4 {
-        is_petii = args[4].parse().unwrap();
+        cfg.is_petii = args[4].parse().unwrap();
     }
     if args.len() == 9 {
         let cx = args[5].parse().unwrap();
         let cy = args[6].parse().unwrap();
         let cw = args[7].parse().unwrap();
         let ch = args[8].parse().unwrap();
-        img = img.crop(cx, cy, cw, ch);
-        img.save("tmp/out0.png").unwrap();
-    }
-
-    let resized_img =
-        img.resize_exact(width * 8, height * 8, image::imageops::FilterType::Lanczos3);
-    resized_img.save("tmp/out1.png").unwrap();
-    let gray_img = resized_img.clone().into_luma8();
-    gray_img.save("tmp/out2.png").unwrap();
-
-    // get petscii images...
-    let vcs = gen_charset_images(false);
-
-    // find background color...
-    let bret = find_background_color(&resized_img, &gray_img, width * 8, height * 8);
-    let back_gray = bret.0;
-    let back_rgb = bret.1;
-
-    println!("width={},height={},texture=255", width, height);
-    for i in 0..height {
-        for j in 0..width {
-            let block_at = get_block_at(&gray_img, j, i);
-            let bm = find_best_match(&block_at, &vcs, back_gray, is_petii);
-            if !is_petii {
-                let block_color = get_block_color(&resized_img, j, i);
-                let bc = find_best_color(block_color);
-                print!("{},{},1 ", bm, bc,);
-            } else {
-                let bc = get_petii_block_color(&resized_img, &gray_img, j, i, back_rgb);
-                // sym, fg, tex, bg
-                print!("{},{},1,{} ", bm, bc.1, bc.0);
-            }
-        }
-        println!("");
-    }
-}
-
-// get color distance
-fn color_distance(e1: &RGB, e2: &RGB) -> f32 {
-    let l1 = Lab::from_rgb(&[e1.r, e1.g, e1.b]);
-    let l2 = Lab::from_rgb(&[e2.r, e2.g, e2.b]);
-    let lab1 = LabValue {
-        l: l1.l,
-        a: l1.a,
-        b: l1.b,
-    };
-    let lab2 = LabValue {
-        l: l2.l,
-        a: l2.a,
-        b: l2.b,
-    };
-    *DeltaE::new(&lab1, &lab2, DE2000).value()
-}
-
-// generate 256 petscii image with 0 and 255
-fn gen_charset_images(low_up: bool) -> Vec<Image8x8> {
-    let data = if low_up { &C64LOW } else { &C64UP };
-    let mut vcs = vec![vec![vec![0u8; 8]; 8]; 256];
-
-    for i in 0..128 {
-        for row in 0..8 {
-            for bit in 0..8 {
-                if data[i][row] >> bit & 1 == 1 {
-                    vcs[i][row][7 - bit] = 255;
-                    vcs[128 + i][row][7 - bit] = 0;
-                } else {
-                    vcs[i][row][7 - bit] = 0;
-                    vcs[128 + i][row][7 - bit] = 255;
-                }
-            }
+        cfg.crop = Some((cx, cy, cw, ch));
+    }
+
+    let is_gif = input_image_path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("gif"));
+    if is_gif {
+        let name = input_image_path.file_stem().unwrap().to_str().unwrap();
+        let out_dir = gif_out_dir(input_image_path, &flags.output);
+        let data = fs::read(input_image_path).expect("Failed to open the input image");
+        let files =
+            gif_to_pix_frames(&data, &cfg, &out_dir, name).expect("Failed to convert gif frames");
+        if flags.json {
+            let manifest = fs::read_to_string(out_dir.join("manifest.json"))
+                .expect("Failed to read the manifest");
+            print!("{}", manifest);
+            return;
         }
-    }
-    vcs
-}
-
-// find background colors...
-fn find_background_color(
-    img: &DynamicImage,
-    image: &ImageBuffer<Luma<u8>, Vec<u8>>,
-    w: u32,
-    h: u32,
-) -> (u8, u32) {
-    // (first_x, first_y, count)
-    let mut cc: HashMap<u32, (u32, u32, u32)> = HashMap::new();
-    for i in 0..h {
-        for j in 0..w {
-            let p = img.get_pixel(j, i);
-            let k: u32 = ((p[0] as u32) << 24)
-                + ((p[1] as u32) << 16)
-                + ((p[2] as u32) << 8)
-                + (p[3] as u32);
-            (*cc.entry(k).or_insert((j, i, 0))).2 += 1;
+        for f in files {
+            println!("{}", out_dir.join(f).display());
         }
+        return;
     }
-    let mut cv: Vec<_> = cc.iter().collect();
-    cv.sort_by(|b, a| (&a.1 .2).cmp(&b.1 .2));
-    let bx = cv[0].1 .0;
-    let by = cv[0].1 .1;
-    let bc = cv[0].0;
-    // for c in cv {
-    //     println!("cc..{:x} {:?}", c.0, c.1);
-    // }
-    // for i in 0..h {
-    //     for j in 0..w {
-    //         print!("{:?} ", image.get_pixel(j, i).0[0]);
-    //     }
-    //     println!("");
-    // }
-    let gray = image.get_pixel(bx, by).0[0];
-    // println!("gray..{}", gray);
-    (gray, *bc)
-}
 
-// get petscii block color
-fn get_petii_block_color(
-    image: &DynamicImage,
-    img: &ImageBuffer<Luma<u8>, Vec<u8>>,
-    x: u32,
-    y: u32,
-    back_rgb: u32,
-) -> (usize, usize) {
-    let mut cc: HashMap<u32, (u32, u32)> = HashMap::new();
-    for i in 0..8usize {
-        for j in 0..8usize {
-            let pixel_x = x * 8 + j as u32;
-            let pixel_y = y * 8 + i as u32;
-            if pixel_x < image.width() && pixel_y < image.height() {
-                let p = image.get_pixel(pixel_x, pixel_y);
-                let k: u32 = ((p[0] as u32) << 24)
-                    + ((p[1] as u32) << 16)
-                    + ((p[2] as u32) << 8)
-                    + (p[3] as u32);
-                cc.entry(k).or_insert((pixel_x, pixel_y));
-            }
-        }
-    }
-    let cv: Vec<_> = cc.iter().collect();
-    let mut include_back = false;
-    let clen = cv.len();
-    for c in &cv {
-        if *c.0 == back_rgb {
-            include_back = true;
-        }
-    }
-    let mut ret = None;
-    if include_back {
-        if clen == 1 {
-            ret = Some((back_rgb, back_rgb));
-            // println!("<B>{:?}", ret);
-        } else if clen == 2 {
-            let mut r = (back_rgb, back_rgb);
-            if *cv[0].0 != back_rgb {
-                r.1 = *cv[0].0;
-            } 
-            if *cv[1].0 != back_rgb {
-                r.1 = *cv[1].0;
-            } 
-            ret = Some(r);
-            // println!("<B,F>{:?}", ret);
-        } else {
-            println!("ERROR!!!");
-        }
+    let img = image::open(input_image_path).expect("Failed to open the input image");
+    let cells = petii_convert(&img, &cfg);
+    if flags.json {
+        write_output(&flags.output, &petii_json(&cells, &cfg).to_string());
     } else {
-        if clen == 1 {
-            ret = Some((*cv[0].0, *cv[0].0));
-            // println!("<F>{:?}", ret);
-        } else if clen == 2 {
-            let g0 = img.get_pixel(cv[0].1.0, cv[0].1.1).0[0];
-            let g1 = img.get_pixel(cv[1].1.0, cv[1].1.1).0[0];
-            if g0 <= g1 {
-                ret = Some((*cv[0].0, *cv[1].0));
-            } else {
-                ret = Some((*cv[1].0, *cv[0].0));
-            }
-            // println!("<F1,F2>{:?}", ret);
-        } else {
-            println!("ERROR2!!!");
-        }
-    }
-    match ret {
-        Some(r) => {
-            (find_best_color_u32(r.0), find_best_color_u32(r.1))
-        }
-        _ => {
-            (0, 0)
-        }
-    }
-}
-
-// get block average color(for not petscii image)
-fn get_block_color(image: &DynamicImage, x: u32, y: u32) -> RGB {
-    let mut r = 0u32;
-    let mut g = 0u32;
-    let mut b = 0u32;
-
-    let mut count = 0u32;
-
-    for i in 0..8usize {
-        for j in 0..8usize {
-            let pixel_x = x * 8 + j as u32;
-            let pixel_y = y * 8 + i as u32;
-
-            if pixel_x < image.width() && pixel_y < image.height() {
-                let p = image.get_pixel(pixel_x, pixel_y);
-                if p[0] != 0 || p[1] != 0 || p[2] != 0 {
-                    r += p[0] as u32;
-                    g += p[1] as u32;
-                    b += p[2] as u32;
-                    count += 1;
-                }
-            }
-        }
-    }
-
-    if count == 0 {
-        return RGB { r: 0, g: 0, b: 0 };
-    }
-
-    RGB {
-        r: (r / count) as u8,
-        g: (g / count) as u8,
-        b: (b / count) as u8,
-    }
-}
-
-fn get_block_at(image: &ImageBuffer<Luma<u8>, Vec<u8>>, x: u32, y: u32) -> Image8x8 {
-    let mut block = vec![vec![0u8; 8]; 8];
-
-    for i in 0..8usize {
-        for j in 0..8usize {
-            let pixel_x = x * 8 + j as u32;
-            let pixel_y = y * 8 + i as u32;
-
-            if pixel_x < image.width() && pixel_y < image.height() {
-                block[i][j] = image.get_pixel(pixel_x, pixel_y).0[0];
-            }
-        }
+        write_output(&flags.output, &petii_to_pix(&cells, &cfg));
     }
-
-    block
 }
 
-fn find_best_match(
-    input_image: &Image8x8,
-    char_images: &[Image8x8],
-    back: u8,
-    is_petii: bool,
-) -> usize {
-    let mut min_mse = f64::MAX;
-    let mut best_match = 0;
-
-    for (i, char_image) in char_images.iter().enumerate() {
-        let mse = calculate_mse(input_image, char_image, back, is_petii);
-        // println!("i..{} mse..{}", i, mse);
-
-        if mse < min_mse {
-            min_mse = mse;
-            best_match = i;
-        }
-    }
-
-    best_match
-}
-
-fn find_best_color_u32(c: u32) -> usize {
-    find_best_color(RGB{
-        r: (c >> 24) as u8,
-        g: (c >> 16) as u8,
-        b: (c >> 8) as u8,
-    })
-}
-
-fn find_best_color(color: RGB) -> usize {
-    let mut min_mse = f32::MAX;
-    let mut best_match = 0;
-
-    for (i, pcolor) in ANSI_COLOR_RGB.iter().enumerate() {
-        let pcrgb = RGB {
-            r: pcolor[0],
-            g: pcolor[1],
-            b: pcolor[2],
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, DynamicImage, Frame, Rgba, RgbaImage};
+
+    #[test]
+    fn gif_frames_to_pix_files() {
+        let mut gif = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut gif);
+            for i in 0..3u32 {
+                let img = RgbaImage::from_pixel(16, 16, Rgba([(i * 80) as u8, 0, 0, 255]));
+                let frame = Frame::from_parts(img, 0, 0, Delay::from_numer_denom_ms(100, 1));
+                encoder.encode_frame(frame).unwrap();
+            }
+        }
+        let cfg = PetiiConfig {
+            width: 2,
+            height: 2,
+            ..Default::default()
         };
-        let mse = color_distance(&pcrgb, &color);
-
-        if mse < min_mse {
-            min_mse = mse;
-            best_match = i;
-        }
-    }
-
-    best_match
-}
-
-fn calc_eigenvector(img: &Image8x8, back: u8, is_petii: bool, is_source: bool) -> Vec<i32> {
-    let mut v = vec![0i32; 10];
-    let mut min = u8::MAX;
-    let mut max = 0u8;
-    let mut include_back = false;
-
-    // find min & max gray value...
-    if is_petii {
-        for x in 0..8 {
-            for y in 0..8 {
-                let p = img[y][x];
-                if !include_back {
-                    if p == back {
-                        include_back = true;
-                    }
-                }
-                if p > max {
-                    max = p;
-                }
-                if p < min {
-                    min = p;
-                }
-            }
-        }
-    }
-
-    for x in 0..8 {
-        for y in 0..8 {
-            let p;
-            if is_petii {
-                // gray image8x8 binarization...
-                let iyx = img[y][x];
-                if is_source {
-                    // for petscii source...
-                    p = if iyx == 0 { 0i32 } else { 1i32 };
-                } else {
-                    if include_back {
-                        // if block include back colors...
-                        p = if iyx == back { 0i32 } else { 1i32 };
-                    } else {
-                        if min == max {
-                            // if only 1 color...
-                            p = 1i32;
-                        } else {
-                            // min to 0 and max to 1...
-                            p = if iyx == min { 0i32 } else { 1i32 };
-                        }
-                    }
-                }
-            } else {
-                // normal image...
-                p = img[y][x] as i32;
-            }
-
-            if x < 4 && y < 4 {
-                v[0] += p;
-            }
-            if x > 3 && y < 4 {
-                v[1] += p;
-            }
-            if x < 4 && y > 3 {
-                v[2] += p;
-            }
-            if x > 3 && y > 3 {
-                v[3] += p;
-            }
-            if x > 2 && x < 6 && y > 2 && y < 6 {
-                v[4] += p;
-            }
-            if x == y || x == (7 - y) {
-                v[5] += p;
-            }
-            if x == 0 {
-                v[6] += p;
-            }
-            if x == 7 {
-                v[7] += p;
-            }
-            if y == 0 {
-                v[8] += p;
-            }
-            if y == 7 {
-                v[9] += p;
-            }
-        }
-    }
-    v
-}
-
-fn calculate_mse(img1: &Image8x8, img2: &Image8x8, back: u8, is_petii: bool) -> f64 {
-    let mut mse = 0.0f64;
-    let v1 = calc_eigenvector(img1, back, is_petii, false);
-    let v2 = calc_eigenvector(img2, back, is_petii, true);
-    // println!("input......{:?}", v1);
-    // println!("petii......{:?}", v2);
-    for i in 0..10usize {
-        mse += ((v1[i] - v2[i]) * (v1[i] - v2[i])) as f64;
+        let dir = env::temp_dir().join("pixel_petii_gif_test");
+        let _ = fs::remove_dir_all(&dir);
+        let files = gif_to_pix_frames(&gif, &cfg, &dir, "anim").unwrap();
+        assert_eq!(files, vec!["anim_0.pix", "anim_1.pix", "anim_2.pix"]);
+        for f in &files {
+            let pix = fs::read_to_string(dir.join(f)).unwrap();
+            assert!(pix.contains("width=2,height=2,texture=255"));
+        }
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("manifest.json")).unwrap()).unwrap();
+        assert_eq!(manifest["frames"].as_array().unwrap().len(), 3);
+        assert_eq!(manifest["frames"][2]["file"], "anim_2.pix");
+        assert_eq!(manifest["frames"][2]["delay_ms"], 100.0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn gif_frames_beside_input() {
+        let input = Path::new("assets").join("walk.gif");
+        assert_eq!(gif_out_dir(&input, &None), Path::new("assets").join("walk"));
+        let out = Some("frames".to_string());
+        assert_eq!(gif_out_dir(&input, &out), PathBuf::from("frames"));
+    }
+
+    #[test]
+    fn json_output_schema() {
+        let mut args: Vec<String> = ["pixel_petii", "--json", "a.png", "--output", "o.json"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let flags = take_flags(&mut args);
+        assert!(flags.json && !flags.version);
+        assert_eq!(flags.output.as_deref(), Some("o.json"));
+        assert_eq!(args, ["pixel_petii", "a.png"]);
+
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(24, 16, Rgba([200, 30, 30, 255])));
+        let cfg = PetiiConfig {
+            width: 3,
+            height: 2,
+            ..Default::default()
+        };
+        let cells = petii_convert(&img, &cfg);
+        let v: serde_json::Value =
+            serde_json::from_str(&petii_json(&cells, &cfg).to_string()).unwrap();
+        assert_eq!(v["width"], 3);
+        assert_eq!(v["height"], 2);
+        let cs = v["cells"].as_array().unwrap();
+        assert_eq!(cs.len(), 6);
+        for k in ["sym", "fg", "tex", "bg"] {
+            assert!(cs[0][k].is_u64(), "{}", k);
+        }
+        let back: Vec<PetiiCell> = serde_json::from_value(v["cells"].clone()).unwrap();
+        assert_eq!(back, cells);
     }
-    mse.sqrt()
 }
@@ -1,15 +1,218 @@
-use deltae::*;
 use image::{DynamicImage, GenericImageView, ImageBuffer, Rgba};
-use lab::Lab;
-use rust_pixel::render::style::ANSI_COLOR_RGB;
+use rust_pixel::render::{
+    style::ANSI_COLOR_RGB,
+    symbols::{find_background_color, Symbol},
+};
 use std::collections::HashMap;
 use std::env;
+use std::fs;
 use std::path::Path;
 
-struct RGB {
-    r: u8,
-    g: u8,
-    b: u8,
+/// symbols found in an image, every cell points to one of them
+struct SymbolResult {
+    grid_w: u32,
+    grid_h: u32,
+    symsize: u32,
+    // bitmaps in order of first appearance
+    symbols: Vec<Vec<Vec<u8>>>,
+    // (symbol index, fg, bg) of every cell, row by row
+    cells: Vec<(usize, usize, usize)>,
+}
+
+/// splits img into width * height blocks of symsize pixels
+fn scan_symbols(img: &DynamicImage, symsize: u32, width: u32, height: u32) -> SymbolResult {
+    // key: binary image, value: symbol index
+    let mut symbol_map = HashMap::new();
+    let mut symbols = vec![];
+    let mut cells = vec![];
+
+    // count pixels for dig background color
+    let back_color = find_background_color(img, width * symsize, height * symsize).rgba;
+
+    // scan blocks
+    for i in 0..height {
+        for j in 0..width {
+            let (bg, fg, block) = process_block(img, symsize as usize, j, i, back_color);
+            let idx = *symbol_map.entry(block.clone()).or_insert_with(|| {
+                symbols.push(block);
+                symbols.len() - 1
+            });
+            cells.push((idx, fg, bg));
+        }
+    }
+    SymbolResult {
+        grid_w: width,
+        grid_h: height,
+        symsize,
+        symbols,
+        cells,
+    }
+}
+
+/// symbols.png holds 128 * 128 symbols in 8 * 8 textures of 16 * 16 symbols,
+/// returns their bitmaps scaled to symsize, index is texidx * 256 + symidx
+fn load_symbol_set(img: &DynamicImage, symsize: u32) -> Vec<Vec<Vec<u8>>> {
+    let tsize = img.width() / 128;
+    let mut set = vec![];
+    for n in 0..128 * 128 {
+        let (texidx, symidx) = (n / 256, n % 256);
+        let x0 = (symidx % 16 + texidx % 8 * 16) * tsize;
+        let y0 = (symidx / 16 + texidx / 8 * 16) * tsize;
+        let mut block = vec![vec![0u8; symsize as usize]; symsize as usize];
+        for (y, row) in block.iter_mut().enumerate() {
+            for (x, b) in row.iter_mut().enumerate() {
+                let p = img.get_pixel(
+                    x0 + x as u32 * tsize / symsize,
+                    y0 + y as u32 * tsize / symsize,
+                );
+                // white on black or on transparent
+                if p[3] >= 128 && p[0].max(p[1]).max(p[2]) >= 128 {
+                    *b = 1;
+                }
+            }
+        }
+        set.push(block);
+    }
+    set
+}
+
+fn hamming(a: &[Vec<u8>], b: &[Vec<u8>]) -> usize {
+    a.iter()
+        .zip(b)
+        .map(|(ra, rb)| ra.iter().zip(rb).filter(|(x, y)| x != y).count())
+        .sum()
+}
+
+/// replaces the found symbols with the closest ones in set,
+/// an inverted match swaps fg and bg, the lowest index wins a tie
+fn match_symbols(r: &SymbolResult, set: Vec<Vec<Vec<u8>>>) -> SymbolResult {
+    let n = r.symsize as usize * r.symsize as usize;
+    let best: Vec<(usize, bool)> = r
+        .symbols
+        .iter()
+        .map(|b| {
+            let mut best = (0, false, usize::MAX);
+            for (i, s) in set.iter().enumerate() {
+                let d = hamming(b, s);
+                if d < best.2 {
+                    best = (i, false, d);
+                }
+                if n - d < best.2 {
+                    best = (i, true, n - d);
+                }
+            }
+            (best.0, best.1)
+        })
+        .collect();
+    let cells = r
+        .cells
+        .iter()
+        .map(|&(idx, fg, bg)| match best[idx] {
+            (i, false) => (i, fg, bg),
+            (i, true) => (i, bg, fg),
+        })
+        .collect();
+    SymbolResult {
+        grid_w: r.grid_w,
+        grid_h: r.grid_h,
+        symsize: r.symsize,
+        symbols: set,
+        cells,
+    }
+}
+
+/// cells of a match_symbols result, index splits into texidx and symidx
+fn match_json(r: &SymbolResult) -> serde_json::Value {
+    let cells: Vec<_> = r
+        .cells
+        .iter()
+        .map(|c| {
+            serde_json::json!({
+                "index": c.0,
+                "texidx": c.0 / 256,
+                "symidx": c.0 % 256,
+                "fg": c.1,
+                "bg": c.2,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "grid_w": r.grid_w,
+        "grid_h": r.grid_h,
+        "cells": cells,
+    })
+}
+
+fn symbol_json(r: &SymbolResult) -> serde_json::Value {
+    let symbols: Vec<_> = r
+        .symbols
+        .iter()
+        .map(|b| serde_json::json!({ "bitmap": b }))
+        .collect();
+    let cells: Vec<_> = r
+        .cells
+        .iter()
+        .map(|c| serde_json::json!({ "index": c.0, "fg": c.1, "bg": c.2 }))
+        .collect();
+    serde_json::json!({
+        "grid_w": r.grid_w,
+        "grid_h": r.grid_h,
+        "symbols": symbols,
+        "cells": cells,
+    })
+}
+
+fn bit(k: &[Vec<u8>], x: u32, y: u32) -> bool {
+    k[y as usize][x as usize] == 1
+}
+
+/// dumps symbols to sout.png, 16 a row, and redraws the image with them to bout.png
+fn save_images(r: &SymbolResult) {
+    let symsize = r.symsize;
+    let symlen = r.symbols.len() as u32;
+    let symw = 16;
+    let symh = symlen.div_ceil(symw);
+    let mut simg = ImageBuffer::new(symsize * symw, symsize * symh);
+    for (n, k) in r.symbols.iter().enumerate() {
+        let n = n as u32;
+        for y in 0..symsize {
+            for x in 0..symsize {
+                let v = if bit(k, x, y) { 255u8 } else { 0 };
+                simg.put_pixel(
+                    (n % symw) * symsize + x,
+                    (n / symw) * symsize + y,
+                    Rgba([v, v, v, 255]),
+                );
+            }
+        }
+    }
+    eprintln!(
+        "dump symbols to sout.png({}symbols {}rows {}cols)",
+        symlen, symh, symw
+    );
+    simg.save("sout.png").expect("save image error");
+    save_redraw(r);
+}
+
+/// redraws the image with the symbols of every cell to bout.png
+fn save_redraw(r: &SymbolResult) {
+    let symsize = r.symsize;
+    let mut nimg = ImageBuffer::new(symsize * r.grid_w, symsize * r.grid_h);
+    for (b, &(idx, fg, bg)) in r.cells.iter().enumerate() {
+        let (i, j) = (b as u32 % r.grid_w, b as u32 / r.grid_w);
+        for y in 0..symsize {
+            for x in 0..symsize {
+                let ac = ANSI_COLOR_RGB[if bit(&r.symbols[idx], x, y) { fg } else { bg }];
+                nimg.put_pixel(
+                    i * symsize + x,
+                    j * symsize + y,
+                    Rgba([ac[0], ac[1], ac[2], 255]),
+                );
+            }
+        }
+    }
+    eprintln!("redraw to bout.png");
+    nimg.save("bout.png").expect("save image error");
 }
 
 fn main() {
@@ -19,17 +222,31 @@ fn main() {
     let mut height: u32;
     let start_x: u32;
     let start_y: u32;
-    // key: binary image, value: block index list
-    let mut symbol_map = HashMap::new();
-    // key: block index, value: bg color, fg color)
-    let mut color_map = HashMap::new();
 
     // parse command line...
-    let args: Vec<String> = env::args().collect();
+    // --json prints the symbols and cells instead of saving sout.png and bout.png,
+    // --output writes the json to a file
+    // --match <symbols.png> maps every block to the closest symbol in symbols.png
+    // instead of finding new ones, only bout.png is saved
+    let mut args: Vec<String> = vec![];
+    let (mut json, mut output, mut matchset) = (false, None, None);
+    let mut it = env::args();
+    while let Some(a) = it.next() {
+        match a.as_str() {
+            "--version" => {
+                println!("pixel_symbol {}", env!("CARGO_PKG_VERSION"));
+                return;
+            }
+            "--json" => json = true,
+            "--output" => output = it.next(),
+            "--match" => matchset = it.next(),
+            _ => args.push(a),
+        }
+    }
     let arglen = args.len();
     if arglen != 3 && arglen != 7 {
-        println!(
-            "Usage: pixel_symbol image_file_path symsize <start_x> <start_y> <width> <height>"
+        eprintln!(
+            "Usage: pixel_symbol [--json] [--output <file>] [--match <symbols.png>] image_file_path symsize <start_x> <start_y> <width> <height>"
         );
         return;
     }
@@ -49,122 +266,32 @@ fn main() {
         height = args[6].parse::<u32>().unwrap() / symsize;
         img = img.crop(start_x, start_y, width * symsize, height * symsize);
     }
-    println!("width={} height={}", width, height);
+    eprintln!("width={} height={}", width, height);
 
-    // count pixels for dig background color
-    let back_color = find_background_color(&img, width * symsize, height * symsize);
-
-    // scan blocks
-    for i in 0..height {
-        for j in 0..width {
-            let c = process_block(&img, symsize as usize, j, i, back_color);
-            color_map.entry(i * width + j).or_insert((c.0, c.1));
-            symbol_map
-                .entry(c.2)
-                .or_insert(Vec::new())
-                .push(i * width + j);
-        }
+    let mut r = scan_symbols(&img, symsize, width, height);
+    if let Some(m) = &matchset {
+        let simg = image::open(m).expect("Failed to open the symbols image");
+        r = match_symbols(&r, load_symbol_set(&simg, symsize));
     }
-    let symlen = symbol_map.len();
-    let symw = 16;
-    let symh = symlen / 16 + if symlen % 16 == 0 { 0 } else { 1 };
-
-    // redraw image...
-    let mut simg = ImageBuffer::new(symsize * symw as u32, symsize * symh as u32);
-    let mut nimg = ImageBuffer::new(symsize * width, symsize * height);
-    let mut scount = 0;
-    for (k, v) in symbol_map.iter() {
-        for y in 0..symsize {
-            for x in 0..symsize {
-                let pixel_value = if k[y as usize][x as usize] == 1 {
-                    [255u8, 255, 255, 255]
-                } else {
-                    [0u8, 0, 0, 255]
-                };
-                simg.put_pixel(
-                    (scount % 16) * symsize + x,
-                    (scount / 16) * symsize + y,
-                    Rgba(pixel_value),
-                );
-            }
+    if json {
+        let s = if matchset.is_some() {
+            match_json(&r)
+        } else {
+            symbol_json(&r)
         }
-        scount += 1;
-
-        for b in v {
-            let i = b % width;
-            let j = b / width;
-            let color = color_map.get(b).unwrap();
-            for y in 0..symsize {
-                for x in 0..symsize {
-                    let pixel_value = if k[y as usize][x as usize] == 1 {
-                        let ac = ANSI_COLOR_RGB[color.1 as usize];
-                        [ac[0], ac[1], ac[2], 255]
-                    } else {
-                        let ac = ANSI_COLOR_RGB[color.0 as usize];
-                        [ac[0], ac[1], ac[2], 255]
-                    };
-                    nimg.put_pixel(i * symsize + x, j * symsize + y, Rgba(pixel_value));
-                }
-            }
-        }
-    }
-    println!("dump symbols to sout.png({}symbols {}rows {}cols)", symlen, symh, symw);
-    simg.save("sout.png").expect("save image error");
-    println!("redraw to bout.png");
-    nimg.save("bout.png").expect("save image error");
-}
-
-// find background colors...
-fn find_background_color(img: &DynamicImage, w: u32, h: u32) -> u32 {
-    // color_u32 : (first_x, first_y, count)
-    let mut cc: HashMap<u32, (u32, u32, u32)> = HashMap::new();
-    for i in 0..h {
-        for j in 0..w {
-            let p = img.get_pixel(j, i);
-            let k: u32 = ((p[0] as u32) << 24)
-                + ((p[1] as u32) << 16)
-                + ((p[2] as u32) << 8)
-                + (p[3] as u32);
-            (*cc.entry(k).or_insert((j, i, 0))).2 += 1;
+        .to_string();
+        match output {
+            Some(f) => fs::write(f, s).expect("Failed to write the output file"),
+            None => println!("{}", s),
         }
+    } else if matchset.is_some() {
+        save_redraw(&r);
+    } else {
+        save_images(&r);
     }
-    let mut cv: Vec<_> = cc.iter().collect();
-    cv.sort_by(|b, a| (&a.1 .2).cmp(&b.1 .2));
-    *cv[0].0
-}
-
-fn luminance(e1: u32) -> f32 {
-    let e1r = (e1 >> 24 & 0xff) as u8;
-    let e1g = (e1 >> 16 & 0xff) as u8;
-    let e1b = (e1 >> 8 & 0xff) as u8;
-    0.299 * e1r as f32 + 0.587 * e1g as f32 + 0.114 * e1b as f32
 }
 
-// get color distance
-fn color_distance(e1: u32, e2: u32) -> f32 {
-    let e1r = (e1 >> 24 & 0xff) as u8;
-    let e1g = (e1 >> 16 & 0xff) as u8;
-    let e1b = (e1 >> 8 & 0xff) as u8;
-    let e2r = (e2 >> 24 & 0xff) as u8;
-    let e2g = (e2 >> 16 & 0xff) as u8;
-    let e2b = (e2 >> 8 & 0xff) as u8;
-
-    let l1 = Lab::from_rgb(&[e1r, e1g, e1b]);
-    let l2 = Lab::from_rgb(&[e2r, e2g, e2b]);
-    let lab1 = LabValue {
-        l: l1.l,
-        a: l1.a,
-        b: l1.b,
-    };
-    let lab2 = LabValue {
-        l: l2.l,
-        a: l2.a,
-        b: l2.b,
-    };
-    *DeltaE::new(&lab1, &lab2, DE2000).value()
-}
-
-// get symbol block color
+// get symbol block color and bitmap, returns (bg, fg, bitmap)
 fn process_block(
     image: &DynamicImage,
     n: usize,
@@ -172,168 +299,62 @@ fn process_block(
     y: u32,
     back_rgb: u32,
 ) -> (usize, usize, Vec<Vec<u8>>) {
-    let mut cc: HashMap<u32, (u32, u32)> = HashMap::new();
-    let mut cm: Vec<u32> = vec![];
-    let mut block = vec![vec![0u8; n]; n];
-    for i in 0..n {
-        for j in 0..n {
-            let pixel_x = x * n as u32 + j as u32;
-            let pixel_y = y * n as u32 + i as u32;
-            if pixel_x < image.width() && pixel_y < image.height() {
-                let p = image.get_pixel(pixel_x, pixel_y);
-                let k: u32 = ((p[0] as u32) << 24)
-                    + ((p[1] as u32) << 16)
-                    + ((p[2] as u32) << 8)
-                    + (p[3] as u32);
-                cc.entry(k).or_insert((pixel_x, pixel_y));
-                cm.push(k);
-            }
-        }
-    }
-    let mut cv: Vec<_> = cc.iter().collect();
-    let mut include_back = false;
-    let clen = cv.len();
-    for c in &mut cv {
-        if *c.0 == back_rgb {
-            include_back = true;
-        } else {
-            let cd = color_distance(*c.0, back_rgb);
-            // fix simliar color to back
-            if cd < 1.0 {
-                // println!("cd={} c1={} c2={}", cd, *c.0, back_rgb);
-                (*c).0 = &back_rgb;
-                include_back = true;
-            }
-        }
-    }
-    let ret;
-    if include_back {
-        if clen == 1 {
-            ret = Some((back_rgb, back_rgb));
-            // println!("<B>{:?}", ret);
-        } else if clen == 2 {
-            let mut r = (back_rgb, back_rgb);
-            if *cv[0].0 != back_rgb {
-                r.1 = *cv[0].0;
-            }
-            if *cv[1].0 != back_rgb {
-                r.1 = *cv[1].0;
-            }
-            ret = Some(r);
-            // println!("<B,F>{:?}", ret);
-        } else {
-            // select bigest distance color to forecolor
-            let mut bigd = 0.0f32;
-            let mut bcv = cv[0];
-            for c in &cv {
-                let cd = color_distance(*c.0, back_rgb);
-                if cd > bigd {
-                    bigd = cd;
-                    bcv = *c;
-                }
-            }
-            ret = Some((back_rgb, *bcv.0));
-            // println!("ERROR!!! clen={} cv={:?}", clen, cv);
-            // println!("bcv={:?}", bcv);
-        }
-    } else {
-        if clen == 1 {
-            ret = Some((*cv[0].0, *cv[0].0));
-            // println!("<F>{:?}", ret);
-        } else if clen == 2 {
-            let l1 = luminance(*cv[0].0);
-            let l2 = luminance(*cv[1].0);
-            if l2 > l1 {
-                ret = Some((*cv[0].0, *cv[1].0));
-            } else {
-                ret = Some((*cv[1].0, *cv[0].0));
-            }
-            // println!("<F1,F2>{:?}", ret);
-        } else {
-            let mut ccv = vec![];
-            cv.sort();
-            // println!("ERROR2!!! clen={} cv={:?}", clen, cv);
-            let mut base = *cv[0].0;
-            ccv.push(cv[0]);
-            for i in 1..clen {
-                let cd = color_distance(*cv[i].0, base);
-                if cd > 1.0 {
-                    ccv.push(cv[i]);
-                }
-                base = *cv[i].0;
-            }
-            let l1 = luminance(*ccv[0].0);
-            let l2 = luminance(*ccv[1].0);
-            if l2 > l1 {
-                ret = Some((*ccv[0].0, *ccv[1].0));
-            } else {
-                ret = Some((*ccv[1].0, *ccv[0].0));
-            }
-            // println!("ccv = {:?}", ccv);
-        }
-    }
-
-    for i in 0..n {
-        for j in 0..n {
-            let color = cm[i * n + j];
-            let cd0 = color_distance(color, ret.unwrap().0);
-            let cd1 = color_distance(color, ret.unwrap().1);
-            if cd0 <= cd1 {
-                block[i][j] = 0;
-            } else {
-                block[i][j] = 1;
-            }
-        }
-    }
-
-    match ret {
-        Some(r) => (find_best_color_u32(r.0), find_best_color_u32(r.1), block),
-        _ => (0, 0, block),
-    }
+    let n32 = n as u32;
+    let block = image.crop_imm(x * n32, y * n32, n32, n32);
+    let mut sym = Symbol::new(n as u8, n as u8, true, &block);
+    sym.make_binary(back_rgb);
+    (
+        sym.back_color as usize,
+        sym.fore_color as usize,
+        sym.binary_data,
+    )
 }
 
-fn find_best_color_u32(c: u32) -> usize {
-    find_best_color(RGB {
-        r: (c >> 24) as u8,
-        g: (c >> 16) as u8,
-        b: (c >> 8) as u8,
-    })
-}
-
-// get color distance
-fn color_distance_rgb(e1: &RGB, e2: &RGB) -> f32 {
-    let l1 = Lab::from_rgb(&[e1.r, e1.g, e1.b]);
-    let l2 = Lab::from_rgb(&[e2.r, e2.g, e2.b]);
-    let lab1 = LabValue {
-        l: l1.l,
-        a: l1.a,
-        b: l1.b,
-    };
-    let lab2 = LabValue {
-        l: l2.l,
-        a: l2.a,
-        b: l2.b,
-    };
-    *DeltaE::new(&lab1, &lab2, DE2000).value()
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn find_best_color(color: RGB) -> usize {
-    let mut min_mse = f32::MAX;
-    let mut best_match = 0;
+    #[test]
+    fn json_output_schema() {
+        // 2x1 blocks of 4x4: a white block and a white block with a red dot
+        let mut img = ImageBuffer::from_pixel(8, 4, Rgba([255u8, 255, 255, 255]));
+        img.put_pixel(5, 1, Rgba([255, 0, 0, 255]));
+        let r = scan_symbols(&DynamicImage::ImageRgba8(img), 4, 2, 1);
+        let v: serde_json::Value = serde_json::from_str(&symbol_json(&r).to_string()).unwrap();
+        assert_eq!(v["grid_w"], 2);
+        assert_eq!(v["grid_h"], 1);
+        let syms = v["symbols"].as_array().unwrap();
+        assert_eq!(syms.len(), 2);
+        assert_eq!(syms[1]["bitmap"][1][1], 1);
+        assert_eq!(syms[1]["bitmap"].as_array().unwrap().len(), 4);
+        let cells = v["cells"].as_array().unwrap();
+        assert_eq!(cells.len(), 2);
+        assert_eq!(cells[1]["index"], 1);
+        for k in ["index", "fg", "bg"] {
+            assert!(cells[0][k].is_u64(), "{}", k);
+        }
+    }
 
-    for (i, pcolor) in ANSI_COLOR_RGB.iter().enumerate() {
-        let pcrgb = RGB {
-            r: pcolor[0],
-            g: pcolor[1],
-            b: pcolor[2],
-        };
-        let mse = color_distance_rgb(&pcrgb, &color);
+    #[test]
+    fn match_finds_known_index() {
+        // a 4x4 diagonal symbol at texidx 1, symidx 17 of an otherwise blank set
+        let mut set = ImageBuffer::from_pixel(512, 512, Rgba([0u8, 0, 0, 255]));
+        let (x0, y0) = ((17 % 16 + 16) * 4, 17 / 16 * 4);
+        for i in 0..4 {
+            set.put_pixel(x0 + i, y0 + i, Rgba([255, 255, 255, 255]));
+        }
+        let set = load_symbol_set(&DynamicImage::ImageRgba8(set), 4);
+        assert_eq!(set.len(), 128 * 128);
 
-        if mse < min_mse {
-            min_mse = mse;
-            best_match = i;
+        let mut img = ImageBuffer::from_pixel(4, 4, Rgba([255u8, 255, 255, 255]));
+        for i in 0..4 {
+            img.put_pixel(i, i, Rgba([255, 0, 0, 255]));
         }
+        let r = scan_symbols(&DynamicImage::ImageRgba8(img), 4, 1, 1);
+        let m = match_symbols(&r, set);
+        assert_eq!(m.cells[0].0, 256 + 17);
+        let v = match_json(&m);
+        assert_eq!(v["cells"][0]["texidx"], 1);
+        assert_eq!(v["cells"][0]["symidx"], 17);
     }
-
-    best_match
 }
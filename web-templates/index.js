@@ -10,6 +10,27 @@ export const js_load_asset = (url) => {
     ;
 };
 
+// web audio, clips are fetched once by url and decoded on first play,
+// they skip js_load_asset as the browser decodes them, see src/audio.rs
+const audio = {ctx: null, clips: {}};
+
+audio.play = (url, loop) => {
+    // browsers only allow audio after a user gesture
+    if (!audio.ctx) audio.ctx = new AudioContext();
+    if (!audio.clips[url]) {
+        audio.clips[url] = fetch(url)
+            .then(data => data.arrayBuffer())
+            .then(res => audio.ctx.decodeAudioData(res));
+    }
+    audio.clips[url].then(buf => {
+        const src = audio.ctx.createBufferSource();
+        src.buffer = buf;
+        src.loop = loop;
+        src.connect(audio.ctx.destination);
+        src.start();
+    });
+};
+
 const utils = {};
 
 utils.loop = update => {
@@ -55,6 +76,11 @@ window.onmousemove = (e) => { sg.key_event(3, e); };
 
 utils.loop(function(timeStep) {
     sg.tick(timeStep);
+    // clips queued by Audio::play_file, as [clip, loop, clip, loop...]
+    const sounds = sg.take_audio();
+    for (let i = 0; i < sounds.length; i += 2) {
+        audio.play(sounds[i], sounds[i + 1]);
+    }
     return true;
 });
 